@@ -27,6 +27,7 @@ use ir::{
     UINT_SYMBOL,
     VOID_SYMBOL,
 };
+use span::Span;
 
 use crate::{
     ArgKind,
@@ -38,12 +39,14 @@ use crate::{
     CondKind,
     DefineStmt,
     EnumItem,
+    ExportItem,
     FieldExpr,
     FnItem,
     IdentNode,
     IfExpr,
     ImplItem,
     ImportItem,
+    IntrinsicExpr,
     Pat,
     Path,
     PathField,
@@ -53,6 +56,7 @@ use crate::{
     StructItem,
     TypedefItem,
     Typing,
+    Visibility,
     VisitAst,
     Visitor,
 };
@@ -69,6 +73,10 @@ pub struct LocalVisitResult {
 pub struct GlobalVisitResult {
     pub pkg_symbol_to_def_id: FxHashMap<Symbol, DefId>,
     pub pkg_def_id_to_res_kind: FxHashMap<DefId, ResKind>,
+    /// Visibility of each package member, and the span of its definition
+    pub pkg_def_id_to_visibility: FxHashMap<DefId, (Visibility, Span)>,
+    /// Variants of each top-level enum, used by glob imports like `import Option.*`
+    pub pkg_enum_def_id_to_variants: FxHashMap<DefId, Vec<DefId>>,
 }
 
 #[derive(Debug)]
@@ -82,6 +90,9 @@ pub struct AstPreResolver<'ctx, 'ast, 'b, E> where E: ResolverHandle<'ctx, 'ast,
     lexical_binding_to_def_id: FxHashMap<LexicalBinding, DefId>,
     pkg_symbol_to_def_id: FxHashMap<Symbol, DefId>,
     pkg_def_id_to_res_kind: FxHashMap<DefId, ResKind>,
+    pkg_def_id_to_visibility: FxHashMap<DefId, (Visibility, Span)>,
+    pkg_enum_def_id_to_variants: FxHashMap<DefId, Vec<DefId>>,
+    exported_symbols: Vec<Symbol>,
     next_scope_id: ScopeId,
     next_context_id: ContextId,
     is_in_impl: bool,
@@ -99,11 +110,14 @@ impl<'ctx, 'ast, 'b, E> VisitAst<'ast, AstUnvalidated>
         where AstUnvalidated: AstState<NextState = N>, N: AstState
     {
         self.visit_stmts(self.ast.main_scope.stmts);
+        self.apply_exports();
         (
             self.ast.next_state(),
             GlobalVisitResult {
                 pkg_symbol_to_def_id: self.pkg_symbol_to_def_id,
                 pkg_def_id_to_res_kind: self.pkg_def_id_to_res_kind,
+                pkg_def_id_to_visibility: self.pkg_def_id_to_visibility,
+                pkg_enum_def_id_to_variants: self.pkg_enum_def_id_to_variants,
             },
             LocalVisitResult {
                 lexical_context_to_parent_lexical_context: self.lexical_context_to_parent_lexical_context,
@@ -145,6 +159,9 @@ impl<'ctx, 'ast, 'b, E> AstPreResolver<'ctx, 'ast, 'b, E>
             ),
             pkg_symbol_to_def_id: FxHashMap::default(),
             pkg_def_id_to_res_kind: FxHashMap::default(),
+            pkg_def_id_to_visibility: FxHashMap::default(),
+            pkg_enum_def_id_to_variants: FxHashMap::default(),
+            exported_symbols: Vec::new(),
             next_scope_id: ScopeId(1),
             next_context_id: ContextId(1),
             is_in_impl: false,
@@ -194,9 +211,22 @@ impl<'ctx, 'ast, 'b, E> AstPreResolver<'ctx, 'ast, 'b, E>
     fn is_in_main_scope(&self) -> bool {
         self.lexical_context_stack.len() == 1
     }
-    fn make_pkg_def_if_in_main_scope(&mut self, def_id: DefId) {
+    fn make_pkg_def_if_in_main_scope(&mut self, def_id: DefId, visibility: Visibility, span: Span) {
         if self.is_in_main_scope() {
             self.pkg_symbol_to_def_id.insert(def_id.symbol, def_id);
+            self.pkg_def_id_to_visibility.insert(def_id, (visibility, span));
+        }
+    }
+    /// Items listed in an `export` item are made public,
+    /// regardless of where in the file the `export` is placed
+    fn apply_exports(&mut self) {
+        for symbol in std::mem::take(&mut self.exported_symbols) {
+            let Some(def_id) = self.pkg_symbol_to_def_id.get(&symbol) else {
+                continue;
+            };
+            if let Some((visibility, _)) = self.pkg_def_id_to_visibility.get_mut(def_id) {
+                *visibility = Visibility::Public;
+            }
         }
     }
     fn end_scope(&mut self) {
@@ -367,14 +397,25 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
         self.visit_expr(field_expr.lhs);
     }
 
+    fn visit_intrinsic_expr(&mut self, intrinsic_expr: &'ast IntrinsicExpr<'ast>) -> Self::Result {
+        self.visit_typing(&intrinsic_expr.typing);
+    }
+
     fn visit_def_stmt(&mut self, def_stmt: &'ast DefineStmt<'ast>) -> Self::Result {
         self.traverse_pat_and_bind_idents(def_stmt.setter_expr);
         self.visit_expr(def_stmt.value_expr);
     }
 
     fn visit_import_item(&mut self, import_item: &'ast ImportItem<'ast>) -> Self::Result {
-        for path in import_item.import_items_path {
-            self.visit_path(*path);
+        for import_path in import_item.import_items_path {
+            self.visit_import_path(*import_path);
+        }
+    }
+
+    fn visit_export_item(&mut self, export_item: &'ast ExportItem<'ast>) -> Self::Result {
+        for ident_node in export_item.exported_items.iter() {
+            self.exported_symbols.push(Symbol::from_node_id(ident_node.ast_node_id));
+            self.visit_ident_expr(ident_node);
         }
     }
 
@@ -398,7 +439,7 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
                 arg.ident.ast_node_id,
                 Symbol::from_node_id(arg.ident.ast_node_id)
             );
-            self.make_pkg_def_if_in_main_scope(def_id);
+            self.make_pkg_def_if_in_main_scope(def_id, Visibility::Private, arg.ident.span);
             self.bind_node_id_to_lexical_context(arg.ident.ast_node_id);
             self.visit_typing(&arg.type_expr);
         }
@@ -458,7 +499,7 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
             typedef_item.ident_node.ast_node_id,
             typedef_name_symbol
         );
-        self.make_pkg_def_if_in_main_scope(def_id);
+        self.make_pkg_def_if_in_main_scope(def_id, typedef_item.visibility, typedef_item.ident_node.span);
         self.bind_node_id_to_lexical_context(typedef_item.ident_node.ast_node_id);
         self.make_lexical_binding_to_def_id(def_id, ResKind::Adt);
 
@@ -476,7 +517,7 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
             struct_item.ident_node.ast_node_id,
             struct_name_symbol
         );
-        self.make_pkg_def_if_in_main_scope(def_id);
+        self.make_pkg_def_if_in_main_scope(def_id, struct_item.visibility, struct_item.ident_node.span);
         self.bind_node_id_to_lexical_context(struct_item.ident_node.ast_node_id);
         self.make_lexical_binding_to_def_id(def_id, ResKind::Adt);
 
@@ -505,10 +546,23 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
             enum_item.ident_node.ast_node_id,
             enum_name_symbol
         );
-        self.make_pkg_def_if_in_main_scope(def_id);
+        self.make_pkg_def_if_in_main_scope(def_id, enum_item.visibility, enum_item.ident_node.span);
         self.bind_node_id_to_lexical_context(enum_item.ident_node.ast_node_id);
         self.make_lexical_binding_to_def_id(def_id, ResKind::Adt);
 
+        if self.is_in_main_scope() {
+            let variant_def_ids = enum_item.variants
+                .iter()
+                .map(|variant| {
+                    DefId::new(
+                        Symbol::from_node_id(variant.ident_node.ast_node_id),
+                        variant.ident_node.ast_node_id
+                    )
+                })
+                .collect();
+            self.pkg_enum_def_id_to_variants.insert(def_id, variant_def_ids);
+        }
+
         self.start_scope();
 
         for variant in enum_item.variants.iter() {
@@ -533,7 +587,7 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
             fn_item.ident_node.ast_node_id,
             Symbol::from_node_id(fn_item.ident_node.ast_node_id)
         );
-        self.make_pkg_def_if_in_main_scope(def_id);
+        self.make_pkg_def_if_in_main_scope(def_id, fn_item.visibility, fn_item.ident_node.span);
         self.bind_node_id_to_lexical_context(fn_item.ident_node.ast_node_id);
         self.make_lexical_binding_to_def_id(def_id, ResKind::Fn);
 
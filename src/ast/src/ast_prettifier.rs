@@ -9,8 +9,10 @@ use crate::{
     Ast,
     IdentNode,
     IfFalseBranchExpr,
+    IntrinsicKind,
     Stmt,
     Typing,
+    Visibility,
 };
 use std::fmt::Write;
 
@@ -56,6 +58,13 @@ impl<'ast, T> AstPrettifier<'ast, T> where T: AstState {
     }
 }
 
+fn get_visibility_prefix(visibility: Visibility) -> &'static str {
+    match visibility {
+        Visibility::Public => "pub ",
+        Visibility::Private => "",
+    }
+}
+
 fn write_typing(buffer: &mut String, src: &str, typing: &Typing<'_>) {
     match typing {
         Typing::SelfType => write!(buffer, "Self").expect("Unexpected write error"),
@@ -162,11 +171,28 @@ impl<'ast, T> Visitor<'ast> for AstPrettifier<'ast, T> where T: AstState {
         Self::default_result()
     }
 
+    fn visit_export_item(&mut self, export_item: &'ast crate::ExportItem<'ast>) -> Self::Result {
+        write!(self.buffer, "{}export ", self.get_indentation())?;
+
+        for (i, ident_node) in export_item.exported_items.iter().enumerate() {
+            write!(self.buffer, "{}", Symbol::from_node_id(ident_node.ast_node_id).get())?;
+
+            if i < export_item.exported_items.len() - 1 {
+                write!(self.buffer, ", ")?;
+            }
+        }
+
+        writeln!(self.buffer)?;
+
+        Self::default_result()
+    }
+
     fn visit_typedef_item(&mut self, typedef_item: &'ast crate::TypedefItem<'ast>) -> Self::Result {
         write!(
             self.buffer,
-            "{}typedef {} ",
+            "{}{}typedef {} ",
             self.get_indentation(),
+            get_visibility_prefix(typedef_item.visibility),
             Symbol::from_node_id(typedef_item.ident_node.ast_node_id).get()
         )?;
 
@@ -180,8 +206,9 @@ impl<'ast, T> Visitor<'ast> for AstPrettifier<'ast, T> where T: AstState {
     fn visit_struct_item(&mut self, struct_item: &'ast crate::StructItem<'ast>) -> Self::Result {
         writeln!(
             self.buffer,
-            "{}struct {} {{",
+            "{}{}struct {} {{",
             self.get_indentation(),
+            get_visibility_prefix(struct_item.visibility),
             Symbol::from_node_id(struct_item.ident_node.ast_node_id).get()
         )?;
 
@@ -207,8 +234,9 @@ impl<'ast, T> Visitor<'ast> for AstPrettifier<'ast, T> where T: AstState {
     fn visit_fn_item(&mut self, fn_item: &'ast crate::FnItem<'ast>) -> Self::Result {
         write!(
             self.buffer,
-            "{}fn {}(",
+            "{}{}fn {}(",
             self.get_indentation(),
+            get_visibility_prefix(fn_item.visibility),
             Symbol::from_node_id(fn_item.ident_node.ast_node_id).get()
         )?;
 
@@ -261,6 +289,15 @@ impl<'ast, T> Visitor<'ast> for AstPrettifier<'ast, T> where T: AstState {
         Self::default_result()
     }
 
+    fn visit_defer_expr(&mut self, defer_expr: &'ast crate::DeferExpr<'ast>) -> Self::Result {
+        write!(self.buffer, "defer ")?;
+        self.visit_expr(defer_expr.expr)?;
+
+        writeln!(self.buffer)?;
+
+        Self::default_result()
+    }
+
     fn visit_def_stmt(&mut self, def_stmt: &'ast crate::DefineStmt<'ast>) -> Self::Result {
         write!(self.buffer, "{}", self.get_indentation())?;
         if def_stmt.mut_span.is_some() {
@@ -315,6 +352,32 @@ impl<'ast, T> Visitor<'ast> for AstPrettifier<'ast, T> where T: AstState {
         Self::default_result()
     }
 
+    fn visit_try_expr(&mut self, try_expr: &'ast crate::TryExpr<'ast>) -> Self::Result {
+        self.visit_expr(try_expr.expr)?;
+        write!(self.buffer, "?")?;
+
+        Self::default_result()
+    }
+
+    fn visit_intrinsic_expr(
+        &mut self,
+        intrinsic_expr: &'ast crate::IntrinsicExpr<'ast>
+    ) -> Self::Result {
+        match intrinsic_expr.kind {
+            IntrinsicKind::SizeOf => write!(self.buffer, "sizeof(")?,
+            IntrinsicKind::AlignOf => write!(self.buffer, "alignof(")?,
+            IntrinsicKind::OffsetOf => write!(self.buffer, "offsetof(")?,
+        }
+        write_typing(&mut self.buffer, self.src, &intrinsic_expr.typing);
+        if let Some(field) = intrinsic_expr.field {
+            write!(self.buffer, ", ")?;
+            self.visit_ident_expr(field)?;
+        }
+        write!(self.buffer, ")")?;
+
+        Self::default_result()
+    }
+
     fn visit_binary_expr(&mut self, binary_expr: &'ast crate::BinaryExpr<'ast>) -> Self::Result {
         write!(self.buffer, "(")?;
         self.visit_expr(binary_expr.lhs)?;
@@ -333,6 +396,9 @@ impl<'ast, T> Visitor<'ast> for AstPrettifier<'ast, T> where T: AstState {
     }
 
     fn visit_loop_expr(&mut self, loop_expr: &'ast crate::LoopExpr<'ast>) -> Self::Result {
+        if let Some(label) = loop_expr.label {
+            write!(self.buffer, "{}: ", Symbol::from_node_id(label.ast_node_id).get())?;
+        }
         writeln!(self.buffer, "loop")?;
         self.increment_scope_depth();
         self.visit_stmts(loop_expr.body.stmts)?;
@@ -342,6 +408,9 @@ impl<'ast, T> Visitor<'ast> for AstPrettifier<'ast, T> where T: AstState {
 
     fn visit_break_expr(&mut self, break_expr: &'ast crate::BreakExpr<'ast>) -> Self::Result {
         write!(self.buffer, "break ")?;
+        if let Some(label) = break_expr.label {
+            write!(self.buffer, "{} ", Symbol::from_node_id(label.ast_node_id).get())?;
+        }
         break_expr.value.map(|expr| self.visit_expr(expr));
         Self::default_result()
     }
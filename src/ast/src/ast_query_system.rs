@@ -10,6 +10,7 @@ use crate::{
     CallExpr,
     CompFnDeclItem,
     ContinueExpr,
+    DeferExpr,
     DefineStmt,
     EnumItem,
     FieldExpr,
@@ -21,6 +22,7 @@ use crate::{
     ImportItem,
     IndexExpr,
     IntegerExpr,
+    IntrinsicExpr,
     LoopExpr,
     NullExpr,
     PathField,
@@ -28,6 +30,7 @@ use crate::{
     StringExpr,
     StructExpr,
     StructItem,
+    TryExpr,
     TupleExpr,
     TupleFieldExpr,
     TupleStructPat,
@@ -89,7 +92,8 @@ pub enum AstQueryEntry<'ast> {
     FieldExpr(&'ast FieldExpr<'ast>),
     PathField(&'ast PathField<'ast>),
     TupleFieldExpr(&'ast TupleFieldExpr<'ast>),
-    ContinueExpr(&'ast ContinueExpr),
+    ContinueExpr(&'ast ContinueExpr<'ast>),
+    DeferExpr(&'ast DeferExpr<'ast>),
     BinaryExpr(&'ast BinaryExpr<'ast>),
     IndexExpr(&'ast IndexExpr<'ast>),
     FnItem(&'ast FnItem<'ast>),
@@ -100,6 +104,8 @@ pub enum AstQueryEntry<'ast> {
     GroupExpr(&'ast GroupExpr<'ast>),
     EnumItem(&'ast EnumItem<'ast>),
     CallExpr(&'ast CallExpr<'ast>),
+    TryExpr(&'ast TryExpr<'ast>),
+    IntrinsicExpr(&'ast IntrinsicExpr<'ast>),
     IntegerExpr(&'ast IntegerExpr),
     BoolExpr(&'ast BoolExpr),
     IdentNode(&'ast IdentNode),
@@ -1,5 +1,5 @@
-use diagnostics::{ report_diagnostics, Diagnostic, ErrorKind };
-use fxhash::{ FxBuildHasher, FxHashMap };
+use diagnostics::{ report_diagnostics, Diagnostic, ErrorKind, WarningKind };
+use fxhash::{ FxBuildHasher, FxHashMap, FxHashSet };
 use ir::{
     Adt,
     ContextId,
@@ -11,6 +11,7 @@ use ir::{
     HasSelfArg,
     LexicalBinding,
     LexicalContext,
+    ModuleId,
     Mutability,
     NameBinding,
     NameBindingKind,
@@ -23,6 +24,8 @@ use ir::{
     TyCtx,
     BIG_SELF_SYMBOL,
     BOOL_SYMBOL,
+    COPY_SYMBOL,
+    DROP_SYMBOL,
     BOOL_TY,
     FLOAT_32_SYMBOL,
     FLOAT_32_TY,
@@ -39,6 +42,7 @@ use ir::{
     INT_8_TY,
     INT_SYMBOL,
     MAIN_SYMBOL,
+    ON_DROP_SYMBOL,
     STR_SYMBOL,
     STR_TY,
     UINT16_SYMBOL,
@@ -50,6 +54,7 @@ use ir::{
     UINT_64_TY,
     UINT_8_TY,
     UINT_SYMBOL,
+    UNKOWN_TY,
     VOID_SYMBOL,
     VOID_TY,
 };
@@ -58,12 +63,17 @@ use span::Span;
 use crate::{
     ast_pre_resolver::{ self },
     get_ident_node_from_arg_kind,
+    get_span_from_path,
+    get_symbol_from_path,
     ArgKind,
     Ast,
     AstPartlyResolved,
     AstState,
+    BreakExpr,
     CompFnDeclItem,
+    ContinueExpr,
     CondKind,
+    DeferExpr,
     DefineStmt,
     EnumItem,
     Expr,
@@ -74,7 +84,10 @@ use crate::{
     IfExpr,
     ImplItem,
     ImportItem,
+    ImportPath,
+    IntrinsicExpr,
     ItemType,
+    LoopExpr,
     Pat,
     Path,
     PathField,
@@ -85,6 +98,7 @@ use crate::{
     TupleStructPat,
     TypedefItem,
     Typing,
+    Visibility,
     VisitAst,
     Visitor,
 };
@@ -95,6 +109,8 @@ pub struct GlobalVisitResult<'ctx, 'ast> {
     pub clib_fns: Vec<DefId>,
     pub pkg_def_id_to_name_binding: FxHashMap<DefId, NameBinding<'ctx>>,
     pub trait_impl_id_to_def_ids: FxHashMap<TraitImplId, Vec<DefId>>,
+    pub adt_def_id_to_drop_fn: FxHashMap<DefId, DefId>,
+    pub copy_adt_def_ids: FxHashSet<DefId>,
 }
 
 #[derive(Debug)]
@@ -104,6 +120,14 @@ pub struct LocalVisitResult<'ctx> {
     pub node_id_to_ty: FxHashMap<NodeId, Ty>,
 }
 
+/// An import path, or a glob import e.g. `pkg.*` (which binds multiple symbols)
+#[derive(Debug)]
+struct Import {
+    symbol: Symbol,
+    span: Span,
+    is_used: bool,
+}
+
 /// Second pass. Visits the Ast from left to right, and resolves all top-level names
 ///
 /// Non top-level names like `x` in `self.x` are validated during type checking,
@@ -121,11 +145,21 @@ pub struct AstResolver<'ctx, 'ast, 'b, E>
     resolver_handle: &'b E,
     trait_impl_context: Option<TraitImplId>,
     trait_impl_id_to_def_ids: FxHashMap<TraitImplId, Vec<DefId>>,
+    /// The `onDrop` method of each ADT implementing `Drop`
+    adt_def_id_to_drop_fn: FxHashMap<DefId, DefId>,
+    /// ADTs implementing `Copy`
+    copy_adt_def_ids: FxHashSet<DefId>,
     def_id_to_name_binding: FxHashMap<DefId, NameBinding<'ctx>>,
     pkg_def_id_to_name_binding: FxHashMap<DefId, NameBinding<'ctx>>,
     fns: Vec<&'ast FnItem<'ast>>,
     clib_fns: Vec<DefId>,
     node_id_to_type: FxHashMap<NodeId, Ty>,
+    /// Labels of the enclosing loops (innermost last)
+    loop_labels: Vec<Symbol>,
+    /// Imports in this file, used to warn about unused imports
+    imports: Vec<Import>,
+    /// Symbols bound by imports, and the index of the import that bound them
+    import_symbol_to_import: FxHashMap<Symbol, (DefId, usize)>,
 
     diagnostics: Vec<Diagnostic>,
 }
@@ -141,6 +175,7 @@ impl<'ctx, 'ast, 'b, E> VisitAst<'ast, AstPartlyResolved>
         where AstPartlyResolved: AstState<NextState = N>, N: AstState
     {
         self.visit_stmts(self.ast.main_scope.stmts);
+        self.report_unused_imports();
         if !self.diagnostics.is_empty() {
             report_diagnostics(self.diagnostics);
         }
@@ -151,6 +186,8 @@ impl<'ctx, 'ast, 'b, E> VisitAst<'ast, AstPartlyResolved>
                 clib_fns: self.clib_fns,
                 pkg_def_id_to_name_binding: self.pkg_def_id_to_name_binding,
                 trait_impl_id_to_def_ids: self.trait_impl_id_to_def_ids,
+                adt_def_id_to_drop_fn: self.adt_def_id_to_drop_fn,
+                copy_adt_def_ids: self.copy_adt_def_ids,
             },
             LocalVisitResult {
                 node_id_to_def_id: self.local_visit_result.node_id_to_def_id,
@@ -195,8 +232,13 @@ impl<'ctx, 'ast, 'b, E> AstResolver<'ctx, 'ast, 'b, E>
                 FxBuildHasher::default()
             ),
             trait_impl_id_to_def_ids: FxHashMap::default(),
+            adt_def_id_to_drop_fn: FxHashMap::default(),
+            copy_adt_def_ids: FxHashSet::default(),
             clib_fns: Vec::new(),
             fns: Vec::with_capacity(ast.metadata.fn_count),
+            loop_labels: Vec::new(),
+            imports: Vec::new(),
+            import_symbol_to_import: FxHashMap::default(),
             ast,
             diagnostics: Vec::new(),
         }
@@ -213,6 +255,242 @@ impl<'ctx, 'ast, 'b, E> AstResolver<'ctx, 'ast, 'b, E>
         self.diagnostics.push(Diagnostic::new_error(error_kind, span, self.ast.metadata.mod_id));
     }
 
+    fn report_warning(&mut self, warning_kind: WarningKind, span: Span) {
+        self.diagnostics.push(Diagnostic::new_warning(warning_kind, span, self.ast.metadata.mod_id));
+    }
+
+    fn report_unused_imports(&mut self) {
+        for import in std::mem::take(&mut self.imports) {
+            if !import.is_used {
+                self.report_warning(WarningKind::UnusedImport { symbol: import.symbol }, import.span);
+            }
+        }
+    }
+
+    fn mark_import_as_used(&mut self, symbol: Symbol, def_id: DefId) {
+        if let Some(&(import_def_id, import_index)) = self.import_symbol_to_import.get(&symbol) {
+            if import_def_id == def_id {
+                self.imports[import_index].is_used = true;
+            }
+        }
+    }
+
+    /// Returns the item bound to `symbol` in the main scope of the file, if any
+    fn lookup_main_scope_binding(&self, symbol: Symbol) -> Option<DefId> {
+        let main_scope = LexicalContext::new(ContextId(0), ScopeId(0));
+
+        [ResKind::Fn, ResKind::Adt, ResKind::ConstVariable, ResKind::Variable]
+            .into_iter()
+            .find_map(|res_kind| {
+                self.local_visit_result.lexical_binding_to_def_id
+                    .get(&LexicalBinding::new(main_scope, symbol, res_kind))
+                    .copied()
+            })
+    }
+
+    /// Binds `symbol` to `def_id` in the main scope of the file
+    ///
+    /// Glob imports never override other items, while explicit imports report an error if
+    /// `symbol` is already bound to another item
+    fn bind_import(
+        &mut self,
+        symbol: Symbol,
+        def_id: DefId,
+        res_kind: ResKind,
+        import_index: usize,
+        is_glob: bool
+    ) {
+        if let Some(existing_def_id) = self.lookup_main_scope_binding(symbol) {
+            if !is_glob && existing_def_id != def_id {
+                // Already an error, so it shouldn't also be reported as unused
+                self.imports[import_index].is_used = true;
+                let span = self.imports[import_index].span;
+                self.report_error(ErrorKind::ConflictingImport { symbol }, span);
+            }
+            return;
+        }
+
+        let lexical_binding = LexicalBinding::new(
+            LexicalContext::new(ContextId(0), ScopeId(0)),
+            symbol,
+            res_kind
+        );
+        self.local_visit_result.lexical_binding_to_def_id.insert(lexical_binding, def_id);
+        self.import_symbol_to_import.insert(symbol, (def_id, import_index));
+    }
+
+    fn add_import(&mut self, symbol: Symbol, span: Span) -> usize {
+        self.imports.push(Import { symbol, span, is_used: false });
+        self.imports.len() - 1
+    }
+
+    fn resolve_import_path(&mut self, import_path: ImportPath<'ast>) {
+        match import_path {
+            ImportPath::Path(path) => {
+                let Some(def_id) = self.resolve_path_def_id(path) else {
+                    return;
+                };
+                let import_index = self.add_import(def_id.symbol, get_span_from_path(path));
+                let res_kind = self.resolver_handle.lookup_pkg_member_res_kind(&def_id);
+                self.bind_import(def_id.symbol, def_id, res_kind, import_index, false);
+            }
+            ImportPath::PathAlias(path_alias) => {
+                let Some(def_id) = self.resolve_path_def_id(path_alias.path) else {
+                    return;
+                };
+                let alias_symbol = Symbol::from_node_id(path_alias.alias.ast_node_id);
+                let import_index = self.add_import(alias_symbol, path_alias.span);
+                let res_kind = self.resolver_handle.lookup_pkg_member_res_kind(&def_id);
+                self.bind_import(alias_symbol, def_id, res_kind, import_index, false);
+            }
+            ImportPath::PathEverything(path_everything) => {
+                let import_symbol = get_symbol_from_path(path_everything.lhs);
+                let import_index = self.add_import(import_symbol, path_everything.span);
+
+                if let Some(module_id) = self.resolve_path_module(path_everything.lhs) {
+                    let mod_id = self.ast.metadata.mod_id;
+                    for def_id in self.resolver_handle.lookup_module_members(module_id) {
+                        if def_id.node_id.mod_id == mod_id {
+                            continue;
+                        }
+                        let (visibility, _) = self.resolver_handle.lookup_pkg_member_visibility(
+                            &def_id
+                        );
+                        if visibility == Visibility::Private {
+                            continue;
+                        }
+                        let res_kind = self.resolver_handle.lookup_pkg_member_res_kind(&def_id);
+                        self.bind_import(def_id.symbol, def_id, res_kind, import_index, true);
+                    }
+                    return;
+                }
+
+                let Some(enum_def_id) = self.resolve_path_def_id(path_everything.lhs) else {
+                    // Already an error, so it shouldn't also be reported as unused
+                    self.imports[import_index].is_used = true;
+                    return;
+                };
+                let variant_def_ids = self.resolver_handle.lookup_enum_variants(&enum_def_id);
+                let Some(variant_def_ids) = variant_def_ids.cloned() else {
+                    // Already an error, so it shouldn't also be reported as unused
+                    self.imports[import_index].is_used = true;
+                    self.report_error(
+                        ErrorKind::ExpectedModule { symbol: enum_def_id.symbol },
+                        get_span_from_path(path_everything.lhs)
+                    );
+                    return;
+                };
+
+                for variant_def_id in variant_def_ids {
+                    self.bind_import(
+                        variant_def_id.symbol,
+                        variant_def_id,
+                        ResKind::Adt,
+                        import_index,
+                        true
+                    );
+                }
+            }
+            ImportPath::PathMultiple(path_multiple) => {
+                for import_path in path_multiple.paths.iter() {
+                    self.resolve_import_path(*import_path);
+                }
+            }
+        }
+    }
+
+    fn check_pkg_member_visibility(&mut self, def_id: DefId, span: Span) {
+        if def_id.node_id.mod_id == self.ast.metadata.mod_id {
+            return;
+        }
+
+        let (visibility, def_span) = self.resolver_handle.lookup_pkg_member_visibility(&def_id);
+        if visibility == Visibility::Private {
+            self.report_error(
+                ErrorKind::ItemIsPrivate {
+                    symbol: def_id.symbol,
+                    def_span,
+                    def_mod_id: def_id.node_id.mod_id,
+                },
+                span
+            );
+        }
+    }
+
+    fn resolve_loop_label(&mut self, label: Option<&'ast IdentNode>) {
+        if let Some(label) = label {
+            let label_symbol = Symbol::from_node_id(label.ast_node_id);
+            if !self.loop_labels.contains(&label_symbol) {
+                self.report_error(ErrorKind::UndefinedLoopLabel { label: label_symbol }, label.span);
+            }
+        }
+    }
+
+    /// `Drop` is the only trait for now, and is built into the compiler. An implementation of it
+    /// must consist of the single method `fn onDrop(*self)`, which is called when a value is dropped
+    fn bind_trait_impl(
+        &mut self,
+        impl_item: &'ast ImplItem<'ast>,
+        trait_path: Path<'ast>,
+        implementor_def_id: DefId
+    ) {
+        let trait_symbol = get_symbol_from_path(trait_path);
+
+        if !matches!(trait_path, Path::PathSegment(_)) {
+            self.report_error(
+                ErrorKind::UndefinedTrait { symbol: trait_symbol },
+                get_span_from_path(trait_path)
+            );
+            return;
+        }
+
+        // `Copy` is only a marker, so it has no methods
+        if trait_symbol == *COPY_SYMBOL {
+            if !impl_item.impl_fns.is_empty() {
+                self.report_error(
+                    ErrorKind::InvalidCopyImpl { adt_symbol: implementor_def_id.symbol },
+                    impl_item.span
+                );
+                return;
+            }
+
+            self.copy_adt_def_ids.insert(implementor_def_id);
+            return;
+        }
+
+        if trait_symbol != *DROP_SYMBOL {
+            self.report_error(
+                ErrorKind::UndefinedTrait { symbol: trait_symbol },
+                get_span_from_path(trait_path)
+            );
+            return;
+        }
+
+        let drop_fn = match impl_item.impl_fns {
+            [fn_item] if
+                Symbol::from_node_id(fn_item.ident_node.ast_node_id) == *ON_DROP_SYMBOL &&
+                matches!(fn_item.args, [ArgKind::PtrSelf(_) | ArgKind::MutPtrSelf(_)]) &&
+                fn_item.return_ty.is_none()
+            => {
+                self.get_def_id_from_node_id(fn_item.ident_node.ast_node_id)
+            }
+            _ => {
+                self.report_error(
+                    ErrorKind::InvalidDropImpl { adt_symbol: implementor_def_id.symbol },
+                    impl_item.span
+                );
+                return;
+            }
+        };
+
+        if self.adt_def_id_to_drop_fn.insert(implementor_def_id, drop_fn).is_some() {
+            self.report_error(
+                ErrorKind::ConflictingDropImpl { adt_symbol: implementor_def_id.symbol },
+                impl_item.span
+            );
+        }
+    }
+
     fn begin_impl_context(&mut self, trait_impl_id: TraitImplId) {
         self.trait_impl_context = Some(trait_impl_id);
     }
@@ -242,23 +520,97 @@ impl<'ctx, 'ast, 'b, E> AstResolver<'ctx, 'ast, 'b, E>
         }
     }
 
-    fn resolve_path_def_id(&mut self, path: Path<'ast>) -> DefId {
+    /// Binds the ident of a package or module e.g. `net` in `pkg.net.Socket`,
+    /// so the type checker knows it's not a value
+    fn bind_module_ident(&mut self, ident_node: &'ast IdentNode) {
+        let def_id = DefId::new(Symbol::from_node_id(ident_node.ast_node_id), ident_node.ast_node_id);
+        self.set_def_id_to_node_id(ident_node.ast_node_id, def_id);
+        self.set_namebinding_to_def_id(def_id, NameBinding::new(NameBindingKind::Pkg(&[])));
+    }
+
+    /// Returns the module a path refers to, if it refers to a module
+    /// e.g. `pkg`, `pkg.net` or `other.net` (where `other` is an imported package)
+    fn resolve_path_module(&mut self, path: Path<'ast>) -> Option<ModuleId> {
         match path {
+            Path::PathPkg(pkg_ident_node) => {
+                let def_id = self.resolver_handle.get_or_set_pkg_def_id(pkg_ident_node);
+                self.set_def_id_to_node_id(pkg_ident_node.ast_node_id, def_id);
+                Some(self.resolver_handle.lookup_pkg_root_module())
+            }
+            Path::PathSegment(pkg_ident_node) => {
+                let pkg_symbol = Symbol::from_node_id(pkg_ident_node.ast_node_id);
+                let module_id = self.resolver_handle.lookup_extern_pkg(pkg_symbol)?;
+                self.bind_module_ident(pkg_ident_node);
+                Some(module_id)
+            }
             Path::PathField(path_field) => {
-                let _ = self.resolve_path_def_id(path_field.lhs);
-
-                if let Path::PathPkg(_) = path_field.lhs {
-                    let rhs_symbol = Symbol::from_node_id(path_field.rhs.ast_node_id);
-                    let def_id = self.resolver_handle
-                        .lookup_pkg_member(rhs_symbol)
-                        .unwrap_or_else(||
-                            panic!("`{}` is not a member of package", rhs_symbol.get())
-                        );
-                    self.set_def_id_to_node_id(path_field.rhs.ast_node_id, def_id);
-                    def_id
-                } else {
-                    panic!("Extern packages are not yet supported")
-                }
+                let module_id = self.resolve_path_module(path_field.lhs)?;
+                let rhs_symbol = Symbol::from_node_id(path_field.rhs.ast_node_id);
+                let submodule_id = self.resolver_handle.lookup_submodule(module_id, rhs_symbol)?;
+                self.bind_module_ident(path_field.rhs);
+                Some(submodule_id)
+            }
+        }
+    }
+
+    /// The shortest part of a path which isn't a package or module e.g. `other.make` in
+    /// `other.make.x.y`, where `make` is a function
+    fn get_non_module_prefix(&mut self, path: Path<'ast>) -> Path<'ast> {
+        if let Path::PathField(path_field) = path {
+            if self.resolve_path_module(path_field.lhs).is_none() {
+                return self.get_non_module_prefix(path_field.lhs);
+            }
+        }
+        path
+    }
+
+    /// Same as `resolve_path_module`, but for expressions e.g. `pkg.net` in `pkg.net.connect()`
+    fn resolve_expr_module(&mut self, expr: Expr<'ast>) -> Option<ModuleId> {
+        match expr {
+            Expr::ExprWithoutBlock(ExprWithoutBlock::PlaceExpr(PlaceExpr::PkgIdentExpr(_))) => {
+                Some(self.resolver_handle.lookup_pkg_root_module())
+            }
+            Expr::ExprWithoutBlock(ExprWithoutBlock::PlaceExpr(PlaceExpr::FieldExpr(field_expr))) => {
+                let module_id = self.resolve_expr_module(field_expr.lhs)?;
+                let rhs_symbol = Symbol::from_node_id(field_expr.rhs.ast_node_id);
+                let submodule_id = self.resolver_handle.lookup_submodule(module_id, rhs_symbol)?;
+                self.bind_module_ident(field_expr.rhs);
+                Some(submodule_id)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns `None` after reporting an error if the path doesn't refer to a definition
+    fn resolve_path_def_id(&mut self, path: Path<'ast>) -> Option<DefId> {
+        match path {
+            Path::PathField(path_field) => {
+                let rhs_symbol = Symbol::from_node_id(path_field.rhs.ast_node_id);
+
+                let Some(module_id) = self.resolve_path_module(path_field.lhs) else {
+                    let non_module_path = self.get_non_module_prefix(path_field.lhs);
+                    self.report_error(
+                        ErrorKind::ExpectedModule { symbol: get_symbol_from_path(non_module_path) },
+                        get_span_from_path(non_module_path)
+                    );
+                    return None;
+                };
+
+                let def_id = self.resolver_handle.lookup_module_member(module_id, rhs_symbol);
+                let Some(def_id) = def_id else {
+                    self.report_error(
+                        ErrorKind::UndefinedModuleMember {
+                            symbol: rhs_symbol,
+                            module_symbol: get_symbol_from_path(path_field.lhs),
+                        },
+                        path_field.rhs.span
+                    );
+                    return None;
+                };
+
+                self.check_pkg_member_visibility(def_id, path_field.rhs.span);
+                self.set_def_id_to_node_id(path_field.rhs.ast_node_id, def_id);
+                Some(def_id)
             }
             Path::PathSegment(ident_node) => {
                 let def_id = self
@@ -270,12 +622,12 @@ impl<'ctx, 'ast, 'b, E> AstResolver<'ctx, 'ast, 'b, E>
                         )
                     );
                 self.set_def_id_to_node_id(ident_node.ast_node_id, def_id);
-                def_id
+                Some(def_id)
             }
             Path::PathPkg(pkg_ident_node) => {
                 let def_id = self.resolver_handle.get_or_set_pkg_def_id(pkg_ident_node);
                 self.set_def_id_to_node_id(pkg_ident_node.ast_node_id, def_id);
-                def_id
+                Some(def_id)
             }
         }
     }
@@ -361,7 +713,7 @@ impl<'ctx, 'ast, 'b, E> AstResolver<'ctx, 'ast, 'b, E>
                     t if t == *FLOAT_32_SYMBOL => FLOAT_32_TY,
                     t if t == *FLOAT_64_SYMBOL => FLOAT_64_TY,
 
-                    str => {
+                    symbol => {
                         if
                             let Some(def_id) = self.lookup_ident_declaration(
                                 ident_node,
@@ -370,7 +722,11 @@ impl<'ctx, 'ast, 'b, E> AstResolver<'ctx, 'ast, 'b, E>
                         {
                             Ty::Adt(def_id)
                         } else {
-                            panic!("Expected Algebric Data Type: {}", str.get());
+                            self.report_error(
+                                ErrorKind::UndefinedLookup { symbol, res_kind: ResKind::Adt },
+                                ident_node.span
+                            );
+                            UNKOWN_TY
                         }
                     }
                 }
@@ -432,7 +788,9 @@ impl<'ctx, 'ast, 'b, E> AstResolver<'ctx, 'ast, 'b, E>
                             &LexicalBinding::new(current_context, symbol, res_kind)
                         )
                     {
-                        return Some(*def_id);
+                        let def_id = *def_id;
+                        self.mark_import_as_used(symbol, def_id);
+                        return Some(def_id);
                     }
                     if
                         let Some(parent_context) =
@@ -469,7 +827,9 @@ impl<'ctx, 'ast, 'b, E> AstResolver<'ctx, 'ast, 'b, E>
                             &LexicalBinding::new(current_context, symbol, res_kind)
                         )
                     {
-                        return Some(*def_id);
+                        let def_id = *def_id;
+                        self.mark_import_as_used(symbol, def_id);
+                        return Some(def_id);
                     }
                     if
                         let Some(parent_context) =
@@ -499,7 +859,9 @@ impl<'ctx, 'ast, 'b, E> AstResolver<'ctx, 'ast, 'b, E>
                             &lexical_binding
                         )
                     {
-                        return Some(*def_id);
+                        let def_id = *def_id;
+                        self.mark_import_as_used(symbol, def_id);
+                        return Some(def_id);
                     }
                     if
                         let Some(parent_context) =
@@ -546,12 +908,9 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
     }
 
     fn visit_field_expr(&mut self, field_expr: &'ast FieldExpr<'ast>) -> Self::Result {
-        if
-            let Expr::ExprWithoutBlock(ExprWithoutBlock::PlaceExpr(PlaceExpr::PkgIdentExpr(_))) =
-                &field_expr.lhs
-        {
+        if let Some(module_id) = self.resolve_expr_module(field_expr.lhs) {
             let rhs_symbol = Symbol::from_node_id(field_expr.rhs.ast_node_id);
-            let pkg_member = self.resolver_handle.lookup_pkg_member(rhs_symbol);
+            let pkg_member = self.resolver_handle.lookup_module_member(module_id, rhs_symbol);
             if let Some(def_id) = pkg_member {
                 let is_symbol_in_file = self.local_visit_result.lexical_binding_to_def_id
                     .values()
@@ -564,7 +923,10 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
                         rhs_symbol.get()
                     );
                 }
+                self.check_pkg_member_visibility(def_id, field_expr.rhs.span);
                 self.set_def_id_to_node_id(field_expr.rhs.ast_node_id, def_id);
+            } else if self.resolver_handle.lookup_submodule(module_id, rhs_symbol).is_some() {
+                self.bind_module_ident(field_expr.rhs);
             } else {
                 todo!("Report error: Undefined package member: {}", rhs_symbol.get());
             }
@@ -573,6 +935,12 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
         }
     }
 
+    fn visit_intrinsic_expr(&mut self, intrinsic_expr: &'ast IntrinsicExpr<'ast>) -> Self::Result {
+        // Pointers are allowed here, since only the size and alignment of the type is needed
+        let ty = self.type_from_typing(&intrinsic_expr.typing, ItemType::C);
+        self.set_type_to_node_id(intrinsic_expr.typing_ast_node_id, ty);
+    }
+
     fn visit_struct_expr(&mut self, struct_expr: &'ast StructExpr<'ast>) -> Self::Result {
         self.visit_ident_expr(struct_expr.ident_node);
 
@@ -642,15 +1010,38 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
         }
     }
 
+    fn visit_loop_expr(&mut self, loop_expr: &'ast LoopExpr<'ast>) -> Self::Result {
+        if let Some(label) = loop_expr.label {
+            self.loop_labels.push(Symbol::from_node_id(label.ast_node_id));
+            self.visit_block_expr(loop_expr.body);
+            self.loop_labels.pop();
+        } else {
+            self.visit_block_expr(loop_expr.body);
+        }
+    }
+
+    fn visit_break_expr(&mut self, break_expr: &'ast BreakExpr<'ast>) -> Self::Result {
+        self.resolve_loop_label(break_expr.label);
+
+        if let Some(value) = break_expr.value {
+            self.visit_expr(value);
+        }
+    }
+
+    fn visit_continue_expr(&mut self, continue_expr: &'ast ContinueExpr<'ast>) -> Self::Result {
+        self.resolve_loop_label(continue_expr.label);
+    }
+
+    fn visit_defer_expr(&mut self, defer_expr: &'ast DeferExpr<'ast>) -> Self::Result {
+        // Deferred expressions run on scope exit, so they can't jump to the enclosing loops
+        let prev_loop_labels = std::mem::take(&mut self.loop_labels);
+        self.visit_expr(defer_expr.expr);
+        self.loop_labels = prev_loop_labels;
+    }
+
     fn visit_import_item(&mut self, import_item: &'ast ImportItem<'ast>) -> Self::Result {
-        for import_item in import_item.import_items_path.iter() {
-            let def_id = self.resolve_path_def_id(*import_item);
-            let lexical_binding = LexicalBinding::new(
-                LexicalContext { context_id: ContextId(0), scope_id: ScopeId(0) },
-                def_id.symbol,
-                self.resolver_handle.lookup_pkg_member_res_kind(&def_id)
-            );
-            self.local_visit_result.lexical_binding_to_def_id.insert(lexical_binding, def_id);
+        for import_path in import_item.import_items_path.iter() {
+            self.resolve_import_path(*import_path);
         }
     }
 
@@ -701,7 +1092,14 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
     }
 
     fn visit_impl_item(&mut self, impl_item: &'ast ImplItem<'ast>) -> Self::Result {
-        let implementor_id = self.resolve_path_def_id(impl_item.implementor_path);
+        let Some(implementor_id) = self.resolve_path_def_id(impl_item.implementor_path) else {
+            self.set_type_to_node_id(impl_item.ast_node_id, VOID_TY);
+            return;
+        };
+
+        if let Some(trait_path) = impl_item.implemented_trait {
+            self.bind_trait_impl(impl_item, trait_path, implementor_id);
+        }
 
         let trait_impl_id = TraitImplId::new(implementor_id, None);
 
@@ -794,7 +1192,10 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
             self.fns.push(fn_item);
         }
 
+        // Loop labels can't be used across function boundaries
+        let prev_loop_labels = std::mem::take(&mut self.loop_labels);
         self.visit_stmts(fn_item.body);
+        self.loop_labels = prev_loop_labels;
     }
 
     fn visit_enum_item(&mut self, enum_item: &'ast EnumItem<'ast>) -> Self::Result {
@@ -15,6 +15,7 @@ use ir::{
     Ty,
     TyCtx,
     BOOL_TY,
+    INT_64_TY,
     NEVER_TY,
     NULL_TY,
     STR_TY,
@@ -36,6 +37,7 @@ use crate::{
     CallExpr,
     CondKind,
     ContinueExpr,
+    DeferExpr,
     DefineStmt,
     FieldExpr,
     FnItem,
@@ -44,6 +46,8 @@ use crate::{
     IfExpr,
     IndexExpr,
     IntegerExpr,
+    IntrinsicExpr,
+    IntrinsicKind,
     LoopExpr,
     NullExpr,
     Pat,
@@ -53,6 +57,7 @@ use crate::{
     ReturnExpr,
     StringExpr,
     StructExpr,
+    TryExpr,
     TupleExpr,
     TupleFieldExpr,
     TupleStructPat,
@@ -83,10 +88,12 @@ pub struct AstTypeChecker<'ctx, 'ast, 'b, E>
     node_id_to_def_id: FxHashMap<NodeId, DefId>,
     // trait_impl_context: Option<TraitImplId>,
     marker: PhantomData<&'ctx ()>,
-    /// First option checks if it's present (meain if we're inside a loop or function)
-    /// Second one checks whether or not the ty is set
-    loop_ret_ty: Option<Option<Ty>>,
+    /// One entry per enclosing loop (innermost last) with its label, and the type
+    /// of its `break` values once the first one has been seen
+    loop_ret_tys: Vec<(Option<Symbol>, Option<Ty>)>,
     fn_ret_ty: Option<Ty>,
+    /// Deferred expressions are emitted on scope exit, so they can't return themselves
+    is_in_defer_expr: bool,
 }
 
 impl<'ast, 'ctx, 'c, E> VisitAst<'ast, AstResolved>
@@ -134,9 +141,10 @@ impl<'ctx, 'ast, 'b, E> AstTypeChecker<'ctx, 'ast, 'b, E>
         local_visit_result: ast_resolver::LocalVisitResult<'ctx>
     ) -> Self {
         Self {
-            loop_ret_ty: None,
+            loop_ret_tys: Vec::new(),
 
             fn_ret_ty: None,
+            is_in_defer_expr: false,
             resolver_handle,
             node_id_to_type: local_visit_result.node_id_to_ty,
             def_id_to_name_binding: local_visit_result.def_id_to_name_binding,
@@ -212,6 +220,32 @@ impl<'ctx, 'ast, 'b, E> AstTypeChecker<'ctx, 'ast, 'b, E>
         self.node_id_to_type.get(&node_id).copied().expect("Type not found") //.unwrap_or(UNKOWN_TY)
     }
 
+    /// Returns the payload types of the `Ok` and `Err` variants if `ty` is an enum that has both
+    fn try_get_ok_and_err_tys(&self, ty: Ty) -> Option<(&'ctx [Ty], &'ctx [Ty])> {
+        let variants = match ty {
+            Ty::Adt(def_id) =>
+                match self.try_get_namebinding_from_def_id(def_id)?.kind {
+                    NameBindingKind::Adt(Adt::Enum(variants)) => variants,
+                    _ => {
+                        return None;
+                    }
+                }
+            _ => {
+                return None;
+            }
+        };
+
+        let get_variant_tys = |variant_name: &str| {
+            let variant_def_id = variants.iter().find(|x| x.symbol.get() == variant_name)?;
+            match self.try_get_namebinding_from_def_id(*variant_def_id)?.kind {
+                NameBindingKind::Adt(Adt::EnumVariant(_, _, variant_tys)) => Some(variant_tys),
+                _ => None,
+            }
+        };
+
+        Some((get_variant_tys("Ok")?, get_variant_tys("Err")?))
+    }
+
     fn try_get_def_id_from_trait_impl_id(
         &self,
         trait_impl_id: &TraitImplId,
@@ -318,10 +352,17 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
                     self.set_type_to_node_id(ident_node.ast_node_id, ty);
                     ty
                 }
-                NameBindingKind::Adt(Adt::EnumVariant(_, _, _)) =>
-                    panic!(
-                        "Allow this if enum variants when enum variants can be used as top level names"
-                    ),
+                // Enum variants imported with e.g. `import Option.*`
+                NameBindingKind::Adt(Adt::EnumVariant(_, _, enum_fields)) => {
+                    let ty = if enum_fields[0] == Ty::ZeroSized {
+                        Ty::Adt(def_id)
+                    } else {
+                        Ty::AtdConstructer(def_id)
+                    };
+                    self.set_def_id_to_node_id(ident_node.ast_node_id, def_id);
+                    self.set_type_to_node_id(ident_node.ast_node_id, ty);
+                    ty
+                }
                 NameBindingKind::ConstStr(_) => unreachable!("Const strings should not be here"),
             }
         } else {
@@ -351,6 +392,11 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
                 todo!("Expected {} arguments, got {}", variant_ty.len(), arg_tys.len());
             } else {
                 for (i, arg_ty) in arg_tys.iter().enumerate() {
+                    // The error of an argument of unknown type has already been reported
+                    if arg_ty.deref_if_stack_ptr() == UNKOWN_TY {
+                        continue;
+                    }
+
                     let is_valid_arg = TypeChecker::test_valid_arg(
                         ArgCmp {
                             arg_ty: variant_ty[i],
@@ -438,6 +484,10 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
                 continue;
             }
 
+            if given_arg_ty.deref_if_stack_ptr() == UNKOWN_TY {
+                continue;
+            }
+
             let arg_cmp = ArgCmp {
                 arg_ty: *arg_ty,
                 provided_ty: given_arg_ty,
@@ -475,6 +525,12 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
             VOID_TY
         };
 
+        if self.is_in_defer_expr {
+            self.resolver_handle.report_error(
+                Error::new(ErrorKind::ReturnInsideDefer, return_expr.span)
+            );
+        }
+
         if let Some(fn_ret_ty) = self.fn_ret_ty {
             self.set_type_to_node_id(return_expr.ast_node_id, fn_ret_ty);
             if
@@ -501,6 +557,95 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
         NEVER_TY
     }
 
+    fn visit_intrinsic_expr(&mut self, intrinsic_expr: &'ast IntrinsicExpr<'ast>) -> Self::Result {
+        if let (IntrinsicKind::OffsetOf, Some(field)) = (intrinsic_expr.kind, intrinsic_expr.field) {
+            let ty = self.get_type_from_node_id(intrinsic_expr.typing_ast_node_id);
+            let field_symbol = Symbol::from_node_id(field.ast_node_id);
+
+            let struct_fields = match ty {
+                Ty::Adt(def_id) =>
+                    match self.try_get_namebinding_from_def_id(def_id).map(|x| x.kind) {
+                        Some(NameBindingKind::Adt(Adt::Struct(struct_fields))) =>
+                            Some((def_id, struct_fields)),
+                        _ => None,
+                    }
+                _ => None,
+            };
+
+            match struct_fields {
+                Some((def_id, struct_fields)) => {
+                    let has_field = struct_fields
+                        .iter()
+                        .any(|(def_id, _)| def_id.symbol.get() == field_symbol.get());
+                    if !has_field {
+                        self.resolver_handle.report_error(
+                            Error::new(
+                                ErrorKind::UndefinedStructField(def_id.symbol, field_symbol),
+                                field.span
+                            )
+                        );
+                    }
+                }
+                None => {
+                    self.resolver_handle.report_error(
+                        Error::new(ErrorKind::InvalidStruct(ty), intrinsic_expr.span)
+                    );
+                }
+            }
+        }
+
+        self.set_type_to_node_id(intrinsic_expr.ast_node_id, INT_64_TY);
+        INT_64_TY
+    }
+
+    fn visit_try_expr(&mut self, try_expr: &'ast TryExpr<'ast>) -> Self::Result {
+        let operand_ty = self.visit_expr(try_expr.expr).deref_if_stack_ptr();
+
+        if self.is_in_defer_expr {
+            self.resolver_handle.report_error(Error::new(ErrorKind::TryInsideDefer, try_expr.span));
+        }
+
+        let (ok_tys, err_tys) = match self.try_get_ok_and_err_tys(operand_ty) {
+            Some((ok_tys, err_tys)) if ok_tys.len() == 1 => (ok_tys, err_tys),
+            _ => {
+                self.resolver_handle.report_error(
+                    Error::new(ErrorKind::InvalidTryOperand(operand_ty), try_expr.span)
+                );
+                self.set_type_to_node_id(try_expr.ast_node_id, UNKOWN_TY);
+                return UNKOWN_TY;
+            }
+        };
+
+        // The payload of the `Err` variant is returned as the `Err` variant of the function's return type
+        let fn_ret_ty = self.fn_ret_ty.unwrap_or(VOID_TY);
+        let is_valid_ret_ty = match self.try_get_ok_and_err_tys(fn_ret_ty) {
+            Some((_, ret_err_tys)) =>
+                ret_err_tys.len() == err_tys.len() &&
+                    ret_err_tys
+                        .iter()
+                        .zip(err_tys.iter())
+                        .all(|(ret_err_ty, err_ty)| {
+                            TypeChecker::test_eq_loose(
+                                *ret_err_ty,
+                                *err_ty,
+                                &(|def_id: DefId| { self.try_get_namebinding_from_def_id(def_id) })
+                            ).is_ok()
+                        }),
+            None => false,
+        };
+
+        if !is_valid_ret_ty {
+            self.resolver_handle.report_error(
+                Error::new(ErrorKind::MismatchedTryReturnType(fn_ret_ty, operand_ty), try_expr.span)
+            );
+        }
+
+        let ty = if ok_tys[0] == Ty::ZeroSized { VOID_TY } else { ok_tys[0] };
+
+        self.set_type_to_node_id(try_expr.ast_node_id, ty);
+        ty
+    }
+
     fn visit_struct_expr(&mut self, struct_expr: &'ast StructExpr<'ast>) -> Self::Result {
         let lhs_ty = self.visit_ident_expr(struct_expr.ident_node);
 
@@ -587,12 +732,14 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
 
         if let Ty::Package = lhs_ty {
             let def_id = self.get_def_id_from_node_id(field_expr.rhs.ast_node_id);
-            let name_binding = self.resolver_handle.lookup_pkg_member_name_binding(&def_id);
+            let name_binding = self.try_get_namebinding_from_def_id(def_id);
 
             if let Some(name_binding) = name_binding {
                 let ty = match name_binding.kind {
                     NameBindingKind::Adt(_) => Ty::AtdConstructer(def_id),
                     NameBindingKind::Fn(_, _, _) => Ty::FnDef(def_id),
+                    // Nested module
+                    NameBindingKind::Pkg(_) => Ty::Package,
                     _ => panic!("Expected adt or function"),
                 };
 
@@ -824,20 +971,33 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
         let def_id = match lhs_ty {
             Ty::AtdConstructer(def_id) => def_id,
             Ty::Package => {
-                let def_id = self.get_def_id_from_node_id(path_field.rhs.ast_node_id);
-                let name_binding = self.resolver_handle
-                    .lookup_pkg_member_name_binding(&def_id)
+                // Not a member of the package, which the resolver already reported
+                let def_id = self.try_get_def_id_from_node_id(path_field.rhs.ast_node_id);
+                let Some(def_id) = def_id else {
+                    return Ty::Unkown;
+                };
+                let name_binding = self
+                    .try_get_namebinding_from_def_id(def_id)
                     .expect("Package member not found");
 
                 return match name_binding.kind {
                     NameBindingKind::Adt(_) => Ty::AtdConstructer(def_id),
                     NameBindingKind::Fn(_, _, _) => Ty::FnDef(def_id),
+                    // Nested module
+                    NameBindingKind::Pkg(_) => {
+                        self.set_type_to_node_id(path_field.rhs.ast_node_id, Ty::Package);
+                        Ty::Package
+                    }
                     _ =>
                         panic!(
                             "Expected adt or function (because that's the only kinds of members a package can have and export)"
                         ),
                 };
             }
+            // Not a package or module, which the resolver already reported
+            Ty::Unkown => {
+                return Ty::Unkown;
+            }
             ty => { panic!("Invalid lhs of path field: {}\n{:?}", ty, path_field.lhs) }
         };
 
@@ -867,6 +1027,14 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
     }
 
     fn visit_path_segment(&mut self, path_segment: &'ast IdentNode) -> Self::Result {
+        // Extern packages e.g. `other` in `import other.Item`
+        if let Some(def_id) = self.try_get_def_id_from_node_id(path_segment.ast_node_id) {
+            if let NameBindingKind::Pkg(_) = self.get_namebinding_from_def_id(def_id).kind {
+                self.set_type_to_node_id(path_segment.ast_node_id, Ty::Package);
+                return Ty::Package;
+            }
+        }
+
         let ty = self.visit_ident_expr(path_segment);
         self.set_type_to_node_id(path_segment.ast_node_id, ty);
         ty
@@ -1038,21 +1206,42 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
     }
 
     fn visit_loop_expr(&mut self, loop_expr: &'ast LoopExpr<'ast>) -> Self::Result {
-        let prev_loop_ret_ty = std::mem::replace(&mut self.loop_ret_ty, Some(None));
+        let label = loop_expr.label.map(|label| Symbol::from_node_id(label.ast_node_id));
+        self.loop_ret_tys.push((label, None));
 
         self.visit_block_expr(loop_expr.body);
 
-        let ty = self.loop_ret_ty
+        let ty = self.loop_ret_tys
+            .pop()
             .expect("This is always present pushed above")
-            .unwrap_or(Self::default_result());
-
-        self.loop_ret_ty = prev_loop_ret_ty;
+            .1.unwrap_or(Self::default_result());
 
         self.set_type_to_node_id(loop_expr.ast_node_id, ty);
         ty
     }
 
-    fn visit_continue_expr(&mut self, continue_expr: &'ast ContinueExpr) -> Self::Result {
+    fn visit_defer_expr(&mut self, defer_expr: &'ast DeferExpr<'ast>) -> Self::Result {
+        // A `break` inside a deferred expression can only target a loop inside of it
+        let prev_loop_ret_tys = std::mem::take(&mut self.loop_ret_tys);
+        let prev_is_in_defer_expr = std::mem::replace(&mut self.is_in_defer_expr, true);
+
+        self.visit_expr(defer_expr.expr);
+
+        self.is_in_defer_expr = prev_is_in_defer_expr;
+        self.loop_ret_tys = prev_loop_ret_tys;
+
+        self.set_type_to_node_id(defer_expr.ast_node_id, VOID_TY);
+        VOID_TY
+    }
+
+    fn visit_continue_expr(&mut self, continue_expr: &'ast ContinueExpr<'ast>) -> Self::Result {
+        // Unknown labels are reported during resolving
+        if self.loop_ret_tys.is_empty() && continue_expr.label.is_none() {
+            self.resolver_handle.report_error(
+                Error::new(ErrorKind::ContinueOutsideLoop, continue_expr.span)
+            );
+        }
+
         self.set_type_to_node_id(continue_expr.ast_node_id, VOID_TY);
         VOID_TY
     }
@@ -1062,26 +1251,40 @@ impl<'ctx, 'ast, 'b, E> Visitor<'ast>
             .map(|expr| self.visit_expr(expr))
             .unwrap_or(Self::default_result());
 
-        if let Some(loop_ret_ty) = &self.loop_ret_ty {
-            if let Some(expected_ty) = loop_ret_ty {
+        let loop_idx = match break_expr.label {
+            Some(label) => {
+                let label = Symbol::from_node_id(label.ast_node_id);
+                self.loop_ret_tys.iter().rposition(|(loop_label, _)| *loop_label == Some(label))
+            }
+            None => self.loop_ret_tys.len().checked_sub(1),
+        };
+
+        match loop_idx.map(|loop_idx| (loop_idx, self.loop_ret_tys[loop_idx].1)) {
+            // A diverging break value (e.g. `break ret x`) doesn't decide the type of the loop
+            Some((loop_idx, None | Some(NEVER_TY))) => {
+                self.loop_ret_tys[loop_idx].1 = Some(break_ty);
+            }
+            Some((_, Some(expected_ty))) => {
                 if
-                    let Err(errors) = TypeChecker::test_eq_loose(
-                        *expected_ty,
+                    break_ty != NEVER_TY &&
+                    TypeChecker::test_eq_loose(
+                        expected_ty,
                         break_ty,
                         &(|def_id: DefId| { self.try_get_namebinding_from_def_id(def_id) })
-                    )
+                    ).is_err()
                 {
                     self.resolver_handle.report_error(
-                        Error::new(ErrorKind::BreakTypeError(*expected_ty, break_ty), Span::dummy())
+                        Error::new(ErrorKind::BreakTypeError(expected_ty, break_ty), break_expr.span)
                     );
                 }
-            } else {
-                self.loop_ret_ty = Some(Some(break_ty));
             }
-        } else {
-            self.resolver_handle.report_error(
-                Error::new(ErrorKind::BreakOutsideLoop, Span::dummy())
-            );
+            // Unknown labels are reported during resolving
+            None if break_expr.label.is_some() => {}
+            None => {
+                self.resolver_handle.report_error(
+                    Error::new(ErrorKind::BreakOutsideLoop, break_expr.span)
+                );
+            }
         }
 
         self.set_type_to_node_id(break_expr.ast_node_id, break_ty);
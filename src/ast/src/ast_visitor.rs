@@ -1,9 +1,10 @@
-use crate::{ ast_state::AstState, FnItem, PkgIdentNode, StringExpr };
+use crate::{ ast_state::AstState, FnItem, PkgIdentNode, StringExpr, Visibility };
 use error::Error;
 use fxhash::FxHashMap;
-use ir::{ DefId, LexicalContext, NameBinding, NodeId, ResKind, TraitImplId };
+use ir::{ DefId, LexicalContext, ModuleId, NameBinding, NodeId, ResKind, TraitImplId };
 
 use ir::Symbol;
+use span::Span;
 
 /// This can call functions on the Resolver struct in the resolver crate,
 /// which also implements this trait
@@ -14,8 +15,18 @@ pub trait ResolverHandle<'ctx, 'ast, T> where T: AstState {
     fn report_error(&self, error: Error);
 
     fn lookup_pkg_member(&self, symbol: Symbol) -> Option<DefId>;
+    fn lookup_pkg_root_module(&self) -> ModuleId;
+    /// Returns the root module of an imported package
+    fn lookup_extern_pkg(&self, pkg_symbol: Symbol) -> Option<ModuleId>;
+    fn lookup_module_member(&self, module_id: ModuleId, symbol: Symbol) -> Option<DefId>;
+    fn lookup_submodule(&self, module_id: ModuleId, symbol: Symbol) -> Option<ModuleId>;
+    /// Returns all members of a module, used by glob imports like `import pkg.*`
+    fn lookup_module_members(&self, module_id: ModuleId) -> Vec<DefId>;
     fn lookup_pkg_member_name_binding(&self, def_id: &DefId) -> Option<&NameBinding<'ctx>>;
     fn lookup_pkg_member_res_kind(&self, def_id: &DefId) -> ResKind;
+    fn lookup_pkg_member_visibility(&self, def_id: &DefId) -> (Visibility, Span);
+    /// Returns the variants of a top-level enum
+    fn lookup_enum_variants(&self, def_id: &DefId) -> Option<&Vec<DefId>>;
     fn lookup_trait_impl_def_ids(&self, trait_impl_id: &TraitImplId) -> Option<&Vec<DefId>>;
     fn get_or_set_pkg_def_id(&self, pkg_ident_node: &'ast PkgIdentNode) -> DefId;
 
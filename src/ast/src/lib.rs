@@ -69,7 +69,7 @@ pub use ast_visitor::ResolverHandle;
 pub use visitor::*;
 
 use std::marker::PhantomData;
-use ir::{ ModId, Mutability, NodeId };
+use ir::{ InlineAttr, ModId, Mutability, NodeId, Symbol, PKG_SYMBOL };
 use op::BinaryOp;
 use span::Span;
 use derive_new::new;
@@ -109,6 +109,14 @@ pub enum ItemType {
     Normal,
 }
 
+/// Items are private to the file they're defined in,
+/// unless they're marked with `pub` or listed in an `export` item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Public,
+    Private,
+}
+
 #[derive(Debug, new)]
 pub struct GlobalScope<'ast> {
     pub stmts: Stmts<'ast>,
@@ -143,18 +151,30 @@ pub enum ItemStmt<'ast> {
     ImplItem(&'ast ImplItem<'ast>),
     CompDeclItem(CompDeclItem<'ast>),
     ImportItem(&'ast ImportItem<'ast>),
+    ExportItem(&'ast ExportItem<'ast>),
 }
 
 #[derive(Debug, new)]
 pub struct ImportItem<'ast> {
-    pub import_items_path: &'ast [Path<'ast>],
+    pub import_items_path: &'ast [ImportPath<'ast>],
     pub span: Span,
     pub ast_node_id: NodeId,
 }
 
+/// `export Foo, Bar` makes items defined in the current file public
+#[derive(Debug, new)]
+pub struct ExportItem<'ast> {
+    pub exported_items: &'ast [&'ast IdentNode],
+    pub span: Span,
+    pub ast_node_id: NodeId,
+}
+
+/// `impl Foo { ... }`, or `impl Drop for Foo { ... }` when implementing a trait
 #[derive(Debug, new)]
 pub struct ImplItem<'ast> {
     pub implementor_path: Path<'ast>,
+    /// Only the built-in traits `Drop` and `Copy` can be implemented for now
+    pub implemented_trait: Option<Path<'ast>>,
     pub impl_fns: &'ast [&'ast FnItem<'ast>],
     pub span: Span,
     pub ast_node_id: NodeId,
@@ -170,6 +190,7 @@ pub struct CompFnDeclItem<'ast> {
     pub ident_node: &'ast IdentNode,
     pub args: &'ast [&'ast Field<'ast>],
     pub return_ty: Option<Typing<'ast>>,
+    pub visibility: Visibility,
     pub span: Span,
     pub ast_node_id: NodeId,
 }
@@ -179,17 +200,21 @@ pub struct TypedefItem<'ast> {
     pub ident_node: &'ast IdentNode,
     pub type_expr: Typing<'ast>,
     pub item_type: ItemType,
+    pub visibility: Visibility,
     pub span: Span,
     pub ast_node_id: NodeId,
 }
 
 #[derive(Debug, new)]
+#[allow(clippy::too_many_arguments)]
 pub struct FnItem<'ast> {
     pub ident_node: &'ast IdentNode,
     pub body: Stmts<'ast>,
     pub args: &'ast [ArgKind<'ast>],
     pub return_ty: Option<Typing<'ast>>,
     pub item_type: ItemType,
+    pub inline_attr: InlineAttr,
+    pub visibility: Visibility,
     pub span: Span,
     pub ast_node_id: NodeId,
 }
@@ -199,6 +224,7 @@ pub struct StructItem<'ast> {
     pub ident_node: &'ast IdentNode,
     pub field_declarations: &'ast [&'ast Field<'ast>],
     pub item_type: ItemType,
+    pub visibility: Visibility,
     pub span: Span,
     pub ast_node_id: NodeId,
 }
@@ -208,6 +234,7 @@ pub struct EnumItem<'ast> {
     pub ident_node: &'ast IdentNode,
     pub variants: &'ast [EnumVariant<'ast>],
     pub item_type: ItemType,
+    pub visibility: Visibility,
     pub span: Span,
     pub ast_node_id: NodeId,
 }
@@ -306,20 +333,34 @@ pub enum Path<'ast> {
 #[derive(Debug, Clone, Copy)]
 pub enum ImportPath<'ast> {
     Path(Path<'ast>),
-    PathEverything(&'ast PathEverything),
+    PathAlias(&'ast PathAlias<'ast>),
+    PathEverything(&'ast PathEverything<'ast>),
+    PathMultiple(&'ast PathMultiple<'ast>),
 }
 
-/// `*` in an import path to import everything e.g. `pkg.*`
+/// `as` in an import path to import an item under another name e.g. `pkg.Point as P`
 #[derive(Debug, Clone, Copy, new)]
-pub struct PathEverything {
+pub struct PathAlias<'ast> {
+    pub path: Path<'ast>,
+    pub alias: &'ast IdentNode,
+    pub span: Span,
+    pub ast_node_id: NodeId,
+}
+
+/// `*` in an import path to import everything e.g. `pkg.*` or `Option.*`
+#[derive(Debug, Clone, Copy, new)]
+pub struct PathEverything<'ast> {
+    pub lhs: Path<'ast>,
     pub span: Span,
     pub ast_node_id: NodeId,
 }
 
 /// Multiple paths seperated by commas `,` in brackes e.g. `pkg.{A, B, C}`
+///
+/// Each path is stored in full, so `pkg.{A, B as C}` is the same as `pkg.A, pkg.B as C`
 #[derive(Debug, Clone, Copy, new)]
 pub struct PathMultiple<'ast> {
-    pub paths: &'ast [Path<'ast>],
+    pub paths: &'ast [ImportPath<'ast>],
     pub span: Span,
     pub ast_node_id: NodeId,
 }
@@ -348,6 +389,7 @@ pub enum ExprWithBlock<'ast> {
 
 #[derive(Debug, new)]
 pub struct LoopExpr<'ast> {
+    pub label: Option<&'ast IdentNode>,
     pub body: &'ast BlockExpr<'ast>,
     pub span: Span,
     pub ast_node_id: NodeId,
@@ -386,12 +428,14 @@ pub enum ExprWithoutBlock<'ast> {
     PlaceExpr(PlaceExpr<'ast>),
     ValueExpr(ValueExpr<'ast>),
     BreakExpr(&'ast BreakExpr<'ast>),
-    ContinueExpr(&'ast ContinueExpr),
+    ContinueExpr(&'ast ContinueExpr<'ast>),
     ReturnExpr(&'ast ReturnExpr<'ast>),
+    DeferExpr(&'ast DeferExpr<'ast>),
 }
 
 #[derive(Debug, new)]
 pub struct BreakExpr<'ast> {
+    pub label: Option<&'ast IdentNode>,
     pub value: Option<Expr<'ast>>,
     pub span: Span,
     pub ast_node_id: NodeId,
@@ -404,8 +448,18 @@ pub struct ReturnExpr<'ast> {
     pub ast_node_id: NodeId,
 }
 
+/// `defer expr` runs `expr` on every exit from the enclosing block,
+/// in reverse order of declaration
+#[derive(Debug, new)]
+pub struct DeferExpr<'ast> {
+    pub expr: Expr<'ast>,
+    pub span: Span,
+    pub ast_node_id: NodeId,
+}
+
 #[derive(Debug, new)]
-pub struct ContinueExpr {
+pub struct ContinueExpr<'ast> {
+    pub label: Option<&'ast IdentNode>,
     pub span: Span,
     pub ast_node_id: NodeId,
 }
@@ -470,6 +524,8 @@ pub enum ValueExpr<'ast> {
     StructExpr(&'ast StructExpr<'ast>),
     ConstExpr(ConstExpr<'ast>),
     CallExpr(&'ast CallExpr<'ast>),
+    TryExpr(&'ast TryExpr<'ast>),
+    IntrinsicExpr(&'ast IntrinsicExpr<'ast>),
 }
 
 #[derive(Debug, new)]
@@ -480,6 +536,36 @@ pub struct CallExpr<'ast> {
     pub ast_node_id: NodeId,
 }
 
+/// `expr?` returns the `Err` variant of `expr` from the enclosing function,
+/// otherwise it evaluates to the payload of the `Ok` variant
+#[derive(Debug, new)]
+pub struct TryExpr<'ast> {
+    pub expr: Expr<'ast>,
+    pub span: Span,
+    pub ast_node_id: NodeId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntrinsicKind {
+    SizeOf,
+    AlignOf,
+    OffsetOf,
+}
+
+/// `sizeof(T)`, `alignof(T)` or `offsetof(Struct, field)`,
+/// which are folded into integer constants during ICFG building
+#[derive(Debug, new)]
+pub struct IntrinsicExpr<'ast> {
+    pub kind: IntrinsicKind,
+    pub typing: Typing<'ast>,
+    /// The field of `offsetof`
+    pub field: Option<&'ast IdentNode>,
+    /// The type of `typing` is bound to this node during resolving
+    pub typing_ast_node_id: NodeId,
+    pub span: Span,
+    pub ast_node_id: NodeId,
+}
+
 #[derive(Debug, new)]
 pub struct TupleExpr<'ast> {
     pub fields: &'ast [Expr<'ast>],
@@ -569,6 +655,7 @@ pub fn get_node_id_from_expr(expr: Expr) -> NodeId {
                 ExprWithoutBlock::BreakExpr(break_expr) => break_expr.ast_node_id,
                 ExprWithoutBlock::ContinueExpr(continue_expr) => continue_expr.ast_node_id,
                 ExprWithoutBlock::ReturnExpr(return_expr) => return_expr.ast_node_id,
+                ExprWithoutBlock::DeferExpr(defer_expr) => defer_expr.ast_node_id,
                 ExprWithoutBlock::PlaceExpr(place_expr) => get_node_id_from_place_expr(place_expr),
                 ExprWithoutBlock::ValueExpr(value_expr) => get_node_id_from_value_expr(value_expr),
             }
@@ -593,6 +680,8 @@ pub fn get_node_id_from_value_expr(value_expr: ValueExpr) -> NodeId {
         ValueExpr::TupleExpr(tuple_expr) => tuple_expr.ast_node_id,
         ValueExpr::StructExpr(struct_expr) => struct_expr.ast_node_id,
         ValueExpr::CallExpr(call_expr) => call_expr.ast_node_id,
+        ValueExpr::TryExpr(try_expr) => try_expr.ast_node_id,
+        ValueExpr::IntrinsicExpr(intrinsic_expr) => intrinsic_expr.ast_node_id,
         ValueExpr::ConstExpr(const_expr) => {
             match const_expr {
                 ConstExpr::BoolExpr(bool_expr) => bool_expr.ast_node_id,
@@ -611,6 +700,23 @@ pub fn get_node_id_from_pattern(pat: Pat) -> NodeId {
     }
 }
 
+pub fn get_span_from_path(path: Path) -> Span {
+    match path {
+        Path::PathSegment(ident_node) => ident_node.span,
+        Path::PathPkg(pkg_ident_node) => pkg_ident_node.span,
+        Path::PathField(path_field) => path_field.span,
+    }
+}
+
+/// The last symbol of a path e.g. `Socket` in `pkg.net.Socket`
+pub fn get_symbol_from_path(path: Path) -> Symbol {
+    match path {
+        Path::PathSegment(ident_node) => Symbol::from_node_id(ident_node.ast_node_id),
+        Path::PathPkg(_) => *PKG_SYMBOL,
+        Path::PathField(path_field) => Symbol::from_node_id(path_field.rhs.ast_node_id),
+    }
+}
+
 pub fn is_stmt_adt(stmt: &Stmt) -> bool {
     match stmt {
         Stmt::ItemStmt(
@@ -21,8 +21,10 @@ use crate::{
     CondKind,
     ConstExpr,
     ContinueExpr,
+    DeferExpr,
     DefineStmt,
     EnumItem,
+    ExportItem,
     Expr,
     ExprWithBlock,
     ExprWithoutBlock,
@@ -34,14 +36,18 @@ use crate::{
     IfFalseBranchExpr,
     ImplItem,
     ImportItem,
+    ImportPath,
     IndexExpr,
+    IntrinsicExpr,
     IntegerExpr,
     ItemStmt,
     LoopExpr,
     NullExpr,
     Pat,
     Path,
+    PathAlias,
     PathEverything,
+    PathMultiple,
     PathField,
     PkgIdentNode,
     PlaceExpr,
@@ -50,6 +56,7 @@ use crate::{
     StringExpr,
     StructExpr,
     StructItem,
+    TryExpr,
     TupleExpr,
     TupleFieldExpr,
     TupleStructPat,
@@ -106,7 +113,7 @@ pub trait Visitor<'ast>: Sized {
     }
 
     #[allow(unused_variables)]
-    fn visit_continue_expr(&mut self, continue_expr: &'ast ContinueExpr) -> Self::Result {
+    fn visit_continue_expr(&mut self, continue_expr: &'ast ContinueExpr<'ast>) -> Self::Result {
         Self::default_result()
     }
 
@@ -120,10 +127,6 @@ pub trait Visitor<'ast>: Sized {
         Self::default_result()
     }
 
-    #[allow(unused_variables)]
-    fn visit_path_everything(&mut self, path_everything: &'ast PathEverything) -> Self::Result {
-        Self::default_result()
-    }
 
     #[allow(unused_variables)]
     fn visit_pkg_ident_expr(&mut self, pkg_ident_expr: &'ast PkgIdentNode) -> Self::Result {
@@ -143,6 +146,22 @@ pub trait Visitor<'ast>: Sized {
         walk_path(self, path)
     }
 
+    fn visit_import_path(&mut self, import_path: ImportPath<'ast>) -> Self::Result {
+        walk_import_path(self, import_path)
+    }
+
+    fn visit_path_alias(&mut self, path_alias: &'ast PathAlias<'ast>) -> Self::Result {
+        walk_path_alias(self, path_alias)
+    }
+
+    fn visit_path_everything(&mut self, path_everything: &'ast PathEverything<'ast>) -> Self::Result {
+        walk_path_everything(self, path_everything)
+    }
+
+    fn visit_path_multiple(&mut self, path_multiple: &'ast PathMultiple<'ast>) -> Self::Result {
+        walk_path_multiple(self, path_multiple)
+    }
+
     fn visit_const_expr(&mut self, const_expr: ConstExpr<'ast>) -> Self::Result {
         walk_const_expr(self, const_expr)
     }
@@ -155,6 +174,10 @@ pub trait Visitor<'ast>: Sized {
         walk_return_expr(self, return_expr)
     }
 
+    fn visit_defer_expr(&mut self, defer_expr: &'ast DeferExpr<'ast>) -> Self::Result {
+        walk_defer_expr(self, defer_expr)
+    }
+
     fn visit_expr(&mut self, expr: Expr<'ast>) -> Self::Result {
         walk_expr(self, expr)
     }
@@ -200,6 +223,7 @@ pub trait Visitor<'ast>: Sized {
     fn visit_item(&mut self, item: ItemStmt<'ast>) -> Self::Result {
         match item {
             ItemStmt::ImportItem(import_item) => self.visit_import_item(import_item),
+            ItemStmt::ExportItem(export_item) => self.visit_export_item(export_item),
             ItemStmt::FnItem(fn_item) => self.visit_fn_item(fn_item),
             ItemStmt::StructItem(struct_item) => self.visit_struct_item(struct_item),
             ItemStmt::TypedefItem(typedef_item) => self.visit_typedef_item(typedef_item),
@@ -213,6 +237,10 @@ pub trait Visitor<'ast>: Sized {
         walk_import_item(self, import_item)
     }
 
+    fn visit_export_item(&mut self, export_item: &'ast ExportItem<'ast>) -> Self::Result {
+        walk_export_item(self, export_item)
+    }
+
     fn visit_comp_decl_item(&mut self, comp_decl_item: CompDeclItem<'ast>) -> Self::Result {
         walk_comp_decl_item(self, comp_decl_item)
     }
@@ -276,6 +304,15 @@ pub trait Visitor<'ast>: Sized {
         walk_call_expr(self, call_expr)
     }
 
+    fn visit_try_expr(&mut self, try_expr: &'ast TryExpr<'ast>) -> Self::Result {
+        walk_try_expr(self, try_expr)
+    }
+
+    #[allow(unused_variables)]
+    fn visit_intrinsic_expr(&mut self, intrinsic_expr: &'ast IntrinsicExpr<'ast>) -> Self::Result {
+        Self::default_result()
+    }
+
     fn visit_block_expr(&mut self, expr: &'ast BlockExpr<'ast>) -> Self::Result {
         self.visit_stmts(expr.stmts)
     }
@@ -361,6 +398,7 @@ pub fn walk_expr_without_block<'a, V>(
         ExprWithoutBlock::BreakExpr(break_expr) => visitor.visit_break_expr(break_expr),
         ExprWithoutBlock::ContinueExpr(continue_expr) => visitor.visit_continue_expr(continue_expr),
         ExprWithoutBlock::ReturnExpr(return_expr) => visitor.visit_return_expr(return_expr),
+        ExprWithoutBlock::DeferExpr(defer_expr) => visitor.visit_defer_expr(defer_expr),
     }
 }
 
@@ -382,6 +420,12 @@ pub fn walk_return_expr<'a, V>(visitor: &mut V, return_expr: &'a ReturnExpr<'a>)
     return_expr.value.map(|expr| visitor.visit_expr(expr)).unwrap_or(V::default_result())
 }
 
+pub fn walk_defer_expr<'a, V>(visitor: &mut V, defer_expr: &'a DeferExpr<'a>) -> V::Result
+    where V: Visitor<'a>
+{
+    visitor.visit_expr(defer_expr.expr)
+}
+
 pub fn walk_if_expr<'a, V>(visitor: &mut V, if_expr: &'a IfExpr<'a>) -> V::Result
     where V: Visitor<'a>
 {
@@ -451,6 +495,8 @@ pub fn walk_value_expr<'a, V>(visitor: &mut V, value_expr: ValueExpr<'a>) -> V::
         ValueExpr::ConstExpr(const_expr) => visitor.visit_const_expr(const_expr),
         ValueExpr::StructExpr(struct_expr) => visitor.visit_struct_expr(struct_expr),
         ValueExpr::CallExpr(call_expr) => visitor.visit_call_expr(call_expr),
+        ValueExpr::TryExpr(try_expr) => visitor.visit_try_expr(try_expr),
+        ValueExpr::IntrinsicExpr(intrinsic_expr) => visitor.visit_intrinsic_expr(intrinsic_expr),
     }
 }
 
@@ -468,7 +514,17 @@ pub fn walk_import_item<'a, V>(visitor: &mut V, import_item: &'a ImportItem<'a>)
     where V: Visitor<'a>
 {
     for import_item in import_item.import_items_path.iter() {
-        visitor.visit_path(*import_item);
+        visitor.visit_import_path(*import_item);
+    }
+
+    V::default_result()
+}
+
+pub fn walk_export_item<'a, V>(visitor: &mut V, export_item: &'a ExportItem<'a>) -> V::Result
+    where V: Visitor<'a>
+{
+    for ident_node in export_item.exported_items.iter() {
+        visitor.visit_ident_expr(ident_node);
     }
 
     V::default_result()
@@ -508,6 +564,12 @@ pub fn walk_call_expr<'a, V>(visitor: &mut V, call_expr: &'a CallExpr<'a>) -> V:
     V::default_result()
 }
 
+pub fn walk_try_expr<'a, V>(visitor: &mut V, try_expr: &'a TryExpr<'a>) -> V::Result
+    where V: Visitor<'a>
+{
+    visitor.visit_expr(try_expr.expr)
+}
+
 pub fn walk_tuple_expr<'a, V>(visitor: &mut V, tuple_expr: &'a TupleExpr<'a>) -> V::Result
     where V: Visitor<'a>
 {
@@ -627,6 +689,42 @@ pub fn walk_path<'a, V>(visitor: &mut V, path: Path<'a>) -> V::Result where V: V
     }
 }
 
+pub fn walk_import_path<'a, V>(visitor: &mut V, import_path: ImportPath<'a>) -> V::Result
+    where V: Visitor<'a>
+{
+    match import_path {
+        ImportPath::Path(path) => visitor.visit_path(path),
+        ImportPath::PathAlias(path_alias) => visitor.visit_path_alias(path_alias),
+        ImportPath::PathEverything(path_everything) => visitor.visit_path_everything(path_everything),
+        ImportPath::PathMultiple(path_multiple) => visitor.visit_path_multiple(path_multiple),
+    }
+}
+
+pub fn walk_path_alias<'a, V>(visitor: &mut V, path_alias: &'a PathAlias<'a>) -> V::Result
+    where V: Visitor<'a>
+{
+    visitor.visit_path(path_alias.path)
+}
+
+pub fn walk_path_everything<'a, V>(
+    visitor: &mut V,
+    path_everything: &'a PathEverything<'a>
+) -> V::Result
+    where V: Visitor<'a>
+{
+    visitor.visit_path(path_everything.lhs)
+}
+
+pub fn walk_path_multiple<'a, V>(visitor: &mut V, path_multiple: &'a PathMultiple<'a>) -> V::Result
+    where V: Visitor<'a>
+{
+    for import_path in path_multiple.paths.iter() {
+        visitor.visit_import_path(*import_path);
+    }
+
+    V::default_result()
+}
+
 pub fn walk_path_field<'a, V>(visitor: &mut V, path_field: &'a PathField<'a>) -> V::Result
     where V: Visitor<'a>
 {
@@ -10,11 +10,14 @@ use icfg::{
     Cfg,
     CfgVisitor,
     Const,
+    DropNode,
     Icfg,
     Operand,
+    PhiNode,
     PlaceKind,
     ReturnNode,
     TyCastNode,
+    UnreachableNode,
 };
 use op::{ ArithmeticOp, BinaryOp, ComparisonOp };
 use ir::{
@@ -35,7 +38,12 @@ use ir::{
 };
 use threadpool::ThreadPool;
 use threadpool_scope::scope_with;
-use std::{ fmt::{ Display, Write }, fs::File, process::Command, sync::Mutex };
+use std::{
+    fmt::{ Display, Write },
+    path::{ Path, PathBuf },
+    process::{ Command, ExitStatus },
+    sync::Mutex,
+};
 
 const INDENTATION: usize = 4;
 
@@ -142,14 +150,15 @@ impl CfgVisitor for CodeGenUnitHelper<'_> {
         self.place_to_ssa_id.insert(PlaceKind::TempId(call_node.result_place), next_ssa_id);
     }
 
-    fn visit_return_node(&mut self, return_node: &ReturnNode, cfg: &Cfg) -> Self::Result {
-        self.next_ssa_id += 1;
-    }
-
     fn visit_ty_cast_node(&mut self, ty_cast_node: &TyCastNode, cfg: &Cfg) -> Self::Result {
         let next_ssa_id = self.get_next_ssa_id();
         self.place_to_ssa_id.insert(PlaceKind::TempId(ty_cast_node.result_place), next_ssa_id);
     }
+
+    fn visit_phi_node(&mut self, phi_node: &PhiNode, _cfg: &Cfg) -> Self::Result {
+        let next_ssa_id = self.get_next_ssa_id();
+        self.place_to_ssa_id.insert(PlaceKind::TempId(phi_node.result_place), next_ssa_id);
+    }
 }
 
 pub enum LLVMSSA {
@@ -186,6 +195,8 @@ pub(crate) struct CodeGenUnit<'a> {
     buffer: String,
     place_to_ssa_id: FxHashMap<PlaceKind, usize>,
     basic_block_id_to_ssa_id: FxHashMap<BasicBlockId, usize>,
+    /// The result of the tail call of the current basic block and how the call is marked
+    tail_call: Option<(TempId, &'static str)>,
 }
 
 impl<'a> CodeGenUnit<'a> {
@@ -200,6 +211,7 @@ impl<'a> CodeGenUnit<'a> {
             buffer: String::with_capacity(2048),
             place_to_ssa_id,
             basic_block_id_to_ssa_id,
+            tail_call: None,
         }
     }
 
@@ -247,6 +259,42 @@ impl<'a> CodeGenUnit<'a> {
     }
 }
 
+/// Calls returning their result from the caller are marked with `tail`, which lets LLVM reuse the
+/// stack frame of the caller. It's only allowed when the callee can't access the stack of the
+/// caller, so every argument has to be passed by value. When the callee has the same signature
+/// as the caller, `musttail` guarantees it even without optimizations
+fn get_tail_call_marker(call_node: &CallNode, cfg: &Cfg) -> Option<&'static str> {
+    // `main` returns the exit code instead of the result
+    if matches!(cfg.cfg_fn_kind, CfgFnKind::Main(_)) {
+        return None;
+    }
+
+    let is_by_value = |ty: Ty| {
+        matches!(
+            ty,
+            Ty::PrimTy(PrimTy::Int(_) | PrimTy::Uint(_) | PrimTy::Float(_) | PrimTy::Bool)
+        )
+    };
+    let is_variadic = call_node.args_ty.iter().any(|ty| ty.is_variadic_args());
+    let args_are_by_value = call_node.args_ty
+        .iter()
+        .filter(|ty| !ty.is_variadic_args())
+        .all(|ty| is_by_value(*ty));
+    if !args_are_by_value || !(call_node.ret_ty.is_void() || is_by_value(call_node.ret_ty)) {
+        return None;
+    }
+
+    let has_same_signature =
+        !is_variadic &&
+        call_node.ret_ty == cfg.ret_ty &&
+        call_node.args_ty.iter().eq(cfg.args.iter().map(|(_, ty)| ty));
+    if has_same_signature && matches!(call_node.callee, Operand::Const(Const::FnPtr(_))) {
+        Some("musttail")
+    } else {
+        Some("tail")
+    }
+}
+
 fn get_llvm_ty(ty: Ty, resolved_information: &ResolvedInformation<'_>) -> String {
     match &ty {
         Ty::PrimTy(prim_ty) => {
@@ -305,13 +353,19 @@ impl CfgVisitor for CodeGenUnit<'_> {
 
     fn visit_cfg(&mut self, cfg: &Cfg) -> Self::Result {
         match cfg.cfg_fn_kind {
-            CfgFnKind::Main => writeln!(self.buffer, "define i32 @main() {{")?,
-            CfgFnKind::Fn(def_id) => {
+            CfgFnKind::Main(_) => writeln!(self.buffer, "define i32 @main() {{")?,
+            CfgFnKind::Fn(_) | CfgFnKind::DropGlue(_) => {
+                let fn_name = match cfg.cfg_fn_kind {
+                    CfgFnKind::Fn(def_id) => def_id.display_as_fn(),
+                    CfgFnKind::DropGlue(ty) => ty.display_as_drop_fn(),
+                    CfgFnKind::Main(_) => unreachable!(),
+                };
+
                 write!(
                     self.buffer,
                     "define {} {}(",
                     get_llvm_ty(cfg.ret_ty, self.resolved_information),
-                    def_id.display_as_fn()
+                    fn_name
                 )?;
 
                 for (i, (temp_id, arg_ty)) in cfg.args.iter().enumerate() {
@@ -339,13 +393,6 @@ impl CfgVisitor for CodeGenUnit<'_> {
         writeln!(self.buffer, "{}br label %{}", " ".repeat(INDENTATION), first_bb_id)?;
 
         walk_basic_blocks(self, cfg)?;
-        if cfg.cfg_fn_kind == CfgFnKind::Main {
-            writeln!(self.buffer, "{}ret i32 0", " ".repeat(INDENTATION))?;
-        } else if cfg.ret_ty.is_void() {
-            writeln!(self.buffer, "{}ret void", " ".repeat(INDENTATION))?;
-        } else {
-            writeln!(self.buffer, "{}unreachable", " ".repeat(INDENTATION))?;
-        }
         writeln!(self.buffer, "}}")
     }
 
@@ -383,6 +430,12 @@ impl CfgVisitor for CodeGenUnit<'_> {
         let bb_id = self.get_bb_id(&basic_block.basic_block_id);
         writeln!(self.buffer, "{}:", bb_id)?;
 
+        self.tail_call = basic_block
+            .get_tail_call()
+            .and_then(|call_node| {
+                get_tail_call_marker(&call_node, cfg).map(|marker| (call_node.result_place, marker))
+            });
+
         walk_basic_block(self, basic_block, cfg)
     }
 
@@ -457,6 +510,60 @@ impl CfgVisitor for CodeGenUnit<'_> {
         )
     }
 
+    fn visit_switch_node(&mut self, switch_node: &icfg::SwitchNode, _cfg: &Cfg) -> Self::Result {
+        let value = self.get_llvm_operand(&switch_node.value);
+        let default = self.get_bb_id(&switch_node.default);
+        let ty = get_llvm_ty(switch_node.ty, self.resolved_information);
+
+        write!(
+            self.buffer,
+            "{}switch {} {}, label %{} [",
+            " ".repeat(INDENTATION),
+            ty,
+            value,
+            default
+        )?;
+        for (case, branch) in switch_node.cases.iter() {
+            write!(
+                self.buffer,
+                "\n{}{} {}, label %{}",
+                " ".repeat(INDENTATION * 2),
+                ty,
+                case,
+                self.get_bb_id(branch)
+            )?;
+        }
+        writeln!(self.buffer, "\n{}]", " ".repeat(INDENTATION))
+    }
+
+    fn visit_unreachable_node(
+        &mut self,
+        _unreachable_node: &UnreachableNode,
+        _cfg: &Cfg
+    ) -> Self::Result {
+        writeln!(self.buffer, "{}unreachable", " ".repeat(INDENTATION))
+    }
+
+    fn visit_phi_node(&mut self, phi_node: &PhiNode, _cfg: &Cfg) -> Self::Result {
+        let ssa_id = self.get_ssa_id_from_place(&PlaceKind::TempId(phi_node.result_place));
+        let incoming = phi_node.incoming
+            .iter()
+            .map(|(operand, bb_id)| {
+                format!("[ {}, %{} ]", self.get_llvm_operand(operand), self.get_bb_id(bb_id))
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        writeln!(
+            self.buffer,
+            "{}{} = phi {} {}",
+            " ".repeat(INDENTATION),
+            ssa_id,
+            get_llvm_ty(phi_node.ty, self.resolved_information),
+            incoming
+        )
+    }
+
     fn visit_load_node(&mut self, load_node: &icfg::LoadNode, cfg: &Cfg) -> Self::Result {
         let ssa_id = self.get_ssa_id_from_place(&PlaceKind::TempId(load_node.result_place));
         let var_place = self.get_ssa_id_from_place(&load_node.load_place);
@@ -502,6 +609,11 @@ impl CfgVisitor for CodeGenUnit<'_> {
     }
 
     fn visit_return_node(&mut self, return_node: &ReturnNode, cfg: &Cfg) -> Self::Result {
+        // `main` returns the exit code of the program
+        if matches!(cfg.cfg_fn_kind, CfgFnKind::Main(_)) {
+            return writeln!(self.buffer, "{}ret i32 0", " ".repeat(INDENTATION));
+        }
+
         match return_node.ret_ty {
             VOID_TY => writeln!(self.buffer, "{}ret void", " ".repeat(INDENTATION)),
             ty => {
@@ -527,6 +639,11 @@ impl CfgVisitor for CodeGenUnit<'_> {
             write!(self.buffer, "{} = ", ssa_id)?;
         }
 
+        if let Some((_, marker)) = self.tail_call.filter(|(temp_id, _)| {
+            *temp_id == call_node.result_place
+        }) {
+            write!(self.buffer, "{} ", marker)?;
+        }
         write!(self.buffer, "call {} ", get_llvm_ty(call_node.ret_ty, self.resolved_information))?;
 
         write!(self.buffer, "(")?;
@@ -568,6 +685,22 @@ impl CfgVisitor for CodeGenUnit<'_> {
         writeln!(self.buffer, ")")
     }
 
+    fn visit_drop_node(&mut self, drop_node: &DropNode, _cfg: &Cfg) -> Self::Result {
+        let drop_flag = match drop_node.drop_flag {
+            Some(temp_id) => self.get_ssa_id_from_place(&PlaceKind::TempId(temp_id)).to_string(),
+            None => "1".to_string(),
+        };
+
+        writeln!(
+            self.buffer,
+            "{}call void {}(ptr noundef {}, i8 noundef {})",
+            " ".repeat(INDENTATION),
+            drop_node.ty.display_as_drop_fn(),
+            self.get_ssa_id_from_place(&drop_node.place),
+            drop_flag
+        )
+    }
+
     fn visit_ty_cast_node(&mut self, ty_cast_node: &TyCastNode, cfg: &Cfg) -> Self::Result {
         writeln!(
             self.buffer,
@@ -582,6 +715,84 @@ impl CfgVisitor for CodeGenUnit<'_> {
     }
 }
 
+/// How the generated LLVM IR is compiled into an executable
+#[derive(Debug, Clone)]
+pub struct BackendOptions {
+    /// The C compiler used to compile and link the LLVM IR, which has to accept `.ll` files
+    pub cc: String,
+    /// Passed to the C compiler as `-fuse-ld=<linker>` (e.g. `lld`)
+    pub linker: Option<String>,
+}
+
+impl Default for BackendOptions {
+    fn default() -> Self {
+        Self { cc: "clang".to_string(), linker: None }
+    }
+}
+
+#[derive(Debug)]
+pub enum BackendError {
+    /// The LLVM IR couldn't be written
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// The C compiler couldn't be started (e.g. it isn't installed)
+    CcNotFound {
+        cc: String,
+        error: std::io::Error,
+    },
+    /// The C compiler rejected the LLVM IR or failed to link it
+    CcFailed {
+        cc: String,
+        ll_file: PathBuf,
+        status: ExitStatus,
+        stderr: String,
+    },
+}
+
+impl Display for BackendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "Couldn't write `{}`: {}", path.display(), error),
+            Self::CcNotFound { cc, error } => {
+                write!(
+                    f,
+                    "Couldn't run the C compiler `{}` ({}). Choose another one with `--cc` or \
+                     `VISKUM_CC`",
+                    cc,
+                    error
+                )
+            }
+            Self::CcFailed { cc, ll_file, status, stderr } => {
+                if stderr.contains("linker command failed") {
+                    write!(f, "Linking the LLVM IR in `{}` failed", ll_file.display())?;
+                } else {
+                    write!(f, "Compiling the LLVM IR in `{}` failed", ll_file.display())?;
+                }
+                match status.code() {
+                    Some(code) => write!(f, " (`{}` exited with code {})", cc, code)?,
+                    None => write!(f, " (`{}` was terminated by a signal)", cc)?,
+                }
+                if !stderr.trim().is_empty() {
+                    write!(f, ":\n{}", stderr.trim_end())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Relative paths starting with a `-` (e.g. `-o -out`) would be taken as options by the C
+/// compiler, so they are given as `./-out`
+fn path_as_cc_arg(path: &Path) -> PathBuf {
+    if path.as_os_str().as_encoded_bytes().starts_with(b"-") {
+        Path::new(".").join(path)
+    } else {
+        path.to_path_buf()
+    }
+}
+
 pub struct CodeGen<'icfg> {
     icfg: &'icfg Icfg<'icfg>,
     threadpool: &'icfg ThreadPool,
@@ -592,7 +803,13 @@ impl<'icfg> CodeGen<'icfg> {
         Self { icfg, threadpool }
     }
 
-    pub fn gen_code(&self, file_name: &str) {
+    /// Writes the LLVM IR next to the executable (`output` with `.ll` appended) and compiles it
+    /// into the executable at `output`
+    pub fn gen_code(
+        &self,
+        output: &Path,
+        backend_options: &BackendOptions
+    ) -> Result<(), BackendError> {
         let now = std::time::Instant::now();
 
         let buffer = Mutex::new(String::with_capacity(65536));
@@ -669,26 +886,47 @@ impl<'icfg> CodeGen<'icfg> {
             }
         });
 
-        let file_name_with_extension = "./viskum/dist/main.ll".to_string();
+        let mut ll_file = output.as_os_str().to_owned();
+        ll_file.push(".ll");
+        let ll_file = PathBuf::from(ll_file);
         {
-            // Ensure directory exists
-            std::fs::create_dir_all("./viskum/dist").expect("Error creating directory");
-
-            let mut file = File::create(&file_name_with_extension).expect("Error creating file");
-            use std::io::Write;
-            file.write_all(buffer.lock().unwrap().as_bytes()).expect("Error writing to file");
+            let write_ll_file = || {
+                // Ensure directory exists
+                if let Some(output_dir) = output.parent() {
+                    std::fs::create_dir_all(output_dir)?;
+                }
+                std::fs::write(&ll_file, buffer.lock().unwrap().as_bytes())
+            };
+            write_ll_file().map_err(|error| BackendError::Io { path: ll_file.clone(), error })?;
         }
 
         println!("Code generation took: {:?}", now.elapsed());
 
-        let result = Command::new("clang")
-            .arg("-O0")
-            .arg(&file_name_with_extension)
-            .arg("-o")
-            .arg("./viskum/dist/main")
-            .output()
-            .expect("Failed to execute clang");
+        let mut command = Command::new(&backend_options.cc);
+        command.arg("-O0").arg(path_as_cc_arg(&ll_file)).arg("-o").arg(path_as_cc_arg(output));
+        if let Some(linker) = &backend_options.linker {
+            command.arg(format!("-fuse-ld={}", linker));
+        }
+
+        let result = command.output().map_err(|error| BackendError::CcNotFound {
+            cc: backend_options.cc.clone(),
+            error,
+        })?;
+        let stderr = String::from_utf8_lossy(&result.stderr).to_string();
+        if !result.status.success() {
+            return Err(BackendError::CcFailed {
+                cc: backend_options.cc.clone(),
+                ll_file,
+                status: result.status,
+                stderr,
+            });
+        }
 
-        println!("{}", String::from_utf8(result.stderr).expect("Error converting to string"));
+        // Warnings of the C compiler
+        if !stderr.is_empty() {
+            println!("{}", stderr);
+        }
+
+        Ok(())
     }
 }
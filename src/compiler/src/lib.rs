@@ -1,22 +1,59 @@
 use std::{ path::{ self, PathBuf }, sync::Mutex };
 
-use ast::{ Ast, AstArena, AstArenaObject, AstUnvalidated, VisitAst };
+use ast::{
+    walk_field_expr,
+    Ast,
+    AstArena,
+    AstArenaObject,
+    AstUnvalidated,
+    Expr,
+    ExprWithoutBlock,
+    FieldExpr,
+    IdentNode,
+    ImportPath,
+    ItemStmt,
+    Path,
+    PathField,
+    PlaceExpr,
+    Stmt,
+    VisitAst,
+    Visitor,
+};
 use bumpalo::Bump;
-use codegen::CodeGen;
+use codegen::{ BackendOptions, CodeGen };
 
-use diagnostics::set_mode_id_to_file_path;
-use icfg::Icfg;
+use diagnostics::{ set_mode_id_to_file_path, Diagnostic, ErrorKind };
+use icfg::{ Icfg, IcfgDotExporter };
 use icfg_builder::IcfgBuilder;
-use ir::ModId;
+use ir::{ ModId, PkgId, ResolvedInformation, Symbol };
 use parser::Parser;
-use resolver::Resolver;
+use resolver::{ ResolvedFunctions, ResolvedPkg, Resolver };
 use threadpool::ThreadPool;
 use threadpool_scope::scope_with;
 
 pub struct Compiler {
     entry_dir: PathBuf,
-    entry_file: PathBuf,
     threadpool: ThreadPool,
+    /// Promotes variables to SSA values before codegen (`--mem2reg`)
+    mem2reg: bool,
+    /// Inlines small functions and functions marked with `@inline` (disabled by `--no-inline`)
+    inline: bool,
+    /// Verifies the ICFG after it's built and before codegen
+    /// (`--verify`, always on in debug builds)
+    verify: bool,
+    /// Runs the ICFG with the interpreter instead of generating code (`--interpret`)
+    interpret: bool,
+    /// Writes the control flow graphs as a Graphviz DOT file (`--dot`)
+    dot: bool,
+    /// Writes the call graph as a Graphviz DOT file (`--dot-call-graph`)
+    dot_call_graph: bool,
+    /// Directory of the generated files (`--out-dir`, by default `viskum/dist` in the current
+    /// directory)
+    out_dir: PathBuf,
+    /// Path of the executable (`-o`, by default named after the entry package in `out_dir`)
+    output: Option<PathBuf>,
+    /// The C compiler (`--cc` or `VISKUM_CC`) and linker (`--linker` or `VISKUM_LINKER`)
+    backend_options: BackendOptions,
 }
 
 impl Default for Compiler {
@@ -37,8 +74,78 @@ impl Compiler {
 
         let threadpool = ThreadPool::new(workers_amount);
 
-        let mut input_file = if let Some(input_file) = args.next() {
-            path::Path::new(&input_file).to_path_buf()
+        let mut input_file = None;
+        let mut mem2reg = false;
+        let mut inline = true;
+        let mut verify = cfg!(debug_assertions);
+        let mut interpret = false;
+        let mut dot = false;
+        let mut dot_call_graph = false;
+        let mut out_dir = None;
+        let mut output = None;
+        let mut backend_options = BackendOptions::default();
+        if let Ok(cc) = std::env::var("VISKUM_CC") {
+            backend_options.cc = cc;
+        }
+        if let Ok(linker) = std::env::var("VISKUM_LINKER") {
+            backend_options.linker = Some(linker);
+        }
+        while let Some(arg) = args.next() {
+            // Options with a value can also be given as `--out-dir=<path>`
+            let (option, mut value) = match arg.split_once('=') {
+                Some((option, value)) if VALUE_OPTIONS.contains(&option) => {
+                    (option.to_string(), Some(value.to_string()))
+                }
+                _ => (arg, None),
+            };
+            let mut expect_value = || {
+                expect_arg_value(&option, value.take().or_else(|| args.next()))
+            };
+
+            match option.as_str() {
+                "--mem2reg" => {
+                    mem2reg = true;
+                }
+                "--no-inline" => {
+                    inline = false;
+                }
+                "--verify" => {
+                    verify = true;
+                }
+                "--interpret" => {
+                    interpret = true;
+                }
+                "--dot" => {
+                    dot = true;
+                }
+                "--dot-call-graph" => {
+                    dot_call_graph = true;
+                }
+                "--out-dir" => {
+                    out_dir = Some(PathBuf::from(expect_value()));
+                }
+                "-o" => {
+                    output = Some(PathBuf::from(expect_value()));
+                }
+                "--cc" => {
+                    backend_options.cc = expect_value();
+                }
+                "--linker" => {
+                    backend_options.linker = Some(expect_value());
+                }
+                _ if option.starts_with("--") => {
+                    println!("Unknown option: {}", option);
+                    std::process::exit(1);
+                }
+                _ if input_file.is_none() => {
+                    input_file = Some(path::Path::new(&option).to_path_buf());
+                }
+                _ => {}
+            }
+        }
+
+        let mut input_file = if let Some(input_file) = input_file {
+            input_file
         } else {
             println!("Missing file input");
             std::process::exit(1);
@@ -54,12 +161,28 @@ impl Compiler {
             std::process::exit(1);
         }
 
-        // Get last part of the path
-        let entry_file = input_file.clone();
+        // The entry package is the directory of the file (e.g. `.` for `main.vs`)
         input_file.pop();
-        let entry_dir = input_file;
-
-        Self { entry_file, entry_dir, threadpool }
+        let entry_dir = if input_file.as_os_str().is_empty() {
+            PathBuf::from(".")
+        } else {
+            input_file
+        };
+        let out_dir = out_dir.unwrap_or_else(|| PathBuf::from("./viskum/dist"));
+
+        Self {
+            entry_dir,
+            threadpool,
+            mem2reg,
+            inline,
+            verify,
+            interpret,
+            dot,
+            dot_call_graph,
+            out_dir,
+            output,
+            backend_options,
+        }
     }
 
     pub fn compile_entry(&self) {
@@ -72,53 +195,236 @@ impl Compiler {
 
         println!("Viskum compilation took: {:?}", now.elapsed());
 
+        let artifact_name = self.get_entry_pkg_name();
+        if self.dot {
+            let dot = IcfgDotExporter::new(&icfg).export_cfgs();
+            self.write_dot_file(&format!("{}.dot", artifact_name), dot);
+        }
+        if self.dot_call_graph {
+            let dot = IcfgDotExporter::new(&icfg).export_call_graph();
+            self.write_dot_file(&format!("{}.call_graph.dot", artifact_name), dot);
+        }
+
+        if self.interpret {
+            self.interpret_icfg(&icfg);
+        }
+
         let now = std::time::Instant::now();
-        CodeGen::new(&icfg, &self.threadpool).gen_code(
-            self.entry_file.as_os_str().to_str().unwrap()
-        );
+        let output = self.output.clone().unwrap_or_else(|| self.out_dir.join(&artifact_name));
+        let result = CodeGen::new(&icfg, &self.threadpool).gen_code(&output, &self.backend_options);
+        if let Err(error) = result {
+            diagnostics::print_error(&error.to_string());
+            std::process::exit(1);
+        }
         println!("LLVM compilation took: {:?}", now.elapsed());
     }
 
-    pub fn parse_all_files_in_package<'ast>(
-        &self,
-        ast_arena: &'ast AstArena
-    ) -> (Vec<(Ast<'ast, AstUnvalidated>, String, ModId)>, ModId) {
-        let files = match std::fs::read_dir(&self.entry_dir) {
-            Ok(files) => {
-                files
-                    .filter_map(|file| {
-                        let file = file.unwrap();
-                        let path = file.path();
-                        if let Some(ext) = path.extension() {
-                            if ext == "vs" { Some(path) } else { None }
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<_>>()
-            }
-            Err(e) => {
-                eprintln!("Error reading directory: {}", e);
+    /// Parses the entry package and all packages it (transitively) imports
+    ///
+    /// The packages are returned in the order they should be resolved in,
+    /// so a package always comes after the packages it imports
+    pub fn parse_all_packages<'ast>(&self, ast_arena: &'ast AstArena) -> Vec<ParsedPkg<'ast>> {
+        let entry_pkg_name = self.get_entry_pkg_name();
+        let mut module_refs = Vec::new();
+
+        loop {
+            let mut pkg_graph = PkgGraph {
+                parsed_pkgs: Vec::new(),
+                pkg_stack: Vec::new(),
+                next_mod_id: 0,
+                module_refs,
+                skipped_modules: Vec::new(),
+            };
+
+            self.parse_pkg_and_dependencies(
+                Symbol::new(&entry_pkg_name),
+                &self.entry_dir,
+                ast_arena,
+                &mut pkg_graph
+            );
+
+            if diagnostics::has_error() {
+                diagnostics::print_diagnostics();
                 std::process::exit(1);
             }
-        };
 
-        let entry_file_id = files
+            // A module can first be referenced by a package parsed after the package of the module
+            // (e.g. `import other.net.Socket`). Parsing is then started over with the references
+            // found so far, so the module's imports are parsed before its package
+            let has_missed_modules = pkg_graph.skipped_modules
+                .iter()
+                .any(|(pkg_symbol, module_path)| {
+                    pkg_graph.is_module_referenced(*pkg_symbol, module_path)
+                });
+            if !has_missed_modules {
+                return pkg_graph.parsed_pkgs;
+            }
+            module_refs = pkg_graph.module_refs;
+        }
+    }
+
+    fn parse_pkg_and_dependencies<'ast>(
+        &self,
+        pkg_symbol: Symbol,
+        pkg_dir: &path::Path,
+        ast_arena: &'ast AstArena,
+        pkg_graph: &mut PkgGraph<'ast>
+    ) {
+        let (asts, module_paths) = self.parse_all_files_in_package(
+            pkg_symbol,
+            pkg_dir,
+            ast_arena,
+            pkg_graph
+        );
+        pkg_graph.pkg_stack.push(pkg_symbol);
+
+        let mut diagnostics = Vec::new();
+
+        let pkg_adt_symbols = asts
             .iter()
-            .enumerate()
-            .find_map(|(i, file)| {
-                if file == &self.entry_file { Some(ModId(i as u32)) } else { None }
-            })
-            .expect("Cannot find entry file in directory");
+            .flat_map(|(ast, _, _)| get_adt_symbols(ast))
+            .collect::<Vec<_>>();
 
-        let asts = Mutex::new(Vec::with_capacity(files.len()));
+        for (ast, _, mod_id) in asts.iter() {
+            for pkg_ident_node in get_imported_pkgs(ast) {
+                let imported_pkg_symbol = Symbol::from_node_id(pkg_ident_node.ast_node_id);
 
-        scope_with(&self.threadpool, |s| {
-            let mut next_mod_id: u32 = 0;
+                // e.g. `Option` in `import Option.*` is an enum in this package
+                if pkg_adt_symbols.contains(&imported_pkg_symbol) {
+                    continue;
+                }
+
+                if pkg_graph.pkg_stack.contains(&imported_pkg_symbol) {
+                    diagnostics.push(
+                        Diagnostic::new_error(
+                            ErrorKind::CyclicPackageImport { pkg_symbol: imported_pkg_symbol },
+                            pkg_ident_node.span,
+                            *mod_id
+                        )
+                    );
+                    continue;
+                }
 
+                if pkg_graph.parsed_pkgs.iter().any(|x| x.pkg_symbol == imported_pkg_symbol) {
+                    continue;
+                }
+
+                let imported_pkg_dir = self.get_pkgs_dir().join(imported_pkg_symbol.get());
+                if imported_pkg_dir.is_dir() {
+                    self.parse_pkg_and_dependencies(
+                        imported_pkg_symbol,
+                        &imported_pkg_dir,
+                        ast_arena,
+                        pkg_graph
+                    );
+                } else {
+                    diagnostics.push(
+                        Diagnostic::new_error(
+                            ErrorKind::UnresolvedPackage { pkg_symbol: imported_pkg_symbol },
+                            pkg_ident_node.span,
+                            *mod_id
+                        )
+                    );
+                }
+            }
+        }
+
+        if !diagnostics.is_empty() {
+            diagnostics::report_diagnostics(diagnostics);
+        }
+
+        pkg_graph.pkg_stack.pop();
+        pkg_graph.parsed_pkgs.push(ParsedPkg { pkg_symbol, asts, module_paths });
+    }
+
+    /// Packages are directories placed next to the entry package
+    fn get_pkgs_dir(&self) -> PathBuf {
+        self.entry_dir.join("..")
+    }
+
+    /// Parses all files in a package, including the files in subdirectories (nested modules).
+    /// A subdirectory is only parsed when a path goes through it (e.g. `pkg.net.Socket`), so
+    /// unrelated directories next to the files of a package are left alone
+    ///
+    /// Also returns the module path of each file e.g. `[net, http]` for a file in `net/http`
+    fn parse_all_files_in_package<'ast>(
+        &self,
+        pkg_symbol: Symbol,
+        pkg_dir: &path::Path,
+        ast_arena: &'ast AstArena,
+        pkg_graph: &mut PkgGraph<'ast>
+    ) -> (Vec<ParsedFile<'ast>>, Vec<(ModId, Vec<Symbol>)>) {
+        let mut asts = Vec::new();
+        let mut module_paths = Vec::new();
+        let mut module_dirs = vec![(pkg_dir.to_path_buf(), Vec::new())];
+
+        loop {
+            let (referenced_dirs, skipped_dirs) = module_dirs
+                .into_iter()
+                .partition::<Vec<_>, _>(|(_, module_path)| {
+                    module_path.is_empty() ||
+                        pkg_graph.is_module_referenced(pkg_symbol, module_path)
+                });
+            module_dirs = skipped_dirs;
+
+            if referenced_dirs.is_empty() {
+                break;
+            }
+
+            let parsed_count = asts.len();
+            for (dir, module_path) in referenced_dirs {
+                let (files, subdirs) = read_module_dir(&dir);
+                self.parse_module_files(
+                    files,
+                    &module_path,
+                    ast_arena,
+                    &mut pkg_graph.next_mod_id,
+                    &mut asts,
+                    &mut module_paths
+                );
+
+                for (subdir, dir_symbol) in subdirs {
+                    let mut subdir_module_path = module_path.clone();
+                    subdir_module_path.push(dir_symbol);
+                    module_dirs.push((subdir, subdir_module_path));
+                }
+            }
+
+            let mut module_refs_collector = ModuleRefsCollector {
+                pkg_symbol,
+                module_refs: &mut pkg_graph.module_refs,
+            };
+            for (ast, _, _) in asts[parsed_count..].iter() {
+                module_refs_collector.visit_stmts(ast.main_scope.stmts);
+            }
+        }
+
+        pkg_graph.skipped_modules.extend(
+            module_dirs.into_iter().map(|(_, module_path)| (pkg_symbol, module_path))
+        );
+
+        (asts, module_paths)
+    }
+
+    /// Parses the files directly inside the directory of a module
+    fn parse_module_files<'ast>(
+        &self,
+        files: Vec<PathBuf>,
+        module_path: &[Symbol],
+        ast_arena: &'ast AstArena,
+        next_mod_id: &mut u32,
+        asts: &mut Vec<ParsedFile<'ast>>,
+        module_paths: &mut Vec<(ModId, Vec<Symbol>)>
+    ) {
+        let module_asts = Mutex::new(Vec::with_capacity(files.len()));
+
+        scope_with(&self.threadpool, |s| {
             for file in files {
-                let mod_id = ModId(next_mod_id);
-                let asts_ref = &asts;
+                // Mod ids are unique across all packages, which makes all node ids and def ids unique too
+                let mod_id = ModId(*next_mod_id);
+                let asts_ref = &module_asts;
+
+                module_paths.push((mod_id, module_path.to_vec()));
 
                 set_mode_id_to_file_path(mod_id, file.clone());
 
@@ -129,11 +435,11 @@ impl Compiler {
                     asts_ref.lock().unwrap().push((ast, file_content, mod_id));
                 });
 
-                next_mod_id += 1;
+                *next_mod_id += 1;
             }
         });
 
-        (asts.into_inner().unwrap(), entry_file_id)
+        asts.extend(module_asts.into_inner().unwrap());
     }
 
     fn parse_file<'ast>(
@@ -166,147 +472,497 @@ impl Compiler {
 
         let (resolved_functions, resolved_information) = {
             let now = std::time::Instant::now();
-            let (asts, _) = self.parse_all_files_in_package(&ast_arena);
+            let parsed_pkgs = self.parse_all_packages(&ast_arena);
             println!("Parsing took: {:?}", now.elapsed());
 
-            let now = std::time::Instant::now();
+            let entry_pkg_symbol = parsed_pkgs.last().expect("Expected entry package").pkg_symbol;
 
-            let total_nodes = asts
-                .iter()
-                .map(|(ast, _, _)| ast.metadata.node_count)
-                .sum::<usize>();
+            let mut resolved_pkgs = Vec::with_capacity(parsed_pkgs.len());
+            let mut resolved_functions = ResolvedFunctions::default();
+            let mut resolved_information = ResolvedInformation::default();
 
-            let total_def_count = asts
-                .iter()
-                .map(|(ast, _, _)| ast.metadata.def_count)
-                .sum::<usize>();
+            for (i, parsed_pkg) in parsed_pkgs.into_iter().enumerate() {
+                let pkg_symbol = parsed_pkg.pkg_symbol;
+                let (pkg_functions, pkg_information, resolved_pkg) = self.resolve_pkg(
+                    arena,
+                    PkgId(i as u32),
+                    parsed_pkg,
+                    &resolved_pkgs
+                );
 
-            let mut resolver = Resolver::new(
-                arena,
-                total_nodes,
-                total_def_count /*, global_mems */
-            );
+                // Only the entry package decides the entry point of the program
+                if pkg_symbol == entry_pkg_symbol {
+                    resolved_functions.main_fn = pkg_functions.main_fn;
+                }
+                resolved_functions.pending_functions.extend(pkg_functions.pending_functions);
+                resolved_information.extend(pkg_information);
+                resolved_pkgs.push(resolved_pkg);
+            }
 
-            println!("Setting up resolver took: {:?}", now.elapsed());
-            let now = std::time::Instant::now();
+            (resolved_functions, resolved_information)
+        };
+
+        let now = std::time::Instant::now();
+
+        let icfg_builder = IcfgBuilder::new(resolved_information, &self.threadpool);
+        let mut icfg = icfg_builder.build(resolved_functions);
+        self.verify_icfg(&icfg, "building");
+
+        // Errors of the packages have already stopped the compilation, so only warnings
+        // and errors found in the ICFG are left
+        icfg.check_moves();
+        icfg.analyze();
+        icfg.check_null_derefs();
+        if diagnostics::has_error() {
+            diagnostics::print_diagnostics();
+            std::process::exit(1);
+        }
 
-            let asts_count = asts.len();
+        icfg.elaborate_drops();
+        icfg.fold_constants();
+        icfg.eliminate_dead_functions();
+        icfg.eliminate_tail_calls();
+        if self.inline {
+            icfg.inline_functions();
+        }
+        if self.mem2reg {
+            icfg.mem2reg();
+        }
+        self.verify_icfg(&icfg, "optimizing");
+        if diagnostics::has_diagnostics() {
+            diagnostics::print_diagnostics();
+        }
 
-            let ast_pre_resolve_visit_results = {
-                let ast_visit_results = Mutex::new(Vec::with_capacity(asts_count));
-                let ast_visit_results_ref = &ast_visit_results;
+        println!("Building ICFG took: {:?}", now.elapsed());
+        icfg
+    }
 
-                let global_visit_results = Mutex::new(Vec::with_capacity(asts_count));
-                let global_visit_results_ref = &global_visit_results;
+    /// The name of the directory of the entry package, which the generated files are named after
+    fn get_entry_pkg_name(&self) -> String {
+        std::fs
+            ::canonicalize(&self.entry_dir)
+            .ok()
+            .and_then(|entry_dir| entry_dir.file_name().map(|x| x.to_string_lossy().to_string()))
+            .unwrap_or_else(|| "main".to_string())
+    }
 
-                scope_with(&self.threadpool, |s| {
-                    let resolver_handle = &resolver;
-                    for (ast, _, _) in asts {
-                        s.execute(move || {
-                            let (ast, global_visit_result, local_visit_result) = ast
-                                .into_visitor(resolver_handle)
-                                .visit();
+    /// Writes a DOT graph next to the generated code
+    fn write_dot_file(&self, file_name: &str, dot: String) {
+        let path = self.out_dir.join(file_name);
+        std::fs::create_dir_all(&self.out_dir).expect("Error creating directory");
+        std::fs::write(&path, dot).expect("Error writing DOT file");
+        println!("Wrote DOT graph to: {}", path.display());
+    }
 
-                            global_visit_results_ref.lock().unwrap().push(global_visit_result);
-                            ast_visit_results_ref.lock().unwrap().push((ast, local_visit_result));
-                        });
-                    }
-                });
+    /// Runs the program and exits with its exit code
+    fn interpret_icfg(&self, icfg: &Icfg) -> ! {
+        match icfg.interpret(&mut std::io::stdout()) {
+            Ok(exit_code) => std::process::exit(exit_code),
+            Err(error) => {
+                println!("Interpreter error: {}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Exits if the ICFG is malformed, since codegen would either panic or produce invalid LLVM IR
+    fn verify_icfg(&self, icfg: &Icfg, stage: &str) {
+        if !self.verify {
+            return;
+        }
 
-                // let merged_results = global_visit_results
-                //     .into_inner()
-                //     .unwrap()
-                //     .into_iter()
-                //     .fold(MergedResults::new(), |mut acc, result| {
-                //         acc.merge(result);
-                //         acc
-                //     });
-
-                for global_visit_result in global_visit_results.into_inner().unwrap() {
-                    resolver.use_visit_result_from_pre_resolve(global_visit_result);
+        if let Err(errors) = icfg.verify() {
+            println!("ICFG verification failed after {}:", stage);
+            for error in errors {
+                println!("    {}", error);
+            }
+            std::process::exit(1);
+        }
+    }
+
+    /// Resolves and type checks a single package with its own `Resolver`,
+    /// where `resolved_pkgs` are the packages it's able to import from
+    fn resolve_pkg<'a, 'ast>(
+        &self,
+        arena: &'a Bump,
+        pkg_id: PkgId,
+        parsed_pkg: ParsedPkg<'ast>,
+        resolved_pkgs: &[ResolvedPkg<'a>]
+    ) -> (ResolvedFunctions<'ast>, ResolvedInformation<'a>, ResolvedPkg<'a>) where 'a: 'ast {
+        let ParsedPkg { pkg_symbol, asts, module_paths } = parsed_pkg;
+
+        let now = std::time::Instant::now();
+
+        let total_nodes = asts
+            .iter()
+            .map(|(ast, _, _)| ast.metadata.node_count)
+            .sum::<usize>();
+
+        let total_def_count = asts
+            .iter()
+            .map(|(ast, _, _)| ast.metadata.def_count)
+            .sum::<usize>();
+
+        let mut resolver = Resolver::new(
+            arena,
+            pkg_id,
+            total_nodes,
+            total_def_count /*, global_mems */
+        );
+
+        for (mod_id, module_path) in module_paths {
+            resolver.add_mod_to_module(mod_id, &module_path);
+        }
+
+        for resolved_pkg in resolved_pkgs {
+            resolver.use_resolved_pkg(resolved_pkg);
+        }
+
+        println!("Setting up resolver took: {:?}", now.elapsed());
+        let now = std::time::Instant::now();
+
+        let asts_count = asts.len();
+
+        let ast_pre_resolve_visit_results = {
+            let ast_visit_results = Mutex::new(Vec::with_capacity(asts_count));
+            let ast_visit_results_ref = &ast_visit_results;
+
+            let global_visit_results = Mutex::new(Vec::with_capacity(asts_count));
+            let global_visit_results_ref = &global_visit_results;
+
+            scope_with(&self.threadpool, |s| {
+                let resolver_handle = &resolver;
+                for (ast, _, _) in asts {
+                    s.execute(move || {
+                        let (ast, global_visit_result, local_visit_result) = ast
+                            .into_visitor(resolver_handle)
+                            .visit();
+
+                        global_visit_results_ref.lock().unwrap().push(global_visit_result);
+                        ast_visit_results_ref.lock().unwrap().push((ast, local_visit_result));
+                    });
                 }
+            });
+
+            // let merged_results = global_visit_results
+            //     .into_inner()
+            //     .unwrap()
+            //     .into_iter()
+            //     .fold(MergedResults::new(), |mut acc, result| {
+            //         acc.merge(result);
+            //         acc
+            //     });
+
+            for global_visit_result in global_visit_results.into_inner().unwrap() {
+                resolver.use_visit_result_from_pre_resolve(global_visit_result);
+            }
 
-                ast_visit_results.into_inner().unwrap()
-            };
+            ast_visit_results.into_inner().unwrap()
+        };
 
-            println!("Pre-resolving took: {:?}", now.elapsed());
-            let now = std::time::Instant::now();
+        println!("Pre-resolving took: {:?}", now.elapsed());
+        let now = std::time::Instant::now();
 
-            let ast_resolve_visit_results = {
-                let ast_visit_results = Mutex::new(Vec::with_capacity(asts_count));
-                let ast_visit_results_ref = &ast_visit_results;
-
-                let global_visit_results = Mutex::new(Vec::with_capacity(asts_count));
-                let global_visit_results_ref = &global_visit_results;
-
-                scope_with(&self.threadpool, |s| {
-                    let resolver_handle = &resolver;
-                    for (ast, local_visit_result) in ast_pre_resolve_visit_results {
-                        s.execute(move || {
-                            let (ast, global_visit_result, local_visit_result) = ast
-                                .into_visitor(resolver_handle, local_visit_result)
-                                .visit();
-
-                            global_visit_results_ref.lock().unwrap().push(global_visit_result);
-                            ast_visit_results_ref.lock().unwrap().push((ast, local_visit_result));
-                        });
-                    }
-                });
+        let ast_resolve_visit_results = {
+            let ast_visit_results = Mutex::new(Vec::with_capacity(asts_count));
+            let ast_visit_results_ref = &ast_visit_results;
+
+            let global_visit_results = Mutex::new(Vec::with_capacity(asts_count));
+            let global_visit_results_ref = &global_visit_results;
 
-                for global_visit_result in global_visit_results.into_inner().unwrap() {
-                    resolver.use_visit_result_from_resolve(global_visit_result);
+            scope_with(&self.threadpool, |s| {
+                let resolver_handle = &resolver;
+                for (ast, local_visit_result) in ast_pre_resolve_visit_results {
+                    s.execute(move || {
+                        let (ast, global_visit_result, local_visit_result) = ast
+                            .into_visitor(resolver_handle, local_visit_result)
+                            .visit();
+
+                        global_visit_results_ref.lock().unwrap().push(global_visit_result);
+                        ast_visit_results_ref.lock().unwrap().push((ast, local_visit_result));
+                    });
                 }
+            });
 
-                ast_visit_results.into_inner().unwrap()
-            };
+            for global_visit_result in global_visit_results.into_inner().unwrap() {
+                resolver.use_visit_result_from_resolve(global_visit_result);
+            }
 
-            println!("Resolving took: {:?}", now.elapsed());
-            let now = std::time::Instant::now();
+            ast_visit_results.into_inner().unwrap()
+        };
 
-            let _ = {
-                let ast_visit_results = Mutex::new(Vec::with_capacity(asts_count));
-                let ast_visit_results_ref = &ast_visit_results;
-
-                let global_visit_results = Mutex::new(Vec::with_capacity(asts_count));
-                let global_visit_results_ref = &global_visit_results;
-
-                scope_with(&self.threadpool, |s| {
-                    let resolver_handle = &resolver;
-                    for (ast, local_visit_result) in ast_resolve_visit_results {
-                        s.execute(move || {
-                            let (ast, global_visit_result, local_visit_result) = ast
-                                .into_visitor(resolver_handle, local_visit_result)
-                                .visit();
-
-                            global_visit_results_ref.lock().unwrap().push(global_visit_result);
-                            ast_visit_results_ref.lock().unwrap().push((ast, local_visit_result));
-                        });
-                    }
-                });
+        println!("Resolving took: {:?}", now.elapsed());
+        let now = std::time::Instant::now();
+
+        let _ = {
+            let ast_visit_results = Mutex::new(Vec::with_capacity(asts_count));
+            let ast_visit_results_ref = &ast_visit_results;
 
-                for global_visit_result in global_visit_results.into_inner().unwrap() {
-                    resolver.use_visit_result_from_type_check(global_visit_result);
+            let global_visit_results = Mutex::new(Vec::with_capacity(asts_count));
+            let global_visit_results_ref = &global_visit_results;
+
+            scope_with(&self.threadpool, |s| {
+                let resolver_handle = &resolver;
+                for (ast, local_visit_result) in ast_resolve_visit_results {
+                    s.execute(move || {
+                        let (ast, global_visit_result, local_visit_result) = ast
+                            .into_visitor(resolver_handle, local_visit_result)
+                            .visit();
+
+                        global_visit_results_ref.lock().unwrap().push(global_visit_result);
+                        ast_visit_results_ref.lock().unwrap().push((ast, local_visit_result));
+                    });
                 }
+            });
+
+            for global_visit_result in global_visit_results.into_inner().unwrap() {
+                resolver.use_visit_result_from_type_check(global_visit_result);
+            }
+
+            ast_visit_results.into_inner().unwrap()
+        };
+
+        println!("Type checking took: {:?}", now.elapsed());
+
+        if diagnostics::has_error() {
+            diagnostics::print_diagnostics();
+            std::process::exit(1);
+        }
+
+        let resolved_pkg = resolver.get_resolved_pkg(pkg_symbol);
+        let (resolved_functions, resolved_information) = resolver.take_resolved_information();
+
+        (resolved_functions, resolved_information, resolved_pkg)
+    }
+}
+
+/// The options which don't take a value
+const FLAGS: [&str; 6] = [
+    "--mem2reg",
+    "--no-inline",
+    "--verify",
+    "--interpret",
+    "--dot",
+    "--dot-call-graph",
+];
+
+/// The options which take a value, either as the next argument or after a `=`
+const VALUE_OPTIONS: [&str; 4] = ["--out-dir", "-o", "--cc", "--linker"];
+
+/// The value given for an option (e.g. `-o main`), exits if it's missing. The value is only
+/// considered missing if it's empty or another option, so `-o -main` is allowed
+fn expect_arg_value(option: &str, value: Option<String>) -> String {
+    let is_option = |value: &str| {
+        let value_option = value.split_once('=').map_or(value, |(option, _)| option);
+        FLAGS.contains(&value) || VALUE_OPTIONS.contains(&value_option)
+    };
+
+    match value {
+        Some(value) if !value.is_empty() && !is_option(&value) => value,
+        _ => {
+            println!("Missing value after {}", option);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The Ast of a file alongside its content
+pub type ParsedFile<'ast> = (Ast<'ast, AstUnvalidated>, String, ModId);
+
+/// A package is a directory, where all `.vs` files inside it are part of the package.
+/// Files in subdirectories are part of nested modules e.g. `pkg.net.Socket`
+pub struct ParsedPkg<'ast> {
+    pub pkg_symbol: Symbol,
+    pub asts: Vec<ParsedFile<'ast>>,
+    pub module_paths: Vec<(ModId, Vec<Symbol>)>,
+}
 
-                ast_visit_results.into_inner().unwrap()
+/// Used while discovering the packages the entry package depends on
+struct PkgGraph<'ast> {
+    parsed_pkgs: Vec<ParsedPkg<'ast>>,
+    /// Packages currently being parsed. Importing one of these again means there's a cycle
+    pkg_stack: Vec<Symbol>,
+    next_mod_id: u32,
+    /// Paths which may go through a module, starting with the package e.g. `[other, net, Socket]`
+    module_refs: Vec<Vec<Symbol>>,
+    /// Subdirectories which weren't referenced when their package was parsed
+    skipped_modules: Vec<(Symbol, Vec<Symbol>)>,
+}
+
+impl PkgGraph<'_> {
+    fn is_module_referenced(&self, pkg_symbol: Symbol, module_path: &[Symbol]) -> bool {
+        self.module_refs
+            .iter()
+            .any(|module_ref| {
+                module_ref.len() > module_path.len() &&
+                    module_ref[0] == pkg_symbol &&
+                    module_ref[1..].starts_with(module_path)
+            })
+    }
+}
+
+/// Returns the `.vs` files and the subdirectories (with their names) directly inside a directory
+fn read_module_dir(dir: &path::Path) -> (Vec<PathBuf>, Vec<(PathBuf, Symbol)>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Error reading directory: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut files = Vec::new();
+    let mut subdirs = Vec::new();
+    for entry in entries {
+        let path = entry.unwrap().path();
+
+        if path.is_dir() {
+            let Some(dir_name) = path.file_name().map(|x| x.to_string_lossy()) else {
+                continue;
             };
+            // Skip hidden directories like `.git`
+            if dir_name.starts_with('.') {
+                continue;
+            }
+            let dir_symbol = Symbol::new(&dir_name);
+            subdirs.push((path, dir_symbol));
+        } else if let Some(ext) = path.extension() {
+            if ext == "vs" {
+                files.push(path);
+            }
+        }
+    }
 
-            println!("Type checking took: {:?}", now.elapsed());
+    (files, subdirs)
+}
 
-            if diagnostics::has_error() {
-                diagnostics::print_diagnostics();
-                std::process::exit(1);
+/// Collects the paths which may go through a module, e.g. `pkg.net.Socket` or
+/// `import other.net.Socket`
+struct ModuleRefsCollector<'a> {
+    pkg_symbol: Symbol,
+    module_refs: &'a mut Vec<Vec<Symbol>>,
+}
+
+impl<'ast> Visitor<'ast> for ModuleRefsCollector<'_> {
+    type Result = ();
+
+    fn default_result() -> Self::Result {}
+
+    fn visit_path_field(&mut self, path_field: &'ast PathField<'ast>) -> Self::Result {
+        let mut module_ref = vec![Symbol::from_node_id(path_field.rhs.ast_node_id)];
+        let mut lhs = path_field.lhs;
+        loop {
+            match lhs {
+                Path::PathField(path_field) => {
+                    module_ref.push(Symbol::from_node_id(path_field.rhs.ast_node_id));
+                    lhs = path_field.lhs;
+                }
+                Path::PathSegment(ident_node) => {
+                    module_ref.push(Symbol::from_node_id(ident_node.ast_node_id));
+                    break;
+                }
+                Path::PathPkg(_) => {
+                    module_ref.push(self.pkg_symbol);
+                    break;
+                }
+            }
+        }
+
+        module_ref.reverse();
+        self.module_refs.push(module_ref);
+    }
+
+    fn visit_field_expr(&mut self, field_expr: &'ast FieldExpr<'ast>) -> Self::Result {
+        // Only `pkg.net.connect` can go through a module, and not e.g. `socket.net.connect`
+        let mut module_ref = vec![Symbol::from_node_id(field_expr.rhs.ast_node_id)];
+        let mut lhs = field_expr.lhs;
+        loop {
+            let Expr::ExprWithoutBlock(ExprWithoutBlock::PlaceExpr(place_expr)) = lhs else {
+                return walk_field_expr(self, field_expr);
+            };
+
+            match place_expr {
+                PlaceExpr::FieldExpr(lhs_expr) => {
+                    module_ref.push(Symbol::from_node_id(lhs_expr.rhs.ast_node_id));
+                    lhs = lhs_expr.lhs;
+                }
+                PlaceExpr::PkgIdentExpr(_) => {
+                    module_ref.push(self.pkg_symbol);
+                    module_ref.reverse();
+                    self.module_refs.push(module_ref);
+                    return;
+                }
+                _ => {
+                    return walk_field_expr(self, field_expr);
+                }
             }
+        }
+    }
+}
+
+/// Finds the packages imported in an Ast e.g. `other` in `import other.Item`
+fn get_imported_pkgs<'ast>(ast: &Ast<'ast, AstUnvalidated>) -> Vec<&'ast IdentNode> {
+    let mut imported_pkgs = Vec::new();
 
-            resolver.take_resolved_information()
+    for stmt in ast.main_scope.stmts.iter() {
+        let Stmt::ItemStmt(ItemStmt::ImportItem(import_item)) = stmt else {
+            continue;
         };
 
-        let now = std::time::Instant::now();
+        for import_path in import_item.import_items_path.iter() {
+            get_import_path_roots(*import_path, &mut imported_pkgs);
+        }
+    }
 
-        let icfg_builder = IcfgBuilder::new(resolved_information, &self.threadpool);
-        let icfg = icfg_builder.build(resolved_functions);
+    imported_pkgs
+}
 
-        println!("Building ICFG took: {:?}", now.elapsed());
-        icfg
+fn get_import_path_roots<'ast>(import_path: ImportPath<'ast>, roots: &mut Vec<&'ast IdentNode>) {
+    let mut root_path = match import_path {
+        ImportPath::Path(path) => {
+            let Path::PathField(path_field) = path else {
+                return;
+            };
+            path_field.lhs
+        }
+        ImportPath::PathAlias(path_alias) => {
+            let Path::PathField(path_field) = path_alias.path else {
+                return;
+            };
+            path_field.lhs
+        }
+        ImportPath::PathEverything(path_everything) => path_everything.lhs,
+        ImportPath::PathMultiple(path_multiple) => {
+            for import_path in path_multiple.paths.iter() {
+                get_import_path_roots(*import_path, roots);
+            }
+            return;
+        }
+    };
+
+    while let Path::PathField(path_field) = root_path {
+        root_path = path_field.lhs;
+    }
+
+    if let Path::PathSegment(pkg_ident_node) = root_path {
+        roots.push(pkg_ident_node);
     }
 }
+
+/// Returns the names of the top-level structs, enums and typedefs in an Ast
+fn get_adt_symbols(ast: &Ast<'_, AstUnvalidated>) -> Vec<Symbol> {
+    ast.main_scope.stmts
+        .iter()
+        .filter_map(|stmt| {
+            let ident_node = match stmt {
+                Stmt::ItemStmt(ItemStmt::StructItem(struct_item)) => struct_item.ident_node,
+                Stmt::ItemStmt(ItemStmt::EnumItem(enum_item)) => enum_item.ident_node,
+                Stmt::ItemStmt(ItemStmt::TypedefItem(typedef_item)) => typedef_item.ident_node,
+                _ => {
+                    return None;
+                }
+            };
+            Some(Symbol::from_node_id(ident_node.ast_node_id))
+        })
+        .collect()
+}
@@ -30,6 +30,11 @@ pub fn has_error() -> bool {
     DIAGNOSTICS.lock().unwrap().highest_severity.is_some()
 }
 
+/// Returns true if any diagnostic has been reported, including warnings
+pub fn has_diagnostics() -> bool {
+    !DIAGNOSTICS.lock().unwrap().diagnostics.is_empty()
+}
+
 pub fn print_diagnostics() {
     let mut diagnostics_lock = DIAGNOSTICS.lock().unwrap();
     diagnostics_lock.sort_by_mod_id();
@@ -52,10 +57,18 @@ pub fn print_diagnostics() {
         }
 
         if let DiagnosticKind::Error(_) = diagnostic.kind {
-            diagnostic.write_msg(&mut error_buffer, &file_content);
+            diagnostic.write_msg(
+                &mut error_buffer,
+                &file_content,
+                &diagnostics_lock.mod_name_to_file_path
+            );
             writeln!(error_buffer).unwrap();
         } else if let DiagnosticKind::Warning(_) = diagnostic.kind {
-            diagnostic.write_msg(&mut warning_buffer, &file_content);
+            diagnostic.write_msg(
+                &mut warning_buffer,
+                &file_content,
+                &diagnostics_lock.mod_name_to_file_path
+            );
             writeln!(warning_buffer).unwrap();
         }
     }
@@ -69,6 +82,12 @@ pub fn print_diagnostics() {
     }
 }
 
+/// Prints an error which doesn't belong to a place in the source (e.g. a failing C compiler) in
+/// the same format as the errors of the diagnostics, but on stderr
+pub fn print_error(msg: &str) {
+    eprintln!("\n\x1b[91mErrors:\x1b[0m\n{}\n", msg);
+}
+
 pub struct ProgramDiagnostics {
     diagnostics: Vec<Diagnostic>,
     highest_severity: Option<Severity>,
@@ -139,6 +158,14 @@ impl Diagnostic {
         }
     }
 
+    pub fn new_warning(kind: WarningKind, range: Span, mod_id: ModId) -> Self {
+        Self {
+            kind: DiagnosticKind::Warning(kind),
+            range,
+            mod_id,
+        }
+    }
+
     pub fn get_severity_code(&self) -> u8 {
         match self.kind {
             DiagnosticKind::Error(_) => 1,
@@ -157,10 +184,16 @@ impl Diagnostic {
         }
     }
 
-    pub fn write_msg(&self, buffer: &mut String, file_content: &str) {
+    pub fn write_msg(
+        &self,
+        buffer: &mut String,
+        file_content: &str,
+        mod_id_to_file_path: &FxHashMap<ModId, PathBuf>
+    ) {
         match self.kind {
-            DiagnosticKind::Error(kind) => kind.write_msg(buffer, &self.range, file_content),
-            DiagnosticKind::Warning(_) => todo!(),
+            DiagnosticKind::Error(kind) =>
+                kind.write_msg(buffer, &self.range, file_content, mod_id_to_file_path),
+            DiagnosticKind::Warning(kind) => kind.write_msg(buffer, &self.range),
             DiagnosticKind::Info(_) => todo!(),
             DiagnosticKind::Hint(_) => todo!(),
         }
@@ -244,6 +277,57 @@ pub enum ErrorKind {
     ExpectedExprOrItem {
         found: Symbol,
     },
+    UndefinedLoopLabel {
+        label: Symbol,
+    },
+    ItemIsPrivate {
+        symbol: Symbol,
+        def_span: Span,
+        def_mod_id: ModId,
+    },
+    UnresolvedPackage {
+        pkg_symbol: Symbol,
+    },
+    CyclicPackageImport {
+        pkg_symbol: Symbol,
+    },
+    ConflictingImport {
+        symbol: Symbol,
+    },
+    UndefinedTrait {
+        symbol: Symbol,
+    },
+    InvalidDropImpl {
+        adt_symbol: Symbol,
+    },
+    ConflictingDropImpl {
+        adt_symbol: Symbol,
+    },
+    InvalidCopyImpl {
+        adt_symbol: Symbol,
+    },
+    UseAfterMove {
+        symbol: Symbol,
+        move_span: Span,
+    },
+    UseOfPartiallyMovedValue {
+        symbol: Symbol,
+        move_span: Span,
+    },
+    /// `None` if it's an implicit variable (e.g. the result of an `if` expression)
+    UninitializedRead {
+        symbol: Option<Symbol>,
+    },
+    UnknownAttribute {
+        symbol: Symbol,
+    },
+    UndefinedModuleMember {
+        symbol: Symbol,
+        module_symbol: Symbol,
+    },
+    ExpectedModule {
+        symbol: Symbol,
+    },
 }
 
 impl ErrorKind {
@@ -262,8 +346,23 @@ impl ErrorKind {
             Self::ExpectedIdent { .. } => Severity::Severe,
             Self::ExpectedToken { .. } => Severity::Severe,
             Self::ExpectedExprOrItem { .. } => Severity::Severe,
+            Self::UndefinedLoopLabel { .. } => Severity::Severe,
+            Self::ItemIsPrivate { .. } => Severity::Severe,
+            Self::UnresolvedPackage { .. } => Severity::Severe,
+            Self::CyclicPackageImport { .. } => Severity::Severe,
+            Self::ConflictingImport { .. } => Severity::Severe,
+            Self::UndefinedTrait { .. } => Severity::Severe,
+            Self::InvalidDropImpl { .. } => Severity::Severe,
+            Self::ConflictingDropImpl { .. } => Severity::Severe,
+            Self::InvalidCopyImpl { .. } => Severity::Severe,
+            Self::UseAfterMove { .. } => Severity::Severe,
+            Self::UseOfPartiallyMovedValue { .. } => Severity::Severe,
+            Self::UninitializedRead { .. } => Severity::Severe,
+            Self::UndefinedModuleMember { .. } => Severity::Severe,
+            Self::ExpectedModule { .. } => Severity::Severe,
 
             Self::FnWithoutBody { .. } => Severity::NoImpact,
+            Self::UnknownAttribute { .. } => Severity::NoImpact,
             Self::ReturnOutsideFn => Severity::NoImpact,
             Self::MissingReturn => Severity::NoImpact,
             Self::ExpectedBoolExprInCond { .. } => Severity::NoImpact,
@@ -296,11 +395,163 @@ impl ErrorKind {
             Self::ExpectedIdent { .. } => "E018",
             Self::ExpectedToken { .. } => "E019",
             Self::ExpectedExprOrItem { .. } => "E020",
+            Self::UndefinedLoopLabel { .. } => "E021",
+            Self::ItemIsPrivate { .. } => "E022",
+            Self::UnresolvedPackage { .. } => "E023",
+            Self::CyclicPackageImport { .. } => "E024",
+            Self::ConflictingImport { .. } => "E025",
+            Self::UndefinedTrait { .. } => "E026",
+            Self::InvalidDropImpl { .. } => "E027",
+            Self::ConflictingDropImpl { .. } => "E028",
+            Self::InvalidCopyImpl { .. } => "E029",
+            Self::UseAfterMove { .. } => "E030",
+            Self::UseOfPartiallyMovedValue { .. } => "E031",
+            Self::UninitializedRead { .. } => "E032",
+            Self::UnknownAttribute { .. } => "E033",
+            Self::UndefinedModuleMember { .. } => "E034",
+            Self::ExpectedModule { .. } => "E035",
         }
     }
 
-    pub fn write_msg(&self, buffer: &mut String, span: &Span, file_content: &str) {
+    pub fn write_msg(
+        &self,
+        buffer: &mut String,
+        span: &Span,
+        file_content: &str,
+        mod_id_to_file_path: &FxHashMap<ModId, PathBuf>
+    ) {
         let write_error = match self {
+            Self::ExpectedModule { symbol } => {
+                write!(
+                    buffer,
+                    "`{}` is not a package or module at line {}, so nothing can be imported from it",
+                    symbol.get(),
+                    span.get_line()
+                )
+            }
+            Self::UndefinedModuleMember { symbol, module_symbol } => {
+                write!(
+                    buffer,
+                    "`{}` is not a member of `{}` at line {}",
+                    symbol.get(),
+                    module_symbol.get(),
+                    span.get_line()
+                )
+            }
+            Self::UnknownAttribute { symbol } => {
+                write!(
+                    buffer,
+                    "Unknown attribute `@{}` at line {}. Available attributes are `@inline` and `@noinline`",
+                    symbol.get(),
+                    span.get_line()
+                )
+            }
+            Self::UninitializedRead { symbol: Some(symbol) } => {
+                write!(
+                    buffer,
+                    "Variable `{}` defined at line {} is read before it's initialized",
+                    symbol.get(),
+                    span.get_line()
+                )
+            }
+            Self::UninitializedRead { symbol: None } => {
+                write!(
+                    buffer,
+                    "Value at line {} is read before it's initialized",
+                    span.get_line()
+                )
+            }
+            Self::UseAfterMove { symbol, move_span } => {
+                write!(
+                    buffer,
+                    "Use of moved value `{}` at line {}. The value was moved at line {}",
+                    symbol.get(),
+                    span.get_line(),
+                    move_span.get_line()
+                )
+            }
+            Self::UseOfPartiallyMovedValue { symbol, move_span } => {
+                write!(
+                    buffer,
+                    "Use of partially moved value `{}` at line {}. A part of the value was moved at line {}",
+                    symbol.get(),
+                    span.get_line(),
+                    move_span.get_line()
+                )
+            }
+            Self::InvalidCopyImpl { adt_symbol } => {
+                write!(
+                    buffer,
+                    "Invalid implementation of `Copy` for `{}` at line {}. `Copy` has no methods",
+                    adt_symbol.get(),
+                    span.get_line()
+                )
+            }
+            Self::ConflictingDropImpl { adt_symbol } => {
+                write!(
+                    buffer,
+                    "Conflicting implementations of `Drop` for `{}` at line {}",
+                    adt_symbol.get(),
+                    span.get_line()
+                )
+            }
+            Self::InvalidDropImpl { adt_symbol } => {
+                write!(
+                    buffer,
+                    "Invalid implementation of `Drop` for `{}` at line {}. Expected a single method `fn onDrop(*self)`",
+                    adt_symbol.get(),
+                    span.get_line()
+                )
+            }
+            Self::UndefinedTrait { symbol } => {
+                write!(
+                    buffer,
+                    "Undefined trait `{}` at line {}. Only the built-in traits `Drop` and `Copy` can be implemented",
+                    symbol.get(),
+                    span.get_line()
+                )
+            }
+            Self::ConflictingImport { symbol } => {
+                write!(
+                    buffer,
+                    "Import of `{}` at line {} conflicts with another item of the same name. Try importing it with `as`",
+                    symbol.get(),
+                    span.get_line()
+                )
+            }
+            Self::CyclicPackageImport { pkg_symbol } => {
+                write!(
+                    buffer,
+                    "Cyclic import of package `{}` at line {}. Packages cannot depend on each other",
+                    pkg_symbol.get(),
+                    span.get_line()
+                )
+            }
+            Self::UnresolvedPackage { pkg_symbol } => {
+                write!(
+                    buffer,
+                    "Unresolved package `{}` at line {}. Expected a directory named `{}` next to the current package",
+                    pkg_symbol.get(),
+                    span.get_line(),
+                    pkg_symbol.get()
+                )
+            }
+            Self::ItemIsPrivate { symbol, def_span, def_mod_id } => {
+                write!(
+                    buffer,
+                    "`{}` is private at line {}. It's defined at line {} in {}, and can be made public with `pub` or `export`",
+                    symbol.get(),
+                    span.get_line(),
+                    def_span.get_line(),
+                    match mod_id_to_file_path.get(def_mod_id) {
+                        Some(file_path) => file_path.display().to_string(),
+                        None => "another file".to_string(),
+                    }
+                )
+            }
+            Self::UndefinedLoopLabel { label } => {
+                write!(buffer, "Undefined loop label `{}` at line {}", label.get(), span.get_line())
+            }
             Self::ExpectedExprOrItem { found } => {
                 write!(
                     buffer,
@@ -491,7 +742,62 @@ pub enum Severity {
 }
 
 #[derive(Debug, Clone, Copy)]
-pub enum WarningKind {}
+pub enum WarningKind {
+    UnusedImport {
+        symbol: Symbol,
+    },
+    UnusedFunction {
+        symbol: Symbol,
+    },
+    ArithmeticOverflow,
+    NullDereference,
+    PossibleNullDereference,
+    DivisionByZero,
+}
+
+impl WarningKind {
+    /// Used with the LSP (in the future) to make each warning unique
+    pub fn get_issue_code(&self) -> &str {
+        match self {
+            Self::UnusedImport { .. } => "W001",
+            Self::UnusedFunction { .. } => "W002",
+            Self::ArithmeticOverflow => "W003",
+            Self::NullDereference => "W004",
+            Self::PossibleNullDereference => "W005",
+            Self::DivisionByZero => "W006",
+        }
+    }
+
+    pub fn write_msg(&self, buffer: &mut String, span: &Span) {
+        let write_warning = match self {
+            Self::UnusedImport { symbol } => {
+                write!(buffer, "Unused import `{}` at line {}", symbol.get(), span.get_line())
+            }
+            Self::UnusedFunction { symbol } => {
+                write!(
+                    buffer,
+                    "Function `{}` at line {} is never used",
+                    symbol.get(),
+                    span.get_line()
+                )
+            }
+            Self::ArithmeticOverflow => {
+                write!(buffer, "Arithmetic overflow at line {}", span.get_line())
+            }
+            Self::NullDereference => {
+                write!(buffer, "Dereference of a null pointer at line {}", span.get_line())
+            }
+            Self::PossibleNullDereference => {
+                write!(buffer, "Dereference of a possibly null pointer at line {}", span.get_line())
+            }
+            Self::DivisionByZero => {
+                write!(buffer, "Division by zero at line {}", span.get_line())
+            }
+        };
+
+        write_warning.expect("Unexpected write error");
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub enum InfoKind {}
@@ -44,12 +44,17 @@ pub enum ErrorKind {
     MissingStructField(Symbol),
     MismatchedReturnTypes(Ty, Ty),
     ReturnOutsideFn,
+    ReturnInsideDefer,
     MissingReturn,
     UndefinedStructField(Symbol, Symbol),
     ExpectedBoolExpr(Ty),
     AssignmentToImmutable(Symbol),
     BreakTypeError(Ty, Ty),
     BreakOutsideLoop,
+    ContinueOutsideLoop,
+    InvalidTryOperand(Ty),
+    MismatchedTryReturnType(Ty, Ty),
+    TryInsideDefer,
     BinaryExprTypeError(BinaryOp, Ty, Ty),
     InvalidTuple(Ty),
     InvalidStruct(Ty),
@@ -66,13 +71,18 @@ impl ErrorKind {
             Self::InvalidPattern => Severity::Fatal,
             Self::BinaryExprTypeError(_, _, _) => Severity::Fatal,
             Self::TupleAccessOutOfBounds(_, _) => Severity::Fatal,
+            Self::InvalidTryOperand(_) => Severity::Fatal,
+            Self::MismatchedTryReturnType(_, _) => Severity::Fatal,
             Self::MismatchedReturnTypes(_, _) => Severity::NoImpact,
             Self::ReturnOutsideFn => Severity::NoImpact,
+            Self::ReturnInsideDefer => Severity::NoImpact,
+            Self::TryInsideDefer => Severity::NoImpact,
             Self::MissingReturn => Severity::NoImpact,
             Self::MismatchedFieldTypes(_, _, _, _) => Severity::NoImpact,
             Self::MissingStructField(_) => Severity::NoImpact,
             Self::UndefinedStructField(_, _) => Severity::NoImpact,
             Self::BreakOutsideLoop => Severity::NoImpact,
+            Self::ContinueOutsideLoop => Severity::NoImpact,
             Self::BreakTypeError(_, _) => Severity::NoImpact,
             Self::ExpectedBoolExpr(_) => Severity::NoImpact,
             Self::AssignmentToImmutable(_) => Severity::NoImpact,
@@ -102,6 +112,9 @@ impl ErrorKind {
             Self::ReturnOutsideFn => {
                 write!(buffer, "Keyword `return` cannot be used outside of functions")
             }
+            Self::ReturnInsideDefer => {
+                write!(buffer, "Keyword `ret` cannot be used inside a `defer` expression")
+            }
             Self::InvalidTuple(found_ty) => {
                 write!(buffer, "Expected tuple but found type `{}`", found_ty)
             }
@@ -135,6 +148,27 @@ impl ErrorKind {
             Self::BreakOutsideLoop => {
                 write!(buffer, "Keyword `break` cannot be used outside of loops")
             }
+            Self::ContinueOutsideLoop => {
+                write!(buffer, "Keyword `continue` cannot be used outside of loops")
+            }
+            Self::InvalidTryOperand(found_ty) => {
+                write!(
+                    buffer,
+                    "Operator `?` expected an enum with `Ok` and `Err` variants but found type `{}`",
+                    found_ty
+                )
+            }
+            Self::MismatchedTryReturnType(fn_ret_ty, found_ty) => {
+                write!(
+                    buffer,
+                    "Operator `?` cannot propagate the `Err` variant of `{}` in a function returning `{}`",
+                    found_ty,
+                    fn_ret_ty
+                )
+            }
+            Self::TryInsideDefer => {
+                write!(buffer, "Operator `?` cannot be used inside a `defer` expression")
+            }
             Self::BinaryExprTypeError(binary_op, lhs_ty, rhs_ty) => {
                 write!(buffer, "`{}` is not defined for `{}` and `{}`", binary_op, lhs_ty, rhs_ty)
             }
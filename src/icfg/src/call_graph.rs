@@ -0,0 +1,129 @@
+/*
+
+Dead function elimination
+
+The call graph has an edge from a function to each function it refers to, either by calling it or
+by using it as a value (e.g. storing it in a variable). Dropping a value refers to the drop glue of
+its type, which in turn refers to the `onDrop` method of the type
+
+Functions that can't be reached from the roots (`main` and the exported `fn.C` functions) are
+never run, so they're removed before codegen. Unused private functions are reported as warnings
+
+*/
+
+use diagnostics::{ Diagnostic, WarningKind };
+use fxhash::FxHashMap;
+use ir::{ CfgFnKind, DefId, Ty };
+
+use crate::{ Cfg, Const, Icfg, Liveness, NodeKind, Operand };
+
+/// Edges between the cfgs of an icfg (by their index)
+pub struct CallGraph {
+    callees: Vec<Vec<usize>>,
+}
+
+impl CallGraph {
+    pub fn new(cfgs: &[Cfg]) -> Self {
+        let mut fn_def_id_to_index: FxHashMap<DefId, usize> = Default::default();
+        let mut drop_glue_ty_to_index: FxHashMap<Ty, usize> = Default::default();
+        for (i, cfg) in cfgs.iter().enumerate() {
+            match cfg.cfg_fn_kind {
+                CfgFnKind::Main(def_id) | CfgFnKind::Fn(def_id) => {
+                    fn_def_id_to_index.insert(def_id, i);
+                }
+                CfgFnKind::DropGlue(ty) => {
+                    drop_glue_ty_to_index.insert(ty, i);
+                }
+            }
+        }
+
+        let callees = cfgs
+            .iter()
+            .map(|cfg| {
+                let mut callees = Vec::new();
+
+                let nodes = cfg.basic_blocks.iter().flat_map(|basic_block| basic_block.nodes.iter());
+                for node in nodes {
+                    if let NodeKind::DropNode(drop_node) = node.kind {
+                        callees.extend(drop_glue_ty_to_index.get(&drop_node.ty));
+                    }
+
+                    // Functions from the Clib have no cfg
+                    for operand in node.kind.get_operands() {
+                        if let Operand::Const(Const::FnPtr(def_id)) = operand {
+                            callees.extend(fn_def_id_to_index.get(&def_id));
+                        }
+                    }
+                }
+
+                callees
+            })
+            .collect();
+
+        Self { callees }
+    }
+
+    pub fn callees(&self, index: usize) -> &[usize] {
+        &self.callees[index]
+    }
+
+    /// Which cfgs can be reached from the roots
+    pub fn get_reachable(&self, roots: impl IntoIterator<Item = usize>) -> Vec<bool> {
+        let mut reachable = vec![false; self.callees.len()];
+        let mut stack = Vec::new();
+
+        for root in roots {
+            if !reachable[root] {
+                reachable[root] = true;
+                stack.push(root);
+            }
+        }
+
+        while let Some(index) = stack.pop() {
+            for callee in self.callees(index) {
+                if !reachable[*callee] {
+                    reachable[*callee] = true;
+                    stack.push(*callee);
+                }
+            }
+        }
+
+        reachable
+    }
+}
+
+impl Icfg<'_> {
+    /// Computes the liveness of each cfg, and removes the cfgs which are dead
+    pub fn eliminate_dead_functions(&mut self) {
+        let call_graph = CallGraph::new(&self.cfgs);
+        let roots = self.cfgs
+            .iter()
+            .enumerate()
+            .filter(|(_, cfg)| matches!(cfg.cfg_fn_kind, CfgFnKind::Main(_)) || cfg.is_exported)
+            .map(|(i, _)| i);
+        let reachable = call_graph.get_reachable(roots);
+
+        let mut diagnostics = Vec::new();
+        for (cfg, is_reachable) in self.cfgs.iter_mut().zip(reachable) {
+            cfg.liveness = if is_reachable { Liveness::Alive } else { Liveness::Dead };
+            if is_reachable {
+                continue;
+            }
+
+            if let CfgFnKind::Fn(def_id) = cfg.cfg_fn_kind {
+                if let Some(span) = self.resolved_information.private_fns.get(&def_id) {
+                    diagnostics.push(
+                        Diagnostic::new_warning(
+                            WarningKind::UnusedFunction { symbol: def_id.symbol },
+                            *span,
+                            def_id.node_id.mod_id
+                        )
+                    );
+                }
+            }
+        }
+        diagnostics::report_diagnostics(diagnostics);
+
+        self.cfgs.retain(|cfg| cfg.liveness == Liveness::Alive);
+    }
+}
@@ -1,13 +1,166 @@
-use crate::{ CallNode, Cfg, CfgVisitor };
+/*
+
+Definite initialization
+
+Proves that every variable (`LocalMem`) and implicit variable (`ResultMem`) is written to before
+it's read. This is a forward dataflow analysis over the basic blocks, where a memory location is
+only initialized at some point if it's written to on all paths leading to that point
+
+Writing to any part of a memory location (e.g. a field of a struct) counts as initializing it
+
+*/
+
+use diagnostics::ErrorKind;
+use fxhash::FxHashMap;
+use span::Span;
+
+use crate::{ Cfg, DataflowAnalysis, Node, NodeKind, NodeLocation, PlaceKind, TempId };
+
+/// Whether each `LocalMem` and `ResultMem` is initialized at some point
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitializedMems {
+    local_mems: Vec<bool>,
+    result_mems: Vec<bool>,
+}
+
+impl InitializedMems {
+    fn is_init(&self, mem: PlaceKind) -> bool {
+        match mem {
+            PlaceKind::LocalMemId(local_mem_id) => self.local_mems[local_mem_id.0 as usize],
+            PlaceKind::ResultMemId(result_mem_id) => self.result_mems[result_mem_id.0 as usize],
+            PlaceKind::TempId(_) => unreachable!("Expected memory location"),
+        }
+    }
+
+    fn set_init(&mut self, mem: PlaceKind) {
+        match mem {
+            PlaceKind::LocalMemId(local_mem_id) => {
+                self.local_mems[local_mem_id.0 as usize] = true;
+            }
+            PlaceKind::ResultMemId(result_mem_id) => {
+                self.result_mems[result_mem_id.0 as usize] = true;
+            }
+            PlaceKind::TempId(_) => unreachable!("Expected memory location"),
+        }
+    }
+}
 
 pub struct CfgAnalyzer<'a, 'b> where 'a: 'b {
-    pub cfg: &'b mut Cfg<'a>,
+    pub cfg: &'b Cfg<'a>,
+    /// Temps pointing into a memory location (e.g. a field of it)
+    temp_id_to_mem: FxHashMap<TempId, PlaceKind>,
+    /// Only the first uninitialized read of each memory location is reported
+    reported_mems: Vec<PlaceKind>,
+    pub errors: Vec<(ErrorKind, Span)>,
 }
 
-impl<'a, 'b> CfgVisitor for CfgAnalyzer<'a, 'b> where 'a: 'b {
-    type Result = ();
+impl<'a, 'b> CfgAnalyzer<'a, 'b> where 'a: 'b {
+    pub fn new(cfg: &'b Cfg<'a>) -> Self {
+        Self {
+            cfg,
+            temp_id_to_mem: Default::default(),
+            reported_mems: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn analyze(&mut self) {
+        let cfg = self.cfg;
+        let results = self.solve(cfg);
+
+        results.visit_with(cfg, self, |analyzer, node, _, state| {
+            let NodeKind::LoadNode(load_node) = node.kind else {
+                return;
+            };
+            if let Some(mem) = analyzer.get_mem(&load_node.load_place) {
+                if !state.is_init(mem) {
+                    analyzer.report_uninit_read(mem, node.span);
+                }
+            }
+        });
+    }
+
+    fn get_mem(&self, place: &PlaceKind) -> Option<PlaceKind> {
+        match place {
+            PlaceKind::LocalMemId(_) | PlaceKind::ResultMemId(_) => Some(*place),
+            PlaceKind::TempId(temp_id) => self.temp_id_to_mem.get(temp_id).copied(),
+        }
+    }
+
+    fn report_uninit_read(&mut self, mem: PlaceKind, node_span: Option<Span>) {
+        if self.reported_mems.contains(&mem) {
+            return;
+        }
+        self.reported_mems.push(mem);
+
+        let (error_kind, span) = match mem {
+            PlaceKind::LocalMemId(local_mem_id) => {
+                let local_mem = self.cfg.get_local_mem(local_mem_id);
+                (ErrorKind::UninitializedRead { symbol: Some(local_mem.symbol) }, local_mem.span)
+            }
+            _ => {
+                (ErrorKind::UninitializedRead { symbol: None }, node_span.unwrap_or(Span::dummy()))
+            }
+        };
+
+        self.errors.push((error_kind, span));
+    }
+}
+
+impl<'a, 'b> DataflowAnalysis for CfgAnalyzer<'a, 'b> where 'a: 'b {
+    type Domain = InitializedMems;
+
+    fn boundary_state(&self, cfg: &Cfg) -> Self::Domain {
+        InitializedMems {
+            local_mems: vec![false; cfg.local_mems.len()],
+            result_mems: vec![false; cfg.result_mems.len()],
+        }
+    }
+
+    /// A memory location is only initialized if it's initialized on all paths
+    fn join(&self, state: &mut Self::Domain, other: &Self::Domain) -> bool {
+        let mems = state.local_mems
+            .iter_mut()
+            .zip(other.local_mems.iter())
+            .chain(state.result_mems.iter_mut().zip(other.result_mems.iter()));
 
-    fn default_result() -> Self::Result {}
+        let mut changed = false;
+        for (init, other_init) in mems {
+            if *init && !*other_init {
+                *init = false;
+                changed = true;
+            }
+        }
+        changed
+    }
 
-    fn visit_call_node(&mut self, call_node: &CallNode, cfg: &Cfg) -> Self::Result {}
+    fn transfer_node(
+        &mut self,
+        node: &Node,
+        _location: NodeLocation,
+        state: &mut Self::Domain,
+        _cfg: &Cfg
+    ) {
+        match node.kind {
+            NodeKind::StoreNode(store_node) => {
+                if let Some(mem) = self.get_mem(&store_node.setter) {
+                    state.set_init(mem);
+                }
+            }
+            NodeKind::ByteAccessNode(byte_access_node) => {
+                let PlaceKind::TempId(temp_id) = byte_access_node.result_place else {
+                    return;
+                };
+                if let Some(mem) = self.get_mem(&byte_access_node.access_place) {
+                    self.temp_id_to_mem.insert(temp_id, mem);
+                }
+            }
+            NodeKind::IndexNode(index_node) => {
+                if let Some(mem) = self.get_mem(&index_node.array_place) {
+                    self.temp_id_to_mem.insert(index_node.result_place, mem);
+                }
+            }
+            _ => {}
+        }
+    }
 }
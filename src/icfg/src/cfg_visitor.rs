@@ -8,16 +8,20 @@ use crate::{
     ByteAccessNode,
     CallNode,
     Cfg,
+    DropNode,
     IndexNode,
     LoadNode,
     LocalMem,
     Node,
     NodeKind,
+    PhiNode,
     ResultMem,
     ReturnNode,
     StoreNode,
+    SwitchNode,
     TempId,
     TyCastNode,
+    UnreachableNode,
 };
 
 // struct Linear;
@@ -80,6 +84,11 @@ pub trait CfgVisitor: Sized {
         Self::default_result()
     }
 
+    #[allow(unused_variables)]
+    fn visit_switch_node(&mut self, switch_node: &SwitchNode, cfg: &Cfg) -> Self::Result {
+        Self::default_result()
+    }
+
     #[allow(unused_variables)]
     fn visit_branch_node(&mut self, branch_node: &BranchNode, cfg: &Cfg) -> Self::Result {
         Self::default_result()
@@ -123,6 +132,25 @@ pub trait CfgVisitor: Sized {
     fn visit_ty_cast_node(&mut self, ty_cast_node: &TyCastNode, cfg: &Cfg) -> Self::Result {
         Self::default_result()
     }
+
+    #[allow(unused_variables)]
+    fn visit_drop_node(&mut self, drop_node: &DropNode, cfg: &Cfg) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_phi_node(&mut self, phi_node: &PhiNode, cfg: &Cfg) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_unreachable_node(
+        &mut self,
+        unreachable_node: &UnreachableNode,
+        cfg: &Cfg
+    ) -> Self::Result {
+        Self::default_result()
+    }
 }
 
 pub fn walk_cfg<'ctx, V>(visitor: &mut V, cfg: &Cfg) -> V::Result where V: CfgVisitor {
@@ -180,6 +208,7 @@ pub fn walk_node<'ctx, V>(visitor: &mut V, node: &Node, cfg: &Cfg) -> V::Result
         NodeKind::BinaryNode(binary_node) => visitor.visit_binary_node(binary_node, cfg),
         NodeKind::BranchCondNode(branch_cond_node) =>
             visitor.visit_branch_cond_node(branch_cond_node, cfg),
+        NodeKind::SwitchNode(switch_node) => visitor.visit_switch_node(switch_node, cfg),
         NodeKind::StoreNode(store_node) => visitor.visit_store_node(store_node, cfg),
         NodeKind::BranchNode(branch_node) => visitor.visit_branch_node(branch_node, cfg),
         NodeKind::LoadNode(load_node) => visitor.visit_load_node(load_node, cfg),
@@ -189,5 +218,9 @@ pub fn walk_node<'ctx, V>(visitor: &mut V, node: &Node, cfg: &Cfg) -> V::Result
         NodeKind::ReturnNode(return_node) => visitor.visit_return_node(return_node, cfg),
         NodeKind::CallNode(call_node) => visitor.visit_call_node(call_node, cfg),
         NodeKind::TyCastNode(ty_cast_node) => visitor.visit_ty_cast_node(ty_cast_node, cfg),
+        NodeKind::DropNode(drop_node) => visitor.visit_drop_node(drop_node, cfg),
+        NodeKind::PhiNode(phi_node) => visitor.visit_phi_node(phi_node, cfg),
+        NodeKind::UnreachableNode(unreachable_node) =>
+            visitor.visit_unreachable_node(unreachable_node, cfg),
     }
 }
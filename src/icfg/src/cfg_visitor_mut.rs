@@ -0,0 +1,180 @@
+use ir::{ LocalMem, ResultMem, TempId, Ty };
+
+use crate::{
+    BasicBlock,
+    BinaryNode,
+    BranchCondNode,
+    BranchNode,
+    ByteAccessNode,
+    CallNode,
+    Cfg,
+    DropNode,
+    IndexNode,
+    LoadNode,
+    Node,
+    NodeKind,
+    PhiNode,
+    ReturnNode,
+    StoreNode,
+    SwitchNode,
+    TyCastNode,
+    UnreachableNode,
+};
+
+/// The mutable counterpart of `CfgVisitor`, used by passes transforming a cfg in place
+///
+/// First it visits all local mems (variables).
+/// Then it visits all basic blocks
+///
+/// Unlike `CfgVisitor` the cfg isn't passed along to each node, as it's already mutably borrowed
+pub trait CfgVisitorMut: Sized {
+    type Result: Sized;
+
+    fn default_result() -> Self::Result;
+
+    fn visit_cfg(&mut self, cfg: &mut Cfg) -> Self::Result {
+        walk_cfg_mut(self, cfg)
+    }
+
+    #[allow(unused_variables)]
+    fn visit_arg(&mut self, arg: &mut (TempId, Ty)) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_local_mem(&mut self, local_mem: &mut LocalMem) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_result_mem(&mut self, result_mem: &mut ResultMem) -> Self::Result {
+        Self::default_result()
+    }
+
+    fn visit_basic_block(&mut self, basic_block: &mut BasicBlock) -> Self::Result {
+        walk_basic_block_mut(self, basic_block)
+    }
+
+    fn visit_node(&mut self, node: &mut Node) -> Self::Result {
+        walk_node_mut(self, node)
+    }
+
+    #[allow(unused_variables)]
+    fn visit_binary_node(&mut self, binary_node: &mut BinaryNode) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_branch_cond_node(&mut self, branch_cond_node: &mut BranchCondNode) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_switch_node(&mut self, switch_node: &mut SwitchNode) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_branch_node(&mut self, branch_node: &mut BranchNode) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_load_node(&mut self, load_node: &mut LoadNode) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_store_node(&mut self, store_node: &mut StoreNode) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_index_node(&mut self, index_node: &mut IndexNode) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_byte_access_node(&mut self, byte_access_node: &mut ByteAccessNode) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_return_node(&mut self, return_node: &mut ReturnNode) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_call_node(&mut self, call_node: &mut CallNode) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_ty_cast_node(&mut self, ty_cast_node: &mut TyCastNode) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_drop_node(&mut self, drop_node: &mut DropNode) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_phi_node(&mut self, phi_node: &mut PhiNode) -> Self::Result {
+        Self::default_result()
+    }
+
+    #[allow(unused_variables)]
+    fn visit_unreachable_node(&mut self, unreachable_node: &mut UnreachableNode) -> Self::Result {
+        Self::default_result()
+    }
+}
+
+pub fn walk_cfg_mut<V>(visitor: &mut V, cfg: &mut Cfg) -> V::Result where V: CfgVisitorMut {
+    cfg.args.iter_mut().for_each(|arg| {
+        visitor.visit_arg(arg);
+    });
+    cfg.local_mems.iter_mut().for_each(|local_mem| {
+        visitor.visit_local_mem(local_mem);
+    });
+    cfg.result_mems.iter_mut().for_each(|result_mem| {
+        visitor.visit_result_mem(result_mem);
+    });
+    cfg.basic_blocks.iter_mut().for_each(|basic_block| {
+        visitor.visit_basic_block(basic_block);
+    });
+
+    V::default_result()
+}
+
+pub fn walk_basic_block_mut<V>(visitor: &mut V, basic_block: &mut BasicBlock) -> V::Result
+    where V: CfgVisitorMut
+{
+    basic_block.nodes.iter_mut().for_each(|node| {
+        visitor.visit_node(node);
+    });
+
+    V::default_result()
+}
+
+pub fn walk_node_mut<V>(visitor: &mut V, node: &mut Node) -> V::Result where V: CfgVisitorMut {
+    match &mut node.kind {
+        NodeKind::BinaryNode(binary_node) => visitor.visit_binary_node(binary_node),
+        NodeKind::BranchCondNode(branch_cond_node) =>
+            visitor.visit_branch_cond_node(branch_cond_node),
+        NodeKind::SwitchNode(switch_node) => visitor.visit_switch_node(switch_node),
+        NodeKind::StoreNode(store_node) => visitor.visit_store_node(store_node),
+        NodeKind::BranchNode(branch_node) => visitor.visit_branch_node(branch_node),
+        NodeKind::LoadNode(load_node) => visitor.visit_load_node(load_node),
+        NodeKind::IndexNode(index_node) => visitor.visit_index_node(index_node),
+        NodeKind::ByteAccessNode(byte_access_node) =>
+            visitor.visit_byte_access_node(byte_access_node),
+        NodeKind::ReturnNode(return_node) => visitor.visit_return_node(return_node),
+        NodeKind::CallNode(call_node) => visitor.visit_call_node(call_node),
+        NodeKind::TyCastNode(ty_cast_node) => visitor.visit_ty_cast_node(ty_cast_node),
+        NodeKind::DropNode(drop_node) => visitor.visit_drop_node(drop_node),
+        NodeKind::PhiNode(phi_node) => visitor.visit_phi_node(phi_node),
+        NodeKind::UnreachableNode(unreachable_node) =>
+            visitor.visit_unreachable_node(unreachable_node),
+    }
+}
@@ -0,0 +1,532 @@
+/*
+
+Constant folding and propagation
+
+Evaluates nodes whose operands are all constants at compile time:
+
+- Binary nodes (e.g. `2 * 3`) and type casts of constants are replaced by their value
+- Variables which are only written to once (with a constant) are replaced by that constant
+- Conditional branches on a constant become unconditional
+
+This is repeated until nothing more can be folded, since folding a node can make the nodes using
+its result constant as well. Folded nodes are removed if their result is no longer used (e.g. it's
+still used as an argument of a call)
+
+Arithmetic that overflows the type of the operation is reported as a warning. The value is then
+wrapped around (two's complement)
+
+Division by a divisor which folds to zero is also reported as a warning, but isn't folded (it's
+left to fail at runtime)
+
+*/
+
+use diagnostics::{ Diagnostic, WarningKind };
+use fxhash::{ FxHashMap, FxHashSet };
+use ir::{ IntTy, PrimTy, TempId, Ty };
+use op::{ ArithmeticOp, BinaryOp, ComparisonOp };
+use span::Span;
+
+use crate::{
+    BinaryNode,
+    BranchCondNode,
+    BranchNode,
+    Cfg,
+    CfgVisitorMut,
+    Const,
+    Icfg,
+    IndexNode,
+    Node,
+    NodeKind,
+    Operand,
+    PlaceKind,
+    ReturnNode,
+    StoreNode,
+    SwitchNode,
+    TyCastKind,
+    TyCastNode,
+};
+
+impl Icfg<'_> {
+    /// Folds constant expressions and branches in all cfgs
+    pub fn fold_constants(&mut self) {
+        let mut diagnostics = Vec::new();
+
+        for cfg in self.cfgs.iter_mut() {
+            let warnings = ConstFolder::new(cfg).fold(cfg);
+
+            // Compiler generated functions don't overflow or divide by zero
+            if let Some(mod_id) = cfg.get_mod_id() {
+                diagnostics.extend(
+                    warnings
+                        .into_iter()
+                        .map(|(warning_kind, span)| {
+                            Diagnostic::new_warning(warning_kind, span, mod_id)
+                        })
+                );
+            }
+        }
+
+        diagnostics::report_diagnostics(diagnostics);
+    }
+}
+
+struct ConstFolder {
+    /// Value of each variable which is only written to once with a constant
+    local_mem_consts: Vec<Option<Const>>,
+    temp_consts: FxHashMap<TempId, Const>,
+    warnings: Vec<(WarningKind, Span)>,
+}
+
+impl ConstFolder {
+    fn new(cfg: &Cfg) -> Self {
+        Self {
+            local_mem_consts: get_local_mem_consts(cfg),
+            temp_consts: Default::default(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn fold(mut self, cfg: &mut Cfg) -> Vec<(WarningKind, Span)> {
+        let mut folded_temp_ids = FxHashSet::default();
+
+        loop {
+            let mut changed = false;
+
+            for basic_block in cfg.basic_blocks.iter_mut() {
+                for node in basic_block.nodes.iter_mut() {
+                    self.visit_node(node);
+                    changed |= self.fold_node(node, &mut folded_temp_ids);
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        // Divisions by zero are never folded, so each one is reported once here
+        for node in cfg.basic_blocks.iter().flat_map(|basic_block| basic_block.nodes.iter()) {
+            if
+                let NodeKind::BinaryNode(
+                    BinaryNode {
+                        op: BinaryOp::ArithmeticOp(ArithmeticOp::Div),
+                        rhs: Operand::Const(Const::Int(0, _)),
+                        ..
+                    },
+                ) = node.kind
+            {
+                if let Some(span) = node.span {
+                    self.warnings.push((WarningKind::DivisionByZero, span));
+                }
+            }
+        }
+
+        // Nodes computing a constant are removed when their result is no longer used
+        let used_temp_ids = cfg.basic_blocks
+            .iter()
+            .flat_map(|basic_block| basic_block.nodes.iter())
+            .flat_map(|node| node.kind.get_places())
+            .filter_map(|place| {
+                match place {
+                    PlaceKind::TempId(temp_id) => Some(temp_id),
+                    _ => None,
+                }
+            })
+            .collect::<FxHashSet<_>>();
+
+        for basic_block in cfg.basic_blocks.iter_mut() {
+            basic_block.nodes.retain(|node| {
+                let result_place = match node.kind {
+                    NodeKind::BinaryNode(binary_node) => binary_node.result_place,
+                    NodeKind::TyCastNode(ty_cast_node) => ty_cast_node.result_place,
+                    NodeKind::LoadNode(load_node) => load_node.result_place,
+                    _ => {
+                        return true;
+                    }
+                };
+
+                !folded_temp_ids.contains(&result_place) || used_temp_ids.contains(&result_place)
+            });
+        }
+
+        self.warnings
+    }
+
+    /// Returns true if the node was folded
+    fn fold_node(&mut self, node: &mut Node, folded_temp_ids: &mut FxHashSet<TempId>) -> bool {
+        let (result_place, result) = match node.kind {
+            NodeKind::BranchCondNode(
+                BranchCondNode { condition: Operand::Const(Const::Bool(condition)), .. },
+            ) => {
+                let NodeKind::BranchCondNode(branch_cond_node) = node.kind else {
+                    unreachable!();
+                };
+                let branch = if condition {
+                    branch_cond_node.true_branch
+                } else {
+                    branch_cond_node.false_branch
+                };
+                node.kind = NodeKind::BranchNode(BranchNode::new(branch));
+                return true;
+            }
+            NodeKind::SwitchNode(
+                SwitchNode { value: Operand::Const(Const::Int(value, _)), default, cases, .. },
+            ) => {
+                let branch = cases
+                    .iter()
+                    .find(|(case, _)| *case == value)
+                    .map_or(default, |(_, branch)| *branch);
+                node.kind = NodeKind::BranchNode(BranchNode::new(branch));
+                return true;
+            }
+            NodeKind::LoadNode(load_node) => {
+                let PlaceKind::LocalMemId(local_mem_id) = load_node.load_place else {
+                    return false;
+                };
+                let Some(value) = self.local_mem_consts[local_mem_id.0 as usize] else {
+                    return false;
+                };
+                (load_node.result_place, value)
+            }
+            // Overflows are only reported the first time the node is folded
+            NodeKind::BinaryNode(binary_node) if
+                !folded_temp_ids.contains(&binary_node.result_place)
+            => {
+                let Some(result) = self.fold_binary_node(&binary_node, node.span) else {
+                    return false;
+                };
+                (binary_node.result_place, result)
+            }
+            NodeKind::TyCastNode(ty_cast_node) => {
+                let Some(result) = fold_ty_cast_node(&ty_cast_node) else {
+                    return false;
+                };
+                (ty_cast_node.result_place, result)
+            }
+            _ => {
+                return false;
+            }
+        };
+
+        if !folded_temp_ids.insert(result_place) {
+            return false;
+        }
+        self.temp_consts.insert(result_place, result);
+        true
+    }
+
+    fn fold_binary_node(&mut self, binary_node: &BinaryNode, span: Option<Span>) -> Option<Const> {
+        match (binary_node.lhs, binary_node.rhs) {
+            (Operand::Const(Const::Int(lhs, _)), Operand::Const(Const::Int(rhs, _))) => {
+                let Ty::PrimTy(PrimTy::Int(int_ty)) = binary_node.op_ty else {
+                    return None;
+                };
+                let (lhs, rhs) = (lhs as i128, rhs as i128);
+
+                let result = match binary_node.op {
+                    BinaryOp::ArithmeticOp(ArithmeticOp::Add) => lhs + rhs,
+                    BinaryOp::ArithmeticOp(ArithmeticOp::Sub) => lhs - rhs,
+                    BinaryOp::ArithmeticOp(ArithmeticOp::Mul) => lhs * rhs,
+                    // Division by zero is left to runtime (and reported after folding)
+                    BinaryOp::ArithmeticOp(ArithmeticOp::Div) if rhs == 0 => {
+                        return None;
+                    }
+                    BinaryOp::ArithmeticOp(ArithmeticOp::Div) => lhs / rhs,
+                    BinaryOp::ComparisonOp(comparison_op) => {
+                        return Some(Const::Bool(compare(comparison_op, lhs, rhs)));
+                    }
+                };
+
+                let wrapped = wrap_int(result, int_ty);
+                if wrapped as i128 != result {
+                    if let Some(span) = span {
+                        self.warnings.push((WarningKind::ArithmeticOverflow, span));
+                    }
+                }
+
+                Some(Const::Int(wrapped, int_ty))
+            }
+            (Operand::Const(Const::Bool(lhs)), Operand::Const(Const::Bool(rhs))) => {
+                match binary_node.op {
+                    BinaryOp::ComparisonOp(comparison_op) => {
+                        Some(Const::Bool(compare(comparison_op, lhs, rhs)))
+                    }
+                    BinaryOp::ArithmeticOp(_) => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Substitutes temps which have been folded with their value
+impl CfgVisitorMut for ConstFolder {
+    type Result = ();
+
+    fn default_result() -> Self::Result {}
+
+    fn visit_binary_node(&mut self, binary_node: &mut BinaryNode) -> Self::Result {
+        self.substitute(&mut binary_node.lhs);
+        self.substitute(&mut binary_node.rhs);
+    }
+
+    fn visit_branch_cond_node(&mut self, branch_cond_node: &mut BranchCondNode) -> Self::Result {
+        self.substitute(&mut branch_cond_node.condition);
+    }
+
+    fn visit_switch_node(&mut self, switch_node: &mut SwitchNode) -> Self::Result {
+        self.substitute(&mut switch_node.value);
+    }
+
+    fn visit_store_node(&mut self, store_node: &mut StoreNode) -> Self::Result {
+        self.substitute(&mut store_node.value);
+    }
+
+    fn visit_index_node(&mut self, index_node: &mut IndexNode) -> Self::Result {
+        self.substitute(&mut index_node.index);
+    }
+
+    fn visit_return_node(&mut self, return_node: &mut ReturnNode) -> Self::Result {
+        self.substitute(&mut return_node.ret_val);
+    }
+
+    fn visit_ty_cast_node(&mut self, ty_cast_node: &mut TyCastNode) -> Self::Result {
+        self.substitute(&mut ty_cast_node.operand);
+    }
+}
+
+impl ConstFolder {
+    fn substitute(&self, operand: &mut Operand) {
+        if let Operand::PlaceKind(PlaceKind::TempId(temp_id)) = operand {
+            if let Some(value) = self.temp_consts.get(temp_id) {
+                *operand = Operand::Const(*value);
+            }
+        }
+    }
+}
+
+/// Finds the variables that are written to exactly once with a constant, and are otherwise
+/// only loaded from (e.g. not referenced by another variable or passed as a pointer)
+fn get_local_mem_consts(cfg: &Cfg) -> Vec<Option<Const>> {
+    let mut stores = vec![0; cfg.local_mems.len()];
+    let mut values = vec![None; cfg.local_mems.len()];
+    let mut is_only_loaded_or_stored = cfg.local_mems
+        .iter()
+        .map(|local_mem| !local_mem.requires_drop)
+        .collect::<Vec<_>>();
+
+    for node in cfg.basic_blocks.iter().flat_map(|basic_block| basic_block.nodes.iter()) {
+        match node.kind {
+            NodeKind::StoreNode(
+                StoreNode { setter: PlaceKind::LocalMemId(local_mem_id), value, op_ty, .. },
+            ) => {
+                let i = local_mem_id.0 as usize;
+                stores[i] += 1;
+                values[i] = match value {
+                    Operand::Const(value) if is_propagated(value, op_ty) => Some(value),
+                    _ => None,
+                };
+                if let Operand::PlaceKind(PlaceKind::LocalMemId(local_mem_id)) = value {
+                    is_only_loaded_or_stored[local_mem_id.0 as usize] = false;
+                }
+            }
+            NodeKind::LoadNode(load_node) => {
+                if let PlaceKind::LocalMemId(local_mem_id) = load_node.load_place {
+                    let i = local_mem_id.0 as usize;
+                    if load_node.load_ty != cfg.local_mems[i].ty {
+                        is_only_loaded_or_stored[i] = false;
+                    }
+                }
+            }
+            _ => {
+                for place in node.kind.get_places() {
+                    if let PlaceKind::LocalMemId(local_mem_id) = place {
+                        is_only_loaded_or_stored[local_mem_id.0 as usize] = false;
+                    }
+                }
+            }
+        }
+    }
+
+    (0..cfg.local_mems.len())
+        .map(|i| {
+            if stores[i] == 1 && is_only_loaded_or_stored[i] { values[i] } else { None }
+        })
+        .collect()
+}
+
+/// Only constants which are never used as places are propagated
+fn is_propagated(value: Const, ty: Ty) -> bool {
+    match value {
+        Const::Int(_, int_ty) => ty == Ty::PrimTy(PrimTy::Int(int_ty)),
+        Const::Bool(_) => ty == Ty::PrimTy(PrimTy::Bool),
+        Const::FnPtr(_) | Const::Str(_) | Const::Null | Const::Void => false,
+    }
+}
+
+fn fold_ty_cast_node(ty_cast_node: &TyCastNode) -> Option<Const> {
+    let Ty::PrimTy(PrimTy::Int(to_int_ty)) = ty_cast_node.to_ty else {
+        return None;
+    };
+    let Ty::PrimTy(PrimTy::Int(from_int_ty)) = ty_cast_node.from_ty else {
+        return None;
+    };
+    let Operand::Const(Const::Int(value, _)) = ty_cast_node.operand else {
+        return None;
+    };
+
+    let value = match ty_cast_node.cast_kind {
+        TyCastKind::Trunc | TyCastKind::Sext => value as i128,
+        TyCastKind::Zext => (value as i128) & ((1i128 << get_bits(from_int_ty)) - 1),
+    };
+
+    Some(Const::Int(wrap_int(value, to_int_ty), to_int_ty))
+}
+
+fn compare<T: PartialOrd>(comparison_op: ComparisonOp, lhs: T, rhs: T) -> bool {
+    match comparison_op {
+        ComparisonOp::Eq => lhs == rhs,
+        ComparisonOp::Ne => lhs != rhs,
+        ComparisonOp::Ge => lhs >= rhs,
+        ComparisonOp::Gt => lhs > rhs,
+        ComparisonOp::Le => lhs <= rhs,
+        ComparisonOp::Lt => lhs < rhs,
+    }
+}
+
+fn get_bits(int_ty: IntTy) -> u32 {
+    match int_ty {
+        IntTy::Int8 => 8,
+        IntTy::Int16 => 16,
+        IntTy::Int32 => 32,
+        IntTy::Int64 => 64,
+    }
+}
+
+/// Wraps a value around to fit in a signed integer of the given type
+fn wrap_int(value: i128, int_ty: IntTy) -> i64 {
+    let shift = 128 - get_bits(int_ty);
+    ((value << shift) >> shift) as i64
+}
+
+#[cfg(test)]
+mod test {
+    use diagnostics::WarningKind;
+    use span::Span;
+
+    use crate::{ const_folding::ConstFolder, Icfg, IcfgParser, IcfgPrettifier };
+
+    /// Folds the first cfg, where every node has a span so warnings are reported
+    fn fold(src: &str) -> (Icfg<'static>, Vec<WarningKind>) {
+        let mut icfg = IcfgParser::new(src).parse_icfg().unwrap();
+        let cfg = &mut icfg.cfgs[0];
+        for basic_block in cfg.basic_blocks.iter_mut() {
+            for node in basic_block.nodes.iter_mut() {
+                node.span = Some(Span::dummy());
+            }
+        }
+
+        let warnings = ConstFolder::new(cfg).fold(cfg);
+        let warnings = warnings
+            .into_iter()
+            .map(|(warning_kind, _)| warning_kind)
+            .collect();
+        (icfg, warnings)
+    }
+
+    #[test]
+    fn fold_constants() {
+        let src =
+            r##"
+fn f#0:0(_0: int32) -> int32 {
+    local l0 x: int32
+bb0:
+    store init int32 3i32, l0
+    _2 = load int32, l0
+    _3 = mul int32 _2, 4i32
+    _4 = gt int32 _3, 10i32
+    br _4, bb1, bb2
+bb1:
+    _5 = add int32 _0, _3
+    ret int32 _5
+bb2:
+    ret int32 0i32
+}
+"##;
+        let (icfg, warnings) = fold(src);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        let expected =
+            r##"
+fn f#0:0(_0: int32) -> int32 {
+    local l0 x: int32
+bb0:
+    store init int32 3i32, l0
+    br bb1
+bb1:
+    _5 = add int32 _0, 12i32
+    ret int32 _5
+bb2:
+    ret int32 0i32
+}
+"##;
+        assert_eq!(IcfgPrettifier::new(&icfg).prettify().trim(), expected.trim());
+    }
+
+    #[test]
+    fn folded_overflow() {
+        let src =
+            r##"
+fn f#0:0() -> int32 {
+bb0:
+    _0 = mul int32 200000i32, 200000i32
+    _1 = add int8 127i8, 1i8
+    _2 = sext int8 _1 to int32
+    _3 = add int32 _0, _2
+    ret int32 _3
+}
+"##;
+        let (icfg, warnings) = fold(src);
+
+        // Both the multiplication and the addition overflow, and are wrapped around
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings.iter().all(|warning| matches!(warning, WarningKind::ArithmeticOverflow)));
+
+        let expected =
+            r##"
+fn f#0:0() -> int32 {
+bb0:
+    ret int32 1345294208i32
+}
+"##;
+        assert_eq!(IcfgPrettifier::new(&icfg).prettify().trim(), expected.trim());
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let src =
+            r##"
+fn f#0:0(_0: int32) -> int32 {
+    local l0 zero: int32
+bb0:
+    store init int32 0i32, l0
+    _1 = load int32, l0
+    _2 = div int32 _0, _1
+    _3 = div int32 10i32, 0i32
+    _4 = div int32 _0, 2i32
+    _5 = add int32 _2, _3
+    _6 = add int32 _5, _4
+    ret int32 _6
+}
+"##;
+        let (icfg, warnings) = fold(src);
+
+        // Divisions by zero are reported once each, and aren't folded
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings.iter().all(|warning| matches!(warning, WarningKind::DivisionByZero)));
+
+        let prettified = IcfgPrettifier::new(&icfg).prettify();
+        assert!(prettified.contains("_2 = div int32 _0, 0i32"), "{}", prettified);
+        assert!(prettified.contains("_3 = div int32 10i32, 0i32"), "{}", prettified);
+    }
+}
@@ -0,0 +1,380 @@
+/*
+
+Dataflow framework
+
+Analyses over a cfg (e.g. definite initialization, moves and drops) are written as an
+implementation of `DataflowAnalysis`, which describes:
+
+- The direction of the analysis (forward or backward)
+- The state at the boundary of the cfg (the entry of the cfg, or each return if backward)
+- How two states are joined where control flow merges
+- How each node changes the state (its transfer function)
+- Optionally how the state changes along an edge between two basic blocks (e.g. refined by the
+  condition of a branch)
+
+The solver then runs a worklist algorithm (in reverse postorder, or postorder if backward) until
+the states at the start of each basic block (in the direction of the analysis) reach a fixpoint
+
+Basic blocks which aren't reached by the analysis (e.g. unreachable code, or code that never
+returns in a backward analysis) have no state
+
+*/
+
+use std::collections::BTreeSet;
+
+use crate::{ BasicBlockId, Cfg, Node, NodeIndex };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+/// Position of a node in a cfg
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
+pub struct NodeLocation {
+    pub basic_block_id: BasicBlockId,
+    pub node_index: NodeIndex,
+}
+
+/// Successors and predecessors of each basic block in a cfg (derived from its branches)
+#[derive(Debug)]
+pub struct CfgGraph {
+    successors: Vec<Vec<BasicBlockId>>,
+    predecessors: Vec<Vec<BasicBlockId>>,
+}
+
+impl CfgGraph {
+    pub fn new(cfg: &Cfg) -> Self {
+        let successors = cfg.basic_blocks
+            .iter()
+            .map(|basic_block| basic_block.get_successors())
+            .collect::<Vec<_>>();
+
+        let mut predecessors = vec![Vec::new(); cfg.basic_blocks.len()];
+        for (bb_id, bb_successors) in successors.iter().enumerate() {
+            for successor in bb_successors.iter() {
+                predecessors[successor.0 as usize].push(BasicBlockId(bb_id as u32));
+            }
+        }
+
+        Self { successors, predecessors }
+    }
+
+    pub fn successors(&self, basic_block_id: BasicBlockId) -> &[BasicBlockId] {
+        &self.successors[basic_block_id.0 as usize]
+    }
+
+    pub fn predecessors(&self, basic_block_id: BasicBlockId) -> &[BasicBlockId] {
+        &self.predecessors[basic_block_id.0 as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.successors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.successors.is_empty()
+    }
+
+    /// Basic blocks reachable from the entry, where each block comes after its children
+    pub fn postorder(&self) -> Vec<BasicBlockId> {
+        let mut postorder = Vec::with_capacity(self.len());
+        if self.is_empty() {
+            return postorder;
+        }
+
+        let mut visited = vec![false; self.len()];
+        // Each entry is a basic block and the index of the next successor to visit
+        let mut stack = vec![(BasicBlockId(0), 0)];
+        visited[0] = true;
+
+        while let Some((bb_id, successor_index)) = stack.last_mut() {
+            let bb_id = *bb_id;
+            match self.successors(bb_id).get(*successor_index) {
+                Some(successor) => {
+                    *successor_index += 1;
+                    if !visited[successor.0 as usize] {
+                        visited[successor.0 as usize] = true;
+                        stack.push((*successor, 0));
+                    }
+                }
+                None => {
+                    stack.pop();
+                    postorder.push(bb_id);
+                }
+            }
+        }
+
+        postorder
+    }
+
+    /// Basic blocks reachable from the entry, where each block comes before its children
+    /// (ignoring back edges of loops)
+    pub fn reverse_postorder(&self) -> Vec<BasicBlockId> {
+        let mut order = self.postorder();
+        order.reverse();
+        order
+    }
+
+    /// Basic blocks without any successors (i.e. returns)
+    pub fn exits(&self) -> Vec<BasicBlockId> {
+        (0..self.len() as u32)
+            .map(BasicBlockId)
+            .filter(|bb_id| self.successors(*bb_id).is_empty())
+            .collect()
+    }
+
+    /// The immediate dominator of each basic block reachable from the entry
+    /// (the entry is its own immediate dominator)
+    ///
+    /// Uses the algorithm from "A Simple, Fast Dominance Algorithm" by Cooper, Harvey and Kennedy
+    pub fn immediate_dominators(&self) -> Vec<Option<BasicBlockId>> {
+        let mut idoms = vec![None; self.len()];
+        if self.is_empty() {
+            return idoms;
+        }
+
+        let order = self.reverse_postorder();
+        let mut order_index = vec![usize::MAX; self.len()];
+        for (i, bb_id) in order.iter().enumerate() {
+            order_index[bb_id.0 as usize] = i;
+        }
+
+        let intersect = |idoms: &[Option<BasicBlockId>], a: BasicBlockId, b: BasicBlockId| {
+            let (mut a, mut b) = (a, b);
+            while a != b {
+                while order_index[a.0 as usize] > order_index[b.0 as usize] {
+                    a = idoms[a.0 as usize].expect("Expected processed block");
+                }
+                while order_index[b.0 as usize] > order_index[a.0 as usize] {
+                    b = idoms[b.0 as usize].expect("Expected processed block");
+                }
+            }
+            a
+        };
+
+        idoms[0] = Some(BasicBlockId(0));
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for bb_id in order.iter().skip(1) {
+                let mut new_idom = None;
+                for predecessor in self.predecessors(*bb_id) {
+                    if idoms[predecessor.0 as usize].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        Some(new_idom) => intersect(&idoms, *predecessor, new_idom),
+                        None => *predecessor,
+                    });
+                }
+
+                if new_idom.is_some() && idoms[bb_id.0 as usize] != new_idom {
+                    idoms[bb_id.0 as usize] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        idoms
+    }
+
+    /// The dominance frontier of each basic block, i.e. the blocks where its dominance ends
+    pub fn dominance_frontiers(
+        &self,
+        idoms: &[Option<BasicBlockId>]
+    ) -> Vec<BTreeSet<BasicBlockId>> {
+        let mut frontiers = vec![BTreeSet::new(); self.len()];
+
+        for (bb_id, idom) in idoms.iter().enumerate() {
+            let Some(idom) = idom else {
+                continue;
+            };
+            let predecessors = self.predecessors(BasicBlockId(bb_id as u32));
+            if predecessors.len() < 2 {
+                continue;
+            }
+
+            for predecessor in predecessors {
+                let mut runner = *predecessor;
+                // Unreachable predecessors have no dominator
+                while idoms[runner.0 as usize].is_some() && runner != *idom {
+                    frontiers[runner.0 as usize].insert(BasicBlockId(bb_id as u32));
+                    runner = idoms[runner.0 as usize].unwrap();
+                }
+            }
+        }
+
+        frontiers
+    }
+}
+
+pub trait DataflowAnalysis {
+    type Domain: Clone;
+
+    const DIRECTION: Direction = Direction::Forward;
+
+    /// State at the entry of the cfg (or at each return if it's a backward analysis)
+    fn boundary_state(&self, cfg: &Cfg) -> Self::Domain;
+
+    /// Joins `other` into `state`, returns true if `state` changed
+    fn join(&self, state: &mut Self::Domain, other: &Self::Domain) -> bool;
+
+    fn transfer_node(
+        &mut self,
+        node: &Node,
+        location: NodeLocation,
+        state: &mut Self::Domain,
+        cfg: &Cfg
+    );
+
+    /// Changes the state flowing from `from` to `to` (in the direction of the analysis), e.g. to
+    /// refine it with the condition of the branch between them
+    fn transfer_edge(
+        &mut self,
+        _from: BasicBlockId,
+        _to: BasicBlockId,
+        _state: &mut Self::Domain,
+        _cfg: &Cfg
+    ) {}
+
+    /// Applies the transfer function of each node in a basic block (in the direction of the analysis)
+    fn transfer_basic_block(
+        &mut self,
+        basic_block_id: BasicBlockId,
+        state: &mut Self::Domain,
+        cfg: &Cfg
+    ) {
+        for_each_location(basic_block_id, Self::DIRECTION, cfg, |node, location| {
+            self.transfer_node(node, location, state, cfg);
+        });
+    }
+
+    /// Runs the analysis to a fixpoint
+    fn solve(&mut self, cfg: &Cfg) -> DataflowResults<Self::Domain> where Self: Sized {
+        solve(self, cfg)
+    }
+}
+
+/// The state of each basic block at its start (or its end if it's a backward analysis)
+#[derive(Debug)]
+pub struct DataflowResults<D> {
+    pub direction: Direction,
+    pub states: Vec<Option<D>>,
+}
+
+impl<D: Clone> DataflowResults<D> {
+    pub fn get_state(&self, basic_block_id: BasicBlockId) -> Option<&D> {
+        self.states[basic_block_id.0 as usize].as_ref()
+    }
+
+    /// Visits each node of the reached basic blocks (in the direction of the analysis) along with
+    /// the state right before its transfer function is applied
+    pub fn visit_with<A>(
+        &self,
+        cfg: &Cfg,
+        analysis: &mut A,
+        mut visit: impl FnMut(&mut A, &Node, NodeLocation, &D)
+    )
+        where A: DataflowAnalysis<Domain = D>
+    {
+        for (bb_id, state) in self.states.iter().enumerate() {
+            let Some(mut state) = state.clone() else {
+                continue;
+            };
+
+            for_each_location(BasicBlockId(bb_id as u32), self.direction, cfg, |node, location| {
+                visit(analysis, node, location, &state);
+                analysis.transfer_node(node, location, &mut state, cfg);
+            });
+        }
+    }
+}
+
+fn for_each_location(
+    basic_block_id: BasicBlockId,
+    direction: Direction,
+    cfg: &Cfg,
+    mut f: impl FnMut(&Node, NodeLocation)
+) {
+    let nodes = &cfg.basic_blocks[basic_block_id.0 as usize].nodes;
+    let location = |i: usize| NodeLocation { basic_block_id, node_index: NodeIndex(i as u32) };
+
+    match direction {
+        Direction::Forward => {
+            for (i, node) in nodes.iter().enumerate() {
+                f(node, location(i));
+            }
+        }
+        Direction::Backward => {
+            for (i, node) in nodes.iter().enumerate().rev() {
+                f(node, location(i));
+            }
+        }
+    }
+}
+
+fn solve<A: DataflowAnalysis>(analysis: &mut A, cfg: &Cfg) -> DataflowResults<A::Domain> {
+    let graph = CfgGraph::new(cfg);
+    let mut states: Vec<Option<A::Domain>> = vec![None; graph.len()];
+
+    let (order, boundary) = match A::DIRECTION {
+        Direction::Forward => (graph.reverse_postorder(), vec![BasicBlockId(0)]),
+        Direction::Backward => (graph.postorder(), graph.exits()),
+    };
+    if graph.is_empty() {
+        return DataflowResults { direction: A::DIRECTION, states };
+    }
+
+    // Basic blocks are always taken from the worklist in the order of the analysis
+    let mut order_index = vec![usize::MAX; graph.len()];
+    for (i, bb_id) in order.iter().enumerate() {
+        order_index[bb_id.0 as usize] = i;
+    }
+
+    let mut worklist = BTreeSet::new();
+    for bb_id in boundary {
+        // Unreachable returns are not part of the analysis
+        if order_index[bb_id.0 as usize] == usize::MAX {
+            continue;
+        }
+        states[bb_id.0 as usize] = Some(analysis.boundary_state(cfg));
+        worklist.insert(order_index[bb_id.0 as usize]);
+    }
+
+    while let Some(i) = worklist.pop_first() {
+        let bb_id = order[i];
+        let mut state = states[bb_id.0 as usize].clone().expect("Expected reached block");
+        analysis.transfer_basic_block(bb_id, &mut state, cfg);
+
+        let next_bb_ids = match A::DIRECTION {
+            Direction::Forward => graph.successors(bb_id),
+            Direction::Backward => graph.predecessors(bb_id),
+        };
+
+        for next_bb_id in next_bb_ids {
+            let next_index = order_index[next_bb_id.0 as usize];
+            if next_index == usize::MAX {
+                continue;
+            }
+
+            let mut edge_state = state.clone();
+            analysis.transfer_edge(bb_id, *next_bb_id, &mut edge_state, cfg);
+
+            let changed = match &mut states[next_bb_id.0 as usize] {
+                Some(next_state) => analysis.join(next_state, &edge_state),
+                next_state @ None => {
+                    *next_state = Some(edge_state);
+                    true
+                }
+            };
+
+            if changed {
+                worklist.insert(next_index);
+            }
+        }
+    }
+
+    DataflowResults { direction: A::DIRECTION, states }
+}
@@ -0,0 +1,889 @@
+/*
+
+Drop elaboration
+
+The builder emits a `DropNode` for each owning variable on every exit from its scope. This pass
+decides for each of these drops, if the variable is initialized at that point:
+
+- Definitely initialized: The variable is always dropped
+- Definitely moved (or never initialized): The drop is removed
+- Only moved on some paths: The variable gets a drop flag, which is set when it's initialized
+  and cleared when it's moved. The drop is then only done if the flag is set
+
+A variable is moved when its whole value is loaded (e.g. passed to a function or returned). The
+fields of a struct (or tuple) without `onDrop` are tracked on their own as drop paths, so when
+some of them are moved out of the variable, only the remaining ones are dropped
+
+Lastly the drop glue for each dropped type is built (calling `onDrop` and dropping the fields)
+
+*/
+
+use std::ops::Range;
+
+use data_structures::FxIndexSet;
+use ir::{
+    Adt,
+    CfgFnKind,
+    GetTyAttr,
+    IntTy,
+    LocalMem,
+    LocalMemId,
+    Mutability,
+    NameBindingKind,
+    ResolvedInformation,
+    Symbol,
+    TempId,
+    Ty,
+    TyCtx,
+    INT_64_TY,
+    INT_8_TY,
+    VOID_TY,
+};
+use op::{ BinaryOp, ComparisonOp };
+
+use crate::{
+    move_checker::{ PlaceOrigin, PlaceOrigins },
+    BasicBlock,
+    BasicBlockId,
+    BinaryNode,
+    BranchCondNode,
+    BranchNode,
+    ByteAccessNode,
+    CallNode,
+    Cfg,
+    Const,
+    DataflowAnalysis,
+    DropNode,
+    Icfg,
+    LoadNode,
+    Node,
+    NodeKind,
+    NodeLocation,
+    Operand,
+    PlaceKind,
+    ReturnNode,
+    StoreKind,
+    StoreNode,
+};
+
+impl Icfg<'_> {
+    /// Computes drop flags of all cfgs, and builds the drop glue of all dropped types
+    pub fn elaborate_drops(&mut self) {
+        for cfg in self.cfgs.iter_mut() {
+            elaborate_cfg_drops(cfg, &self.resolved_information);
+        }
+
+        let mut dropped_tys = FxIndexSet::default();
+        for cfg in self.cfgs.iter() {
+            for node in cfg.basic_blocks.iter().flat_map(|basic_block| basic_block.nodes.iter()) {
+                if let NodeKind::DropNode(drop_node) = node.kind {
+                    dropped_tys.insert(drop_node.ty);
+                }
+            }
+        }
+
+        // Drop glue can drop other types (its fields), which are added to the end of the set
+        let mut i = 0;
+        while i < dropped_tys.len() {
+            let drop_glue = DropGlueBuilder::new(&self.resolved_information).build(dropped_tys[i]);
+
+            for node in drop_glue.basic_blocks.iter().flat_map(|basic_block| basic_block.nodes.iter()) {
+                if let NodeKind::DropNode(drop_node) = node.kind {
+                    dropped_tys.insert(drop_node.ty);
+                }
+            }
+
+            self.cfgs.push(drop_glue);
+            i += 1;
+        }
+    }
+}
+
+/// Whether a drop path may be initialized and/or may be uninitialized (moved) at some point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct InitState {
+    maybe_init: bool,
+    maybe_uninit: bool,
+}
+
+impl InitState {
+    const INIT: Self = Self { maybe_init: true, maybe_uninit: false };
+    const UNINIT: Self = Self { maybe_init: false, maybe_uninit: true };
+
+    fn join(self, other: Self) -> Self {
+        Self {
+            maybe_init: self.maybe_init || other.maybe_init,
+            maybe_uninit: self.maybe_uninit || other.maybe_uninit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DropKind {
+    Always,
+    Never,
+    Flagged,
+}
+
+/// Part of an owning variable which is dropped on its own. The fields of structs (and tuples)
+/// without `onDrop` are split into their own drop paths, so moving one of them out of the
+/// variable doesn't stop the others from being dropped
+#[derive(Debug, Clone, Copy)]
+struct DropPath {
+    local_mem_id: LocalMemId,
+    byte_offset: usize,
+    size_bytes: usize,
+    ty: Ty,
+}
+
+impl DropPath {
+    fn is_whole_var(&self, local_mems: &[LocalMem]) -> bool {
+        self.byte_offset == 0 && self.ty == local_mems[self.local_mem_id.0 as usize].ty
+    }
+}
+
+/// The drop paths of all owning variables of a cfg, where the drop paths of each variable are
+/// ordered by their byte offset
+struct DropPaths {
+    drop_paths: Vec<DropPath>,
+    local_mem_id_to_drop_paths: Vec<Range<usize>>,
+}
+
+impl DropPaths {
+    fn new(cfg: &Cfg, resolved_information: &ResolvedInformation) -> Self {
+        let mut drop_paths = Vec::new();
+        let mut local_mem_id_to_drop_paths = Vec::with_capacity(cfg.local_mems.len());
+
+        for local_mem in cfg.local_mems.iter() {
+            let start = drop_paths.len();
+            if local_mem.requires_drop {
+                Self::push_drop_paths(
+                    &mut drop_paths,
+                    local_mem.local_mem_id,
+                    0,
+                    local_mem.ty,
+                    resolved_information
+                );
+            }
+            local_mem_id_to_drop_paths.push(start..drop_paths.len());
+        }
+
+        Self { drop_paths, local_mem_id_to_drop_paths }
+    }
+
+    fn push_drop_paths(
+        drop_paths: &mut Vec<DropPath>,
+        local_mem_id: LocalMemId,
+        byte_offset: usize,
+        ty: Ty,
+        resolved_information: &ResolvedInformation
+    ) {
+        let fields = match ty {
+            Ty::Tuple(tys) => Some(tys.to_vec()),
+            Ty::Adt(def_id) if resolved_information.get_drop_fn(&def_id).is_none() => {
+                match resolved_information.get_name_binding_from_def_id(&def_id).kind {
+                    NameBindingKind::Adt(Adt::Struct(fields)) =>
+                        Some(
+                            fields
+                                .iter()
+                                .map(|(_, ty)| *ty)
+                                .collect::<Vec<_>>()
+                        ),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        let Some(fields) = fields else {
+            drop_paths.push(DropPath {
+                local_mem_id,
+                byte_offset,
+                size_bytes: ty.get_ty_attr(resolved_information).size_bytes,
+                ty,
+            });
+            return;
+        };
+
+        // Fields are laid out in order (each one aligned), like in the drop glue
+        let mut field_offset = byte_offset;
+        for field_ty in fields {
+            let field_ty_attr = field_ty.get_ty_attr(resolved_information);
+            field_offset = field_ty_attr.align_offset(field_offset);
+
+            if resolved_information.needs_drop(field_ty) {
+                Self::push_drop_paths(
+                    drop_paths,
+                    local_mem_id,
+                    field_offset,
+                    field_ty,
+                    resolved_information
+                );
+            }
+            field_offset += field_ty_attr.size_bytes;
+        }
+    }
+
+    fn get_drop_paths(&self, local_mem_id: LocalMemId) -> Range<usize> {
+        self.local_mem_id_to_drop_paths[local_mem_id.0 as usize].clone()
+    }
+
+    /// The drop paths of the variable which are inside of the bytes `start..end`, or which
+    /// overlap with them if `include_overlapping`
+    fn get_drop_paths_in(
+        &self,
+        local_mem_id: LocalMemId,
+        start: usize,
+        end: usize,
+        include_overlapping: bool
+    ) -> Range<usize> {
+        let drop_paths = self.get_drop_paths(local_mem_id);
+        let is_in = |drop_path: &DropPath| {
+            let drop_path_end = drop_path.byte_offset + drop_path.size_bytes;
+            if include_overlapping {
+                drop_path.byte_offset < end && start < drop_path_end
+            } else {
+                start <= drop_path.byte_offset && drop_path_end <= end
+            }
+        };
+
+        let paths = &self.drop_paths[drop_paths.clone()];
+        let first = paths.iter().position(is_in).unwrap_or(paths.len());
+        let count = paths[first..].iter().take_while(|drop_path| is_in(drop_path)).count();
+
+        drop_paths.start + first..drop_paths.start + first + count
+    }
+}
+
+/// Finds how nodes change the init state of the drop paths of owning variables
+struct InitEffects<'a, 'b> {
+    place_origins: PlaceOrigins,
+    drop_paths: DropPaths,
+    resolved_information: &'b ResolvedInformation<'a>,
+}
+
+impl InitEffects<'_, '_> {
+    fn get(&self, node: &Node) -> Option<(Range<usize>, InitState)> {
+        let (drop_paths, init_state) = match node.kind {
+            // Initializes the drop paths which are completely overwritten
+            NodeKind::StoreNode(store_node) => {
+                let origin = self.place_origins.get_origin(&store_node.setter)?;
+                let (start, end) = self.get_range(origin, store_node.op_ty);
+
+                (
+                    self.drop_paths.get_drop_paths_in(origin.local_mem_id, start, end, false),
+                    InitState::INIT,
+                )
+            }
+            // Moving a value out of a drop path, which needs to be dropped, means the rest of
+            // the drop path isn't dropped either (e.g. the payload of an enum variant)
+            NodeKind::LoadNode(load_node) => {
+                if self.resolved_information.is_copy(load_node.load_ty) {
+                    return None;
+                }
+
+                let origin = self.place_origins.get_origin(&load_node.load_place)?;
+                let (start, end) = self.get_range(origin, load_node.load_ty);
+                let include_overlapping = self.resolved_information.needs_drop(load_node.load_ty);
+
+                (
+                    self.drop_paths.get_drop_paths_in(
+                        origin.local_mem_id,
+                        start,
+                        end,
+                        include_overlapping
+                    ),
+                    InitState::UNINIT,
+                )
+            }
+            NodeKind::DropNode(DropNode { place: PlaceKind::LocalMemId(local_mem_id), .. }) =>
+                (self.drop_paths.get_drop_paths(local_mem_id), InitState::UNINIT),
+            _ => {
+                return None;
+            }
+        };
+
+        if drop_paths.is_empty() {
+            None
+        } else {
+            Some((drop_paths, init_state))
+        }
+    }
+
+    /// The bytes of the variable a value of type `ty` at `origin` takes up
+    fn get_range(&self, origin: PlaceOrigin, ty: Ty) -> (usize, usize) {
+        let start = origin.field_offset.unwrap_or(0);
+        (start, start + ty.get_ty_attr(self.resolved_information).size_bytes)
+    }
+}
+
+/// Forward dataflow analysis of the init states of all drop paths
+impl DataflowAnalysis for InitEffects<'_, '_> {
+    type Domain = Vec<InitState>;
+
+    fn boundary_state(&self, _cfg: &Cfg) -> Self::Domain {
+        vec![InitState::UNINIT; self.drop_paths.drop_paths.len()]
+    }
+
+    fn join(&self, state: &mut Self::Domain, other: &Self::Domain) -> bool {
+        let mut changed = false;
+        for (init_state, other_init_state) in state.iter_mut().zip(other.iter()) {
+            let joined = init_state.join(*other_init_state);
+            changed |= joined != *init_state;
+            *init_state = joined;
+        }
+        changed
+    }
+
+    fn transfer_node(
+        &mut self,
+        node: &Node,
+        _location: NodeLocation,
+        state: &mut Self::Domain,
+        _cfg: &Cfg
+    ) {
+        if let Some((drop_paths, init_state)) = self.get(node) {
+            state[drop_paths].fill(init_state);
+        }
+    }
+}
+
+fn elaborate_cfg_drops(cfg: &mut Cfg, resolved_information: &ResolvedInformation) {
+    if !cfg.local_mems.iter().any(|local_mem| local_mem.requires_drop) {
+        return;
+    }
+
+    let mut init_effects = InitEffects {
+        place_origins: PlaceOrigins::new(cfg),
+        drop_paths: DropPaths::new(cfg, resolved_information),
+        resolved_information,
+    };
+    let results = init_effects.solve(cfg);
+
+    // The kinds of the drop paths of each drop of a variable, in the order they appear in each
+    // basic block
+    let mut drop_kinds = vec![Vec::new(); cfg.basic_blocks.len()];
+    let mut flagged_drop_paths = FxIndexSet::default();
+
+    results.visit_with(cfg, &mut init_effects, |init_effects, node, location, state| {
+        if let NodeKind::DropNode(DropNode { place: PlaceKind::LocalMemId(local_mem_id), .. }) =
+            node.kind
+        {
+            let drop_path_kinds = init_effects.drop_paths
+                .get_drop_paths(local_mem_id)
+                .map(|drop_path| {
+                    match state[drop_path] {
+                        InitState { maybe_init: false, .. } => DropKind::Never,
+                        InitState { maybe_uninit: false, .. } => DropKind::Always,
+                        _ => {
+                            flagged_drop_paths.insert(drop_path);
+                            DropKind::Flagged
+                        }
+                    }
+                })
+                .collect::<Vec<_>>();
+            drop_kinds[location.basic_block_id.0 as usize].push(drop_path_kinds);
+        }
+    });
+
+    // Drops in unreachable blocks are kept as they are
+    let drop_paths = &init_effects.drop_paths.drop_paths;
+    let mut drop_path_to_flag = vec![None; drop_paths.len()];
+    for drop_path in flagged_drop_paths.iter() {
+        let DropPath { local_mem_id, byte_offset, .. } = drop_paths[*drop_path];
+        let local_mem = cfg.local_mems[local_mem_id.0 as usize];
+        let symbol = if drop_paths[*drop_path].is_whole_var(&cfg.local_mems) {
+            format!("{}.dropflag", local_mem.symbol.get())
+        } else {
+            format!("{}.{}.dropflag", local_mem.symbol.get(), byte_offset)
+        };
+
+        let flag_local_mem_id = LocalMemId(cfg.local_mems.len() as u32);
+        cfg.local_mems.push(
+            LocalMem::new(
+                flag_local_mem_id,
+                Symbol::new(&symbol),
+                local_mem.span,
+                INT_8_TY,
+                Mutability::Mutable,
+                false
+            )
+        );
+        drop_path_to_flag[*drop_path] = Some(flag_local_mem_id);
+    }
+
+    let set_flag = |flag_local_mem_id: LocalMemId, val: i64, store_kind: StoreKind| {
+        Node::new(
+            NodeKind::StoreNode(
+                StoreNode::new(
+                    PlaceKind::LocalMemId(flag_local_mem_id),
+                    INT_8_TY,
+                    Operand::Const(Const::Int(val, IntTy::Int8)),
+                    store_kind
+                )
+            )
+        )
+    };
+
+    let mut next_temp_id = cfg.get_next_temp_id();
+    let mut get_temp_id = || {
+        let temp_id = next_temp_id;
+        next_temp_id = TempId(next_temp_id.0 + 1);
+        temp_id
+    };
+
+    for basic_block in cfg.basic_blocks.iter_mut() {
+        let mut drop_kinds = std::mem::take(
+            &mut drop_kinds[basic_block.basic_block_id.0 as usize]
+        ).into_iter();
+        let mut nodes = Vec::with_capacity(basic_block.nodes.len());
+
+        if basic_block.basic_block_id.0 == 0 {
+            for flag_local_mem_id in drop_path_to_flag.iter().flatten() {
+                nodes.push(set_flag(*flag_local_mem_id, 0, StoreKind::Init));
+            }
+        }
+
+        for node in basic_block.nodes.drain(..) {
+            if let NodeKind::DropNode(DropNode { place: PlaceKind::LocalMemId(local_mem_id), .. }) =
+                node.kind
+            {
+                let Some(drop_path_kinds) = drop_kinds.next() else {
+                    nodes.push(node);
+                    continue;
+                };
+
+                // The variable is dropped as a whole if all of it is initialized
+                if drop_path_kinds.iter().all(|drop_kind| *drop_kind == DropKind::Always) {
+                    nodes.push(node);
+                    continue;
+                }
+
+                // Otherwise each drop path is dropped on its own, in the order of the drop glue
+                let drop_paths_range = init_effects.drop_paths.get_drop_paths(local_mem_id);
+                for (drop_path, drop_kind) in drop_paths_range.zip(drop_path_kinds) {
+                    let DropPath { byte_offset, ty, .. } = drop_paths[drop_path];
+
+                    let drop_flag = match drop_kind {
+                        DropKind::Never => {
+                            continue;
+                        }
+                        DropKind::Always => None,
+                        DropKind::Flagged => {
+                            let flag_local_mem_id = drop_path_to_flag[drop_path].expect(
+                                "Expected drop flag"
+                            );
+
+                            let flag_temp_id = get_temp_id();
+                            nodes.push(
+                                Node::new(
+                                    NodeKind::LoadNode(
+                                        LoadNode::new(
+                                            flag_temp_id,
+                                            PlaceKind::LocalMemId(flag_local_mem_id),
+                                            INT_8_TY
+                                        )
+                                    )
+                                )
+                            );
+                            Some((flag_temp_id, flag_local_mem_id))
+                        }
+                    };
+
+                    let place = if byte_offset == 0 {
+                        PlaceKind::LocalMemId(local_mem_id)
+                    } else {
+                        let temp_id = get_temp_id();
+                        nodes.push(
+                            Node::new(
+                                NodeKind::ByteAccessNode(
+                                    ByteAccessNode::new(
+                                        PlaceKind::TempId(temp_id),
+                                        PlaceKind::LocalMemId(local_mem_id),
+                                        byte_offset
+                                    )
+                                )
+                            )
+                        );
+                        PlaceKind::TempId(temp_id)
+                    };
+
+                    nodes.push(
+                        Node::new(
+                            NodeKind::DropNode(
+                                DropNode::new(
+                                    place,
+                                    ty,
+                                    drop_flag.map(|(flag_temp_id, _)| flag_temp_id)
+                                )
+                            )
+                        )
+                    );
+                    if let Some((_, flag_local_mem_id)) = drop_flag {
+                        nodes.push(set_flag(flag_local_mem_id, 0, StoreKind::Assign));
+                    }
+                }
+                continue;
+            }
+
+            let init_effect = init_effects.get(&node);
+            nodes.push(node);
+
+            if let Some((drop_paths, init_state)) = init_effect {
+                let val = if init_state == InitState::INIT { 1 } else { 0 };
+                for flag_local_mem_id in drop_path_to_flag[drop_paths].iter().flatten() {
+                    nodes.push(set_flag(*flag_local_mem_id, val, StoreKind::Assign));
+                }
+            }
+        }
+
+        basic_block.nodes = nodes;
+    }
+}
+
+/// Builds the function dropping a value of some type, which it gets as a pointer along with a
+/// drop flag (the value is only dropped if the flag is set)
+struct DropGlueBuilder<'a, 'b> {
+    resolved_information: &'b ResolvedInformation<'a>,
+    basic_blocks: Vec<BasicBlock<'a>>,
+    next_temp_id: u32,
+}
+
+impl<'a, 'b> DropGlueBuilder<'a, 'b> {
+    fn new(resolved_information: &'b ResolvedInformation<'a>) -> Self {
+        Self {
+            resolved_information,
+            basic_blocks: vec![BasicBlock::new(BasicBlockId(0))],
+            next_temp_id: 0,
+        }
+    }
+
+    fn build(mut self, ty: Ty) -> Cfg<'a> {
+        let value_temp_id = self.get_temp_id();
+        let flag_temp_id = self.get_temp_id();
+
+        let is_flag_set = self.get_temp_id();
+        self.push_node(
+            Node::new(
+                NodeKind::BinaryNode(
+                    BinaryNode::new(
+                        is_flag_set,
+                        INT_8_TY,
+                        BinaryOp::ComparisonOp(ComparisonOp::Ne),
+                        Operand::from(flag_temp_id),
+                        Operand::Const(Const::Int(0, IntTy::Int8))
+                    )
+                )
+            )
+        );
+
+        let drop_bb_id = self.new_basic_block();
+        let mut exit_bb_ids = self.build_drop(PlaceKind::TempId(value_temp_id), ty);
+        exit_bb_ids.push(self.get_curr_bb_id());
+
+        let end_bb_id = self.new_basic_block();
+        self.push_node_to(
+            BasicBlockId(0),
+            Node::new(
+                NodeKind::BranchCondNode(
+                    BranchCondNode::new(Operand::from(is_flag_set), drop_bb_id, end_bb_id)
+                )
+            )
+        );
+        for exit_bb_id in exit_bb_ids {
+            self.push_node_to(
+                exit_bb_id,
+                Node::new(NodeKind::BranchNode(BranchNode::new(end_bb_id)))
+            );
+        }
+        self.push_node(
+            Node::new(
+                NodeKind::ReturnNode(ReturnNode::new(Operand::Const(Const::Void), VOID_TY))
+            )
+        );
+
+        Cfg::new(
+            vec![(value_temp_id, ty.to_ptr_ty()), (flag_temp_id, INT_8_TY)],
+            Vec::new(),
+            Vec::new(),
+            self.basic_blocks,
+            CfgFnKind::DropGlue(ty),
+            VOID_TY
+        )
+    }
+
+    /// Drops the value at `place` starting in the current basic block, and returns the basic blocks
+    /// (other than the current one) which are done dropping
+    fn build_drop(&mut self, place: PlaceKind, ty: Ty) -> Vec<BasicBlockId> {
+        let mut exit_bb_ids = Vec::new();
+
+        match ty {
+            Ty::Tuple(tys) => self.drop_fields(place, 0, tys),
+            Ty::Adt(def_id) => {
+                if let Some(drop_fn) = self.resolved_information.get_drop_fn(&def_id) {
+                    let result_temp_id = self.get_temp_id();
+                    self.push_node(
+                        Node::new(
+                            NodeKind::CallNode(
+                                CallNode::new(
+                                    result_temp_id,
+                                    Operand::Const(Const::FnPtr(drop_fn)),
+                                    TyCtx::intern_many_types(vec![Operand::PlaceKind(place)]),
+                                    TyCtx::intern_many_types(vec![ty.to_ptr_ty()]),
+                                    VOID_TY
+                                )
+                            )
+                        )
+                    );
+                }
+
+                match self.resolved_information.get_name_binding_from_def_id(&def_id).kind {
+                    NameBindingKind::Adt(Adt::Struct(fields)) => {
+                        let tys = fields
+                            .iter()
+                            .map(|(_, ty)| *ty)
+                            .collect::<Vec<_>>();
+                        self.drop_fields(place, 0, &tys);
+                    }
+                    NameBindingKind::Adt(Adt::Typedef(ty)) => {
+                        self.push_drop_node(place, ty);
+                    }
+                    NameBindingKind::Adt(Adt::Enum(variants)) => {
+                        let discriminant_temp_id = self.get_temp_id();
+                        self.push_node(
+                            Node::new(
+                                NodeKind::LoadNode(
+                                    LoadNode::new(discriminant_temp_id, place, INT_64_TY)
+                                )
+                            )
+                        );
+
+                        for variant_def_id in variants.iter() {
+                            let (variant_id, enum_data) = match
+                                self.resolved_information.get_name_binding_from_def_id(
+                                    variant_def_id
+                                ).kind
+                            {
+                                NameBindingKind::Adt(
+                                    Adt::EnumVariant(_, variant_id, enum_data),
+                                ) => (variant_id, enum_data),
+                                t => panic!("Expected enum variant, got {:?}", t),
+                            };
+
+                            if !enum_data.iter().any(|ty| self.resolved_information.needs_drop(*ty)) {
+                                continue;
+                            }
+
+                            let is_variant = self.get_temp_id();
+                            self.push_node(
+                                Node::new(
+                                    NodeKind::BinaryNode(
+                                        BinaryNode::new(
+                                            is_variant,
+                                            INT_64_TY,
+                                            BinaryOp::ComparisonOp(ComparisonOp::Eq),
+                                            Operand::from(discriminant_temp_id),
+                                            Operand::Const(
+                                                Const::Int(variant_id.0 as i64, IntTy::Int64)
+                                            )
+                                        )
+                                    )
+                                )
+                            );
+                            let cond_bb_id = self.get_curr_bb_id();
+
+                            // The data of the variant is placed after the discriminant (8 bytes)
+                            let variant_bb_id = self.new_basic_block();
+                            self.drop_fields(place, 8, enum_data);
+                            exit_bb_ids.push(self.get_curr_bb_id());
+
+                            let next_bb_id = self.new_basic_block();
+                            self.push_node_to(
+                                cond_bb_id,
+                                Node::new(
+                                    NodeKind::BranchCondNode(
+                                        BranchCondNode::new(
+                                            Operand::from(is_variant),
+                                            variant_bb_id,
+                                            next_bb_id
+                                        )
+                                    )
+                                )
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+
+        exit_bb_ids
+    }
+
+    /// Drops fields laid out in order (each one aligned) from `byte_offset`
+    fn drop_fields(&mut self, place: PlaceKind, mut byte_offset: usize, tys: &[Ty]) {
+        for ty in tys.iter() {
+            let ty_attr = ty.get_ty_attr(self.resolved_information);
+            byte_offset = ty_attr.align_offset(byte_offset);
+
+            if self.resolved_information.needs_drop(*ty) {
+                let field_place = if byte_offset == 0 {
+                    place
+                } else {
+                    let temp_id = self.get_temp_id();
+                    self.push_node(
+                        Node::new(
+                            NodeKind::ByteAccessNode(
+                                ByteAccessNode::new(PlaceKind::TempId(temp_id), place, byte_offset)
+                            )
+                        )
+                    );
+                    PlaceKind::TempId(temp_id)
+                };
+
+                self.push_drop_node(field_place, *ty);
+            }
+
+            byte_offset += ty_attr.size_bytes;
+        }
+    }
+
+    fn push_drop_node(&mut self, place: PlaceKind, ty: Ty) {
+        self.push_node(Node::new(NodeKind::DropNode(DropNode::new(place, ty, None))));
+    }
+
+    fn get_temp_id(&mut self) -> TempId {
+        self.next_temp_id += 1;
+        TempId(self.next_temp_id - 1)
+    }
+
+    fn get_curr_bb_id(&self) -> BasicBlockId {
+        BasicBlockId((self.basic_blocks.len() - 1) as u32)
+    }
+
+    fn new_basic_block(&mut self) -> BasicBlockId {
+        let bb_id = BasicBlockId(self.basic_blocks.len() as u32);
+        self.basic_blocks.push(BasicBlock::new(bb_id));
+        bb_id
+    }
+
+    fn push_node(&mut self, node: Node<'a>) {
+        let bb_id = self.get_curr_bb_id();
+        self.push_node_to(bb_id, node);
+    }
+
+    fn push_node_to(&mut self, bb_id: BasicBlockId, node: Node<'a>) {
+        self.basic_blocks[bb_id.0 as usize].push_node(node);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ IcfgParser, IcfgPrettifier };
+
+    #[test]
+    fn drop_partially_moved_vars() {
+        let src =
+            r##"
+extern fn printf#0:0(Str, ...) -> int32
+str "drop %d\\0A\\00"#0:1 9
+str "end\\0A\\00"#0:2 5
+struct Foo#0:3 { a#0:4: int32 }
+struct Pair#0:5 { a#0:6: Foo#0:3, n#0:7: int32, b#0:8: Foo#0:3 }
+on_drop Foo#0:3 = onDrop#0:9
+
+fn onDrop#0:9(_0: *Foo#0:3) -> Void {
+bb0:
+    _1 = load int32, _0
+    _2 = call int32 fn printf#0:0(str "drop %d\\0A\\00"#0:1, _1) : (Str, ..., int32)
+    ret Void ()
+}
+
+fn consume#0:10(_0: Foo#0:3) -> Void {
+    local l0 f: Foo#0:3 drop
+bb0:
+    store init Foo#0:3 _0, l0
+    drop Foo#0:3 l0
+    ret Void ()
+}
+
+fn partial#0:11(_0: int32) -> Void {
+    local l0 pr: Pair#0:5 drop
+    result r0: Pair#0:5
+bb0:
+    store init int32 1i32, r0
+    _1 = byte_access r0, 4
+    store init int32 0i32, _1
+    _2 = byte_access r0, 8
+    store init int32 2i32, _2
+    _3 = load Pair#0:5, r0
+    store init Pair#0:5 _3, l0
+    _4 = eq int32 _0, 1i32
+    br _4, bb1, bb2
+bb1:
+    _5 = load Foo#0:3, l0
+    _6 = call Void fn consume#0:10(_5) : (Foo#0:3)
+    br bb2
+bb2:
+    _7 = call int32 fn printf#0:0(str "end\\0A\\00"#0:2) : (Str, ...)
+    drop Pair#0:5 l0
+    ret Void ()
+}
+
+main fn main#0:12() -> Void {
+bb0:
+    _0 = call Void fn partial#0:11(1i32) : (int32)
+    _1 = call Void fn partial#0:11(0i32) : (int32)
+    ret Void ()
+}
+"##;
+        let mut icfg = IcfgParser::new(src).parse_icfg().unwrap();
+        icfg.elaborate_drops();
+        assert!(icfg.verify().is_ok());
+
+        // `pr.a` is only moved on some paths and gets a drop flag, while `pr.b` is always dropped
+        let expected =
+            r##"
+fn partial#0:11(_0: int32) -> Void {
+    local l0 pr: Pair#0:5 drop
+    local l1 "pr.0.dropflag": mut int8
+    result r0: Pair#0:5
+bb0:
+    store init int8 0i8, l1
+    store init int32 1i32, r0
+    _1 = byte_access r0, 4
+    store init int32 0i32, _1
+    _2 = byte_access r0, 8
+    store init int32 2i32, _2
+    _3 = load Pair#0:5, r0
+    store init Pair#0:5 _3, l0
+    store assign int8 1i8, l1
+    _4 = eq int32 _0, 1i32
+    br _4, bb1, bb2
+bb1:
+    _5 = load Foo#0:3, l0
+    store assign int8 0i8, l1
+    _6 = call Void fn consume#0:10(_5) : (Foo#0:3)
+    br bb2
+bb2:
+    _7 = call int32 fn printf#0:0(str "end\\0A\\00"#0:2) : (Str, ...)
+    _8 = load int8, l1
+    drop Foo#0:3 l0 if _8
+    store assign int8 0i8, l1
+    _9 = byte_access l0, 8
+    drop Foo#0:3 _9
+    ret Void ()
+}
+"##;
+        let prettified = IcfgPrettifier::new(&icfg).prettify();
+        assert!(prettified.contains(expected.trim_start()), "{}", prettified);
+
+        let mut out = Vec::new();
+        assert_eq!(icfg.interpret(&mut out).unwrap(), 0);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "drop 1\nend\ndrop 2\nend\ndrop 1\ndrop 2\n"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+/*
+
+Graphviz DOT export
+
+Each cfg becomes a cluster of the graph, with a node per basic block listing its nodes in the
+textual ICFG syntax (see `icfg_prettifier`). Edges of conditional branches are labelled `true`
+and `false`, and edges of switches with their case value or `default`
+
+The call graph view has a node per cfg and an edge from a cfg to each cfg it refers to (see
+`call_graph`)
+
+*/
+
+use std::fmt::Write;
+
+use crate::{
+    icfg_prettifier::display_cfg_fn_kind,
+    BasicBlockId,
+    CallGraph,
+    Icfg,
+    IcfgPrettifier,
+    NodeKind,
+};
+
+const INDENTATION: usize = 4;
+
+pub struct IcfgDotExporter<'b> {
+    icfg: &'b Icfg<'b>,
+    buffer: String,
+}
+
+impl<'b> IcfgDotExporter<'b> {
+    pub fn new(icfg: &'b Icfg) -> Self {
+        Self { icfg, buffer: String::with_capacity(2048) }
+    }
+
+    /// A graph with the control flow graph of every cfg
+    pub fn export_cfgs(mut self) -> String {
+        self.write_header("icfg");
+
+        for (cfg_index, cfg) in self.icfg.cfgs.iter().enumerate() {
+            let indentation = " ".repeat(INDENTATION);
+            writeln!(self.buffer, "\n{}subgraph cluster_{} {{", indentation, cfg_index).unwrap();
+            writeln!(
+                self.buffer,
+                "{}{}label=\"{}\";",
+                indentation,
+                indentation,
+                escape(&display_cfg_fn_kind(cfg.cfg_fn_kind))
+            ).unwrap();
+
+            for basic_block in cfg.basic_blocks.iter() {
+                let basic_block_id = basic_block.basic_block_id;
+                let nodes = IcfgPrettifier::new(self.icfg).prettify_basic_block(basic_block, cfg);
+                let mut label = format!("bb{}:\\l", basic_block_id.0);
+                for line in nodes.lines() {
+                    label.push_str(&escape(line));
+                    label.push_str("\\l");
+                }
+
+                writeln!(
+                    self.buffer,
+                    "{}{}{} [label=\"{}\"];",
+                    indentation,
+                    indentation,
+                    block_name(cfg_index, basic_block_id),
+                    label
+                ).unwrap();
+
+                for node in basic_block.nodes.iter() {
+                    let edges = match &node.kind {
+                        NodeKind::BranchNode(branch_node) => vec![(branch_node.branch, None)],
+                        NodeKind::BranchCondNode(branch_cond_node) =>
+                            vec![
+                                (branch_cond_node.true_branch, Some("true".to_string())),
+                                (branch_cond_node.false_branch, Some("false".to_string()))
+                            ],
+                        NodeKind::SwitchNode(switch_node) => {
+                            let default_edge = (switch_node.default, Some("default".to_string()));
+                            let mut edges = vec![default_edge];
+                            for (case, branch) in switch_node.cases.iter() {
+                                edges.push((*branch, Some(case.to_string())));
+                            }
+                            edges
+                        }
+                        _ => continue,
+                    };
+
+                    for (target, label) in edges {
+                        write!(
+                            self.buffer,
+                            "{}{}{} -> {}",
+                            indentation,
+                            indentation,
+                            block_name(cfg_index, basic_block_id),
+                            block_name(cfg_index, target)
+                        ).unwrap();
+                        if let Some(label) = label {
+                            write!(self.buffer, " [label=\"{}\"]", label).unwrap();
+                        }
+                        writeln!(self.buffer, ";").unwrap();
+                    }
+                }
+            }
+
+            writeln!(self.buffer, "{}}}", indentation).unwrap();
+        }
+
+        writeln!(self.buffer, "}}").unwrap();
+        self.buffer
+    }
+
+    /// A graph with a node per cfg and an edge to each cfg it calls or refers to
+    pub fn export_call_graph(mut self) -> String {
+        self.write_header("call_graph");
+
+        let indentation = " ".repeat(INDENTATION);
+        let call_graph = CallGraph::new(&self.icfg.cfgs);
+        for (cfg_index, cfg) in self.icfg.cfgs.iter().enumerate() {
+            writeln!(
+                self.buffer,
+                "{}cfg{} [label=\"{}\"];",
+                indentation,
+                cfg_index,
+                escape(&display_cfg_fn_kind(cfg.cfg_fn_kind))
+            ).unwrap();
+        }
+
+        for cfg_index in 0..self.icfg.cfgs.len() {
+            let mut callees = call_graph.callees(cfg_index).to_vec();
+            callees.sort_unstable();
+            callees.dedup();
+            for callee in callees {
+                writeln!(self.buffer, "{}cfg{} -> cfg{};", indentation, cfg_index, callee).unwrap();
+            }
+        }
+
+        writeln!(self.buffer, "}}").unwrap();
+        self.buffer
+    }
+
+    fn write_header(&mut self, name: &str) {
+        self.buffer.clear();
+        writeln!(self.buffer, "digraph {} {{", name).unwrap();
+        writeln!(
+            self.buffer,
+            "{}node [shape=box, fontname=\"monospace\"];",
+            " ".repeat(INDENTATION)
+        ).unwrap();
+    }
+}
+
+fn block_name(cfg_index: usize, basic_block_id: BasicBlockId) -> String {
+    format!("cfg{}_bb{}", cfg_index, basic_block_id.0)
+}
+
+/// Escapes text to be used in a quoted DOT string
+fn escape(text: &str) -> String {
+    text.trim().replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ IcfgDotExporter, IcfgParser };
+
+    const SRC: &str =
+        r##"
+fn add#0:0(_0: int32) -> int32 {
+bb0:
+    _1 = gt int32 _0, 0i32
+    br _1, bb1, bb2
+bb1:
+    ret int32 _0
+bb2:
+    _2 = call int32 fn add#0:0(1i32) : (int32)
+    br bb1
+}
+
+main fn main#0:1() -> Void {
+bb0:
+    _0 = call int32 fn add#0:0(5i32) : (int32)
+    ret Void ()
+}
+"##;
+
+    #[test]
+    fn export_cfgs() {
+        let icfg = IcfgParser::new(SRC).parse_icfg().unwrap();
+        let dot = IcfgDotExporter::new(&icfg).export_cfgs();
+
+        assert!(dot.starts_with("digraph icfg {"));
+        assert!(dot.contains("label=\"fn add#0:0\";"));
+        assert!(
+            dot.contains("cfg0_bb0 [label=\"bb0:\\l_1 = gt int32 _0, 0i32\\lbr _1, bb1, bb2\\l\"];")
+        );
+        assert!(dot.contains("cfg0_bb0 -> cfg0_bb1 [label=\"true\"];"));
+        assert!(dot.contains("cfg0_bb0 -> cfg0_bb2 [label=\"false\"];"));
+        assert!(dot.contains("cfg0_bb2 -> cfg0_bb1;"));
+        assert!(dot.contains("label=\"main fn main#0:1\";"));
+    }
+
+    #[test]
+    fn export_call_graph() {
+        let icfg = IcfgParser::new(SRC).parse_icfg().unwrap();
+        let dot = IcfgDotExporter::new(&icfg).export_call_graph();
+
+        assert_eq!(
+            dot,
+            r#"digraph call_graph {
+    node [shape=box, fontname="monospace"];
+    cfg0 [label="fn add#0:0"];
+    cfg1 [label="main fn main#0:1"];
+    cfg0 -> cfg0;
+    cfg1 -> cfg0;
+}
+"#
+        );
+    }
+}
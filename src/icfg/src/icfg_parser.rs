@@ -0,0 +1,1069 @@
+/*
+
+Parses the textual ICFG printed by `IcfgPrettifier` (see its module for the syntax)
+
+This makes it possible to write small icfgs by hand (e.g. to test optimizations or codegen)
+without having to go through a whole `.vs` program
+
+The resolved information is reconstructed from the declarations, and each function gets a name
+binding from its args and return type. Parsed nodes and local mems have no spans
+
+*/
+
+use std::fmt::Display;
+
+use fxhash::FxHashSet;
+use ir::{
+    Adt,
+    CfgFnKind,
+    ConstStrLen,
+    DefId,
+    EmumVaraintId,
+    Externism,
+    FloatTy,
+    FnSig,
+    HasSelfArg,
+    InlineAttr,
+    IntTy,
+    LocalMem,
+    LocalMemId,
+    ModId,
+    Mutability,
+    NameBinding,
+    NameBindingKind,
+    NodeId,
+    PrimTy,
+    ResolvedInformation,
+    ResultMem,
+    ResultMemId,
+    Symbol,
+    TempId,
+    Ty,
+    TyCtx,
+    UintTy,
+};
+use op::{ ArithmeticOp, BinaryOp, ComparisonOp };
+use span::Span;
+
+use crate::{
+    BasicBlock,
+    BasicBlockId,
+    BinaryNode,
+    BranchCondNode,
+    BranchNode,
+    ByteAccessNode,
+    CallNode,
+    Cfg,
+    Const,
+    DropNode,
+    Icfg,
+    IndexNode,
+    LoadNode,
+    Node,
+    NodeKind,
+    Operand,
+    PhiNode,
+    PlaceKind,
+    ReturnNode,
+    StoreKind,
+    StoreNode,
+    SwitchNode,
+    TyCastKind,
+    TyCastNode,
+    UnreachableNode,
+};
+
+#[derive(Debug)]
+pub struct IcfgParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for IcfgParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+type ParseResult<T> = Result<T, IcfgParseError>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind<'src> {
+    Ident(&'src str),
+    /// A number with an optional suffix (e.g. `8` or `-5i32`)
+    Int(&'src str),
+    Str(String),
+    Punct(&'static str),
+    Eof,
+}
+
+impl Display for TokenKind<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ident(ident) => write!(f, "`{}`", ident),
+            Self::Int(int) => write!(f, "`{}`", int),
+            Self::Str(str) => write!(f, "\"{}\"", str),
+            Self::Punct(punct) => write!(f, "`{}`", punct),
+            Self::Eof => write!(f, "end of file"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Token<'src> {
+    kind: TokenKind<'src>,
+    line: usize,
+}
+
+// Longer punctuations come first, so they're matched before their prefixes
+const PUNCTS: [&str; 16] = [
+    "...",
+    "->",
+    "#",
+    ":",
+    ",",
+    "(",
+    ")",
+    "[",
+    "]",
+    "{",
+    "}",
+    "*",
+    "=",
+    "<",
+    ">",
+    "!",
+];
+
+fn tokenize(src: &str) -> ParseResult<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut rest = src;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '\n' {
+            line += 1;
+            rest = &rest[1..];
+        } else if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if rest.starts_with("//") {
+            rest = rest.find('\n').map_or("", |i| &rest[i..]);
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token { kind: TokenKind::Ident(&rest[..len]), line });
+            rest = &rest[len..];
+        } else if
+            c.is_ascii_digit() ||
+            (c == '-' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let len =
+                1 +
+                rest[1..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len() - 1);
+            tokens.push(Token { kind: TokenKind::Int(&rest[..len]), line });
+            rest = &rest[len..];
+        } else if c == '"' {
+            let mut str = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, '"')) => {
+                        break i + 2;
+                    }
+                    Some((_, '\\')) => {
+                        match chars.next() {
+                            Some((_, c @ ('\\' | '"'))) => str.push(c),
+                            _ => {
+                                return Err(IcfgParseError {
+                                    line,
+                                    message: "Expected `\\\\` or `\\\"` after `\\`".to_string(),
+                                });
+                            }
+                        }
+                    }
+                    Some((_, '\n')) | None => {
+                        return Err(IcfgParseError {
+                            line,
+                            message: "Unterminated string".to_string(),
+                        });
+                    }
+                    Some((_, c)) => str.push(c),
+                }
+            };
+            tokens.push(Token { kind: TokenKind::Str(str), line });
+            rest = &rest[end..];
+        } else if let Some(punct) = PUNCTS.iter().find(|punct| rest.starts_with(**punct)) {
+            tokens.push(Token { kind: TokenKind::Punct(punct), line });
+            rest = &rest[punct.len()..];
+        } else {
+            return Err(IcfgParseError { line, message: format!("Unexpected character `{}`", c) });
+        }
+    }
+
+    tokens.push(Token { kind: TokenKind::Eof, line });
+    Ok(tokens)
+}
+
+/// The local mems, result mems and basic blocks of a cfg
+struct CfgBody<'a> {
+    local_mems: Vec<LocalMem>,
+    result_mems: Vec<ResultMem>,
+    basic_blocks: Vec<BasicBlock<'a>>,
+}
+
+pub struct IcfgParser<'src> {
+    src: &'src str,
+    tokens: Vec<Token<'src>>,
+    pos: usize,
+    resolved_information: ResolvedInformation<'static>,
+}
+
+impl<'src> IcfgParser<'src> {
+    pub fn new(src: &'src str) -> Self {
+        Self {
+            src,
+            tokens: Vec::new(),
+            pos: 0,
+            resolved_information: ResolvedInformation::default(),
+        }
+    }
+
+    pub fn parse_icfg(mut self) -> ParseResult<Icfg<'static>> {
+        self.tokens = tokenize(self.src)?;
+        let mut cfgs = Vec::new();
+        let mut fn_def_ids = FxHashSet::default();
+
+        while *self.peek() != TokenKind::Eof {
+            if let Some(cfg) = self.parse_item()? {
+                if let CfgFnKind::Main(def_id) | CfgFnKind::Fn(def_id) = cfg.cfg_fn_kind {
+                    if !fn_def_ids.insert(def_id) {
+                        let message = format!("`{}` is defined twice", def_id.symbol.get());
+                        return Err(self.error(message));
+                    }
+                }
+                cfgs.push(cfg);
+            }
+        }
+
+        // Functions are referred to by their def id (e.g. by codegen), so they need a name binding
+        for cfg in cfgs.iter() {
+            if let CfgFnKind::Main(def_id) | CfgFnKind::Fn(def_id) = cfg.cfg_fn_kind {
+                let args = cfg.args
+                    .iter()
+                    .map(|(_, ty)| *ty)
+                    .collect::<Vec<_>>();
+                let fn_sig = FnSig::new(
+                    TyCtx::intern_many_types(args),
+                    TyCtx::intern_type(cfg.ret_ty)
+                );
+                self.resolved_information.def_id_to_name_binding
+                    .entry(def_id)
+                    .or_insert(
+                        NameBinding::new(
+                            NameBindingKind::Fn(fn_sig, HasSelfArg::No, Externism::NoExtern)
+                        )
+                    );
+            }
+        }
+
+        Ok(Icfg::new(cfgs, self.resolved_information))
+    }
+
+    /// Parses a declaration, or a cfg (which is returned)
+    fn parse_item(&mut self) -> ParseResult<Option<Cfg<'static>>> {
+        let keyword = self.expect_ident()?;
+        match keyword {
+            "extern" => {
+                self.expect_keyword("fn")?;
+                let def_id = self.parse_def_id()?;
+                self.expect_punct("(")?;
+                let args = self.parse_tys(")")?;
+                self.expect_punct("->")?;
+                let ret_ty = self.parse_ty()?;
+
+                let fn_sig = FnSig::new(args, TyCtx::intern_type(ret_ty));
+                let clib_fns = &mut self.resolved_information.clib_fns;
+                if !clib_fns.iter().any(|clib_fn| clib_fn.symbol == def_id.symbol) {
+                    clib_fns.push(def_id);
+                }
+                self.bind(def_id, NameBindingKind::Fn(fn_sig, HasSelfArg::No, Externism::Clib))?;
+            }
+            "str" => {
+                let def_id = self.parse_def_id()?;
+                let len = ConstStrLen(self.parse_int()?);
+                self.resolved_information.const_strs.push((def_id, len));
+                self.bind(def_id, NameBindingKind::ConstStr(len))?;
+            }
+            "struct" => {
+                let def_id = self.parse_def_id()?;
+                self.expect_punct("{")?;
+                let mut fields = Vec::new();
+                while !self.eat_punct("}") {
+                    if !fields.is_empty() {
+                        self.expect_punct(",")?;
+                    }
+                    let field = self.parse_def_id()?;
+                    self.expect_punct(":")?;
+                    fields.push((field, self.parse_ty()?));
+                }
+
+                let fields = TyCtx::intern_many_types(fields);
+                self.bind(def_id, NameBindingKind::Adt(Adt::Struct(fields)))?;
+            }
+            "enum" => {
+                let def_id = self.parse_def_id()?;
+                self.expect_punct("{")?;
+                let mut variants = Vec::new();
+                while !self.eat_punct("}") {
+                    if !variants.is_empty() {
+                        self.expect_punct(",")?;
+                    }
+                    variants.push(self.parse_def_id()?);
+                }
+
+                let variants = TyCtx::intern_many_types(variants);
+                self.bind(def_id, NameBindingKind::Adt(Adt::Enum(variants)))?;
+            }
+            "variant" => {
+                let def_id = self.parse_def_id()?;
+                self.expect_punct("=")?;
+                let enum_def_id = self.parse_def_id()?;
+                let variant_id = EmumVaraintId(self.parse_int()?);
+                self.expect_punct("(")?;
+                let tys = self.parse_tys(")")?;
+
+                let adt = Adt::EnumVariant(enum_def_id, variant_id, tys);
+                self.bind(def_id, NameBindingKind::Adt(adt))?;
+            }
+            "typedef" => {
+                let def_id = self.parse_def_id()?;
+                self.expect_punct("=")?;
+                let ty = self.parse_ty()?;
+                self.bind(def_id, NameBindingKind::Adt(Adt::Typedef(ty)))?;
+            }
+            "copy" => {
+                let def_id = self.parse_def_id()?;
+                self.resolved_information.copy_adt_def_ids.insert(def_id);
+            }
+            "on_drop" => {
+                let adt_def_id = self.parse_def_id()?;
+                self.expect_punct("=")?;
+                let drop_fn = self.parse_def_id()?;
+                self.resolved_information.adt_def_id_to_drop_fn.insert(adt_def_id, drop_fn);
+            }
+            "exported" | "inline" | "noinline" => {
+                let mut is_exported = false;
+                let mut inline_attr = InlineAttr::Auto;
+                let mut keyword = keyword;
+                loop {
+                    match keyword {
+                        "exported" => {
+                            is_exported = true;
+                        }
+                        "inline" => {
+                            inline_attr = InlineAttr::Always;
+                        }
+                        "noinline" => {
+                            inline_attr = InlineAttr::Never;
+                        }
+                        _ => {
+                            break;
+                        }
+                    }
+                    keyword = self.expect_ident()?;
+                }
+
+                let mut cfg = self.parse_cfg(keyword)?;
+                cfg.is_exported = is_exported;
+                cfg.inline_attr = inline_attr;
+                return Ok(Some(cfg));
+            }
+            _ => {
+                return self.parse_cfg(keyword).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn bind(&mut self, def_id: DefId, kind: NameBindingKind<'static>) -> ParseResult<()> {
+        let name_binding = NameBinding::new(kind);
+        if self.resolved_information.def_id_to_name_binding.insert(def_id, name_binding).is_some() {
+            return Err(self.error(format!("`{}` is declared twice", def_id.symbol.get())));
+        }
+        Ok(())
+    }
+
+    /// Parses a cfg after its first keyword
+    fn parse_cfg(&mut self, keyword: &str) -> ParseResult<Cfg<'static>> {
+        let cfg_fn_kind = match keyword {
+            "main" => {
+                self.expect_keyword("fn")?;
+                CfgFnKind::Main(self.parse_def_id()?)
+            }
+            "fn" => CfgFnKind::Fn(self.parse_def_id()?),
+            "drop_glue" => CfgFnKind::DropGlue(self.parse_ty()?),
+            _ => {
+                let message = format!("Expected a declaration or a cfg, found `{}`", keyword);
+                return Err(self.error(message));
+            }
+        };
+
+        self.expect_punct("(")?;
+        let mut args = Vec::new();
+        while !self.eat_punct(")") {
+            if !args.is_empty() {
+                self.expect_punct(",")?;
+            }
+            let temp_id = self.parse_temp_id()?;
+            self.expect_punct(":")?;
+            args.push((temp_id, self.parse_ty()?));
+        }
+        self.expect_punct("->")?;
+        let ret_ty = self.parse_ty()?;
+
+        self.expect_punct("{")?;
+        let body = self.parse_cfg_body()?;
+
+        Ok(
+            Cfg::new(
+                args,
+                body.local_mems,
+                body.result_mems,
+                body.basic_blocks,
+                cfg_fn_kind,
+                ret_ty
+            )
+        )
+    }
+
+    fn parse_cfg_body(&mut self) -> ParseResult<CfgBody<'static>> {
+        let mut body = CfgBody {
+            local_mems: Vec::new(),
+            result_mems: Vec::new(),
+            basic_blocks: Vec::new(),
+        };
+
+        while !self.eat_punct("}") {
+            match self.peek() {
+                TokenKind::Ident("local") => {
+                    self.next();
+                    let local_mem_id = match self.parse_place()? {
+                        PlaceKind::LocalMemId(local_mem_id) => local_mem_id,
+                        _ => {
+                            return Err(self.error("Expected local mem (e.g. `l0`)".to_string()));
+                        }
+                    };
+                    if local_mem_id.0 as usize != body.local_mems.len() {
+                        return Err(
+                            self.error(format!("Expected local mem `l{}`", body.local_mems.len()))
+                        );
+                    }
+                    let symbol = self.parse_symbol()?;
+                    self.expect_punct(":")?;
+                    let mutability = self.parse_mutability();
+                    let ty = self.parse_ty()?;
+                    let requires_drop = self.eat_keyword("drop");
+
+                    body.local_mems.push(
+                        LocalMem::new(
+                            local_mem_id,
+                            symbol,
+                            Span::dummy(),
+                            ty,
+                            mutability,
+                            requires_drop
+                        )
+                    );
+                }
+                TokenKind::Ident("result") => {
+                    self.next();
+                    let result_mem_id = match self.parse_place()? {
+                        PlaceKind::ResultMemId(result_mem_id) => result_mem_id,
+                        _ => {
+                            return Err(self.error("Expected result mem (e.g. `r0`)".to_string()));
+                        }
+                    };
+                    if result_mem_id.0 as usize != body.result_mems.len() {
+                        return Err(
+                            self.error(format!("Expected result mem `r{}`", body.result_mems.len()))
+                        );
+                    }
+                    self.expect_punct(":")?;
+                    let ty = self.parse_ty()?;
+
+                    body.result_mems.push(ResultMem::new(result_mem_id, ty));
+                }
+                TokenKind::Ident(ident) if self.peek_nth(1) == &TokenKind::Punct(":") => {
+                    let ident = *ident;
+                    let basic_block_id = self.parse_basic_block_id()?;
+                    if basic_block_id.0 as usize != body.basic_blocks.len() {
+                        return Err(
+                            self.error(
+                                format!(
+                                    "Expected basic block `bb{}`, found `{}`",
+                                    body.basic_blocks.len(),
+                                    ident
+                                )
+                            )
+                        );
+                    }
+                    self.expect_punct(":")?;
+                    body.basic_blocks.push(BasicBlock::new(basic_block_id));
+                }
+                _ => {
+                    let node = self.parse_node()?;
+                    let Some(basic_block) = body.basic_blocks.last_mut() else {
+                        return Err(self.error("Expected basic block before node".to_string()));
+                    };
+                    basic_block.push_node(node);
+                }
+            }
+        }
+
+        Ok(body)
+    }
+
+    fn parse_node(&mut self) -> ParseResult<Node<'static>> {
+        let kind = match self.peek() {
+            TokenKind::Ident("br") => {
+                self.next();
+                if let TokenKind::Ident(ident) = self.peek() {
+                    if ident.starts_with("bb") {
+                        let branch = self.parse_basic_block_id()?;
+                        return Ok(Node::new(NodeKind::BranchNode(BranchNode::new(branch))));
+                    }
+                }
+
+                let condition = self.parse_operand()?;
+                self.expect_punct(",")?;
+                let true_branch = self.parse_basic_block_id()?;
+                self.expect_punct(",")?;
+                let false_branch = self.parse_basic_block_id()?;
+                NodeKind::BranchCondNode(BranchCondNode::new(condition, true_branch, false_branch))
+            }
+            TokenKind::Ident("switch") => {
+                self.next();
+                let ty = self.parse_ty()?;
+                let value = self.parse_operand()?;
+                self.expect_punct(",")?;
+                let default = self.parse_basic_block_id()?;
+                self.expect_punct("[")?;
+                let mut cases: Vec<(i64, BasicBlockId)> = Vec::new();
+                while !self.eat_punct("]") {
+                    if !cases.is_empty() {
+                        self.expect_punct(",")?;
+                    }
+                    let case = self.parse_int()?;
+                    if cases.iter().any(|(other_case, _)| *other_case == case) {
+                        return Err(self.error(format!("Case `{}` is used twice", case)));
+                    }
+                    self.expect_punct(":")?;
+                    cases.push((case, self.parse_basic_block_id()?));
+                }
+                let cases = TyCtx::intern_many_types(cases);
+                NodeKind::SwitchNode(SwitchNode::new(value, ty, default, cases))
+            }
+            TokenKind::Ident("store") => {
+                self.next();
+                let store_kind = match self.expect_ident()? {
+                    "init" => StoreKind::Init,
+                    "assign" => StoreKind::Assign,
+                    found => {
+                        let message = format!("Expected `init` or `assign`, found `{}`", found);
+                        return Err(self.error(message));
+                    }
+                };
+                let op_ty = self.parse_ty()?;
+                let value = self.parse_operand()?;
+                self.expect_punct(",")?;
+                let setter = self.parse_place()?;
+                NodeKind::StoreNode(StoreNode::new(setter, op_ty, value, store_kind))
+            }
+            TokenKind::Ident("ret") => {
+                self.next();
+                let ret_ty = self.parse_ty()?;
+                let ret_val = self.parse_operand()?;
+                NodeKind::ReturnNode(ReturnNode::new(ret_val, ret_ty))
+            }
+            TokenKind::Ident("drop") => {
+                self.next();
+                let ty = self.parse_ty()?;
+                let place = self.parse_place()?;
+                let drop_flag = if self.eat_keyword("if") {
+                    Some(self.parse_temp_id()?)
+                } else {
+                    None
+                };
+                NodeKind::DropNode(DropNode::new(place, ty, drop_flag))
+            }
+            TokenKind::Ident("unreachable") => {
+                self.next();
+                NodeKind::UnreachableNode(UnreachableNode::new())
+            }
+            _ => self.parse_assignment_node()?,
+        };
+
+        Ok(Node::new(kind))
+    }
+
+    /// Parses nodes which assign to a place (e.g. `_3 = load int32, l0`)
+    fn parse_assignment_node(&mut self) -> ParseResult<NodeKind<'static>> {
+        let result_place = self.parse_place()?;
+        self.expect_punct("=")?;
+        let instruction = self.expect_ident()?;
+
+        if instruction == "byte_access" {
+            let access_place = self.parse_place()?;
+            self.expect_punct(",")?;
+            let byte_offset = self.parse_int()?;
+            return Ok(
+                NodeKind::ByteAccessNode(
+                    ByteAccessNode::new(result_place, access_place, byte_offset)
+                )
+            );
+        }
+
+        let PlaceKind::TempId(result_place) = result_place else {
+            let message = format!("Expected the result of `{}` to be a temp", instruction);
+            return Err(self.error(message));
+        };
+
+        let kind = match instruction {
+            "load" => {
+                let load_ty = self.parse_ty()?;
+                self.expect_punct(",")?;
+                let load_place = self.parse_place()?;
+                NodeKind::LoadNode(LoadNode::new(result_place, load_place, load_ty))
+            }
+            "index" => {
+                let place_ty = self.parse_ty()?;
+                self.expect_punct(",")?;
+                let array_place = self.parse_place()?;
+                self.expect_punct(",")?;
+                let index = self.parse_operand()?;
+                NodeKind::IndexNode(IndexNode::new(result_place, array_place, place_ty, index))
+            }
+            "call" => {
+                let ret_ty = self.parse_ty()?;
+                let callee = self.parse_operand()?;
+                self.expect_punct("(")?;
+                let mut args = Vec::new();
+                while !self.eat_punct(")") {
+                    if !args.is_empty() {
+                        self.expect_punct(",")?;
+                    }
+                    args.push(self.parse_operand()?);
+                }
+                self.expect_punct(":")?;
+                self.expect_punct("(")?;
+                let args_ty = self.parse_tys(")")?;
+
+                let args = TyCtx::intern_many_types(args);
+                NodeKind::CallNode(CallNode::new(result_place, callee, args, args_ty, ret_ty))
+            }
+            "trunc" | "zext" | "sext" => {
+                let cast_kind = match instruction {
+                    "trunc" => TyCastKind::Trunc,
+                    "zext" => TyCastKind::Zext,
+                    _ => TyCastKind::Sext,
+                };
+                let from_ty = self.parse_ty()?;
+                let operand = self.parse_operand()?;
+                self.expect_keyword("to")?;
+                let to_ty = self.parse_ty()?;
+                NodeKind::TyCastNode(
+                    TyCastNode::new(result_place, cast_kind, from_ty, to_ty, operand)
+                )
+            }
+            "phi" => {
+                let ty = self.parse_ty()?;
+                let mut incoming = Vec::new();
+                loop {
+                    self.expect_punct("[")?;
+                    let operand = self.parse_operand()?;
+                    self.expect_punct(",")?;
+                    let basic_block_id = self.parse_basic_block_id()?;
+                    self.expect_punct("]")?;
+                    incoming.push((operand, basic_block_id));
+
+                    if !self.eat_punct(",") {
+                        break;
+                    }
+                }
+
+                let incoming = TyCtx::intern_many_types(incoming);
+                NodeKind::PhiNode(PhiNode::new(result_place, ty, incoming))
+            }
+            _ => {
+                let Some(op) = parse_binary_op(instruction) else {
+                    return Err(self.error(format!("Unknown instruction `{}`", instruction)));
+                };
+                let op_ty = self.parse_ty()?;
+                let lhs = self.parse_operand()?;
+                self.expect_punct(",")?;
+                let rhs = self.parse_operand()?;
+                NodeKind::BinaryNode(BinaryNode::new(result_place, op_ty, op, lhs, rhs))
+            }
+        };
+
+        Ok(kind)
+    }
+
+    fn parse_operand(&mut self) -> ParseResult<Operand> {
+        let const_val = match self.peek().clone() {
+            TokenKind::Int(int) => {
+                self.next();
+                let digits_len = int
+                    .find(|c: char| !(c.is_ascii_digit() || c == '-'))
+                    .unwrap_or(int.len());
+                let int_ty = match &int[digits_len..] {
+                    "i8" => IntTy::Int8,
+                    "i16" => IntTy::Int16,
+                    "i32" => IntTy::Int32,
+                    "i64" => IntTy::Int64,
+                    _ => {
+                        let message =
+                            format!("Expected integer with a type (e.g. `5i32`), found `{}`", int);
+                        return Err(self.error(message));
+                    }
+                };
+                let Ok(value) = int[..digits_len].parse() else {
+                    return Err(self.error(format!("Invalid integer `{}`", int)));
+                };
+                Const::Int(value, int_ty)
+            }
+            TokenKind::Ident("true") => {
+                self.next();
+                Const::Bool(true)
+            }
+            TokenKind::Ident("false") => {
+                self.next();
+                Const::Bool(false)
+            }
+            TokenKind::Ident("null") => {
+                self.next();
+                Const::Null
+            }
+            TokenKind::Ident("fn") => {
+                self.next();
+                Const::FnPtr(self.parse_def_id()?)
+            }
+            TokenKind::Ident("str") => {
+                self.next();
+                Const::Str(self.parse_def_id()?)
+            }
+            TokenKind::Punct("(") => {
+                self.next();
+                self.expect_punct(")")?;
+                Const::Void
+            }
+            _ => {
+                return Ok(Operand::PlaceKind(self.parse_place()?));
+            }
+        };
+
+        Ok(Operand::Const(const_val))
+    }
+
+    fn parse_place(&mut self) -> ParseResult<PlaceKind> {
+        let ident = self.expect_ident()?;
+        let place = match ident.split_at(1) {
+            ("_", id) => id.parse().ok().map(|id| PlaceKind::TempId(TempId(id))),
+            ("l", id) => id.parse().ok().map(|id| PlaceKind::LocalMemId(LocalMemId(id))),
+            ("r", id) => id.parse().ok().map(|id| PlaceKind::ResultMemId(ResultMemId(id))),
+            _ => None,
+        };
+
+        place.ok_or_else(|| {
+            self.error(format!("Expected place (e.g. `_0`, `l0` or `r0`), found `{}`", ident))
+        })
+    }
+
+    fn parse_temp_id(&mut self) -> ParseResult<TempId> {
+        match self.parse_place()? {
+            PlaceKind::TempId(temp_id) => Ok(temp_id),
+            _ => Err(self.error("Expected temp (e.g. `_0`)".to_string())),
+        }
+    }
+
+    fn parse_basic_block_id(&mut self) -> ParseResult<BasicBlockId> {
+        let ident = self.expect_ident()?;
+        ident
+            .strip_prefix("bb")
+            .and_then(|id| id.parse().ok())
+            .map(BasicBlockId)
+            .ok_or_else(|| {
+                self.error(format!("Expected basic block (e.g. `bb0`), found `{}`", ident))
+            })
+    }
+
+    fn parse_ty(&mut self) -> ParseResult<Ty> {
+        if self.peek_nth(1) == &TokenKind::Punct("#") {
+            return Ok(Ty::Adt(self.parse_def_id()?));
+        }
+
+        let ty = match self.next() {
+            TokenKind::Punct("(") => Ty::Tuple(self.parse_tys(")")?),
+            TokenKind::Punct("*") => {
+                let mutability = self.parse_mutability();
+                Ty::Ptr(TyCtx::intern_type(self.parse_ty()?), mutability)
+            }
+            TokenKind::Punct("[") => {
+                self.expect_punct("*")?;
+                let mutability = self.parse_mutability();
+                self.expect_punct("]")?;
+                Ty::ManyPtr(TyCtx::intern_type(self.parse_ty()?), mutability)
+            }
+            TokenKind::Punct("!") => Ty::Never,
+            TokenKind::Punct("...") => Ty::VariadicArgs,
+            TokenKind::Punct("{") => {
+                self.expect_keyword("unkown")?;
+                self.expect_punct("}")?;
+                Ty::Unkown
+            }
+            TokenKind::Ident("fn") => {
+                self.expect_punct("(")?;
+                let args = self.parse_tys(")")?;
+                self.expect_punct("->")?;
+                let ret_ty = TyCtx::intern_type(self.parse_ty()?);
+                Ty::FnSig(FnSig::new(args, ret_ty))
+            }
+            TokenKind::Ident("fndef") => Ty::FnDef(self.parse_def_id()?),
+            TokenKind::Ident("constructor") => Ty::AtdConstructer(self.parse_def_id()?),
+            TokenKind::Ident("stack_ptr") => {
+                self.expect_punct("<")?;
+                let mutability = self.parse_mutability();
+                let inner_ty = TyCtx::intern_type(self.parse_ty()?);
+                self.expect_punct(">")?;
+                Ty::StackPtr(inner_ty, mutability)
+            }
+            TokenKind::Ident("null") => Ty::Null,
+            TokenKind::Ident("ZeroSized") => Ty::ZeroSized,
+            TokenKind::Ident("pkg") => Ty::Package,
+            TokenKind::Ident("int8") => Ty::PrimTy(PrimTy::Int(IntTy::Int8)),
+            TokenKind::Ident("int16") => Ty::PrimTy(PrimTy::Int(IntTy::Int16)),
+            TokenKind::Ident("int32") => Ty::PrimTy(PrimTy::Int(IntTy::Int32)),
+            TokenKind::Ident("int64") => Ty::PrimTy(PrimTy::Int(IntTy::Int64)),
+            TokenKind::Ident("uint8") => Ty::PrimTy(PrimTy::Uint(UintTy::Uint8)),
+            TokenKind::Ident("uint16") => Ty::PrimTy(PrimTy::Uint(UintTy::Uint16)),
+            TokenKind::Ident("uint32") => Ty::PrimTy(PrimTy::Uint(UintTy::Uint32)),
+            TokenKind::Ident("uint64") => Ty::PrimTy(PrimTy::Uint(UintTy::Uint64)),
+            TokenKind::Ident("float32") => Ty::PrimTy(PrimTy::Float(FloatTy::Float32)),
+            TokenKind::Ident("float64") => Ty::PrimTy(PrimTy::Float(FloatTy::Float64)),
+            TokenKind::Ident("Str") => Ty::PrimTy(PrimTy::Str),
+            TokenKind::Ident("Bool") => Ty::PrimTy(PrimTy::Bool),
+            TokenKind::Ident("Void") => Ty::PrimTy(PrimTy::Void),
+            found => {
+                return Err(self.error(format!("Expected type, found {}", found)));
+            }
+        };
+
+        Ok(ty)
+    }
+
+    /// Parses comma separated types until the closing punctuation
+    fn parse_tys(&mut self, close: &'static str) -> ParseResult<&'static [Ty]> {
+        let mut tys = Vec::new();
+        while !self.eat_punct(close) {
+            if !tys.is_empty() {
+                self.expect_punct(",")?;
+            }
+            tys.push(self.parse_ty()?);
+        }
+
+        Ok(TyCtx::intern_many_types(tys))
+    }
+
+    fn parse_mutability(&mut self) -> Mutability {
+        if self.eat_keyword("mut") { Mutability::Mutable } else { Mutability::Immutable }
+    }
+
+    fn parse_def_id(&mut self) -> ParseResult<DefId> {
+        let symbol = self.parse_symbol()?;
+        self.expect_punct("#")?;
+        let mod_id = ModId(self.parse_int()?);
+        self.expect_punct(":")?;
+        let node_id = self.parse_int()?;
+
+        Ok(DefId { symbol, node_id: NodeId { node_id, mod_id } })
+    }
+
+    fn parse_symbol(&mut self) -> ParseResult<Symbol> {
+        match self.next() {
+            TokenKind::Ident(ident) => Ok(Symbol::new(ident)),
+            TokenKind::Str(str) => Ok(Symbol::new(&str)),
+            found => Err(self.error(format!("Expected identifier or string, found {}", found))),
+        }
+    }
+
+    fn parse_int<T: std::str::FromStr>(&mut self) -> ParseResult<T> {
+        match self.next() {
+            TokenKind::Int(int) => {
+                int.parse().map_err(|_| self.error(format!("Invalid integer `{}`", int)))
+            }
+            found => Err(self.error(format!("Expected integer, found {}", found))),
+        }
+    }
+
+    fn peek(&self) -> &TokenKind<'src> {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, n: usize) -> &TokenKind<'src> {
+        let i = (self.pos + n).min(self.tokens.len() - 1);
+        &self.tokens[i].kind
+    }
+
+    fn next(&mut self) -> TokenKind<'src> {
+        let kind = self.peek().clone();
+        if kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+        kind
+    }
+
+    fn expect_ident(&mut self) -> ParseResult<&'src str> {
+        match self.next() {
+            TokenKind::Ident(ident) => Ok(ident),
+            found => Err(self.error(format!("Expected identifier, found {}", found))),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> ParseResult<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected `{}`, found {}", keyword, self.peek())))
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if *self.peek() == TokenKind::Ident(keyword) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_punct(&mut self, punct: &'static str) -> ParseResult<()> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected `{}`, found {}", punct, self.peek())))
+        }
+    }
+
+    fn eat_punct(&mut self, punct: &'static str) -> bool {
+        if *self.peek() == TokenKind::Punct(punct) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Error at the line of the last consumed token
+    fn error(&self, message: String) -> IcfgParseError {
+        let line = self.tokens
+            .get(self.pos.saturating_sub(1))
+            .map_or(1, |token| token.line);
+        IcfgParseError { line, message }
+    }
+}
+
+fn parse_binary_op(instruction: &str) -> Option<BinaryOp> {
+    let op = match instruction {
+        "add" => BinaryOp::ArithmeticOp(ArithmeticOp::Add),
+        "sub" => BinaryOp::ArithmeticOp(ArithmeticOp::Sub),
+        "mul" => BinaryOp::ArithmeticOp(ArithmeticOp::Mul),
+        "div" => BinaryOp::ArithmeticOp(ArithmeticOp::Div),
+        "eq" => BinaryOp::ComparisonOp(ComparisonOp::Eq),
+        "ne" => BinaryOp::ComparisonOp(ComparisonOp::Ne),
+        "ge" => BinaryOp::ComparisonOp(ComparisonOp::Ge),
+        "gt" => BinaryOp::ComparisonOp(ComparisonOp::Gt),
+        "le" => BinaryOp::ComparisonOp(ComparisonOp::Le),
+        "lt" => BinaryOp::ComparisonOp(ComparisonOp::Lt),
+        _ => {
+            return None;
+        }
+    };
+
+    Some(op)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ IcfgParser, IcfgPrettifier };
+
+    const SRC: &str =
+        r##"extern fn printf#0:0(Str, ...) -> int32
+str "%d\\0A\\00"#0:1 4
+struct Foo#0:2 { a#0:3: int32, b#0:4: (int64, *mut [*]uint8) }
+enum Holder#0:5 { Empty#0:6, Full#0:7 }
+variant Empty#0:6 = Holder#0:5 0 (ZeroSized)
+variant Full#0:7 = Holder#0:5 1 (Foo#0:2)
+typedef Meters#0:8 = float64
+copy Holder#0:5
+on_drop Foo#0:2 = onDrop#0:9
+
+main fn main#0:10() -> Void {
+    local l0 foo: mut Foo#0:2 drop
+    local l1 "weird name": stack_ptr<mut fn(int32) -> Bool>
+    result r0: int32
+bb0:
+    store init int32 -5i32, l0
+    r0 = byte_access l0, 4
+    _0 = load int32, l0
+    _1 = lt int32 _0, 10i32
+    br _1, bb1, bb2
+bb1:
+    _2 = call int32 fn printf#0:0(str "%d\\0A\\00"#0:1, _0) : (Str, ..., int32)
+    _3 = sext int32 _2 to int64
+    switch int64 _3, bb2 [-1: bb1, 0: bb2, 3: bb2]
+bb2:
+    _4 = phi int32 [_0, bb0], [_2, bb1]
+    store assign int32 _4, l0
+    drop Foo#0:2 l0
+    ret Void ()
+}
+
+exported noinline fn onDrop#0:9(_0: *mut Foo#0:2) -> Void {
+bb0:
+    _1 = index uint8, _0, 2i64
+    _2 = trunc int64 8i64 to int8
+    _3 = load Bool, _0
+    drop Foo#0:2 _0 if _3
+    ret Void ()
+}
+
+drop_glue Holder#0:5(_0: *mut Holder#0:5) -> Void {
+bb0:
+    unreachable
+}
+"##;
+
+    #[test]
+    fn round_trip() {
+        let icfg = IcfgParser::new(SRC).parse_icfg().unwrap();
+        assert_eq!(icfg.cfgs.len(), 3);
+        assert!(icfg.cfgs[1].is_exported);
+        assert_eq!(IcfgPrettifier::new(&icfg).prettify().trim_start(), SRC);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = IcfgParser::new("fn f#0:1() -> Void {\n    ret Void ()\n}")
+            .parse_icfg()
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "Line 2: Expected basic block before node");
+
+        let error = IcfgParser::new("fn f#0:1() -> Void {\nbb1:\n}").parse_icfg().err().unwrap();
+        assert_eq!(error.line, 2);
+    }
+}
@@ -1,5 +1,70 @@
+/*
 
-use crate::{ cfg_visitor::{ walk_basic_block, CfgVisitor }, Cfg, Icfg, Operand, PlaceKind };
+Textual ICFG
+
+The prettifier prints the whole icfg in a stable syntax, which `IcfgParser` reads back. Apart from
+the cfgs it contains the resolved information used after building the icfg (C functions, constant
+strings and ADTs). Spans aren't part of the syntax
+
+Declarations:
+
+- `extern fn printf#0:3(*int8, ...) -> int32`
+- `str "Hello"#0:12 6`                         (constant string and its length)
+- `struct Point#0:4 { x#0:5: int32, y#0:6: int32 }`
+- `enum Option#0:7 { Some#0:8, None#0:9 }`
+- `variant Some#0:8 = Option#0:7 0 (int32)`    (enum, index and payload)
+- `typedef Meters#0:10 = int32`
+- `copy Point#0:4`                              (ADT implementing `Copy`)
+- `on_drop Foo#0:11 = onDrop#0:14`              (ADT implementing `Drop`)
+
+Cfgs (optionally prefixed by `exported` and by `inline` or `noinline`):
+
+```
+main fn main#0:1() -> Void { ... }
+fn add#0:2(_0: int32, _1: int32) -> int32 { ... }
+drop_glue Foo#0:11(_0: *mut Foo#0:11) -> Void { ... }
+```
+
+A cfg body declares its local mems (`local l0 a: mut int32 drop`) and result mems
+(`result r0: int32`) followed by its basic blocks (`bb0:`) and their nodes:
+
+- `br bb1` and `br _3, bb1, bb2`
+- `switch int64 _3, bb4 [0: bb1, 1: bb2]`       (default followed by the cases)
+- `_3 = add int32 _1, _2`                       (add, sub, mul, div, eq, ne, ge, gt, le, lt)
+- `store init int32 5i32, l0`                   (init or assign)
+- `_3 = load int32, l0`
+- `_3 = index int32, l0, _2`
+- `r0 = byte_access l0, 8`
+- `ret int32 _3`
+- `_3 = call int32 fn add#0:2(_1, 5i32) : (int32, int32)`
+- `_3 = sext int8 _1 to int32`                  (trunc, zext or sext)
+- `drop Foo#0:11 l0` and `drop Foo#0:11 l0 if _4`
+- `_3 = phi int32 [_1, bb0], [5i32, bb2]`
+- `unreachable`
+
+Operands are places (`_0`, `l0`, `r0`) or constants (`5i32`, `true`, `null`, `()`,
+`fn add#0:2`, `str "Hello"#0:12`)
+
+Types are written as `int8`..`int64`, `uint8`..`uint64`, `float32`, `float64`, `Str`, `Bool`,
+`Void`, `(T, K)`, `fn(T) -> K`, `fndef add#0:2`, `constructor Point#0:4`, `Point#0:4`,
+`stack_ptr<mut T>`, `*mut T`, `[*]T`, `null`, `!`, `ZeroSized`, `...`, `pkg` and `{unkown}`
+
+Definitions are written as `symbol#mod_id:node_id`, where the symbol is quoted if it isn't an
+identifier. Everything after `//` on a line is a comment
+
+*/
+
+use crate::{
+    cfg_visitor::{ walk_basic_block, walk_cfg, CfgVisitor },
+    Cfg,
+    Const,
+    Icfg,
+    Operand,
+    PlaceKind,
+    StoreKind,
+};
+use ir::{ Adt, CfgFnKind, DefId, Externism, InlineAttr, IntTy, NameBindingKind, Ty };
+use op::{ ArithmeticOp, BinaryOp, ComparisonOp };
 use std::fmt::Write;
 
 const INDENTATION: usize = 4;
@@ -15,52 +80,257 @@ impl<'b> IcfgPrettifier<'b> {
     }
 
     pub fn print_icfg(&mut self) {
-        match self.visit_cfg(&self.icfg.cfgs[0]) {
-            Ok(_) => {}
-            Err(e) => panic!("{}", e),
+        self.write_icfg();
+        println!("{}", self.buffer)
+    }
+
+    /// The textual form of the icfg, which can be read back with `IcfgParser`
+    pub fn prettify(mut self) -> String {
+        self.write_icfg();
+        self.buffer
+    }
+
+    /// The nodes of a basic block, one per line (without its label)
+    pub(crate) fn prettify_basic_block(
+        mut self,
+        basic_block: &crate::BasicBlock,
+        cfg: &Cfg
+    ) -> String {
+        if let Err(e) = walk_basic_block(&mut self, basic_block, cfg) {
+            panic!("{}", e);
         }
+        self.buffer
+    }
 
-        println!("{}", self.buffer)
+    fn write_icfg(&mut self) {
+        self.buffer.clear();
+        let result = self.write_declarations().and_then(|_| {
+            for cfg in self.icfg.cfgs.iter() {
+                self.visit_cfg(cfg)?;
+            }
+            Ok(())
+        });
+
+        if let Err(e) = result {
+            panic!("{}", e);
+        }
     }
 
-    fn display_place_kind(place: &PlaceKind, cfg: &Cfg) -> String {
-        let mut temp_buffer = String::with_capacity(8);
-        {
-            match place {
-                // PlaceKind::GlobalMemId(global_mem_id) => {
-                //     write!(temp_buffer, "{}", cfg.get_global_mem(*global_mem_id))
-                // }
-                PlaceKind::LocalMemId(local_mem_id) => {
-                    write!(temp_buffer, "{}", cfg.get_local_mem(*local_mem_id))
+    fn write_declarations(&mut self) -> Result<(), std::fmt::Error> {
+        let resolved_information = &self.icfg.resolved_information;
+
+        // The same C function can be declared in multiple packages (with different def ids), but
+        // only the first declaration is in `clib_fns`
+        let mut other_clib_fns = resolved_information.def_id_to_name_binding
+            .iter()
+            .filter(|(def_id, name_binding)| {
+                matches!(name_binding.kind, NameBindingKind::Fn(_, _, Externism::Clib)) &&
+                    !resolved_information.clib_fns.contains(def_id)
+            })
+            .map(|(def_id, _)| def_id)
+            .collect::<Vec<_>>();
+        other_clib_fns.sort_by_key(|def_id| sort_key(def_id));
+
+        for def_id in resolved_information.clib_fns.iter().chain(other_clib_fns) {
+            if
+                let NameBindingKind::Fn(fn_sig, _, Externism::Clib) =
+                    resolved_information.get_name_binding_from_def_id(def_id).kind
+            {
+                writeln!(
+                    self.buffer,
+                    "extern fn {}({}) -> {}",
+                    display_def_id(def_id),
+                    display_tys(fn_sig.args),
+                    display_ty(fn_sig.ret_ty)
+                )?;
+            }
+        }
+
+        for (def_id, const_str_len) in resolved_information.const_strs.iter() {
+            writeln!(self.buffer, "str {} {}", display_def_id(def_id), const_str_len.0)?;
+        }
+
+        // Sorted as the order of the hash maps isn't stable
+        let mut adts = resolved_information.def_id_to_name_binding
+            .iter()
+            .filter_map(|(def_id, name_binding)| {
+                match name_binding.kind {
+                    NameBindingKind::Adt(adt) => Some((*def_id, adt)),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+        adts.sort_by_key(|(def_id, _)| sort_key(def_id));
+
+        for (def_id, adt) in adts.iter() {
+            let def_id = display_def_id(def_id);
+            match adt {
+                Adt::Struct(fields) => {
+                    let fields = fields
+                        .iter()
+                        .map(|(field, ty)| {
+                            format!("{}: {}", display_def_id(field), display_ty(ty))
+                        })
+                        .collect::<Vec<_>>();
+                    if fields.is_empty() {
+                        writeln!(self.buffer, "struct {} {{}}", def_id)?;
+                    } else {
+                        writeln!(self.buffer, "struct {} {{ {} }}", def_id, fields.join(", "))?;
+                    }
+                }
+                Adt::Enum(variants) => {
+                    let variants = variants.iter().map(display_def_id).collect::<Vec<_>>();
+                    if variants.is_empty() {
+                        writeln!(self.buffer, "enum {} {{}}", def_id)?;
+                    } else {
+                        writeln!(self.buffer, "enum {} {{ {} }}", def_id, variants.join(", "))?;
+                    }
                 }
-                PlaceKind::ResultMemId(result_mem_id) => {
-                    write!(temp_buffer, "{}", cfg.get_result_mem(*result_mem_id))
+                Adt::EnumVariant(enum_def_id, variant_id, tys) => {
+                    writeln!(
+                        self.buffer,
+                        "variant {} = {} {} ({})",
+                        def_id,
+                        display_def_id(enum_def_id),
+                        variant_id.0,
+                        display_tys(tys)
+                    )?;
+                }
+                Adt::Typedef(ty) => {
+                    writeln!(self.buffer, "typedef {} = {}", def_id, display_ty(ty))?;
                 }
-                PlaceKind::TempId(temp_id) => { write!(temp_buffer, "{}", temp_id) }
             }
-        }.expect("Unexpected write error");
-        temp_buffer
+        }
+
+        let mut copy_adt_def_ids = resolved_information.copy_adt_def_ids
+            .iter()
+            .collect::<Vec<_>>();
+        copy_adt_def_ids.sort_by_key(|def_id| sort_key(def_id));
+        for def_id in copy_adt_def_ids {
+            writeln!(self.buffer, "copy {}", display_def_id(def_id))?;
+        }
+
+        let mut drop_fns = resolved_information.adt_def_id_to_drop_fn.iter().collect::<Vec<_>>();
+        drop_fns.sort_by_key(|(def_id, _)| sort_key(def_id));
+        for (adt_def_id, drop_fn) in drop_fns {
+            writeln!(
+                self.buffer,
+                "on_drop {} = {}",
+                display_def_id(adt_def_id),
+                display_def_id(drop_fn)
+            )?;
+        }
+
+        Ok(())
     }
+}
 
-    fn dislay_operand(operand: &Operand, cfg: &Cfg) -> String {
-        let mut temp_buffer = String::with_capacity(8);
+fn sort_key(def_id: &DefId) -> (u32, u32) {
+    (def_id.node_id.mod_id.0, def_id.node_id.node_id)
+}
 
-        (|| -> Result<(), std::fmt::Error> {
-            write!(temp_buffer, "(")?;
+pub(crate) fn display_cfg_fn_kind(cfg_fn_kind: CfgFnKind) -> String {
+    match cfg_fn_kind {
+        CfgFnKind::Main(def_id) => format!("main fn {}", display_def_id(&def_id)),
+        CfgFnKind::Fn(def_id) => format!("fn {}", display_def_id(&def_id)),
+        CfgFnKind::DropGlue(ty) => format!("drop_glue {}", display_ty(&ty)),
+    }
+}
 
-            match &operand {
-                Operand::PlaceKind(place) => {
-                    let displayed_place = Self::display_place_kind(place, cfg);
-                    write!(temp_buffer, "{}", displayed_place)?;
-                }
-                Operand::Const(const_val) => write!(temp_buffer, "{}", const_val)?,
-            }
+/// Symbols which aren't identifiers (e.g. constant strings) are quoted
+fn display_symbol(symbol: &str) -> String {
+    let mut chars = symbol.chars();
+    let is_ident =
+        chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') &&
+        chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if is_ident {
+        symbol.to_string()
+    } else {
+        format!("\"{}\"", symbol.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+fn display_def_id(def_id: &DefId) -> String {
+    format!(
+        "{}#{}:{}",
+        display_symbol(def_id.symbol.get()),
+        def_id.node_id.mod_id.0,
+        def_id.node_id.node_id
+    )
+}
 
-            // write!(temp_buffer, " as {})", operand.ty)
-            write!(temp_buffer, ")")
-        })().expect("Unexpected write error");
+fn display_ty(ty: &Ty) -> String {
+    match ty {
+        Ty::Tuple(tys) => format!("({})", display_tys(tys)),
+        Ty::FnSig(fn_sig) => {
+            format!("fn({}) -> {}", display_tys(fn_sig.args), display_ty(fn_sig.ret_ty))
+        }
+        Ty::FnDef(def_id) => format!("fndef {}", display_def_id(def_id)),
+        Ty::AtdConstructer(def_id) => format!("constructor {}", display_def_id(def_id)),
+        Ty::Adt(def_id) => display_def_id(def_id),
+        Ty::StackPtr(inner, mutability) => {
+            format!("stack_ptr<{}{}>", mutability, display_ty(inner))
+        }
+        Ty::Ptr(inner, mutability) => format!("*{}{}", mutability, display_ty(inner)),
+        Ty::ManyPtr(inner, mutability) => format!("[*{}]{}", mutability, display_ty(inner)),
+        | Ty::PrimTy(_)
+        | Ty::Null
+        | Ty::Never
+        | Ty::ZeroSized
+        | Ty::VariadicArgs
+        | Ty::Package
+        | Ty::Unkown => ty.to_string(),
+    }
+}
+
+fn display_tys(tys: &[Ty]) -> String {
+    tys.iter().map(display_ty).collect::<Vec<_>>().join(", ")
+}
+
+fn display_int_ty_suffix(int_ty: IntTy) -> &'static str {
+    match int_ty {
+        IntTy::Int8 => "i8",
+        IntTy::Int16 => "i16",
+        IntTy::Int32 => "i32",
+        IntTy::Int64 => "i64",
+    }
+}
 
-        temp_buffer
+fn display_binary_op(op: BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::ArithmeticOp(ArithmeticOp::Add) => "add",
+        BinaryOp::ArithmeticOp(ArithmeticOp::Sub) => "sub",
+        BinaryOp::ArithmeticOp(ArithmeticOp::Mul) => "mul",
+        BinaryOp::ArithmeticOp(ArithmeticOp::Div) => "div",
+        BinaryOp::ComparisonOp(ComparisonOp::Eq) => "eq",
+        BinaryOp::ComparisonOp(ComparisonOp::Ne) => "ne",
+        BinaryOp::ComparisonOp(ComparisonOp::Ge) => "ge",
+        BinaryOp::ComparisonOp(ComparisonOp::Gt) => "gt",
+        BinaryOp::ComparisonOp(ComparisonOp::Le) => "le",
+        BinaryOp::ComparisonOp(ComparisonOp::Lt) => "lt",
+    }
+}
+
+fn display_place_kind(place: &PlaceKind) -> String {
+    match place {
+        PlaceKind::LocalMemId(local_mem_id) => format!("l{}", local_mem_id.0),
+        PlaceKind::ResultMemId(result_mem_id) => format!("r{}", result_mem_id.0),
+        PlaceKind::TempId(temp_id) => format!("{}", temp_id),
+    }
+}
+
+fn display_operand(operand: &Operand) -> String {
+    match operand {
+        Operand::PlaceKind(place) => display_place_kind(place),
+        Operand::Const(Const::Int(int, int_ty)) => {
+            format!("{}{}", int, display_int_ty_suffix(*int_ty))
+        }
+        Operand::Const(Const::Bool(bool)) => bool.to_string(),
+        Operand::Const(Const::FnPtr(def_id)) => format!("fn {}", display_def_id(def_id)),
+        Operand::Const(Const::Str(def_id)) => format!("str {}", display_def_id(def_id)),
+        Operand::Const(Const::Null) => "null".to_string(),
+        Operand::Const(Const::Void) => "()".to_string(),
     }
 }
 
@@ -71,16 +341,47 @@ impl CfgVisitor for IcfgPrettifier<'_> {
         Ok(())
     }
 
+    fn visit_cfg(&mut self, cfg: &Cfg) -> Self::Result {
+        writeln!(self.buffer)?;
+        if cfg.is_exported {
+            write!(self.buffer, "exported ")?;
+        }
+        match cfg.inline_attr {
+            InlineAttr::Auto => {}
+            InlineAttr::Always => write!(self.buffer, "inline ")?,
+            InlineAttr::Never => write!(self.buffer, "noinline ")?,
+        }
+        write!(self.buffer, "{}", display_cfg_fn_kind(cfg.cfg_fn_kind))?;
+
+        let args = cfg.args
+            .iter()
+            .map(|(temp_id, ty)| format!("{}: {}", temp_id, display_ty(ty)))
+            .collect::<Vec<_>>();
+        writeln!(self.buffer, "({}) -> {} {{", args.join(", "), display_ty(&cfg.ret_ty))?;
+        walk_cfg(self, cfg)?;
+        writeln!(self.buffer, "}}")
+    }
+
     fn visit_local_mem(&mut self, local_mem: &crate::LocalMem) -> Self::Result {
-        writeln!(self.buffer, "{}declare {}: {}", " ".repeat(INDENTATION), local_mem, local_mem.ty)
+        writeln!(
+            self.buffer,
+            "{}local l{} {}: {}{}{}",
+            " ".repeat(INDENTATION),
+            local_mem.local_mem_id.0,
+            display_symbol(local_mem.symbol.get()),
+            local_mem.mutability,
+            display_ty(&local_mem.ty),
+            if local_mem.requires_drop { " drop" } else { "" }
+        )
     }
+
     fn visit_result_mem(&mut self, result_mem: &crate::ResultMem) -> Self::Result {
         writeln!(
             self.buffer,
-            "{}declare {}: {}",
+            "{}result {}: {}",
             " ".repeat(INDENTATION),
             result_mem.result_mem_id,
-            result_mem.ty
+            display_ty(&result_mem.ty)
         )
     }
 
@@ -89,27 +390,24 @@ impl CfgVisitor for IcfgPrettifier<'_> {
         basic_block: &crate::BasicBlock,
         cfg: &crate::Cfg
     ) -> Self::Result {
-        writeln!(self.buffer, "bb{}: {{", basic_block.basic_block_id.0)?;
-        walk_basic_block(self, basic_block, cfg)?;
-        writeln!(self.buffer, "}}")?;
-
-        Self::default_result()
+        writeln!(self.buffer, "bb{}:", basic_block.basic_block_id.0)?;
+        walk_basic_block(self, basic_block, cfg)
     }
 
     fn visit_binary_node(
         &mut self,
         binary_node: &crate::BinaryNode,
-        cfg: &crate::Cfg
+        _cfg: &crate::Cfg
     ) -> Self::Result {
         writeln!(
             self.buffer,
-            "{}{}: {} = {} {} {}",
+            "{}{} = {} {} {}, {}",
             " ".repeat(INDENTATION),
             binary_node.result_place,
-            binary_node.op_ty,
-            Self::dislay_operand(&binary_node.lhs, cfg),
-            binary_node.op,
-            Self::dislay_operand(&binary_node.rhs, cfg)
+            display_binary_op(binary_node.op),
+            display_ty(&binary_node.op_ty),
+            display_operand(&binary_node.lhs),
+            display_operand(&binary_node.rhs)
         )
     }
 
@@ -126,35 +424,159 @@ impl CfgVisitor for IcfgPrettifier<'_> {
             self.buffer,
             "{}br {}, bb{}, bb{}",
             " ".repeat(INDENTATION),
-            branch_cond_node.condition,
+            display_operand(&branch_cond_node.condition),
             branch_cond_node.true_branch.0,
             branch_cond_node.false_branch.0
         )
     }
 
-    fn visit_load_node(&mut self, load_node: &crate::LoadNode, cfg: &Cfg) -> Self::Result {
+    fn visit_switch_node(&mut self, switch_node: &crate::SwitchNode, _cfg: &Cfg) -> Self::Result {
+        let cases = switch_node.cases
+            .iter()
+            .map(|(case, basic_block_id)| format!("{}: bb{}", case, basic_block_id.0))
+            .collect::<Vec<_>>();
+
+        writeln!(
+            self.buffer,
+            "{}switch {} {}, bb{} [{}]",
+            " ".repeat(INDENTATION),
+            display_ty(&switch_node.ty),
+            display_operand(&switch_node.value),
+            switch_node.default.0,
+            cases.join(", ")
+        )
+    }
+
+    fn visit_load_node(&mut self, load_node: &crate::LoadNode, _cfg: &Cfg) -> Self::Result {
         writeln!(
             self.buffer,
-            "{}{}: {} = load {}",
+            "{}{} = load {}, {}",
             " ".repeat(INDENTATION),
             load_node.result_place,
-            load_node.load_ty,
-            Self::display_place_kind(&load_node.load_place, cfg)
+            display_ty(&load_node.load_ty),
+            display_place_kind(&load_node.load_place)
         )
     }
 
     fn visit_store_node(
         &mut self,
         store_node: &crate::StoreNode,
-        cfg: &crate::Cfg
+        _cfg: &crate::Cfg
     ) -> Self::Result {
         writeln!(
             self.buffer,
-            "{}{}: {} = {}",
+            "{}store {} {} {}, {}",
+            " ".repeat(INDENTATION),
+            match store_node.store_kind {
+                StoreKind::Init => "init",
+                StoreKind::Assign => "assign",
+            },
+            display_ty(&store_node.op_ty),
+            display_operand(&store_node.value),
+            display_place_kind(&store_node.setter)
+        )
+    }
+
+    fn visit_index_node(&mut self, index_node: &crate::IndexNode, _cfg: &Cfg) -> Self::Result {
+        writeln!(
+            self.buffer,
+            "{}{} = index {}, {}, {}",
+            " ".repeat(INDENTATION),
+            index_node.result_place,
+            display_ty(&index_node.place_ty),
+            display_place_kind(&index_node.array_place),
+            display_operand(&index_node.index)
+        )
+    }
+
+    fn visit_byte_access_node(
+        &mut self,
+        byte_access_node: &crate::ByteAccessNode,
+        _cfg: &Cfg
+    ) -> Self::Result {
+        writeln!(
+            self.buffer,
+            "{}{} = byte_access {}, {}",
+            " ".repeat(INDENTATION),
+            display_place_kind(&byte_access_node.result_place),
+            display_place_kind(&byte_access_node.access_place),
+            byte_access_node.byte_offset
+        )
+    }
+
+    fn visit_return_node(&mut self, return_node: &crate::ReturnNode, _cfg: &Cfg) -> Self::Result {
+        writeln!(
+            self.buffer,
+            "{}ret {} {}",
+            " ".repeat(INDENTATION),
+            display_ty(&return_node.ret_ty),
+            display_operand(&return_node.ret_val)
+        )
+    }
+
+    fn visit_call_node(&mut self, call_node: &crate::CallNode, _cfg: &Cfg) -> Self::Result {
+        writeln!(
+            self.buffer,
+            "{}{} = call {} {}({}) : ({})",
+            " ".repeat(INDENTATION),
+            call_node.result_place,
+            display_ty(&call_node.ret_ty),
+            display_operand(&call_node.callee),
+            call_node.args.iter().map(display_operand).collect::<Vec<_>>().join(", "),
+            display_tys(call_node.args_ty)
+        )
+    }
+
+    fn visit_ty_cast_node(&mut self, ty_cast_node: &crate::TyCastNode, _cfg: &Cfg) -> Self::Result {
+        writeln!(
+            self.buffer,
+            "{}{} = {} {} {} to {}",
+            " ".repeat(INDENTATION),
+            ty_cast_node.result_place,
+            ty_cast_node.cast_kind,
+            display_ty(&ty_cast_node.from_ty),
+            display_operand(&ty_cast_node.operand),
+            display_ty(&ty_cast_node.to_ty)
+        )
+    }
+
+    fn visit_drop_node(&mut self, drop_node: &crate::DropNode, _cfg: &Cfg) -> Self::Result {
+        write!(
+            self.buffer,
+            "{}drop {} {}",
+            " ".repeat(INDENTATION),
+            display_ty(&drop_node.ty),
+            display_place_kind(&drop_node.place)
+        )?;
+        if let Some(drop_flag) = drop_node.drop_flag {
+            write!(self.buffer, " if {}", drop_flag)?;
+        }
+        writeln!(self.buffer)
+    }
+
+    fn visit_unreachable_node(
+        &mut self,
+        _unreachable_node: &crate::UnreachableNode,
+        _cfg: &Cfg
+    ) -> Self::Result {
+        writeln!(self.buffer, "{}unreachable", " ".repeat(INDENTATION))
+    }
+
+    fn visit_phi_node(&mut self, phi_node: &crate::PhiNode, _cfg: &Cfg) -> Self::Result {
+        let incoming = phi_node.incoming
+            .iter()
+            .map(|(operand, basic_block_id)| {
+                format!("[{}, bb{}]", display_operand(operand), basic_block_id.0)
+            })
+            .collect::<Vec<_>>();
+
+        writeln!(
+            self.buffer,
+            "{}{} = phi {} {}",
             " ".repeat(INDENTATION),
-            Self::display_place_kind(&store_node.setter, cfg),
-            store_node.op_ty,
-            Self::dislay_operand(&store_node.value, cfg)
+            phi_node.result_place,
+            display_ty(&phi_node.ty),
+            incoming.join(", ")
         )
     }
 }
@@ -0,0 +1,573 @@
+/*
+
+Function inlining
+
+A call to a function is replaced by a copy of the function's body when the function is marked with
+`@inline`, or when it's small enough (at most `INLINE_THRESHOLD` nodes) and isn't marked with
+`@noinline`
+
+The basic block of the call is split at the call: the nodes before it branch to the entry of the
+copy, and the nodes after it are moved to a new continuation block. Each `ReturnNode` of the copy
+stores the returned value in a new variable and branches to the continuation block, which loads
+the value into the result of the call. The temps, variables, implicit results and basic blocks of
+the copy are numbered after the ones of the caller, and the arguments are replaced by the operands
+given to the call
+
+Calls where the callee can reach the caller in the call graph are recursive and never inlined.
+The cfgs are inlined into bottom up in the call graph, so a callee has already been inlined into
+when it's copied. Calls inside a copy aren't inlined again, which bounds the growth of mutually
+recursive functions
+
+*/
+
+use fxhash::FxHashMap;
+use ir::{
+    CfgFnKind,
+    InlineAttr,
+    LocalMem,
+    LocalMemId,
+    Mutability,
+    ResultMem,
+    ResultMemId,
+    Symbol,
+    TempId,
+    Ty,
+    TyCtx,
+};
+use span::Span;
+
+use crate::{
+    BasicBlock,
+    BasicBlockId,
+    BranchNode,
+    CallGraph,
+    CallNode,
+    Cfg,
+    Const,
+    Icfg,
+    LoadNode,
+    Node,
+    NodeKind,
+    Operand,
+    PhiNode,
+    PlaceKind,
+    StoreKind,
+    StoreNode,
+};
+
+/// Functions with at most this many nodes are inlined without `@inline`
+const INLINE_THRESHOLD: usize = 20;
+
+impl Icfg<'_> {
+    /// Inlines calls to small functions and functions marked with `@inline`
+    pub fn inline_functions(&mut self) {
+        let call_graph = CallGraph::new(&self.cfgs);
+        let fn_def_id_to_index = self.cfgs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, cfg)| {
+                match cfg.cfg_fn_kind {
+                    CfgFnKind::Fn(def_id) => Some((def_id, i)),
+                    CfgFnKind::Main(_) | CfgFnKind::DropGlue(_) => None,
+                }
+            })
+            .collect::<FxHashMap<_, _>>();
+
+        for caller_index in get_post_order(&call_graph, self.cfgs.len()) {
+            // Which cfgs can be called without recursing into the caller
+            let mut is_non_recursive = FxHashMap::default();
+            // Basic blocks of the caller (and the node to start from) which may contain calls
+            let mut worklist = (0..self.cfgs[caller_index].basic_blocks.len())
+                .map(|bb_id| (BasicBlockId(bb_id as u32), 0))
+                .collect::<Vec<_>>();
+
+            while let Some((basic_block_id, start)) = worklist.pop() {
+                let caller = &self.cfgs[caller_index];
+                let nodes = &caller.basic_blocks[basic_block_id.0 as usize].nodes;
+                let call = nodes
+                    .iter()
+                    .enumerate()
+                    .skip(start)
+                    .find_map(|(node_index, node)| {
+                        let NodeKind::CallNode(call_node) = node.kind else {
+                            return None;
+                        };
+                        let Operand::Const(Const::FnPtr(def_id)) = call_node.callee else {
+                            return None;
+                        };
+                        let callee_index = *fn_def_id_to_index.get(&def_id)?;
+                        let callee = &self.cfgs[callee_index];
+
+                        let should_inline = match callee.inline_attr {
+                            InlineAttr::Always => true,
+                            InlineAttr::Never => false,
+                            InlineAttr::Auto => get_size(callee) <= INLINE_THRESHOLD,
+                        };
+                        if !should_inline || call_node.ret_ty.is_never() {
+                            return None;
+                        }
+
+                        let is_non_recursive = *is_non_recursive
+                            .entry(callee_index)
+                            .or_insert_with(|| {
+                                !call_graph.get_reachable([callee_index])[caller_index]
+                            });
+                        is_non_recursive.then_some((node_index, call_node, callee_index))
+                    });
+
+                let Some((node_index, call_node, callee_index)) = call else {
+                    continue;
+                };
+
+                let callee = InlinedBody::new(&self.cfgs[callee_index]);
+                let location = (basic_block_id, node_index);
+                match inline_call(&mut self.cfgs[caller_index], location, call_node, &callee) {
+                    Some(continuation_id) => worklist.push((continuation_id, 0)),
+                    None => worklist.push((basic_block_id, node_index + 1)),
+                }
+            }
+        }
+    }
+}
+
+/// The number of nodes in a cfg
+fn get_size(cfg: &Cfg) -> usize {
+    cfg.basic_blocks
+        .iter()
+        .map(|basic_block| basic_block.nodes.len())
+        .sum()
+}
+
+/// The cfgs ordered such that callees come before their callers (apart from recursive calls)
+fn get_post_order(call_graph: &CallGraph, cfgs_len: usize) -> Vec<usize> {
+    let mut post_order = Vec::with_capacity(cfgs_len);
+    let mut visited = vec![false; cfgs_len];
+
+    for root in 0..cfgs_len {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+
+        // Each entry is a cfg and the index of its next callee to visit
+        let mut stack = vec![(root, 0)];
+        while let Some((index, next_callee)) = stack.last_mut() {
+            let index = *index;
+            if let Some(callee) = call_graph.callees(index).get(*next_callee) {
+                *next_callee += 1;
+                if !visited[*callee] {
+                    visited[*callee] = true;
+                    stack.push((*callee, 0));
+                }
+            } else {
+                post_order.push(index);
+                stack.pop();
+            }
+        }
+    }
+
+    post_order
+}
+
+/// The parts of a callee which are copied into the caller
+struct InlinedBody<'a> {
+    cfg_fn_kind: CfgFnKind,
+    args: Vec<(TempId, Ty)>,
+    local_mems: Vec<LocalMem>,
+    result_mems: Vec<ResultMem>,
+    basic_blocks: Vec<Vec<Node<'a>>>,
+}
+
+impl<'a> InlinedBody<'a> {
+    fn new(cfg: &Cfg<'a>) -> Self {
+        Self {
+            cfg_fn_kind: cfg.cfg_fn_kind,
+            args: cfg.args.clone(),
+            local_mems: cfg.local_mems.clone(),
+            result_mems: cfg.result_mems.clone(),
+            basic_blocks: cfg.basic_blocks
+                .iter()
+                .map(|basic_block| basic_block.nodes.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Replaces the call at the location with the body of the callee, returning the continuation
+/// block. Returns None (without changing the caller) if the callee uses an argument given as a
+/// constant as a place
+fn inline_call<'a>(
+    caller: &mut Cfg<'a>,
+    (basic_block_id, node_index): (BasicBlockId, usize),
+    call_node: CallNode<'a>,
+    callee: &InlinedBody<'a>
+) -> Option<BasicBlockId> {
+    let ret_local_mem_id = if call_node.ret_ty.is_void() {
+        None
+    } else {
+        Some(LocalMemId((caller.local_mems.len() + callee.local_mems.len()) as u32))
+    };
+    let continuation_id = BasicBlockId(
+        (caller.basic_blocks.len() + callee.basic_blocks.len()) as u32
+    );
+
+    let remapper = Remapper {
+        args: callee.args
+            .iter()
+            .map(|(temp_id, _)| *temp_id)
+            .zip(call_node.args.iter().copied())
+            .collect(),
+        temp_offset: caller.get_next_temp_id().0,
+        local_mem_offset: caller.local_mems.len() as u32,
+        result_mem_offset: caller.result_mems.len() as u32,
+        basic_block_offset: caller.basic_blocks.len() as u32,
+    };
+
+    let mut inlined_basic_blocks = Vec::with_capacity(callee.basic_blocks.len());
+    for (i, nodes) in callee.basic_blocks.iter().enumerate() {
+        let mut basic_block = BasicBlock::new(remapper.basic_block(BasicBlockId(i as u32)));
+        for node in nodes.iter() {
+            if let NodeKind::ReturnNode(return_node) = node.kind {
+                if let Some(ret_local_mem_id) = ret_local_mem_id {
+                    let ret_val = remapper.operand(return_node.ret_val)?;
+                    let store_node = StoreNode::new(
+                        PlaceKind::LocalMemId(ret_local_mem_id),
+                        call_node.ret_ty,
+                        ret_val,
+                        StoreKind::Init
+                    );
+                    basic_block.push_node(Node { kind: NodeKind::StoreNode(store_node), ..*node });
+                }
+
+                let branch_node = BranchNode::new(continuation_id);
+                basic_block.push_node(Node { kind: NodeKind::BranchNode(branch_node), ..*node });
+                continue;
+            }
+
+            let kind = remapper.node_kind(node.kind)?;
+            basic_block.push_node(Node { kind, ..*node });
+        }
+        inlined_basic_blocks.push(basic_block);
+    }
+
+    // The caller is only changed once the callee has been copied
+    for local_mem in callee.local_mems.iter() {
+        caller.local_mems.push(LocalMem {
+            local_mem_id: remapper.local_mem_id(local_mem.local_mem_id),
+            ..*local_mem
+        });
+    }
+    if let Some(ret_local_mem_id) = ret_local_mem_id {
+        let symbol = match callee.cfg_fn_kind {
+            CfgFnKind::Main(def_id) | CfgFnKind::Fn(def_id) => {
+                Symbol::new(&format!("{}.ret", def_id.symbol.get()))
+            }
+            CfgFnKind::DropGlue(_) => Symbol::new("drop.ret"),
+        };
+        caller.local_mems.push(
+            LocalMem::new(
+                ret_local_mem_id,
+                symbol,
+                Span::dummy(),
+                call_node.ret_ty,
+                Mutability::Immutable,
+                false
+            )
+        );
+    }
+    for result_mem in callee.result_mems.iter() {
+        caller.result_mems.push(
+            ResultMem::new(remapper.result_mem_id(result_mem.result_mem_id), result_mem.ty)
+        );
+    }
+
+    let basic_block = &mut caller.basic_blocks[basic_block_id.0 as usize];
+    let call_span = basic_block.nodes[node_index].span;
+    let mut continuation = BasicBlock::new(continuation_id);
+    if let Some(ret_local_mem_id) = ret_local_mem_id {
+        let load_node = LoadNode::new(
+            call_node.result_place,
+            PlaceKind::LocalMemId(ret_local_mem_id),
+            call_node.ret_ty
+        );
+        continuation.push_node(Node { kind: NodeKind::LoadNode(load_node), span: call_span });
+    }
+    continuation.nodes.extend(basic_block.nodes.drain(node_index..).skip(1));
+
+    let branch_node = BranchNode::new(remapper.basic_block(BasicBlockId(0)));
+    basic_block.push_node(Node { kind: NodeKind::BranchNode(branch_node), span: call_span });
+
+    // Phis in the successors now come from the continuation block
+    for successor in continuation.get_successors() {
+        for node in caller.basic_blocks[successor.0 as usize].nodes.iter_mut() {
+            if let NodeKind::PhiNode(phi_node) = &mut node.kind {
+                let mut incoming = phi_node.incoming.to_vec();
+                for (_, predecessor) in incoming.iter_mut() {
+                    if *predecessor == basic_block_id {
+                        *predecessor = continuation_id;
+                    }
+                }
+                phi_node.incoming = TyCtx::intern_many_types(incoming);
+            }
+        }
+    }
+
+    caller.basic_blocks.extend(inlined_basic_blocks);
+    caller.basic_blocks.push(continuation);
+
+    Some(continuation_id)
+}
+
+/// Maps the temps, variables, implicit results and basic blocks of a callee to the caller
+struct Remapper {
+    /// The operands given as arguments to the call
+    args: FxHashMap<TempId, Operand>,
+    temp_offset: u32,
+    local_mem_offset: u32,
+    result_mem_offset: u32,
+    basic_block_offset: u32,
+}
+
+impl Remapper {
+    fn temp_id(&self, temp_id: TempId) -> TempId {
+        TempId(temp_id.0 + self.temp_offset)
+    }
+
+    fn local_mem_id(&self, local_mem_id: LocalMemId) -> LocalMemId {
+        LocalMemId(local_mem_id.0 + self.local_mem_offset)
+    }
+
+    fn result_mem_id(&self, result_mem_id: ResultMemId) -> ResultMemId {
+        ResultMemId(result_mem_id.0 + self.result_mem_offset)
+    }
+
+    fn basic_block(&self, basic_block_id: BasicBlockId) -> BasicBlockId {
+        BasicBlockId(basic_block_id.0 + self.basic_block_offset)
+    }
+
+    fn operand(&self, operand: Operand) -> Option<Operand> {
+        match operand {
+            Operand::PlaceKind(PlaceKind::TempId(temp_id)) if self.args.contains_key(&temp_id) => {
+                self.args.get(&temp_id).copied()
+            }
+            Operand::PlaceKind(place) => self.place(place).map(Operand::PlaceKind),
+            Operand::Const(_) => Some(operand),
+        }
+    }
+
+    /// None if the place is an argument given as a constant
+    fn place(&self, place: PlaceKind) -> Option<PlaceKind> {
+        match place {
+            PlaceKind::TempId(temp_id) => {
+                match self.args.get(&temp_id) {
+                    Some(Operand::PlaceKind(arg_place)) => Some(*arg_place),
+                    Some(Operand::Const(_)) => None,
+                    None => Some(PlaceKind::TempId(self.temp_id(temp_id))),
+                }
+            }
+            PlaceKind::LocalMemId(local_mem_id) => {
+                Some(PlaceKind::LocalMemId(self.local_mem_id(local_mem_id)))
+            }
+            PlaceKind::ResultMemId(result_mem_id) => {
+                Some(PlaceKind::ResultMemId(self.result_mem_id(result_mem_id)))
+            }
+        }
+    }
+
+    fn node_kind<'a>(&self, node_kind: NodeKind<'a>) -> Option<NodeKind<'a>> {
+        let node_kind = match node_kind {
+            NodeKind::BranchNode(mut branch_node) => {
+                branch_node.branch = self.basic_block(branch_node.branch);
+                NodeKind::BranchNode(branch_node)
+            }
+            NodeKind::BranchCondNode(mut branch_cond_node) => {
+                branch_cond_node.condition = self.operand(branch_cond_node.condition)?;
+                branch_cond_node.true_branch = self.basic_block(branch_cond_node.true_branch);
+                branch_cond_node.false_branch = self.basic_block(branch_cond_node.false_branch);
+                NodeKind::BranchCondNode(branch_cond_node)
+            }
+            NodeKind::SwitchNode(mut switch_node) => {
+                switch_node.value = self.operand(switch_node.value)?;
+                switch_node.default = self.basic_block(switch_node.default);
+                let cases = switch_node.cases
+                    .iter()
+                    .map(|(case, branch)| (*case, self.basic_block(*branch)))
+                    .collect::<Vec<_>>();
+                switch_node.cases = TyCtx::intern_many_types(cases);
+                NodeKind::SwitchNode(switch_node)
+            }
+            NodeKind::BinaryNode(mut binary_node) => {
+                binary_node.result_place = self.temp_id(binary_node.result_place);
+                binary_node.lhs = self.operand(binary_node.lhs)?;
+                binary_node.rhs = self.operand(binary_node.rhs)?;
+                NodeKind::BinaryNode(binary_node)
+            }
+            NodeKind::StoreNode(mut store_node) => {
+                store_node.setter = self.place(store_node.setter)?;
+                store_node.value = self.operand(store_node.value)?;
+                NodeKind::StoreNode(store_node)
+            }
+            NodeKind::LoadNode(mut load_node) => {
+                load_node.result_place = self.temp_id(load_node.result_place);
+                load_node.load_place = self.place(load_node.load_place)?;
+                NodeKind::LoadNode(load_node)
+            }
+            NodeKind::IndexNode(mut index_node) => {
+                index_node.result_place = self.temp_id(index_node.result_place);
+                index_node.array_place = self.place(index_node.array_place)?;
+                index_node.index = self.operand(index_node.index)?;
+                NodeKind::IndexNode(index_node)
+            }
+            NodeKind::ByteAccessNode(mut byte_access_node) => {
+                byte_access_node.result_place = self.place(byte_access_node.result_place)?;
+                byte_access_node.access_place = self.place(byte_access_node.access_place)?;
+                NodeKind::ByteAccessNode(byte_access_node)
+            }
+            NodeKind::ReturnNode(mut return_node) => {
+                return_node.ret_val = self.operand(return_node.ret_val)?;
+                NodeKind::ReturnNode(return_node)
+            }
+            NodeKind::CallNode(mut call_node) => {
+                call_node.result_place = self.temp_id(call_node.result_place);
+                call_node.callee = self.operand(call_node.callee)?;
+                let args = call_node.args
+                    .iter()
+                    .map(|arg| self.operand(*arg))
+                    .collect::<Option<Vec<_>>>()?;
+                call_node.args = TyCtx::intern_many_types(args);
+                NodeKind::CallNode(call_node)
+            }
+            NodeKind::TyCastNode(mut ty_cast_node) => {
+                ty_cast_node.result_place = self.temp_id(ty_cast_node.result_place);
+                ty_cast_node.operand = self.operand(ty_cast_node.operand)?;
+                NodeKind::TyCastNode(ty_cast_node)
+            }
+            NodeKind::DropNode(mut drop_node) => {
+                drop_node.place = self.place(drop_node.place)?;
+                drop_node.drop_flag = drop_node.drop_flag.map(|temp_id| self.temp_id(temp_id));
+                NodeKind::DropNode(drop_node)
+            }
+            NodeKind::PhiNode(phi_node) => {
+                let incoming = phi_node.incoming
+                    .iter()
+                    .map(|(operand, basic_block_id)| {
+                        Some((self.operand(*operand)?, self.basic_block(*basic_block_id)))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                NodeKind::PhiNode(
+                    PhiNode::new(
+                        self.temp_id(phi_node.result_place),
+                        phi_node.ty,
+                        TyCtx::intern_many_types(incoming)
+                    )
+                )
+            }
+            NodeKind::UnreachableNode(unreachable_node) => {
+                NodeKind::UnreachableNode(unreachable_node)
+            }
+        };
+
+        Some(node_kind)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ Const, IcfgParser, NodeKind, Operand };
+
+    const SRC: &str =
+        r##"
+extern fn printf#0:0(Str, ...) -> int32
+str "%d %d %d\\0A\\00"#0:1 10
+
+fn sign#0:2(_0: int32) -> int32 {
+bb0:
+    _1 = lt int32 _0, 0i32
+    br _1, bb1, bb2
+bb1:
+    ret int32 -1i32
+bb2:
+    ret int32 1i32
+}
+
+fn fact#0:3(_0: int32) -> int32 {
+bb0:
+    _1 = le int32 _0, 1i32
+    br _1, bb1, bb2
+bb1:
+    ret int32 1i32
+bb2:
+    _2 = sub int32 _0, 1i32
+    _3 = call int32 fn fact#0:3(_2) : (int32)
+    _4 = mul int32 _0, _3
+    ret int32 _4
+}
+
+noinline fn inc#0:4(_0: int32) -> int32 {
+bb0:
+    _1 = add int32 _0, 1i32
+    ret int32 _1
+}
+
+main fn main#0:5() -> Void {
+bb0:
+    _0 = call int32 fn sign#0:2(-5i32) : (int32)
+    _1 = call int32 fn fact#0:3(4i32) : (int32)
+    _2 = call int32 fn inc#0:4(_0) : (int32)
+    _3 = call int32 fn printf#0:0(str "%d %d %d\\0A\\00"#0:1, _0, _1, _2) : (Str, ..., int32)
+    ret Void ()
+}
+"##;
+
+    fn get_callees(src: &str) -> Vec<Vec<String>> {
+        let mut icfg = IcfgParser::new(src).parse_icfg().unwrap();
+        icfg.inline_functions();
+        assert!(icfg.verify().is_ok());
+
+        let mut out = Vec::new();
+        assert_eq!(icfg.interpret(&mut out).unwrap(), 0);
+        assert_eq!(String::from_utf8(out).unwrap(), "-1 24 0\n");
+
+        icfg.cfgs
+            .iter()
+            .map(|cfg| {
+                cfg.basic_blocks
+                    .iter()
+                    .flat_map(|basic_block| basic_block.nodes.iter())
+                    .filter_map(|node| {
+                        match node.kind {
+                            NodeKind::CallNode(call_node) =>
+                                match call_node.callee {
+                                    Operand::Const(Const::FnPtr(def_id)) => {
+                                        Some(def_id.symbol.get().to_string())
+                                    }
+                                    _ => None,
+                                }
+                            _ => None,
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn inline_calls() {
+        let callees = get_callees(SRC);
+
+        // The call of `fact` to itself is recursive, while the call in `main` isn't. So `fact` is
+        // inlined into `main` once, leaving the recursive call of the copy
+        assert_eq!(callees[1], ["fact"]);
+        assert_eq!(callees[3], ["fact", "inc", "printf"]);
+    }
+
+    #[test]
+    fn inline_attr() {
+        let src = SRC.replace("noinline fn inc", "fn inc").replace(
+            "\nfn fact",
+            "\nnoinline fn fact"
+        );
+        let callees = get_callees(&src);
+        assert_eq!(callees[3], ["fact", "printf"]);
+    }
+}
@@ -0,0 +1,1034 @@
+/*
+
+ICFG interpreter
+
+Executes the icfg directly (starting from `main`), without going through LLVM and clang. It's used
+for fast test runs, and as a reference to compare the output of the generated LLVM IR against
+
+Values are kept as little-endian bytes with the size of their type, like in LLVM. Local mems and
+result mems are allocated as zeroed byte buffers when a function is called (and freed when it
+returns), so `ByteAccessNode` offsets and `IndexNode` strides work the same way as in codegen
+
+A pointer is 8 bytes, where the upper 32 bits refer to an allocation (0 is `null`) and the lower 32
+bits are the offset into it. Functions and constant strings get their own allocations, so they can
+be used as pointers too
+
+C functions (declared with `fn.C`) are dispatched to host implementations (e.g. `printf`,
+`malloc` and `exit`)
+
+*/
+
+use std::{ fmt::Display, io::Write };
+
+use fxhash::FxHashMap;
+use ir::{ CfgFnKind, DefId, GetTyAttr, PrimTy, Ty };
+use op::{ ArithmeticOp, BinaryOp, ComparisonOp };
+
+use crate::{
+    BasicBlockId,
+    Cfg,
+    Const,
+    Icfg,
+    NodeKind,
+    Operand,
+    PlaceKind,
+    TyCastKind,
+};
+
+/// Deep recursion is reported instead of overflowing the stack of the interpreter itself
+const MAX_CALL_DEPTH: usize = 2048;
+
+/// The interpreter recurses for every call, and each call takes a few kilobytes of the host
+/// stack in debug builds. This leaves enough room for `MAX_CALL_DEPTH` calls
+const INTERPRETER_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct InterpretError {
+    /// The function and basic block being executed (if any)
+    pub location: Option<(CfgFnKind, BasicBlockId)>,
+    pub kind: InterpretErrorKind,
+}
+
+#[derive(Debug)]
+pub enum InterpretErrorKind {
+    MissingMain,
+    UnknownFn(DefId),
+    UnsupportedCFn(&'static str),
+    MissingDropGlue(Ty),
+    UnknownBasicBlock(BasicBlockId),
+    MissingTerminator,
+    UndefinedPlace(PlaceKind),
+    NullDereference,
+    InvalidPointer(u64),
+    UseAfterFree,
+    OutOfBounds {
+        offset: usize,
+        size: usize,
+        allocation_size: usize,
+    },
+    InvalidFree,
+    DivisionByZero,
+    ReachedUnreachable,
+    StackOverflow,
+    Io(String),
+}
+
+impl Display for InterpretError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some((cfg_fn_kind, basic_block_id)) = self.location {
+            let fn_name = match cfg_fn_kind {
+                CfgFnKind::Main(def_id) | CfgFnKind::Fn(def_id) => def_id.display_as_fn(),
+                CfgFnKind::DropGlue(ty) => ty.display_as_drop_fn(),
+            };
+            write!(f, "In `{}` (bb{}): ", fn_name, basic_block_id.0)?;
+        }
+
+        match &self.kind {
+            InterpretErrorKind::MissingMain => write!(f, "No `main` function to run"),
+            InterpretErrorKind::UnknownFn(def_id) => {
+                write!(f, "Called `{}` which has no cfg", def_id.symbol.get())
+            }
+            InterpretErrorKind::UnsupportedCFn(symbol) => {
+                write!(f, "The C function `{}` isn't supported by the interpreter", symbol)
+            }
+            InterpretErrorKind::MissingDropGlue(ty) => write!(f, "No drop glue for `{}`", ty),
+            InterpretErrorKind::UnknownBasicBlock(basic_block_id) => {
+                write!(f, "Branch to bb{} which doesn't exist", basic_block_id.0)
+            }
+            InterpretErrorKind::MissingTerminator => {
+                write!(f, "Basic block doesn't end in a terminator")
+            }
+            InterpretErrorKind::UndefinedPlace(place) => {
+                write!(f, "{:?} is used before it's defined", place)
+            }
+            InterpretErrorKind::NullDereference => write!(f, "Dereferenced a null pointer"),
+            InterpretErrorKind::InvalidPointer(ptr) => write!(f, "Invalid pointer {:#x}", ptr),
+            InterpretErrorKind::UseAfterFree => write!(f, "Accessed memory after it was freed"),
+            InterpretErrorKind::OutOfBounds { offset, size, allocation_size } => {
+                write!(
+                    f,
+                    "Accessed {} bytes at offset {} of an allocation of {} bytes",
+                    size,
+                    offset,
+                    allocation_size
+                )
+            }
+            InterpretErrorKind::InvalidFree => {
+                write!(f, "Freed a pointer which wasn't returned by `malloc`")
+            }
+            InterpretErrorKind::DivisionByZero => write!(f, "Division by zero"),
+            InterpretErrorKind::ReachedUnreachable => write!(f, "Reached unreachable code"),
+            InterpretErrorKind::StackOverflow => {
+                write!(f, "Exceeded the maximum call depth of {}", MAX_CALL_DEPTH)
+            }
+            InterpretErrorKind::Io(error) => write!(f, "Failed to write output: {}", error),
+        }
+    }
+}
+
+/// Why the execution stopped early
+enum Stop {
+    /// `exit` was called
+    Exit(i32),
+    Error(InterpretError),
+}
+
+impl From<InterpretErrorKind> for Stop {
+    fn from(kind: InterpretErrorKind) -> Self {
+        Stop::Error(InterpretError { location: None, kind })
+    }
+}
+
+type ExecResult<T> = Result<T, Stop>;
+
+/// A value of some type as little-endian bytes
+#[derive(Debug, Clone, Default)]
+struct Value(Vec<u8>);
+
+impl Value {
+    fn from_int(int: i64, size: usize) -> Self {
+        Self(int.to_le_bytes()[..size.min(8)].to_vec())
+    }
+
+    fn from_ptr(ptr: u64) -> Self {
+        Self(ptr.to_le_bytes().to_vec())
+    }
+
+    /// Sign extends the value from its size
+    fn to_int(&self) -> i64 {
+        let len = self.0.len().min(8);
+        let is_negative = len > 0 && (self.0[len - 1] as i8) < 0;
+        let mut bytes = [if is_negative { 0xff } else { 0 }; 8];
+        bytes[..len].copy_from_slice(&self.0[..len]);
+        i64::from_le_bytes(bytes)
+    }
+
+    /// Zero extends the value from its size
+    fn to_uint(&self) -> u64 {
+        let mut bytes = [0; 8];
+        let len = self.0.len().min(8);
+        bytes[..len].copy_from_slice(&self.0[..len]);
+        u64::from_le_bytes(bytes)
+    }
+}
+
+enum Allocation {
+    Bytes(Vec<u8>),
+    Fn(DefId),
+    Freed,
+}
+
+#[derive(Default)]
+struct Memory {
+    allocations: Vec<Allocation>,
+}
+
+impl Memory {
+    fn allocate(&mut self, allocation: Allocation) -> u64 {
+        self.allocations.push(allocation);
+        (self.allocations.len() as u64) << 32
+    }
+
+    fn get_allocation_index(&self, ptr: u64) -> ExecResult<usize> {
+        match ptr >> 32 {
+            0 => Err(InterpretErrorKind::NullDereference.into()),
+            id if (id as usize) <= self.allocations.len() => Ok((id - 1) as usize),
+            _ => Err(InterpretErrorKind::InvalidPointer(ptr).into()),
+        }
+    }
+
+    fn get_bytes_mut(&mut self, ptr: u64, size: usize) -> ExecResult<&mut [u8]> {
+        let index = self.get_allocation_index(ptr)?;
+        let offset = (ptr & 0xffff_ffff) as usize;
+
+        match &mut self.allocations[index] {
+            Allocation::Bytes(bytes) => {
+                if offset + size > bytes.len() {
+                    let allocation_size = bytes.len();
+                    return Err(
+                        (InterpretErrorKind::OutOfBounds { offset, size, allocation_size }).into()
+                    );
+                }
+                Ok(&mut bytes[offset..offset + size])
+            }
+            Allocation::Fn(_) => Err(InterpretErrorKind::InvalidPointer(ptr).into()),
+            Allocation::Freed => Err(InterpretErrorKind::UseAfterFree.into()),
+        }
+    }
+
+    fn read(&mut self, ptr: u64, size: usize) -> ExecResult<Value> {
+        Ok(Value(self.get_bytes_mut(ptr, size)?.to_vec()))
+    }
+
+    fn write(&mut self, ptr: u64, value: &Value) -> ExecResult<()> {
+        self.get_bytes_mut(ptr, value.0.len())?.copy_from_slice(&value.0);
+        Ok(())
+    }
+
+    /// Reads a null terminated string
+    fn read_c_str(&mut self, ptr: u64) -> ExecResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = self.get_bytes_mut(ptr + (bytes.len() as u64), 1)?[0];
+            if byte == 0 {
+                return Ok(bytes);
+            }
+            bytes.push(byte);
+        }
+    }
+
+    fn free(&mut self, ptr: u64) -> ExecResult<()> {
+        let index = self.get_allocation_index(ptr)?;
+        match self.allocations[index] {
+            Allocation::Bytes(_) if ptr & 0xffff_ffff == 0 => {
+                self.allocations[index] = Allocation::Freed;
+                Ok(())
+            }
+            _ => Err(InterpretErrorKind::InvalidFree.into()),
+        }
+    }
+}
+
+impl Icfg<'_> {
+    /// Runs `main`, writing the output of the program to `out`. Returns the exit code.
+    /// The program runs on its own thread, since the default stack is too small for deep
+    /// recursion
+    pub fn interpret(&self, out: &mut (impl Write + Send)) -> Result<i32, InterpretError> {
+        std::thread::scope(|scope| {
+            let interpreter_thread = std::thread::Builder
+                ::new()
+                .name("interpreter".to_string())
+                .stack_size(INTERPRETER_STACK_SIZE)
+                .spawn_scoped(scope, || self.interpret_on_current_thread(out))
+                .expect("Error spawning the interpreter thread");
+
+            match interpreter_thread.join() {
+                Ok(result) => result,
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        })
+    }
+
+    fn interpret_on_current_thread(&self, out: &mut impl Write) -> Result<i32, InterpretError> {
+        let mut interpreter = Interpreter::new(self, out);
+
+        let Some(main_index) = self.cfgs
+            .iter()
+            .position(|cfg| matches!(cfg.cfg_fn_kind, CfgFnKind::Main(_))) else {
+            return Err(InterpretError { location: None, kind: InterpretErrorKind::MissingMain });
+        };
+
+        let result = match interpreter.call_cfg(main_index, Vec::new(), 0) {
+            // `main` always returns 0 (like in codegen)
+            Ok(_) => Ok(0),
+            Err(Stop::Exit(code)) => Ok(code),
+            Err(Stop::Error(error)) => Err(error),
+        };
+
+        interpreter.out
+            .flush()
+            .map_err(|error| InterpretError {
+                location: None,
+                kind: InterpretErrorKind::Io(error.to_string()),
+            })?;
+        result
+    }
+}
+
+struct Interpreter<'a, 'b, W: Write> {
+    icfg: &'b Icfg<'a>,
+    out: &'b mut W,
+    memory: Memory,
+    fn_def_id_to_cfg: FxHashMap<DefId, usize>,
+    drop_glue_ty_to_cfg: FxHashMap<Ty, usize>,
+    /// Pointers to functions and constant strings, which are allocated the first time they're used
+    global_ptrs: FxHashMap<DefId, u64>,
+}
+
+impl<'a, 'b, W: Write> Interpreter<'a, 'b, W> {
+    fn new(icfg: &'b Icfg<'a>, out: &'b mut W) -> Self {
+        let mut fn_def_id_to_cfg = FxHashMap::default();
+        let mut drop_glue_ty_to_cfg = FxHashMap::default();
+        for (i, cfg) in icfg.cfgs.iter().enumerate() {
+            match cfg.cfg_fn_kind {
+                CfgFnKind::Main(def_id) | CfgFnKind::Fn(def_id) => {
+                    fn_def_id_to_cfg.insert(def_id, i);
+                }
+                CfgFnKind::DropGlue(ty) => {
+                    drop_glue_ty_to_cfg.insert(ty, i);
+                }
+            }
+        }
+
+        Self {
+            icfg,
+            out,
+            memory: Memory::default(),
+            fn_def_id_to_cfg,
+            drop_glue_ty_to_cfg,
+            global_ptrs: FxHashMap::default(),
+        }
+    }
+
+    fn size_of(&self, ty: Ty) -> usize {
+        match ty {
+            Ty::VariadicArgs | Ty::Never => 0,
+            _ => ty.get_ty_attr(&self.icfg.resolved_information).size_bytes,
+        }
+    }
+
+    fn call_cfg(&mut self, cfg_index: usize, args: Vec<Value>, depth: usize) -> ExecResult<Value> {
+        let icfg = self.icfg;
+        let cfg = &icfg.cfgs[cfg_index];
+        if depth > MAX_CALL_DEPTH {
+            return Err(InterpretErrorKind::StackOverflow.into());
+        }
+
+        let mut frame = Frame { places: FxHashMap::default(), allocations: Vec::new() };
+        for ((temp_id, _), arg) in cfg.args.iter().zip(args) {
+            frame.places.insert(PlaceKind::TempId(*temp_id), arg);
+        }
+        for local_mem in cfg.local_mems.iter() {
+            let ptr = self.allocate_stack(local_mem.ty);
+            frame.allocations.push(ptr);
+            frame.places.insert(
+                PlaceKind::LocalMemId(local_mem.local_mem_id),
+                Value::from_ptr(ptr)
+            );
+        }
+        for result_mem in cfg.result_mems.iter() {
+            let ptr = self.allocate_stack(result_mem.ty);
+            frame.allocations.push(ptr);
+            frame.places.insert(
+                PlaceKind::ResultMemId(result_mem.result_mem_id),
+                Value::from_ptr(ptr)
+            );
+        }
+
+        let mut basic_block_id = BasicBlockId(0);
+        let mut prev_basic_block_id = None;
+        let result = loop {
+            let step = self.execute_basic_block(
+                cfg,
+                &mut frame,
+                basic_block_id,
+                prev_basic_block_id,
+                depth
+            );
+
+            match step {
+                Ok(Step::Branch(next_basic_block_id)) => {
+                    prev_basic_block_id = Some(basic_block_id);
+                    basic_block_id = next_basic_block_id;
+                }
+                Ok(Step::Return(value)) => {
+                    break Ok(value);
+                }
+                // Errors are located in the innermost function they occur in
+                Err(Stop::Error(InterpretError { location: None, kind })) => {
+                    break Err(
+                        Stop::Error(InterpretError {
+                            location: Some((cfg.cfg_fn_kind, basic_block_id)),
+                            kind,
+                        })
+                    );
+                }
+                Err(stop) => {
+                    break Err(stop);
+                }
+            }
+        };
+
+        for ptr in frame.allocations {
+            self.memory.free(ptr)?;
+        }
+        result
+    }
+
+    fn allocate_stack(&mut self, ty: Ty) -> u64 {
+        let size = self.size_of(ty);
+        self.memory.allocate(Allocation::Bytes(vec![0; size]))
+    }
+
+    fn execute_basic_block(
+        &mut self,
+        cfg: &'b Cfg<'a>,
+        frame: &mut Frame,
+        basic_block_id: BasicBlockId,
+        prev_basic_block_id: Option<BasicBlockId>,
+        depth: usize
+    ) -> ExecResult<Step> {
+        let Some(basic_block) = cfg.basic_blocks.get(basic_block_id.0 as usize) else {
+            return Err(InterpretErrorKind::UnknownBasicBlock(basic_block_id).into());
+        };
+
+        // Phis are evaluated at the same time, so they only see values from the predecessor
+        let mut phi_values = Vec::new();
+        for node in basic_block.nodes.iter() {
+            let NodeKind::PhiNode(phi_node) = node.kind else {
+                continue;
+            };
+            let incoming = phi_node.incoming
+                .iter()
+                .find(|(_, basic_block_id)| Some(*basic_block_id) == prev_basic_block_id);
+            if let Some((operand, _)) = incoming {
+                let value = self.eval_operand(frame, operand, Some(phi_node.ty))?;
+                phi_values.push((phi_node.result_place, value));
+            }
+        }
+        for (temp_id, value) in phi_values {
+            frame.places.insert(PlaceKind::TempId(temp_id), value);
+        }
+
+        for node in basic_block.nodes.iter() {
+            match node.kind {
+                NodeKind::PhiNode(_) => {}
+                NodeKind::BranchNode(branch_node) => {
+                    return Ok(Step::Branch(branch_node.branch));
+                }
+                NodeKind::BranchCondNode(branch_cond_node) => {
+                    let condition = self.eval_operand(
+                        frame,
+                        &branch_cond_node.condition,
+                        None
+                    )?;
+                    return Ok(
+                        Step::Branch(
+                            if condition.to_uint() != 0 {
+                                branch_cond_node.true_branch
+                            } else {
+                                branch_cond_node.false_branch
+                            }
+                        )
+                    );
+                }
+                NodeKind::SwitchNode(switch_node) => {
+                    let value = self.eval_operand(frame, &switch_node.value, Some(switch_node.ty))?;
+                    let branch = switch_node.cases
+                        .iter()
+                        .find(|(case, _)| *case == value.to_int())
+                        .map_or(switch_node.default, |(_, branch)| *branch);
+                    return Ok(Step::Branch(branch));
+                }
+                NodeKind::ReturnNode(return_node) => {
+                    let value = if return_node.ret_ty.is_void() {
+                        Value::default()
+                    } else {
+                        self.eval_operand(frame, &return_node.ret_val, Some(return_node.ret_ty))?
+                    };
+                    return Ok(Step::Return(value));
+                }
+                NodeKind::UnreachableNode(_) => {
+                    return Err(InterpretErrorKind::ReachedUnreachable.into());
+                }
+                NodeKind::BinaryNode(binary_node) => {
+                    let lhs = self.eval_operand(frame, &binary_node.lhs, Some(binary_node.op_ty))?;
+                    let rhs = self.eval_operand(frame, &binary_node.rhs, Some(binary_node.op_ty))?;
+                    let (lhs, rhs) = (lhs.to_int(), rhs.to_int());
+                    let size = self.size_of(binary_node.op_ty);
+
+                    let value = match binary_node.op {
+                        BinaryOp::ArithmeticOp(arithmetic_op) => {
+                            let result = match arithmetic_op {
+                                ArithmeticOp::Add => lhs.wrapping_add(rhs),
+                                ArithmeticOp::Sub => lhs.wrapping_sub(rhs),
+                                ArithmeticOp::Mul => lhs.wrapping_mul(rhs),
+                                ArithmeticOp::Div => {
+                                    if rhs == 0 {
+                                        return Err(InterpretErrorKind::DivisionByZero.into());
+                                    }
+                                    lhs.wrapping_div(rhs)
+                                }
+                            };
+                            Value::from_int(result, size)
+                        }
+                        BinaryOp::ComparisonOp(comparison_op) => {
+                            let result = match comparison_op {
+                                ComparisonOp::Eq => lhs == rhs,
+                                ComparisonOp::Ne => lhs != rhs,
+                                ComparisonOp::Ge => lhs >= rhs,
+                                ComparisonOp::Gt => lhs > rhs,
+                                ComparisonOp::Le => lhs <= rhs,
+                                ComparisonOp::Lt => lhs < rhs,
+                            };
+                            Value::from_int(result as i64, 1)
+                        }
+                    };
+                    frame.places.insert(PlaceKind::TempId(binary_node.result_place), value);
+                }
+                NodeKind::StoreNode(store_node) => {
+                    let ptr = frame.get_ptr(&store_node.setter)?;
+                    let value = self.eval_operand(
+                        frame,
+                        &store_node.value,
+                        Some(store_node.op_ty)
+                    )?;
+                    self.memory.write(ptr, &value)?;
+                }
+                NodeKind::LoadNode(load_node) => {
+                    let ptr = frame.get_ptr(&load_node.load_place)?;
+                    let value = self.memory.read(ptr, self.size_of(load_node.load_ty))?;
+                    frame.places.insert(PlaceKind::TempId(load_node.result_place), value);
+                }
+                NodeKind::IndexNode(index_node) => {
+                    let ptr = frame.get_ptr(&index_node.array_place)?;
+                    let index = self.eval_operand(frame, &index_node.index, None)?.to_int();
+                    let stride = self.size_of(index_node.place_ty) as i64;
+                    let ptr = ptr.wrapping_add(index.wrapping_mul(stride) as u64);
+                    frame.places.insert(
+                        PlaceKind::TempId(index_node.result_place),
+                        Value::from_ptr(ptr)
+                    );
+                }
+                NodeKind::ByteAccessNode(byte_access_node) => {
+                    let ptr = frame.get_ptr(&byte_access_node.access_place)?;
+                    let ptr = ptr.wrapping_add(byte_access_node.byte_offset as u64);
+                    frame.places.insert(byte_access_node.result_place, Value::from_ptr(ptr));
+                }
+                NodeKind::TyCastNode(ty_cast_node) => {
+                    let operand = self.eval_operand(
+                        frame,
+                        &ty_cast_node.operand,
+                        Some(ty_cast_node.from_ty)
+                    )?;
+                    let size = self.size_of(ty_cast_node.to_ty);
+                    let value = match ty_cast_node.cast_kind {
+                        TyCastKind::Trunc | TyCastKind::Sext => {
+                            Value::from_int(operand.to_int(), size)
+                        }
+                        TyCastKind::Zext => Value::from_int(operand.to_uint() as i64, size),
+                    };
+                    frame.places.insert(PlaceKind::TempId(ty_cast_node.result_place), value);
+                }
+                NodeKind::CallNode(call_node) => {
+                    // The args after `...` have no type of their own
+                    let mut arg_tys = call_node.args_ty
+                        .iter()
+                        .take_while(|ty| !ty.is_variadic_args())
+                        .copied();
+                    let mut args = Vec::with_capacity(call_node.args.len());
+                    for arg in call_node.args.iter() {
+                        args.push(self.eval_operand(frame, arg, arg_tys.next())?);
+                    }
+
+                    let callee = self.eval_operand(frame, &call_node.callee, None)?;
+                    let def_id = self.get_fn_def_id(callee.to_uint())?;
+                    let value = self.call_fn(def_id, args, call_node.ret_ty, depth)?;
+                    frame.places.insert(PlaceKind::TempId(call_node.result_place), value);
+                }
+                NodeKind::DropNode(drop_node) => {
+                    let drop_glue = self.drop_glue_ty_to_cfg.get(&drop_node.ty).copied();
+                    let Some(cfg_index) = drop_glue else {
+                        return Err(InterpretErrorKind::MissingDropGlue(drop_node.ty).into());
+                    };
+                    let ptr = frame.get_ptr(&drop_node.place)?;
+                    let drop_flag = match drop_node.drop_flag {
+                        Some(temp_id) => frame.get(&PlaceKind::TempId(temp_id))?,
+                        None => Value::from_int(1, 1),
+                    };
+                    self.call_cfg(cfg_index, vec![Value::from_ptr(ptr), drop_flag], depth + 1)?;
+                }
+            }
+        }
+
+        Err(InterpretErrorKind::MissingTerminator.into())
+    }
+
+    /// Constants get the size of the type they're used as (if it's known)
+    fn eval_operand(
+        &mut self,
+        frame: &Frame,
+        operand: &Operand,
+        ty: Option<Ty>
+    ) -> ExecResult<Value> {
+        let const_val = match operand {
+            Operand::PlaceKind(place) => {
+                return frame.get(place);
+            }
+            Operand::Const(const_val) => const_val,
+        };
+
+        let value = match *const_val {
+            Const::Int(int, int_ty) => {
+                let size = match ty {
+                    Some(ty @ Ty::PrimTy(PrimTy::Int(_) | PrimTy::Uint(_) | PrimTy::Bool)) => {
+                        self.size_of(ty)
+                    }
+                    _ => self.size_of(Ty::PrimTy(PrimTy::Int(int_ty))),
+                };
+                Value::from_int(int, size)
+            }
+            Const::Bool(bool) => Value::from_int(bool as i64, 1),
+            Const::Null => Value::from_ptr(0),
+            Const::Void => Value::default(),
+            Const::FnPtr(def_id) => {
+                let ptr = match self.global_ptrs.get(&def_id) {
+                    Some(ptr) => *ptr,
+                    None => {
+                        let ptr = self.memory.allocate(Allocation::Fn(def_id));
+                        self.global_ptrs.insert(def_id, ptr);
+                        ptr
+                    }
+                };
+                Value::from_ptr(ptr)
+            }
+            Const::Str(def_id) => {
+                let ptr = match self.global_ptrs.get(&def_id) {
+                    Some(ptr) => *ptr,
+                    None => {
+                        let bytes = unescape_llvm_str(def_id.symbol.get());
+                        let ptr = self.memory.allocate(Allocation::Bytes(bytes));
+                        self.global_ptrs.insert(def_id, ptr);
+                        ptr
+                    }
+                };
+                Value::from_ptr(ptr)
+            }
+        };
+
+        Ok(value)
+    }
+
+    fn get_fn_def_id(&self, ptr: u64) -> ExecResult<DefId> {
+        let index = self.memory.get_allocation_index(ptr)?;
+        match self.memory.allocations[index] {
+            Allocation::Fn(def_id) if ptr & 0xffff_ffff == 0 => Ok(def_id),
+            _ => Err(InterpretErrorKind::InvalidPointer(ptr).into()),
+        }
+    }
+
+    fn call_fn(
+        &mut self,
+        def_id: DefId,
+        args: Vec<Value>,
+        ret_ty: Ty,
+        depth: usize
+    ) -> ExecResult<Value> {
+        if let Some(cfg_index) = self.fn_def_id_to_cfg.get(&def_id).copied() {
+            return self.call_cfg(cfg_index, args, depth + 1);
+        }
+
+        let is_clib_fn =
+            self.icfg.resolved_information.def_id_to_name_binding.contains_key(&def_id) &&
+            self.icfg.resolved_information.is_clib_fn(&def_id);
+        if !is_clib_fn {
+            return Err(InterpretErrorKind::UnknownFn(def_id).into());
+        }
+
+        let ret_size = self.size_of(ret_ty);
+        let arg = |i: usize| args.get(i).cloned().unwrap_or_default();
+
+        let value = match def_id.symbol.get() {
+            "printf" => {
+                let format = self.memory.read_c_str(arg(0).to_uint())?;
+                let output = self.format(&format, &args[1.min(args.len())..])?;
+                self.write_output(&output)?;
+                Value::from_int(output.len() as i64, ret_size)
+            }
+            "puts" => {
+                let mut output = self.memory.read_c_str(arg(0).to_uint())?;
+                output.push(b'\n');
+                self.write_output(&output)?;
+                Value::from_int(output.len() as i64, ret_size)
+            }
+            "putchar" => {
+                self.write_output(&[arg(0).to_int() as u8])?;
+                Value::from_int(arg(0).to_int(), ret_size)
+            }
+            "malloc" => {
+                let size = arg(0).to_uint() as usize;
+                Value::from_ptr(self.memory.allocate(Allocation::Bytes(vec![0; size])))
+            }
+            "calloc" => {
+                let size = (arg(0).to_uint() as usize) * (arg(1).to_uint() as usize);
+                Value::from_ptr(self.memory.allocate(Allocation::Bytes(vec![0; size])))
+            }
+            "free" => {
+                let ptr = arg(0).to_uint();
+                if ptr != 0 {
+                    self.memory.free(ptr)?;
+                }
+                Value::default()
+            }
+            "strlen" => {
+                let len = self.memory.read_c_str(arg(0).to_uint())?.len();
+                Value::from_int(len as i64, ret_size)
+            }
+            "exit" => {
+                self.out.flush().map_err(|error| InterpretErrorKind::Io(error.to_string()))?;
+                return Err(Stop::Exit(arg(0).to_int() as i32));
+            }
+            symbol => {
+                return Err(InterpretErrorKind::UnsupportedCFn(symbol).into());
+            }
+        };
+
+        Ok(value)
+    }
+
+    fn write_output(&mut self, output: &[u8]) -> ExecResult<()> {
+        self.out
+            .write_all(output)
+            .map_err(|error| InterpretErrorKind::Io(error.to_string()).into())
+    }
+
+    /// Formats like `printf` (supporting flags `-` and `0`, a width, length modifiers and the
+    /// conversions `d`, `i`, `u`, `x`, `X`, `c`, `s`, `p` and `%`)
+    fn format(&mut self, format: &[u8], args: &[Value]) -> ExecResult<Vec<u8>> {
+        let mut output = Vec::with_capacity(format.len());
+        let mut args = args.iter();
+        let mut i = 0;
+
+        while i < format.len() {
+            if format[i] != b'%' {
+                output.push(format[i]);
+                i += 1;
+                continue;
+            }
+            i += 1;
+
+            let (mut left_align, mut zero_pad) = (false, false);
+            while let Some(flag @ (b'-' | b'0')) = format.get(i) {
+                left_align |= *flag == b'-';
+                zero_pad |= *flag == b'0';
+                i += 1;
+            }
+            let mut width = 0;
+            while let Some(digit @ b'0'..=b'9') = format.get(i) {
+                width = width * 10 + ((digit - b'0') as usize);
+                i += 1;
+            }
+            // Args already have the size of their type, so length modifiers aren't needed
+            while let Some(b'l' | b'h' | b'z') = format.get(i) {
+                i += 1;
+            }
+
+            let Some(conversion) = format.get(i) else {
+                output.push(b'%');
+                break;
+            };
+            i += 1;
+
+            let arg = args.next().cloned().unwrap_or_default();
+            let formatted = match conversion {
+                b'd' | b'i' => arg.to_int().to_string().into_bytes(),
+                b'u' => arg.to_uint().to_string().into_bytes(),
+                b'x' => format!("{:x}", arg.to_uint()).into_bytes(),
+                b'X' => format!("{:X}", arg.to_uint()).into_bytes(),
+                b'p' => format!("{:#x}", arg.to_uint()).into_bytes(),
+                b'c' => vec![arg.to_int() as u8],
+                b's' => self.memory.read_c_str(arg.to_uint())?,
+                b'%' => vec![b'%'],
+                other => vec![b'%', *other],
+            };
+
+            let padding = width.saturating_sub(formatted.len());
+            if left_align {
+                output.extend(formatted);
+                output.extend(std::iter::repeat_n(b' ', padding));
+            } else if zero_pad && matches!(conversion, b'd' | b'i' | b'u' | b'x' | b'X') {
+                let (sign, digits) = match formatted.split_first() {
+                    Some((b'-', digits)) => (Some(b'-'), digits),
+                    _ => (None, &formatted[..]),
+                };
+                output.extend(sign);
+                output.extend(std::iter::repeat_n(b'0', padding));
+                output.extend(digits);
+            } else {
+                output.extend(std::iter::repeat_n(b' ', padding));
+                output.extend(formatted);
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+/// What to do after a basic block
+enum Step {
+    Branch(BasicBlockId),
+    Return(Value),
+}
+
+/// The places of a function call, along with its local mems and result mems (to free on return)
+struct Frame {
+    places: FxHashMap<PlaceKind, Value>,
+    allocations: Vec<u64>,
+}
+
+impl Frame {
+    fn get(&self, place: &PlaceKind) -> ExecResult<Value> {
+        self.places
+            .get(place)
+            .cloned()
+            .ok_or_else(|| InterpretErrorKind::UndefinedPlace(*place).into())
+    }
+
+    fn get_ptr(&self, place: &PlaceKind) -> ExecResult<u64> {
+        Ok(self.get(place)?.to_uint())
+    }
+}
+
+/// Constant strings are stored with LLVM escapes (e.g. `\0A` for a new line)
+fn unescape_llvm_str(str: &str) -> Vec<u8> {
+    let bytes = str.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'\\')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                unescaped.push(byte);
+                i += 3;
+            }
+            None => {
+                unescaped.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod test {
+    use crate::IcfgParser;
+
+    fn interpret(src: &str) -> (Result<i32, String>, String) {
+        let icfg = IcfgParser::new(src).parse_icfg().unwrap();
+        let mut out = Vec::new();
+        let result = icfg.interpret(&mut out).map_err(|error| error.to_string());
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn memory_and_c_fns() {
+        let (result, out) = interpret(
+            r##"
+extern fn printf#0:0(Str, ...) -> int32
+extern fn malloc#0:1(int64) -> *mut int8
+extern fn exit#0:2(int32) -> Void
+str "%d %d\\0A\\00"#0:3 7
+
+fn sum#0:4(_0: *(int32, int32)) -> int32 {
+bb0:
+    _1 = load int32, _0
+    _2 = byte_access _0, 4
+    _3 = load int32, _2
+    _4 = add int32 _1, _3
+    ret int32 _4
+}
+
+main fn main#0:5() -> Void {
+    local l0 pair: (int32, int32)
+bb0:
+    store init int32 3i32, l0
+    _0 = byte_access l0, 4
+    store init int32 4i32, _0
+    _1 = call int32 fn sum#0:4(l0) : (*(int32, int32))
+    _2 = call *mut int8 fn malloc#0:1(8i64) : (int64)
+    _3 = index int32, _2, 1i64
+    store init int32 -2i32, _3
+    _4 = load int32, _3
+    _5 = gt int32 _4, 0i32
+    br _5, bb1, bb2
+bb1:
+    br bb2
+bb2:
+    _6 = phi int32 [_1, bb0], [0i32, bb1]
+    _7 = call int32 fn printf#0:0(str "%d %d\\0A\\00"#0:3, _6, _4) : (Str, ...)
+    _8 = call Void fn exit#0:2(_1) : (int32)
+    unreachable
+}
+"##
+        );
+
+        assert_eq!(result, Ok(7));
+        assert_eq!(out, "7 -2\n");
+    }
+
+    #[test]
+    fn switch() {
+        let (result, _) = interpret(
+            r##"
+extern fn exit#0:0(int32) -> Void
+
+fn classify#0:1(_0: int64) -> int32 {
+bb0:
+    switch int64 _0, bb3 [-1: bb1, 2: bb2, 5: bb2]
+bb1:
+    ret int32 1i32
+bb2:
+    ret int32 2i32
+bb3:
+    ret int32 3i32
+}
+
+main fn main#0:2() -> Void {
+bb0:
+    _0 = call int32 fn classify#0:1(-1i64) : (int64)
+    _1 = call int32 fn classify#0:1(5i64) : (int64)
+    _2 = call int32 fn classify#0:1(0i64) : (int64)
+    _3 = mul int32 _0, 100i32
+    _4 = mul int32 _1, 10i32
+    _5 = add int32 _3, _4
+    _6 = add int32 _5, _2
+    _7 = call Void fn exit#0:0(_6) : (int32)
+    unreachable
+}
+"##
+        );
+
+        assert_eq!(result, Ok(123));
+    }
+
+    #[test]
+    fn runtime_errors() {
+        let (result, _) = interpret(
+            r##"
+main fn main#0:0() -> Void {
+    local l0 x: int32
+bb0:
+    _0 = index int32, l0, 1i64
+    _1 = load int32, _0
+    ret Void ()
+}
+"##
+        );
+
+        let message = "Accessed 4 bytes at offset 4 of an allocation of 4 bytes";
+        assert_eq!(result, Err(format!("In `@main0_0` (bb0): {}", message)));
+    }
+
+    const IS_EVEN: &str = r##"
+extern fn exit#0:0(int32) -> Void
+
+fn isEven#0:1(_0: int32) -> int32 {
+bb0:
+    _1 = eq int32 _0, 0i32
+    br _1, bb1, bb2
+bb1:
+    ret int32 1i32
+bb2:
+    _2 = sub int32 _0, 1i32
+    _3 = call int32 fn isOdd#0:2(_2) : (int32)
+    ret int32 _3
+}
+
+fn isOdd#0:2(_0: int32) -> int32 {
+bb0:
+    _1 = eq int32 _0, 0i32
+    br _1, bb1, bb2
+bb1:
+    ret int32 0i32
+bb2:
+    _2 = sub int32 _0, 1i32
+    _3 = call int32 fn isEven#0:1(_2) : (int32)
+    ret int32 _3
+}
+"##;
+
+    #[test]
+    fn call_depth() {
+        // Runs in a debug build, where each call takes far more of the host stack than in release
+        let (result, _) = interpret(
+            &format!(
+                "{}{}",
+                IS_EVEN,
+                r##"
+main fn main#0:3() -> Void {
+bb0:
+    _0 = call int32 fn isEven#0:1(2000i32) : (int32)
+    _1 = call Void fn exit#0:0(_0) : (int32)
+    unreachable
+}
+"##
+            )
+        );
+        assert_eq!(result, Ok(1));
+
+        let (result, _) = interpret(
+            &format!(
+                "{}{}",
+                IS_EVEN,
+                r##"
+main fn main#0:3() -> Void {
+bb0:
+    _0 = call int32 fn isEven#0:1(1000001i32) : (int32)
+    _1 = call Void fn exit#0:0(_0) : (int32)
+    unreachable
+}
+"##
+            )
+        );
+        let message = "Exceeded the maximum call depth of 2048";
+        assert_eq!(result, Err(format!("In `@isOdd0_2` (bb2): {}", message)));
+    }
+}
@@ -11,13 +11,17 @@ Useful links:
 use std::fmt::Display;
 
 use derive_new::new;
+use diagnostics::Diagnostic;
+use span::Span;
 use op::BinaryOp;
 use ir::{
     CfgFnKind,
     DefId,
+    InlineAttr,
     IntTy,
     LocalMem,
     LocalMemId,
+    ModId,
     PrimTy,
     ResolvedInformation,
     ResultMem,
@@ -29,13 +33,35 @@ use ir::{
     VOID_TY,
 };
 mod icfg_prettifier;
+mod icfg_parser;
+mod icfg_dot_exporter;
 mod cfg_visitor;
-// mod cfg_analyzer;
+mod cfg_visitor_mut;
+mod dataflow;
+mod drop_elaboration;
+mod move_checker;
+mod null_checker;
+mod cfg_analyzer;
+mod call_graph;
+mod const_folding;
+mod mem2reg;
+mod inliner;
+mod tail_calls;
+mod verify;
+mod interpreter;
 
 pub use icfg_prettifier::IcfgPrettifier;
+pub use icfg_parser::{ IcfgParser, IcfgParseError };
+pub use icfg_dot_exporter::IcfgDotExporter;
 pub use cfg_visitor::*;
-// pub use cfg_analyzer::*;
-
+pub use cfg_visitor_mut::*;
+pub use dataflow::*;
+pub use cfg_analyzer::*;
+pub use call_graph::CallGraph;
+pub use verify::{ VerifyError, VerifyErrorKind };
+pub use interpreter::{ InterpretError, InterpretErrorKind };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Liveness {
     Alive,
     Dead,
@@ -55,12 +81,27 @@ impl<'a> Icfg<'a> {
         Self { cfgs, resolved_information }
     }
 
-    // pub fn analyze(&mut self) {
-    //     for cfg in self.cfgs.iter_mut() {
-    //         let mut analyzer = CfgAnalyzer { cfg };
-    //         analyzer.visit_cfg();
-    //     }
-    // }
+    /// Reports reads of variables before they're initialized
+    pub fn analyze(&self) {
+        let mut diagnostics = Vec::new();
+
+        for cfg in self.cfgs.iter() {
+            let Some(mod_id) = cfg.get_mod_id() else {
+                continue;
+            };
+
+            let mut analyzer = CfgAnalyzer::new(cfg);
+            analyzer.analyze();
+
+            diagnostics.extend(
+                analyzer.errors
+                    .into_iter()
+                    .map(|(error_kind, span)| Diagnostic::new_error(error_kind, span, mod_id))
+            );
+        }
+
+        diagnostics::report_diagnostics(diagnostics);
+    }
 }
 
 /// One Cfg is constructed for each function
@@ -73,6 +114,10 @@ pub struct Cfg<'a> {
     pub basic_blocks: Vec<BasicBlock<'a>>,
     /// Based on if the function is called or not
     liveness: Liveness,
+    /// Functions declared with `fn.C` can be used from outside the program, so they're always alive
+    pub is_exported: bool,
+    /// Set with `@inline` or `@noinline`
+    pub inline_attr: InlineAttr,
     pub cfg_fn_kind: CfgFnKind,
     pub ret_ty: Ty,
 }
@@ -96,6 +141,8 @@ impl<'a> Cfg<'a> {
             cfg_fn_kind,
             ret_ty,
             liveness: Liveness::Dead,
+            is_exported: false,
+            inline_attr: InlineAttr::Auto,
         }
     }
 
@@ -106,6 +153,14 @@ impl<'a> Cfg<'a> {
     //         .expect("Expected GlobalMem")
     // }
 
+    /// The module the function is defined in (compiler generated functions have none)
+    pub fn get_mod_id(&self) -> Option<ModId> {
+        match self.cfg_fn_kind {
+            CfgFnKind::Main(def_id) | CfgFnKind::Fn(def_id) => Some(def_id.node_id.mod_id),
+            CfgFnKind::DropGlue(_) => None,
+        }
+    }
+
     pub fn get_local_mem(&self, local_mem_id: LocalMemId) -> &LocalMem {
         self.local_mems.get(local_mem_id.0 as usize).expect("Expected LocalMem")
     }
@@ -113,6 +168,39 @@ impl<'a> Cfg<'a> {
     pub fn get_result_mem(&self, result_mem_id: ResultMemId) -> &ResultMem {
         self.result_mems.get(result_mem_id.0 as usize).expect("Expected LocalMem")
     }
+
+    /// The first TempId after all TempIds used in the cfg. Used by passes inserting new nodes
+    pub fn get_next_temp_id(&self) -> TempId {
+        let node_temp_ids = self.basic_blocks
+            .iter()
+            .flat_map(|basic_block| basic_block.nodes.iter())
+            .filter_map(|node| {
+                match node.kind {
+                    NodeKind::BinaryNode(binary_node) => Some(binary_node.result_place),
+                    NodeKind::LoadNode(load_node) => Some(load_node.result_place),
+                    NodeKind::IndexNode(index_node) => Some(index_node.result_place),
+                    NodeKind::CallNode(call_node) => Some(call_node.result_place),
+                    NodeKind::TyCastNode(ty_cast_node) => Some(ty_cast_node.result_place),
+                    NodeKind::ByteAccessNode(ByteAccessNode {
+                        result_place: PlaceKind::TempId(temp_id),
+                        ..
+                    }) => Some(temp_id),
+                    NodeKind::DropNode(drop_node) => drop_node.drop_flag,
+                    NodeKind::PhiNode(phi_node) => Some(phi_node.result_place),
+                    _ => None,
+                }
+            });
+
+        let max_temp_id = self.args
+            .iter()
+            .map(|(temp_id, _)| *temp_id)
+            .chain(node_temp_ids)
+            .map(|temp_id| temp_id.0 + 1)
+            .max()
+            .unwrap_or(0);
+
+        TempId(max_temp_id)
+    }
 }
 
 #[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
@@ -138,8 +226,50 @@ impl<'a> BasicBlock<'a> {
     pub fn push_node(&mut self, node: Node<'a>) {
         self.nodes.push(node);
     }
+
+    /// The basic blocks that can be reached directly from this one (none if it returns)
+    pub fn get_successors(&self) -> Vec<BasicBlockId> {
+        for node in self.nodes.iter() {
+            match node.kind {
+                NodeKind::BranchNode(branch_node) => {
+                    return vec![branch_node.branch];
+                }
+                NodeKind::BranchCondNode(branch_cond_node) => {
+                    return vec![branch_cond_node.true_branch, branch_cond_node.false_branch];
+                }
+                NodeKind::SwitchNode(switch_node) => {
+                    return switch_node.get_targets();
+                }
+                NodeKind::ReturnNode(_) | NodeKind::UnreachableNode(_) => {
+                    return Vec::new();
+                }
+                _ => {}
+            }
+        }
+
+        Vec::new()
+    }
+
+    /// The call at the end of the basic block, if it's immediately followed by returning its
+    /// result (or by returning nothing from a call returning nothing)
+    pub fn get_tail_call(&self) -> Option<CallNode<'a>> {
+        let [call_node, return_node] = self.nodes.last_chunk::<2>()?;
+        let (NodeKind::CallNode(call_node), NodeKind::ReturnNode(return_node)) = (
+            call_node.kind,
+            return_node.kind,
+        ) else {
+            return None;
+        };
+
+        let returns_result = match return_node.ret_val {
+            Operand::PlaceKind(PlaceKind::TempId(temp_id)) => temp_id == call_node.result_place,
+            Operand::Const(Const::Void) => call_node.ret_ty.is_void(),
+            _ => false,
+        };
+        returns_result.then_some(call_node)
+    }
 }
-#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Debug, Clone, Copy)]
 pub struct BasicBlockId(pub u32);
 
 pub struct LocalMemAccess {
@@ -155,13 +285,18 @@ pub enum AccessKind {
 #[derive(Debug, new, Clone, Copy)]
 pub struct Node<'a> {
     pub kind: NodeKind<'a>,
+    /// Span of the expression the node was built from (if any), used for diagnostics
+    #[new(default)]
+    pub span: Option<Span>,
 }
 
+
 /// Based on llvms instructions but are higher level
 #[derive(Debug, Clone, Copy)]
 pub enum NodeKind<'a> {
     BranchNode(BranchNode),
     BranchCondNode(BranchCondNode),
+    SwitchNode(SwitchNode<'a>),
     BinaryNode(BinaryNode),
     StoreNode(StoreNode),
     LoadNode(LoadNode),
@@ -170,6 +305,79 @@ pub enum NodeKind<'a> {
     ReturnNode(ReturnNode),
     CallNode(CallNode<'a>),
     TyCastNode(TyCastNode),
+    DropNode(DropNode),
+    PhiNode(PhiNode<'a>),
+    UnreachableNode(UnreachableNode),
+}
+
+impl NodeKind<'_> {
+    /// If the node ends a basic block (it's always the last node of a basic block)
+    pub fn is_terminator(&self) -> bool {
+        matches!(
+            self,
+            NodeKind::BranchNode(_) |
+                NodeKind::BranchCondNode(_) |
+                NodeKind::SwitchNode(_) |
+                NodeKind::ReturnNode(_) |
+                NodeKind::UnreachableNode(_)
+        )
+    }
+
+    /// The operands the node reads
+    pub fn get_operands(&self) -> Vec<Operand> {
+        match self {
+            NodeKind::BinaryNode(binary_node) => vec![binary_node.lhs, binary_node.rhs],
+            NodeKind::BranchCondNode(branch_cond_node) => vec![branch_cond_node.condition],
+            NodeKind::SwitchNode(switch_node) => vec![switch_node.value],
+            NodeKind::StoreNode(store_node) => vec![store_node.value],
+            NodeKind::IndexNode(index_node) => vec![index_node.index],
+            NodeKind::ReturnNode(return_node) => vec![return_node.ret_val],
+            NodeKind::CallNode(call_node) => {
+                let mut operands = vec![call_node.callee];
+                operands.extend(call_node.args.iter());
+                operands
+            }
+            NodeKind::TyCastNode(ty_cast_node) => vec![ty_cast_node.operand],
+            NodeKind::PhiNode(phi_node) => {
+                phi_node.incoming
+                    .iter()
+                    .map(|(operand, _)| *operand)
+                    .collect()
+            }
+            | NodeKind::BranchNode(_)
+            | NodeKind::LoadNode(_)
+            | NodeKind::ByteAccessNode(_)
+            | NodeKind::DropNode(_)
+            | NodeKind::UnreachableNode(_) => Vec::new(),
+        }
+    }
+
+    /// All places the node refers to (written to, read from or used as operands)
+    pub fn get_places(&self) -> Vec<PlaceKind> {
+        let mut places = match self {
+            NodeKind::StoreNode(store_node) => vec![store_node.setter],
+            NodeKind::LoadNode(load_node) => vec![load_node.load_place],
+            NodeKind::IndexNode(index_node) => vec![index_node.array_place],
+            NodeKind::ByteAccessNode(byte_access_node) => {
+                vec![byte_access_node.result_place, byte_access_node.access_place]
+            }
+            NodeKind::DropNode(drop_node) => vec![drop_node.place],
+            _ => Vec::new(),
+        };
+
+        places.extend(
+            self.get_operands()
+                .into_iter()
+                .filter_map(|operand| {
+                    match operand {
+                        Operand::PlaceKind(place) => Some(place),
+                        Operand::Const(_) => None,
+                    }
+                })
+        );
+
+        places
+    }
 }
 
 /// A hint to the optimizer whether or not the store is used for initializing a complicated data structure
@@ -223,6 +431,15 @@ pub struct ReturnNode {
     pub ret_ty: Ty,
 }
 
+/// Ends a basic block which is never reached (e.g. the end of a function returning a value, where
+/// every path has already returned)
+///
+/// LLVM instruction:
+///
+/// `unreachable`
+#[derive(Debug, new, Clone, Copy)]
+pub struct UnreachableNode;
+
 #[derive(Debug, Clone, Copy)]
 pub enum TyCastKind {
     /// llvm `trunc`
@@ -266,6 +483,35 @@ pub struct CallNode<'a> {
     pub ret_ty: Ty,
 }
 
+/// Drops the value at some place by calling the drop glue of its type
+/// (`onDrop` of the type followed by dropping its fields)
+///
+/// Values that are only moved on some paths have a drop flag, which is set while the value is alive
+///
+/// LLVM instruction:
+///
+/// `call void @drop.{ty}(ptr %{place}, i8 {drop_flag})`
+#[derive(Debug, new, Clone, Copy)]
+pub struct DropNode {
+    pub place: PlaceKind,
+    pub ty: Ty,
+    /// The loaded drop flag. Without one the value is always dropped
+    pub drop_flag: Option<TempId>,
+}
+
+/// Selects a value based on which basic block the control flow came from. Always placed at the
+/// start of a basic block, with a value for each of its predecessors (made by `mem2reg`)
+///
+/// LLVM instruction:
+///
+/// `%{result_place} = phi {ty} [{value}, %{basic_block}], ...`
+#[derive(Debug, new, Clone, Copy)]
+pub struct PhiNode<'a> {
+    pub result_place: TempId,
+    pub ty: Ty,
+    pub incoming: &'a [(Operand, BasicBlockId)],
+}
+
 /// Different from `ByteAccessNode` as this is only used with actual indexing supplied by the user.
 /// E.g. `indexableOperand[2]`
 #[derive(Debug, new, Clone, Copy)]
@@ -300,6 +546,34 @@ pub struct BranchCondNode {
     pub false_branch: BasicBlockId,
 }
 
+/// Used to goto the basic block of the case equal to an integer value, or to the default basic
+/// block if no case is equal. Tests of multiple enum variants in an `if`/`elif` chain become one
+///
+/// LLVM instruction:
+///
+/// `switch {ty} {value}, label %{default} [ {ty} {case}, label %{branch} ... ]`
+#[derive(Debug, new, Clone, Copy)]
+pub struct SwitchNode<'a> {
+    pub value: Operand,
+    pub ty: Ty,
+    pub default: BasicBlockId,
+    /// Each case value is unique
+    pub cases: &'a [(i64, BasicBlockId)],
+}
+
+impl SwitchNode<'_> {
+    /// The default basic block followed by the basic block of each case (without duplicates)
+    pub fn get_targets(&self) -> Vec<BasicBlockId> {
+        let mut targets = vec![self.default];
+        for (_, basic_block_id) in self.cases.iter() {
+            if !targets.contains(basic_block_id) {
+                targets.push(*basic_block_id);
+            }
+        }
+        targets
+    }
+}
+
 /// Unconditional goto
 ///
 /// LLVM instruction:
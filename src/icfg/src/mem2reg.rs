@@ -0,0 +1,573 @@
+/*
+
+Promotion of variables to SSA values (mem2reg)
+
+Variables which are only ever loaded from and stored to (their address is never taken) don't need
+to live on the stack. Their loads are replaced by the value last stored to them, and where control
+flow merges with different values a `PhiNode` selects the value based on the predecessor
+
+Phis are placed at the iterated dominance frontier of the blocks storing to the variable, after
+which the dominator tree is walked to rename each load to its reaching value, as described in
+"Efficiently Computing Static Single Assignment Form and the Control Dependence Graph"
+by Cytron et al.
+
+Only integer variables (and implicit results) without drops are promoted. The pass is optional,
+as clang's own passes do the same when optimizing
+
+*/
+
+use fxhash::{ FxHashMap, FxHashSet };
+use ir::{ IntTy, PrimTy, TempId, Ty, TyCtx };
+
+use crate::{
+    BasicBlock,
+    BasicBlockId,
+    BinaryNode,
+    BranchCondNode,
+    CallNode,
+    Cfg,
+    CfgGraph,
+    CfgVisitorMut,
+    Const,
+    Icfg,
+    IndexNode,
+    Node,
+    NodeKind,
+    Operand,
+    PhiNode,
+    PlaceKind,
+    ReturnNode,
+    StoreNode,
+    SwitchNode,
+    TyCastNode,
+};
+
+impl Icfg<'_> {
+    /// Promotes the variables of all cfgs to SSA values where possible
+    pub fn mem2reg(&mut self) {
+        for cfg in self.cfgs.iter_mut() {
+            promote_variables(cfg);
+        }
+    }
+}
+
+/// A variable which is promoted
+struct PromotedVar {
+    place: PlaceKind,
+    int_ty: IntTy,
+}
+
+impl PromotedVar {
+    fn ty(&self) -> Ty {
+        Ty::PrimTy(PrimTy::Int(self.int_ty))
+    }
+
+    /// The value of the variable where it's not initialized (only in unreachable code)
+    fn undefined(&self) -> Operand {
+        Operand::Const(Const::Int(0, self.int_ty))
+    }
+}
+
+/// A phi which is inserted for a variable at the start of a basic block
+struct InsertedPhi {
+    var: usize,
+    result_place: TempId,
+    incoming: Vec<(Operand, BasicBlockId)>,
+}
+
+fn promote_variables(cfg: &mut Cfg) {
+    let graph = CfgGraph::new(cfg);
+    // Phis can't be placed in the entry, as it has no predecessor to take a value from
+    if graph.is_empty() || !graph.predecessors(BasicBlockId(0)).is_empty() {
+        return;
+    }
+    // Nodes after a terminator end up in a basic block of their own in LLVM, which would be a
+    // predecessor the cfg doesn't know about
+    if cfg.basic_blocks.iter().any(has_nodes_after_terminator) {
+        return;
+    }
+
+    let vars = get_promoted_vars(cfg);
+    if vars.is_empty() {
+        return;
+    }
+    let var_indices = vars
+        .iter()
+        .enumerate()
+        .map(|(i, var)| (var.place, i))
+        .collect::<FxHashMap<_, _>>();
+
+    let idoms = graph.immediate_dominators();
+    let frontiers = graph.dominance_frontiers(&idoms);
+
+    // Phi placement
+    let mut def_blocks = vec![Vec::new(); vars.len()];
+    for basic_block in cfg.basic_blocks.iter() {
+        if idoms[basic_block.basic_block_id.0 as usize].is_none() {
+            continue;
+        }
+        for node in basic_block.nodes.iter() {
+            if let NodeKind::StoreNode(store_node) = node.kind {
+                if let Some(var) = var_indices.get(&store_node.setter) {
+                    def_blocks[*var].push(basic_block.basic_block_id);
+                }
+            }
+        }
+    }
+
+    let mut next_temp_id = cfg.get_next_temp_id();
+    let mut phis: Vec<Vec<InsertedPhi>> = (0..graph.len()).map(|_| Vec::new()).collect();
+    for (var, mut worklist) in def_blocks.into_iter().enumerate() {
+        let mut has_phi = FxHashSet::default();
+        let mut has_def = worklist.iter().copied().collect::<FxHashSet<_>>();
+
+        while let Some(bb_id) = worklist.pop() {
+            for frontier in frontiers[bb_id.0 as usize].iter() {
+                if !has_phi.insert(*frontier) {
+                    continue;
+                }
+                phis[frontier.0 as usize].push(InsertedPhi {
+                    var,
+                    result_place: next_temp_id,
+                    incoming: Vec::new(),
+                });
+                next_temp_id = TempId(next_temp_id.0 + 1);
+
+                if has_def.insert(*frontier) {
+                    worklist.push(*frontier);
+                }
+            }
+        }
+    }
+
+    // Renaming
+    let mut dom_tree_children = vec![Vec::new(); graph.len()];
+    for (bb_id, idom) in idoms.iter().enumerate().skip(1) {
+        if let Some(idom) = idom {
+            dom_tree_children[idom.0 as usize].push(BasicBlockId(bb_id as u32));
+        }
+    }
+
+    let mut renamer = Renamer {
+        graph: &graph,
+        dom_tree_children: &dom_tree_children,
+        vars: &vars,
+        var_indices: &var_indices,
+        phis: &mut phis,
+        stacks: vec![Vec::new(); vars.len()],
+        load_values: Default::default(),
+        removed_nodes: vec![FxHashSet::default(); graph.len()],
+    };
+    renamer.rename(BasicBlockId(0), cfg);
+    renamer.remove_unreachable_accesses(&idoms, cfg);
+
+    let Renamer { load_values, removed_nodes, .. } = renamer;
+
+    // Incoming values of unreachable predecessors are never used, but LLVM still requires them
+    for (bb_id, bb_phis) in phis.iter_mut().enumerate() {
+        for phi in bb_phis.iter_mut() {
+            for predecessor in graph.predecessors(BasicBlockId(bb_id as u32)) {
+                if idoms[predecessor.0 as usize].is_none() {
+                    phi.incoming.push((vars[phi.var].undefined(), *predecessor));
+                }
+            }
+        }
+    }
+
+    for (basic_block, (bb_phis, removed_nodes)) in cfg.basic_blocks
+        .iter_mut()
+        .zip(phis.into_iter().zip(removed_nodes)) {
+        let mut i = 0;
+        basic_block.nodes.retain(|_| {
+            i += 1;
+            !removed_nodes.contains(&(i - 1))
+        });
+
+        let phi_nodes = bb_phis.into_iter().map(|phi| {
+            Node::new(
+                NodeKind::PhiNode(
+                    PhiNode::new(
+                        phi.result_place,
+                        vars[phi.var].ty(),
+                        TyCtx::intern_many_types(phi.incoming)
+                    )
+                )
+            )
+        });
+        basic_block.nodes.splice(0..0, phi_nodes);
+    }
+
+    LoadSubstitutor { load_values }.visit_cfg(cfg);
+}
+
+fn has_nodes_after_terminator(basic_block: &BasicBlock) -> bool {
+    basic_block.nodes
+        .iter()
+        .position(|node| node.kind.is_terminator())
+        .is_some_and(|i| i != basic_block.nodes.len() - 1)
+}
+
+/// Finds the integer variables which are only loaded from and stored to with their own type
+fn get_promoted_vars(cfg: &Cfg) -> Vec<PromotedVar> {
+    let mut candidates: FxHashMap<PlaceKind, PromotedVar> = Default::default();
+    let local_mems = cfg.local_mems
+        .iter()
+        .filter(|local_mem| !local_mem.requires_drop)
+        .map(|local_mem| (PlaceKind::LocalMemId(local_mem.local_mem_id), local_mem.ty));
+    let result_mems = cfg.result_mems
+        .iter()
+        .map(|result_mem| (PlaceKind::ResultMemId(result_mem.result_mem_id), result_mem.ty));
+    for (place, ty) in local_mems.chain(result_mems) {
+        if let Ty::PrimTy(PrimTy::Int(int_ty)) = ty {
+            candidates.insert(place, PromotedVar { place, int_ty });
+        }
+    }
+
+    // Drop flags are loaded right before the drop, which takes the loaded temp as it is
+    let drop_flags = cfg.basic_blocks
+        .iter()
+        .flat_map(|basic_block| basic_block.nodes.iter())
+        .filter_map(|node| {
+            match node.kind {
+                NodeKind::DropNode(drop_node) => drop_node.drop_flag,
+                _ => None,
+            }
+        })
+        .collect::<FxHashSet<_>>();
+
+    let mut not_promoted = FxHashSet::default();
+    for node in cfg.basic_blocks.iter().flat_map(|basic_block| basic_block.nodes.iter()) {
+        let (place, ty, other_places) = match node.kind {
+            NodeKind::LoadNode(load_node) if drop_flags.contains(&load_node.result_place) => {
+                not_promoted.insert(load_node.load_place);
+                continue;
+            }
+            NodeKind::LoadNode(load_node) => (load_node.load_place, load_node.load_ty, Vec::new()),
+            NodeKind::StoreNode(store_node) => {
+                let value_places = match store_node.value {
+                    Operand::PlaceKind(place) => vec![place],
+                    Operand::Const(_) => Vec::new(),
+                };
+                (store_node.setter, store_node.op_ty, value_places)
+            }
+            _ => {
+                not_promoted.extend(node.kind.get_places());
+                continue;
+            }
+        };
+
+        if let Some(var) = candidates.get(&place) {
+            if ty != var.ty() {
+                not_promoted.insert(place);
+            }
+        }
+        not_promoted.extend(other_places);
+    }
+
+    let mut vars = candidates
+        .into_values()
+        .filter(|var| !not_promoted.contains(&var.place))
+        .collect::<Vec<_>>();
+    // Keeps the order of the inserted phis deterministic
+    vars.sort_by_key(|var| {
+        match var.place {
+            PlaceKind::LocalMemId(local_mem_id) => (0, local_mem_id.0),
+            PlaceKind::ResultMemId(result_mem_id) => (1, result_mem_id.0),
+            PlaceKind::TempId(temp_id) => (2, temp_id.0),
+        }
+    });
+
+    vars
+}
+
+struct Renamer<'b> {
+    graph: &'b CfgGraph,
+    dom_tree_children: &'b [Vec<BasicBlockId>],
+    vars: &'b [PromotedVar],
+    var_indices: &'b FxHashMap<PlaceKind, usize>,
+    phis: &'b mut [Vec<InsertedPhi>],
+    /// The reaching value of each variable
+    stacks: Vec<Vec<Operand>>,
+    /// The value each removed load is replaced with
+    load_values: FxHashMap<TempId, Operand>,
+    /// Indices of the loads and stores which are removed in each basic block
+    removed_nodes: Vec<FxHashSet<usize>>,
+}
+
+impl Renamer<'_> {
+    fn rename(&mut self, bb_id: BasicBlockId, cfg: &Cfg) {
+        let mut pushed_vars = Vec::new();
+
+        for phi in self.phis[bb_id.0 as usize].iter() {
+            self.stacks[phi.var].push(phi.result_place.into());
+            pushed_vars.push(phi.var);
+        }
+
+        for (i, node) in cfg.basic_blocks[bb_id.0 as usize].nodes.iter().enumerate() {
+            match node.kind {
+                NodeKind::LoadNode(load_node) => {
+                    let Some(var) = self.var_indices.get(&load_node.load_place) else {
+                        continue;
+                    };
+                    let value = self.get_reaching_value(*var);
+                    self.load_values.insert(load_node.result_place, value);
+                    self.removed_nodes[bb_id.0 as usize].insert(i);
+                }
+                NodeKind::StoreNode(store_node) => {
+                    let Some(var) = self.var_indices.get(&store_node.setter) else {
+                        continue;
+                    };
+                    let value = self.resolve(store_node.value);
+                    self.stacks[*var].push(value);
+                    pushed_vars.push(*var);
+                    self.removed_nodes[bb_id.0 as usize].insert(i);
+                }
+                _ => {}
+            }
+        }
+
+        for successor in self.graph.successors(bb_id) {
+            for phi_index in 0..self.phis[successor.0 as usize].len() {
+                let var = self.phis[successor.0 as usize][phi_index].var;
+                let value = self.get_reaching_value(var);
+                self.phis[successor.0 as usize][phi_index].incoming.push((value, bb_id));
+            }
+        }
+
+        for child in self.dom_tree_children[bb_id.0 as usize].iter() {
+            self.rename(*child, cfg);
+        }
+
+        for var in pushed_vars {
+            self.stacks[var].pop();
+        }
+    }
+
+    /// Loads and stores in unreachable basic blocks are removed as well (the loads are undefined)
+    fn remove_unreachable_accesses(&mut self, idoms: &[Option<BasicBlockId>], cfg: &Cfg) {
+        for (bb_id, basic_block) in cfg.basic_blocks.iter().enumerate() {
+            if idoms[bb_id].is_some() {
+                continue;
+            }
+
+            for (i, node) in basic_block.nodes.iter().enumerate() {
+                let var = match node.kind {
+                    NodeKind::LoadNode(load_node) => {
+                        let var = self.var_indices.get(&load_node.load_place);
+                        if let Some(var) = var {
+                            self.load_values.insert(
+                                load_node.result_place,
+                                self.vars[*var].undefined()
+                            );
+                        }
+                        var
+                    }
+                    NodeKind::StoreNode(store_node) => self.var_indices.get(&store_node.setter),
+                    _ => None,
+                };
+
+                if var.is_some() {
+                    self.removed_nodes[bb_id].insert(i);
+                }
+            }
+        }
+    }
+
+    fn get_reaching_value(&self, var: usize) -> Operand {
+        self.stacks[var].last().copied().unwrap_or_else(|| self.vars[var].undefined())
+    }
+
+    /// The value of an operand, if it's the result of a removed load
+    fn resolve(&self, operand: Operand) -> Operand {
+        match operand {
+            Operand::PlaceKind(PlaceKind::TempId(temp_id)) => {
+                self.load_values.get(&temp_id).copied().unwrap_or(operand)
+            }
+            _ => operand,
+        }
+    }
+}
+
+/// Replaces the results of removed loads with their value
+struct LoadSubstitutor {
+    load_values: FxHashMap<TempId, Operand>,
+}
+
+impl LoadSubstitutor {
+    fn substitute(&self, operand: &mut Operand) {
+        if let Operand::PlaceKind(PlaceKind::TempId(temp_id)) = operand {
+            if let Some(value) = self.load_values.get(temp_id) {
+                *operand = *value;
+            }
+        }
+    }
+}
+
+impl CfgVisitorMut for LoadSubstitutor {
+    type Result = ();
+
+    fn default_result() -> Self::Result {}
+
+    fn visit_binary_node(&mut self, binary_node: &mut BinaryNode) -> Self::Result {
+        self.substitute(&mut binary_node.lhs);
+        self.substitute(&mut binary_node.rhs);
+    }
+
+    fn visit_branch_cond_node(&mut self, branch_cond_node: &mut BranchCondNode) -> Self::Result {
+        self.substitute(&mut branch_cond_node.condition);
+    }
+
+    fn visit_switch_node(&mut self, switch_node: &mut SwitchNode) -> Self::Result {
+        self.substitute(&mut switch_node.value);
+    }
+
+    fn visit_store_node(&mut self, store_node: &mut StoreNode) -> Self::Result {
+        self.substitute(&mut store_node.value);
+    }
+
+    fn visit_index_node(&mut self, index_node: &mut IndexNode) -> Self::Result {
+        self.substitute(&mut index_node.index);
+    }
+
+    fn visit_return_node(&mut self, return_node: &mut ReturnNode) -> Self::Result {
+        self.substitute(&mut return_node.ret_val);
+    }
+
+    fn visit_ty_cast_node(&mut self, ty_cast_node: &mut TyCastNode) -> Self::Result {
+        self.substitute(&mut ty_cast_node.operand);
+    }
+
+    fn visit_call_node(&mut self, call_node: &mut CallNode) -> Self::Result {
+        self.substitute(&mut call_node.callee);
+
+        let mut args = call_node.args.to_vec();
+        args.iter_mut().for_each(|arg| self.substitute(arg));
+        call_node.args = TyCtx::intern_many_types(args);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ IcfgParser, IcfgPrettifier };
+
+    const SRC: &str =
+        r##"
+fn diamond#0:0(_0: int32) -> int32 {
+    local l0 x: int32
+bb0:
+    store init int32 1i32, l0
+    _1 = gt int32 _0, 0i32
+    br _1, bb1, bb2
+bb1:
+    store assign int32 2i32, l0
+    br bb3
+bb2:
+    br bb3
+bb3:
+    _2 = load int32, l0
+    ret int32 _2
+}
+
+fn sum#0:1(_0: int32) -> int32 {
+    local l0 i: int32
+    local l1 acc: int32
+bb0:
+    store init int32 0i32, l0
+    store init int32 0i32, l1
+    br bb1
+bb1:
+    _1 = load int32, l0
+    _2 = lt int32 _1, _0
+    br _2, bb2, bb3
+bb2:
+    _3 = load int32, l1
+    _4 = add int32 _3, _1
+    store assign int32 _4, l1
+    _5 = add int32 _1, 1i32
+    store assign int32 _5, l0
+    br bb1
+bb3:
+    _6 = load int32, l1
+    ret int32 _6
+}
+
+main fn main#0:2() -> Void {
+    local l0 a: int32
+bb0:
+    _0 = call int32 fn diamond#0:0(5i32) : (int32)
+    _1 = call int32 fn diamond#0:0(-5i32) : (int32)
+    _2 = call int32 fn sum#0:1(5i32) : (int32)
+    store init int32 _2, l0
+    _3 = load int32, l0
+    _4 = add int32 _0, _1
+    _5 = add int32 _4, _3
+    _6 = call Void fn exit#0:3(_5) : (int32)
+    unreachable
+}
+
+extern fn exit#0:3(int32) -> Void
+"##;
+
+    #[test]
+    fn place_phis() {
+        let mut icfg = IcfgParser::new(SRC).parse_icfg().unwrap();
+        icfg.mem2reg();
+        assert!(icfg.verify().is_ok());
+
+        // Phis are only placed where different values of a variable merge: after the branch in
+        // `diamond` and at the loop header in `sum`. In `main` the only store dominates the load
+        let expected =
+            r##"
+extern fn exit#0:3(int32) -> Void
+
+fn diamond#0:0(_0: int32) -> int32 {
+    local l0 x: int32
+bb0:
+    _1 = gt int32 _0, 0i32
+    br _1, bb1, bb2
+bb1:
+    br bb3
+bb2:
+    br bb3
+bb3:
+    _3 = phi int32 [2i32, bb1], [1i32, bb2]
+    ret int32 _3
+}
+
+fn sum#0:1(_0: int32) -> int32 {
+    local l0 i: int32
+    local l1 acc: int32
+bb0:
+    br bb1
+bb1:
+    _7 = phi int32 [0i32, bb0], [_5, bb2]
+    _8 = phi int32 [0i32, bb0], [_4, bb2]
+    _2 = lt int32 _7, _0
+    br _2, bb2, bb3
+bb2:
+    _4 = add int32 _8, _7
+    _5 = add int32 _7, 1i32
+    br bb1
+bb3:
+    ret int32 _8
+}
+
+main fn main#0:2() -> Void {
+    local l0 a: int32
+bb0:
+    _0 = call int32 fn diamond#0:0(5i32) : (int32)
+    _1 = call int32 fn diamond#0:0(-5i32) : (int32)
+    _2 = call int32 fn sum#0:1(5i32) : (int32)
+    _4 = add int32 _0, _1
+    _5 = add int32 _4, _2
+    _6 = call Void fn exit#0:3(_5) : (int32)
+    unreachable
+}
+"##;
+        assert_eq!(IcfgPrettifier::new(&icfg).prettify().trim(), expected.trim());
+
+        // 2 + 1 + (0 + 1 + 2 + 3 + 4)
+        let mut out = Vec::new();
+        assert_eq!(icfg.interpret(&mut out).unwrap(), 13);
+    }
+}
@@ -0,0 +1,484 @@
+/*
+
+Move checking
+
+Values of types which aren't `Copy` are moved when they're used by value (e.g. passed to a
+function, returned or assigned to another variable). After a move the variable cannot be used
+before it's assigned again
+
+Moving a field out of a variable is a partial move. The other fields can still be used, but the
+variable as a whole cannot
+
+This is a forward dataflow analysis (see `dataflow.rs`) over each cfg, where a variable is
+considered moved at some point if it's moved on any path leading to that point
+
+*/
+
+use diagnostics::{ Diagnostic, ErrorKind };
+use fxhash::FxHashMap;
+use ir::{ GetTyAttr, LocalMemId, ResolvedInformation, TempId, Ty };
+use span::Span;
+
+use crate::{ Cfg, DataflowAnalysis, Icfg, Node, NodeKind, NodeLocation, Operand, PlaceKind };
+
+/// The variable (and the byte range of it) that a place points into
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PlaceOrigin {
+    pub local_mem_id: LocalMemId,
+    /// `None` if the place is the variable itself (which is also where its first field is)
+    pub field_offset: Option<usize>,
+}
+
+/// Tracks which variables the places of a cfg point into, by following field accesses and
+/// stack references created by e.g. `b := a` (where `b` refers to `a`)
+pub(crate) struct PlaceOrigins {
+    temp_id_to_origin: FxHashMap<TempId, PlaceOrigin>,
+    ref_local_mem_id_to_origin: FxHashMap<LocalMemId, PlaceOrigin>,
+}
+
+impl PlaceOrigins {
+    pub(crate) fn new(cfg: &Cfg) -> Self {
+        let mut place_origins = Self {
+            temp_id_to_origin: Default::default(),
+            ref_local_mem_id_to_origin: Default::default(),
+        };
+
+        for node in cfg.basic_blocks.iter().flat_map(|basic_block| basic_block.nodes.iter()) {
+            match node.kind {
+                NodeKind::StoreNode(store_node) => {
+                    let PlaceKind::LocalMemId(local_mem_id) = store_node.setter else {
+                        continue;
+                    };
+                    if !matches!(cfg.get_local_mem(local_mem_id).ty, Ty::StackPtr(..)) {
+                        continue;
+                    }
+                    if let Some(origin) = place_origins.get_operand_origin(&store_node.value) {
+                        place_origins.ref_local_mem_id_to_origin.insert(local_mem_id, origin);
+                    }
+                }
+                NodeKind::LoadNode(load_node) => {
+                    let PlaceKind::LocalMemId(local_mem_id) = load_node.load_place else {
+                        continue;
+                    };
+                    if
+                        let Some(origin) = place_origins.ref_local_mem_id_to_origin.get(
+                            &local_mem_id
+                        )
+                    {
+                        place_origins.temp_id_to_origin.insert(load_node.result_place, *origin);
+                    }
+                }
+                NodeKind::ByteAccessNode(byte_access_node) => {
+                    let PlaceKind::TempId(temp_id) = byte_access_node.result_place else {
+                        continue;
+                    };
+                    if let Some(origin) = place_origins.get_origin(&byte_access_node.access_place) {
+                        let field_offset =
+                            origin.field_offset.unwrap_or(0) + byte_access_node.byte_offset;
+                        place_origins.temp_id_to_origin.insert(temp_id, PlaceOrigin {
+                            local_mem_id: origin.local_mem_id,
+                            field_offset: Some(field_offset),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        place_origins
+    }
+
+    pub(crate) fn get_origin(&self, place: &PlaceKind) -> Option<PlaceOrigin> {
+        match place {
+            PlaceKind::LocalMemId(local_mem_id) =>
+                Some(PlaceOrigin { local_mem_id: *local_mem_id, field_offset: None }),
+            PlaceKind::TempId(temp_id) => self.temp_id_to_origin.get(temp_id).copied(),
+            PlaceKind::ResultMemId(_) => None,
+        }
+    }
+
+    pub(crate) fn get_operand_origin(&self, operand: &Operand) -> Option<PlaceOrigin> {
+        match operand {
+            Operand::PlaceKind(place) => self.get_origin(place),
+            Operand::Const(_) => None,
+        }
+    }
+}
+
+impl Icfg<'_> {
+    /// Reports uses of moved (or partially moved) variables
+    pub fn check_moves(&self) {
+        let mut diagnostics = Vec::new();
+
+        for cfg in self.cfgs.iter() {
+            let Some(mod_id) = cfg.get_mod_id() else {
+                continue;
+            };
+
+            let mut move_checker = MoveChecker::new(cfg, &self.resolved_information);
+            move_checker.check();
+
+            diagnostics.extend(
+                move_checker.errors
+                    .into_iter()
+                    .map(|(error_kind, span)| Diagnostic::new_error(error_kind, span, mod_id))
+            );
+        }
+
+        diagnostics::report_diagnostics(diagnostics);
+    }
+}
+
+/// Which parts of a variable may have been moved, and where
+#[derive(Debug, Clone, Default)]
+struct MoveState {
+    moved: Option<Span>,
+    /// Byte ranges of the moved fields
+    moved_fields: Vec<(usize, usize, Span)>,
+}
+
+impl MoveState {
+    /// Returns true if the state changed
+    fn join(&mut self, other: &Self) -> bool {
+        let mut changed = false;
+
+        if self.moved.is_none() && other.moved.is_some() {
+            self.moved = other.moved;
+            changed = true;
+        }
+
+        for (start, end, span) in other.moved_fields.iter() {
+            if !self.moved_fields.iter().any(|(s, e, _)| s == start && e == end) {
+                self.moved_fields.push((*start, *end, *span));
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+struct MoveChecker<'a, 'b> {
+    cfg: &'b Cfg<'a>,
+    resolved_information: &'b ResolvedInformation<'a>,
+    place_origins: PlaceOrigins,
+    errors: Vec<(ErrorKind, Span)>,
+    /// Only the first use after a move of each variable is reported
+    reported_local_mem_ids: Vec<bool>,
+}
+
+impl<'a, 'b> MoveChecker<'a, 'b> {
+    fn new(cfg: &'b Cfg<'a>, resolved_information: &'b ResolvedInformation<'a>) -> Self {
+        Self {
+            cfg,
+            resolved_information,
+            place_origins: PlaceOrigins::new(cfg),
+            errors: Vec::new(),
+            reported_local_mem_ids: vec![false; cfg.local_mems.len()],
+        }
+    }
+
+    fn check(&mut self) {
+        let cfg = self.cfg;
+        let results = self.solve(cfg);
+
+        results.visit_with(cfg, self, |move_checker, node, _, state| {
+            move_checker.check_node(node, state);
+        });
+    }
+
+    /// Reports uses of moved variables in a node, given the state before it
+    fn check_node(&mut self, node: &Node, state: &[MoveState]) {
+        let span = node.span.unwrap_or(Span::dummy());
+
+        match node.kind {
+            NodeKind::LoadNode(load_node) => {
+                let Some(origin) = self.place_origins.get_origin(&load_node.load_place) else {
+                    return;
+                };
+                if self.is_copy_local(origin.local_mem_id) {
+                    return;
+                }
+
+                let range = self.get_range(origin, load_node.load_ty);
+                self.check_use(origin.local_mem_id, range, span, state);
+            }
+            NodeKind::StoreNode(store_node) => {
+                self.check_operand_use(&store_node.value, span, state);
+
+                let Some(origin) = self.place_origins.get_origin(&store_node.setter) else {
+                    return;
+                };
+                if self.is_copy_local(origin.local_mem_id) {
+                    return;
+                }
+
+                // Assigning to a field of a moved variable
+                let range = self.get_range(origin, store_node.op_ty);
+                if
+                    !self.is_whole_range(origin.local_mem_id, range) &&
+                    state[origin.local_mem_id.0 as usize].moved.is_some()
+                {
+                    self.check_use(origin.local_mem_id, range, span, state);
+                }
+            }
+            NodeKind::CallNode(call_node) => {
+                for arg in call_node.args.iter() {
+                    self.check_operand_use(arg, span, state);
+                }
+            }
+            NodeKind::IndexNode(index_node) => {
+                self.check_operand_use(&Operand::PlaceKind(index_node.array_place), span, state);
+            }
+            NodeKind::ReturnNode(return_node) => {
+                self.check_operand_use(&return_node.ret_val, span, state);
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks places passed by reference (e.g. `*self` in method calls)
+    fn check_operand_use(&mut self, operand: &Operand, span: Span, state: &[MoveState]) {
+        let Some(origin) = self.place_origins.get_operand_origin(operand) else {
+            return;
+        };
+        if self.is_copy_local(origin.local_mem_id) {
+            return;
+        }
+
+        let local_ty = self.cfg.get_local_mem(origin.local_mem_id).ty;
+        let range = match origin.field_offset {
+            None => self.get_range(origin, local_ty),
+            Some(field_offset) => (field_offset, field_offset + 1),
+        };
+
+        self.check_use(origin.local_mem_id, range, span, state);
+    }
+
+    fn check_use(
+        &mut self,
+        local_mem_id: LocalMemId,
+        (start, end): (usize, usize),
+        span: Span,
+        state: &[MoveState]
+    ) {
+        if self.reported_local_mem_ids[local_mem_id.0 as usize] {
+            return;
+        }
+
+        let move_state = &state[local_mem_id.0 as usize];
+        let symbol = self.cfg.get_local_mem(local_mem_id).symbol;
+
+        let error_kind = if let Some(move_span) = move_state.moved {
+            ErrorKind::UseAfterMove { symbol, move_span }
+        } else if
+            let Some((s, e, move_span)) = move_state.moved_fields
+                .iter()
+                .find(|(s, e, _)| *s < end && start < *e)
+        {
+            // Using (a part of) the moved field itself is just a use after move
+            if *s <= start && end <= *e {
+                ErrorKind::UseAfterMove { symbol, move_span: *move_span }
+            } else {
+                ErrorKind::UseOfPartiallyMovedValue { symbol, move_span: *move_span }
+            }
+        } else {
+            return;
+        };
+
+        self.reported_local_mem_ids[local_mem_id.0 as usize] = true;
+        self.errors.push((error_kind, span));
+    }
+
+    fn is_copy_local(&self, local_mem_id: LocalMemId) -> bool {
+        self.resolved_information.is_copy(self.cfg.get_local_mem(local_mem_id).ty)
+    }
+
+    fn is_whole_range(&self, local_mem_id: LocalMemId, range: (usize, usize)) -> bool {
+        let local_ty = self.cfg.get_local_mem(local_mem_id).ty;
+        range == (0, local_ty.get_ty_attr(self.resolved_information).size_bytes)
+    }
+
+    fn get_range(&self, origin: PlaceOrigin, ty: Ty) -> (usize, usize) {
+        let start = origin.field_offset.unwrap_or(0);
+        (start, start + ty.get_ty_attr(self.resolved_information).size_bytes)
+    }
+}
+
+impl DataflowAnalysis for MoveChecker<'_, '_> {
+    type Domain = Vec<MoveState>;
+
+    fn boundary_state(&self, cfg: &Cfg) -> Self::Domain {
+        vec![MoveState::default(); cfg.local_mems.len()]
+    }
+
+    /// A variable is moved if it's moved on any path
+    fn join(&self, state: &mut Self::Domain, other: &Self::Domain) -> bool {
+        let mut changed = false;
+        for (move_state, other_move_state) in state.iter_mut().zip(other.iter()) {
+            changed |= move_state.join(other_move_state);
+        }
+        changed
+    }
+
+    fn transfer_node(
+        &mut self,
+        node: &Node,
+        _location: NodeLocation,
+        state: &mut Self::Domain,
+        _cfg: &Cfg
+    ) {
+        let span = node.span.unwrap_or(Span::dummy());
+
+        match node.kind {
+            NodeKind::LoadNode(load_node) => {
+                let Some(origin) = self.place_origins.get_origin(&load_node.load_place) else {
+                    return;
+                };
+                if
+                    self.is_copy_local(origin.local_mem_id) ||
+                    self.resolved_information.is_copy(load_node.load_ty)
+                {
+                    return;
+                }
+
+                let range = self.get_range(origin, load_node.load_ty);
+                let is_whole = self.is_whole_range(origin.local_mem_id, range);
+                let move_state = &mut state[origin.local_mem_id.0 as usize];
+                if is_whole {
+                    move_state.moved = Some(span);
+                } else {
+                    move_state.moved_fields.push((range.0, range.1, span));
+                }
+            }
+            NodeKind::StoreNode(store_node) => {
+                let Some(origin) = self.place_origins.get_origin(&store_node.setter) else {
+                    return;
+                };
+                if self.is_copy_local(origin.local_mem_id) {
+                    return;
+                }
+
+                let (start, end) = self.get_range(origin, store_node.op_ty);
+                let move_state = &mut state[origin.local_mem_id.0 as usize];
+                if self.is_whole_range(origin.local_mem_id, (start, end)) {
+                    *move_state = MoveState::default();
+                } else if move_state.moved.is_none() {
+                    move_state.moved_fields.retain(|(s, e, _)| *s < start || *e > end);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use diagnostics::ErrorKind;
+    use ir::CfgFnKind;
+
+    use crate::{ move_checker::MoveChecker, IcfgParser };
+
+    const SRC: &str =
+        r##"
+struct Foo#0:0 { a#0:1: int32 }
+struct Pair#0:2 { a#0:3: Foo#0:0, b#0:4: Foo#0:0 }
+
+fn consume#0:5(_0: Foo#0:0) -> Void {
+bb0:
+    ret Void ()
+}
+
+fn moved_twice#0:6() -> Void {
+    local l0 f: Foo#0:0
+bb0:
+    store init int32 1i32, l0
+    _0 = load Foo#0:0, l0
+    _1 = call Void fn consume#0:5(_0) : (Foo#0:0)
+    _2 = load Foo#0:0, l0
+    _3 = call Void fn consume#0:5(_2) : (Foo#0:0)
+    ret Void ()
+}
+
+fn moved_on_some_path#0:7(_0: Bool, _1: Foo#0:0) -> Void {
+    local l0 f: Foo#0:0
+bb0:
+    store init Foo#0:0 _1, l0
+    br _0, bb1, bb2
+bb1:
+    _2 = load Foo#0:0, l0
+    _3 = call Void fn consume#0:5(_2) : (Foo#0:0)
+    br bb2
+bb2:
+    _4 = load int32, l0
+    ret Void ()
+}
+
+fn partially_moved#0:8(_0: Pair#0:2) -> Void {
+    local l0 pr: Pair#0:2
+bb0:
+    store init Pair#0:2 _0, l0
+    _1 = load Foo#0:0, l0
+    _2 = call Void fn consume#0:5(_1) : (Foo#0:0)
+    _3 = byte_access l0, 4
+    _4 = load Foo#0:0, _3
+    _5 = call Void fn consume#0:5(_4) : (Foo#0:0)
+    _6 = load Pair#0:2, l0
+    ret Void ()
+}
+
+fn reassigned#0:9(_0: Foo#0:0) -> Void {
+    local l0 f: Foo#0:0
+bb0:
+    store init Foo#0:0 _0, l0
+    _1 = load Foo#0:0, l0
+    _2 = call Void fn consume#0:5(_1) : (Foo#0:0)
+    store assign Foo#0:0 _0, l0
+    _3 = load Foo#0:0, l0
+    _4 = call Void fn consume#0:5(_3) : (Foo#0:0)
+    ret Void ()
+}
+"##;
+
+    fn check_moves(fn_name: &str) -> Vec<ErrorKind> {
+        let icfg = IcfgParser::new(SRC).parse_icfg().unwrap();
+        let cfg = icfg.cfgs
+            .iter()
+            .find(|cfg| {
+                matches!(cfg.cfg_fn_kind, CfgFnKind::Fn(def_id) if def_id.symbol.get() == fn_name)
+            })
+            .unwrap();
+
+        let mut move_checker = MoveChecker::new(cfg, &icfg.resolved_information);
+        move_checker.check();
+        move_checker.errors
+            .into_iter()
+            .map(|(error_kind, _)| error_kind)
+            .collect()
+    }
+
+    #[test]
+    fn use_after_move() {
+        let errors = check_moves("moved_twice");
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(matches!(errors[0], ErrorKind::UseAfterMove { .. }));
+
+        // Moving on one branch is enough, even when only a field is used afterwards
+        let errors = check_moves("moved_on_some_path");
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(matches!(errors[0], ErrorKind::UseAfterMove { .. }));
+    }
+
+    #[test]
+    fn use_of_partially_moved_value() {
+        // Both fields can be moved on their own, but the whole value can't be used afterwards
+        let errors = check_moves("partially_moved");
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(matches!(errors[0], ErrorKind::UseOfPartiallyMovedValue { .. }));
+    }
+
+    #[test]
+    fn reassigned_after_move() {
+        assert!(check_moves("reassigned").is_empty());
+    }
+}
@@ -0,0 +1,483 @@
+/*
+
+Null checking
+
+Pointers can be `null` (e.g. `Vec { items: null }`), and some C functions (e.g. `malloc`) return
+`null` when they fail. Indexing or accessing a field through such a pointer is reported as a
+warning
+
+This is a forward dataflow analysis (see `dataflow.rs`) over each cfg. It tracks the nullness of
+pointers stored in variables (at the byte offset of the field holding them), in temps and in the
+memory a pointer variable points to (e.g. `self.items`). A pointer which isn't tracked is assumed
+to not be null
+
+A branch on `ptr != null` (or `ptr == null`) refines the nullness of `ptr` in its targets. A
+pointer which has been dereferenced can't be null afterwards, so each pointer is reported once
+
+*/
+
+use diagnostics::{ Diagnostic, WarningKind };
+use fxhash::FxHashMap;
+use ir::{ GetTyAttr, LocalMemId, ResolvedInformation, ResultMemId, TempId, Ty };
+use op::{ BinaryOp, ComparisonOp };
+use span::Span;
+
+use crate::{
+    move_checker::PlaceOrigins,
+    BasicBlockId,
+    Cfg,
+    Const,
+    DataflowAnalysis,
+    Icfg,
+    Node,
+    NodeKind,
+    NodeLocation,
+    Operand,
+    PlaceKind,
+};
+
+/// C functions which return `null` when they fail
+const MAY_NULL_C_FNS: [&str; 6] = [
+    "malloc",
+    "calloc",
+    "realloc",
+    "aligned_alloc",
+    "strdup",
+    "getenv",
+];
+
+impl Icfg<'_> {
+    /// Reports indexing and field accesses through pointers which may be `null`
+    pub fn check_null_derefs(&self) {
+        let mut diagnostics = Vec::new();
+
+        for cfg in self.cfgs.iter() {
+            let Some(mod_id) = cfg.get_mod_id() else {
+                continue;
+            };
+
+            let mut null_checker = NullChecker::new(cfg, &self.resolved_information);
+            null_checker.check();
+
+            diagnostics.extend(
+                null_checker.warnings
+                    .into_iter()
+                    .map(|(warning_kind, span)| Diagnostic::new_warning(warning_kind, span, mod_id))
+            );
+        }
+
+        diagnostics::report_diagnostics(diagnostics);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Nullness {
+    Null,
+    MaybeNull,
+}
+
+/// What the byte offset of a tracked pointer is relative to
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+enum Base {
+    LocalMemId(LocalMemId),
+    ResultMemId(ResultMemId),
+    /// The value of a temp (e.g. a loaded pointer or struct)
+    TempId(TempId),
+    /// The memory the pointer stored in a variable points to (e.g. `*self`)
+    Pointee(LocalMemId),
+}
+
+type Location = (Base, usize);
+
+/// Pointers which are null or may be null. Every other pointer is assumed to not be null
+type NullState = FxHashMap<Location, Nullness>;
+
+/// How a temp is defined, for the temps the analysis follows
+#[derive(Debug, Clone, Copy)]
+enum TempDef {
+    ByteAccess {
+        access_place: PlaceKind,
+        byte_offset: usize,
+    },
+    Load {
+        load_place: PlaceKind,
+        load_ty: Ty,
+    },
+    /// `ptr == null` or `ptr != null`
+    NullTest {
+        ptr: TempId,
+        is_ne: bool,
+    },
+}
+
+struct NullChecker<'a, 'b> {
+    cfg: &'b Cfg<'a>,
+    resolved_information: &'b ResolvedInformation<'a>,
+    place_origins: PlaceOrigins,
+    temp_defs: FxHashMap<TempId, TempDef>,
+    warnings: Vec<(WarningKind, Span)>,
+}
+
+impl<'a, 'b> NullChecker<'a, 'b> {
+    fn new(cfg: &'b Cfg<'a>, resolved_information: &'b ResolvedInformation<'a>) -> Self {
+        let mut temp_defs = FxHashMap::default();
+
+        for node in cfg.basic_blocks.iter().flat_map(|basic_block| basic_block.nodes.iter()) {
+            match node.kind {
+                NodeKind::ByteAccessNode(byte_access_node) => {
+                    let PlaceKind::TempId(temp_id) = byte_access_node.result_place else {
+                        continue;
+                    };
+                    temp_defs.insert(temp_id, TempDef::ByteAccess {
+                        access_place: byte_access_node.access_place,
+                        byte_offset: byte_access_node.byte_offset,
+                    });
+                }
+                NodeKind::LoadNode(load_node) => {
+                    temp_defs.insert(load_node.result_place, TempDef::Load {
+                        load_place: load_node.load_place,
+                        load_ty: load_node.load_ty,
+                    });
+                }
+                NodeKind::BinaryNode(binary_node) => {
+                    let is_ne = match binary_node.op {
+                        BinaryOp::ComparisonOp(ComparisonOp::Eq) => false,
+                        BinaryOp::ComparisonOp(ComparisonOp::Ne) => true,
+                        _ => continue,
+                    };
+                    let ptr = match (binary_node.lhs, binary_node.rhs) {
+                        (
+                            Operand::PlaceKind(PlaceKind::TempId(ptr)),
+                            Operand::Const(Const::Null),
+                        ) => ptr,
+                        (
+                            Operand::Const(Const::Null),
+                            Operand::PlaceKind(PlaceKind::TempId(ptr)),
+                        ) => ptr,
+                        _ => continue,
+                    };
+                    temp_defs.insert(binary_node.result_place, TempDef::NullTest { ptr, is_ne });
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            cfg,
+            resolved_information,
+            place_origins: PlaceOrigins::new(cfg),
+            temp_defs,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn check(&mut self) {
+        let cfg = self.cfg;
+        let results = self.solve(cfg);
+
+        results.visit_with(cfg, self, |null_checker, node, _, state| {
+            null_checker.check_node(node, state);
+        });
+    }
+
+    /// Reports dereferences of pointers which may be null, given the state before the node
+    fn check_node(&mut self, node: &Node, state: &NullState) {
+        let place = match node.kind {
+            NodeKind::IndexNode(index_node) => index_node.array_place,
+            NodeKind::ByteAccessNode(byte_access_node) => byte_access_node.access_place,
+            _ => {
+                return;
+            }
+        };
+        let PlaceKind::TempId(temp_id) = place else {
+            return;
+        };
+        // Accessing a field of a loaded struct isn't a dereference
+        if let Some(TempDef::Load { load_ty, .. }) = self.temp_defs.get(&temp_id) {
+            if !load_ty.is_ptr() {
+                return;
+            }
+        }
+
+        let warning_kind = match state.get(&(Base::TempId(temp_id), 0)) {
+            Some(Nullness::Null) => WarningKind::NullDereference,
+            Some(Nullness::MaybeNull) => WarningKind::PossibleNullDereference,
+            None => {
+                return;
+            }
+        };
+        self.warnings.push((warning_kind, node.span.unwrap_or(Span::dummy())));
+    }
+
+    /// The memory location of a place, if it's tracked
+    fn get_location(&self, place: PlaceKind) -> Option<Location> {
+        if let Some(origin) = self.place_origins.get_origin(&place) {
+            let offset = origin.field_offset.unwrap_or(0);
+            return Some((Base::LocalMemId(origin.local_mem_id), offset));
+        }
+
+        match place {
+            PlaceKind::LocalMemId(local_mem_id) => Some((Base::LocalMemId(local_mem_id), 0)),
+            PlaceKind::ResultMemId(result_mem_id) => Some((Base::ResultMemId(result_mem_id), 0)),
+            PlaceKind::TempId(temp_id) =>
+                match *self.temp_defs.get(&temp_id)? {
+                    TempDef::ByteAccess { access_place, byte_offset } => {
+                        let (base, offset) = self.get_location(access_place)?;
+                        Some((base, offset + byte_offset))
+                    }
+                    TempDef::Load {
+                        load_place: PlaceKind::LocalMemId(local_mem_id),
+                        load_ty: Ty::Ptr(..) | Ty::ManyPtr(..),
+                    } => Some((Base::Pointee(local_mem_id), 0)),
+                    _ => None,
+                }
+        }
+    }
+
+    fn size_of(&self, ty: Ty) -> usize {
+        ty.get_ty_attr(self.resolved_information).size_bytes
+    }
+
+    /// Forgets the pointers stored in a byte range of a location
+    fn clear(&self, (base, offset): Location, size: usize, state: &mut NullState) {
+        state.retain(|(b, o), _| *b != base || *o < offset || offset + size <= *o);
+
+        // The pointer of the variable itself is overwritten
+        if let (Base::LocalMemId(local_mem_id), 0) = (base, offset) {
+            state.retain(|(b, _), _| *b != Base::Pointee(local_mem_id));
+        }
+    }
+
+    /// Copies the pointers stored in a byte range from one location to another
+    fn copy(
+        &self,
+        (from_base, from_offset): Location,
+        to: Location,
+        size: usize,
+        state: &mut NullState
+    ) {
+        let copied = state
+            .iter()
+            .filter(|((b, o), _)| *b == from_base && from_offset <= *o && *o < from_offset + size)
+            .map(|((_, o), nullness)| ((to.0, to.1 + o - from_offset), *nullness))
+            .collect::<Vec<_>>();
+        state.extend(copied);
+    }
+
+    /// Forgets everything behind pointers stored in variables, e.g. after a call
+    fn clear_pointees(&self, state: &mut NullState) {
+        state.retain(|(base, _), _| !matches!(base, Base::Pointee(_)));
+    }
+
+    /// Sets the nullness of a temp holding a pointer and of the location it was loaded from
+    fn set_nullness(&self, temp_id: TempId, nullness: Option<Nullness>, state: &mut NullState) {
+        let mut locations = vec![(Base::TempId(temp_id), 0)];
+        if let Some(TempDef::Load { load_place, .. }) = self.temp_defs.get(&temp_id) {
+            locations.extend(self.get_location(*load_place));
+        }
+
+        for location in locations {
+            match nullness {
+                Some(nullness) => {
+                    state.insert(location, nullness);
+                }
+                None => {
+                    state.remove(&location);
+                }
+            }
+        }
+    }
+
+    fn is_may_null_c_fn(&self, callee: Operand) -> bool {
+        let Operand::Const(Const::FnPtr(def_id)) = callee else {
+            return false;
+        };
+        self.resolved_information.def_id_to_name_binding.contains_key(&def_id) &&
+            self.resolved_information.is_clib_fn(&def_id) &&
+            MAY_NULL_C_FNS.contains(&def_id.symbol.get())
+    }
+}
+
+impl DataflowAnalysis for NullChecker<'_, '_> {
+    type Domain = NullState;
+
+    fn boundary_state(&self, _cfg: &Cfg) -> Self::Domain {
+        NullState::default()
+    }
+
+    /// A pointer which is only null on some paths may be null
+    fn join(&self, state: &mut Self::Domain, other: &Self::Domain) -> bool {
+        let mut changed = false;
+
+        for (location, nullness) in state.iter_mut() {
+            if other.get(location) != Some(nullness) && *nullness != Nullness::MaybeNull {
+                *nullness = Nullness::MaybeNull;
+                changed = true;
+            }
+        }
+        for location in other.keys() {
+            if !state.contains_key(location) {
+                state.insert(*location, Nullness::MaybeNull);
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    fn transfer_node(
+        &mut self,
+        node: &Node,
+        _location: NodeLocation,
+        state: &mut Self::Domain,
+        _cfg: &Cfg
+    ) {
+        match node.kind {
+            NodeKind::StoreNode(store_node) => {
+                let Some(location) = self.get_location(store_node.setter) else {
+                    // Stores through an untracked pointer may write to any pointee
+                    self.clear_pointees(state);
+                    return;
+                };
+
+                let size = self.size_of(store_node.op_ty);
+                self.clear(location, size, state);
+                match store_node.value {
+                    Operand::Const(Const::Null) => {
+                        state.insert(location, Nullness::Null);
+                    }
+                    Operand::PlaceKind(PlaceKind::TempId(temp_id)) => {
+                        self.copy((Base::TempId(temp_id), 0), location, size, state);
+                    }
+                    _ => {}
+                }
+            }
+            NodeKind::LoadNode(load_node) => {
+                let temp_location = (Base::TempId(load_node.result_place), 0);
+                let size = self.size_of(load_node.load_ty);
+                // Temps are defined again in each iteration of a loop
+                self.clear(temp_location, size, state);
+                if let Some(location) = self.get_location(load_node.load_place) {
+                    self.copy(location, temp_location, size, state);
+                }
+            }
+            NodeKind::CallNode(call_node) => {
+                let temp_location = (Base::TempId(call_node.result_place), 0);
+                self.clear(temp_location, self.size_of(call_node.ret_ty), state);
+                if call_node.ret_ty.is_ptr() && self.is_may_null_c_fn(call_node.callee) {
+                    state.insert(temp_location, Nullness::MaybeNull);
+                }
+
+                // The callee may change the variables passed by reference, and anything behind
+                // a pointer
+                for arg in call_node.args.iter() {
+                    let Operand::PlaceKind(place) = arg else {
+                        continue;
+                    };
+                    if let Some((base, _)) = self.get_location(*place) {
+                        state.retain(|(b, _), _| *b != base);
+                    }
+                }
+                self.clear_pointees(state);
+            }
+            // A pointer which has been dereferenced can't be null afterwards
+            NodeKind::IndexNode(index_node) => {
+                if let PlaceKind::TempId(temp_id) = index_node.array_place {
+                    self.set_nullness(temp_id, None, state);
+                }
+            }
+            NodeKind::ByteAccessNode(byte_access_node) => {
+                if let PlaceKind::TempId(temp_id) = byte_access_node.access_place {
+                    self.set_nullness(temp_id, None, state);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn transfer_edge(
+        &mut self,
+        from: BasicBlockId,
+        to: BasicBlockId,
+        state: &mut Self::Domain,
+        cfg: &Cfg
+    ) {
+        let last_node = cfg.basic_blocks[from.0 as usize].nodes.last();
+        let Some(NodeKind::BranchCondNode(branch_cond_node)) = last_node.map(|n| n.kind) else {
+            return;
+        };
+        if branch_cond_node.true_branch == branch_cond_node.false_branch {
+            return;
+        }
+        let Operand::PlaceKind(PlaceKind::TempId(condition)) = branch_cond_node.condition else {
+            return;
+        };
+        let Some(TempDef::NullTest { ptr, is_ne }) = self.temp_defs.get(&condition).copied() else {
+            return;
+        };
+
+        let non_null_branch = if is_ne {
+            branch_cond_node.true_branch
+        } else {
+            branch_cond_node.false_branch
+        };
+        if to == non_null_branch {
+            self.set_nullness(ptr, None, state);
+        } else {
+            self.set_nullness(ptr, Some(Nullness::Null), state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use diagnostics::WarningKind;
+
+    use crate::{ IcfgParser, null_checker::NullChecker };
+
+    #[test]
+    fn check_null_derefs() {
+        let src =
+            r##"
+extern fn malloc#0:0(int64) -> [*]int32
+
+fn first#0:1() -> int32 {
+    local l0 p: [*]int32
+    local l1 q: [*]int32
+bb0:
+    _0 = call [*]int32 fn malloc#0:0(8i64) : (int64)
+    store init [*]int32 _0, l0
+    _1 = load [*]int32, l0
+    _2 = index int32, _1, 0i64
+    _3 = load [*]int32, l0
+    _4 = index int32, _3, 1i64
+    store init [*]int32 null, l1
+    _5 = call [*]int32 fn malloc#0:0(8i64) : (int64)
+    _6 = ne [*]int32 _5, null
+    br _6, bb1, bb2
+bb1:
+    _7 = index int32, _5, 0i64
+    _8 = load [*]int32, l1
+    _9 = index int32, _8, 0i64
+    br bb2
+bb2:
+    _10 = index int32, _5, 1i64
+    ret int32 0i32
+}
+"##;
+        let icfg = IcfgParser::new(src).parse_icfg().unwrap();
+        let mut null_checker = NullChecker::new(&icfg.cfgs[0], &icfg.resolved_information);
+        null_checker.check();
+
+        // `_2` may be null after `malloc`, `_9` is `null`, and `_10` may be null on the path
+        // skipping `bb1`. `_4` and `_7` are known to not be null
+        let warnings = null_checker.warnings
+            .iter()
+            .map(|(warning_kind, _)| *warning_kind)
+            .collect::<Vec<_>>();
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+        assert!(matches!(warnings[0], WarningKind::PossibleNullDereference));
+        assert!(matches!(warnings[1], WarningKind::NullDereference));
+        assert!(matches!(warnings[2], WarningKind::PossibleNullDereference));
+    }
+}
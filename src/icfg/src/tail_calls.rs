@@ -0,0 +1,242 @@
+/*
+
+Tail call elimination
+
+A call of a function to itself, which is immediately followed by returning the result of the call,
+is replaced by a jump back to the start of the function. Instead of passing the arguments to a new
+call, they are stored in the variables of the arguments
+
+The entry block of a function stores each argument in a variable before anything else, so it's
+split after these stores: the rest of the entry block is moved to a new loop header, which is where
+the tail calls jump to. This runs before mem2reg, which turns the variables of the arguments into
+phis in the loop header
+
+Only functions where every argument is an integer, float or bool are rewritten. An argument
+pointing to a variable of the caller would otherwise see the variable change when it's reused by
+the next iteration
+
+*/
+
+use ir::{ CfgFnKind, LocalMemId, PrimTy, Ty, TyCtx };
+
+use crate::{
+    BasicBlock,
+    BasicBlockId,
+    BranchNode,
+    Cfg,
+    Const,
+    Icfg,
+    Node,
+    NodeKind,
+    Operand,
+    PlaceKind,
+    StoreKind,
+    StoreNode,
+};
+
+impl Icfg<'_> {
+    /// Rewrites self-recursive calls in tail position into jumps back to the start of the function
+    pub fn eliminate_tail_calls(&mut self) {
+        for cfg in self.cfgs.iter_mut() {
+            eliminate_tail_calls(cfg);
+        }
+    }
+}
+
+fn eliminate_tail_calls(cfg: &mut Cfg) {
+    let CfgFnKind::Fn(def_id) = cfg.cfg_fn_kind else {
+        return;
+    };
+    if !cfg.args.iter().all(|(_, ty)| is_scalar(*ty)) {
+        return;
+    }
+    let Some(arg_local_mem_ids) = get_arg_local_mem_ids(cfg) else {
+        return;
+    };
+
+    let tail_call_bb_ids = cfg.basic_blocks
+        .iter()
+        .filter(|basic_block| {
+            let Some(call_node) = basic_block.get_tail_call() else {
+                return false;
+            };
+            matches!(call_node.callee, Operand::Const(Const::FnPtr(callee)) if callee == def_id) &&
+                call_node.args.iter().all(|arg| is_value(*arg))
+        })
+        .map(|basic_block| basic_block.basic_block_id)
+        .collect::<Vec<_>>();
+    if tail_call_bb_ids.is_empty() {
+        return;
+    }
+
+    // The entry block is split after the arguments are stored
+    let header_id = BasicBlockId(cfg.basic_blocks.len() as u32);
+    let mut header = BasicBlock::new(header_id);
+    let entry = &mut cfg.basic_blocks[0];
+    header.nodes.extend(entry.nodes.drain(arg_local_mem_ids.len()..));
+    entry.push_node(Node::new(NodeKind::BranchNode(BranchNode::new(header_id))));
+
+    // Phis in the successors now come from the loop header
+    for successor in header.get_successors() {
+        for node in cfg.basic_blocks[successor.0 as usize].nodes.iter_mut() {
+            if let NodeKind::PhiNode(phi_node) = &mut node.kind {
+                let mut incoming = phi_node.incoming.to_vec();
+                for (_, predecessor) in incoming.iter_mut() {
+                    if *predecessor == BasicBlockId(0) {
+                        *predecessor = header_id;
+                    }
+                }
+                phi_node.incoming = TyCtx::intern_many_types(incoming);
+            }
+        }
+    }
+
+    // The arguments are already evaluated, so storing them one at a time can't change the others
+    for basic_block_id in tail_call_bb_ids {
+        let basic_block = &mut cfg.basic_blocks[basic_block_id.0 as usize];
+        let call_node = basic_block.get_tail_call().unwrap();
+        let call_span = basic_block.nodes[basic_block.nodes.len() - 2].span;
+        basic_block.nodes.truncate(basic_block.nodes.len() - 2);
+
+        for (i, arg) in call_node.args.iter().enumerate() {
+            let store_node = StoreNode::new(
+                PlaceKind::LocalMemId(arg_local_mem_ids[i]),
+                cfg.args[i].1,
+                *arg,
+                StoreKind::Assign
+            );
+            basic_block.push_node(Node { kind: NodeKind::StoreNode(store_node), span: call_span });
+        }
+        let branch_node = BranchNode::new(header_id);
+        basic_block.push_node(Node { kind: NodeKind::BranchNode(branch_node), span: call_span });
+    }
+
+    cfg.basic_blocks.push(header);
+}
+
+/// The variables the arguments are stored in, if the entry block starts by storing each argument
+fn get_arg_local_mem_ids(cfg: &Cfg) -> Option<Vec<LocalMemId>> {
+    let entry = cfg.basic_blocks.first()?;
+    if entry.nodes.len() < cfg.args.len() {
+        return None;
+    }
+
+    cfg.args
+        .iter()
+        .zip(entry.nodes.iter())
+        .map(|((temp_id, _), node)| {
+            match node.kind {
+                NodeKind::StoreNode(
+                    StoreNode {
+                        setter: PlaceKind::LocalMemId(local_mem_id),
+                        value: Operand::PlaceKind(PlaceKind::TempId(value)),
+                        store_kind: StoreKind::Init,
+                        ..
+                    },
+                ) if value == *temp_id => Some(local_mem_id),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Types which are passed by value
+fn is_scalar(ty: Ty) -> bool {
+    matches!(
+        ty,
+        Ty::PrimTy(PrimTy::Int(_) | PrimTy::Uint(_) | PrimTy::Float(_) | PrimTy::Bool)
+    )
+}
+
+/// Operands which don't refer to a variable
+fn is_value(operand: Operand) -> bool {
+    matches!(operand, Operand::Const(_) | Operand::PlaceKind(PlaceKind::TempId(_)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ IcfgParser, IcfgPrettifier };
+
+    #[test]
+    fn eliminate_tail_calls() {
+        let src =
+            r##"
+extern fn exit#0:0(int32) -> Void
+
+fn count#0:1(_0: int32, _1: int32) -> int32 {
+    local l0 n: int32
+    local l1 acc: int32
+bb0:
+    store init int32 _0, l0
+    store init int32 _1, l1
+    _2 = load int32, l0
+    _3 = eq int32 _2, 0i32
+    br _3, bb1, bb2
+bb1:
+    _4 = load int32, l1
+    ret int32 _4
+bb2:
+    _5 = load int32, l0
+    _6 = sub int32 _5, 1i32
+    _7 = load int32, l1
+    _8 = add int32 _7, 2i32
+    _9 = call int32 fn count#0:1(_6, _8) : (int32, int32)
+    ret int32 _9
+}
+
+fn sum#0:2(_0: int32) -> int32 {
+    local l0 n: int32
+bb0:
+    store init int32 _0, l0
+    _1 = load int32, l0
+    _2 = sub int32 _1, 1i32
+    _3 = call int32 fn sum#0:2(_2) : (int32)
+    _4 = add int32 _3, _1
+    ret int32 _4
+}
+
+main fn main#0:3() -> Void {
+bb0:
+    _0 = call int32 fn count#0:1(100000i32, 0i32) : (int32, int32)
+    _1 = call Void fn exit#0:0(_0) : (int32)
+    unreachable
+}
+"##;
+        let mut icfg = IcfgParser::new(src).parse_icfg().unwrap();
+        icfg.eliminate_tail_calls();
+
+        let expected =
+            r##"
+fn count#0:1(_0: int32, _1: int32) -> int32 {
+    local l0 n: int32
+    local l1 acc: int32
+bb0:
+    store init int32 _0, l0
+    store init int32 _1, l1
+    br bb3
+bb1:
+    _4 = load int32, l1
+    ret int32 _4
+bb2:
+    _5 = load int32, l0
+    _6 = sub int32 _5, 1i32
+    _7 = load int32, l1
+    _8 = add int32 _7, 2i32
+    store assign int32 _6, l0
+    store assign int32 _8, l1
+    br bb3
+bb3:
+    _2 = load int32, l0
+    _3 = eq int32 _2, 0i32
+    br _3, bb1, bb2
+}
+"##;
+        let output = IcfgPrettifier::new(&icfg).prettify();
+        assert!(output.contains(expected.trim()), "{}", output);
+        // The recursive call of `sum` isn't in tail position
+        assert!(output.contains("_3 = call int32 fn sum#0:2(_2) : (int32)"));
+
+        let mut out = Vec::new();
+        assert_eq!(icfg.interpret(&mut out).unwrap(), 200000);
+    }
+}
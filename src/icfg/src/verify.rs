@@ -0,0 +1,486 @@
+/*
+
+ICFG verifier
+
+Checks that the cfgs are well-formed before they're given to codegen, so a malformed cfg is caught
+where it's made instead of as a panic in codegen or as LLVM IR that clang rejects:
+
+- Every basic block ends in exactly one terminator (a branch or a return)
+- Branches only target basic blocks of the cfg
+- Phis are placed at the start of a basic block, with a value for each predecessor
+- Every temp is defined once, and its definition dominates all of its uses
+- Variables and implicit results referred to exist in the cfg
+- Operands have the type of the node using them (only checked for primitive types, since other
+  types are passed around as pointers)
+
+*/
+
+use std::fmt::Display;
+
+use ir::{ CfgFnKind, PrimTy, TempId, Ty, BOOL_TY };
+
+use crate::{
+    BasicBlock,
+    BasicBlockId,
+    ByteAccessNode,
+    Cfg,
+    CfgGraph,
+    Const,
+    Icfg,
+    NodeKind,
+    Operand,
+    PlaceKind,
+};
+
+#[derive(Debug)]
+pub struct VerifyError {
+    pub cfg_fn_kind: CfgFnKind,
+    pub basic_block_id: BasicBlockId,
+    pub kind: VerifyErrorKind,
+}
+
+#[derive(Debug)]
+pub enum VerifyErrorKind {
+    MissingTerminator,
+    NodeAfterTerminator,
+    InvalidBasicBlockId,
+    UnknownBranchTarget(BasicBlockId),
+    UnknownPlace(PlaceKind),
+    MisplacedPhi(TempId),
+    PhiPredecessorMismatch(TempId),
+    UndefinedTemp(TempId),
+    RedefinedTemp(TempId),
+    UseBeforeDefinition(TempId),
+    TyMismatch {
+        expected: Ty,
+        found: Ty,
+    },
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fn_name = match self.cfg_fn_kind {
+            CfgFnKind::Main(def_id) | CfgFnKind::Fn(def_id) => def_id.display_as_fn(),
+            CfgFnKind::DropGlue(ty) => ty.display_as_drop_fn(),
+        };
+        write!(f, "In `{}` (bb{}): ", fn_name, self.basic_block_id.0)?;
+
+        match self.kind {
+            VerifyErrorKind::MissingTerminator => {
+                write!(f, "Basic block doesn't end in a terminator")
+            }
+            VerifyErrorKind::NodeAfterTerminator => {
+                write!(f, "Basic block has nodes after its terminator")
+            }
+            VerifyErrorKind::InvalidBasicBlockId => {
+                write!(f, "Basic block id doesn't match its position in the cfg")
+            }
+            VerifyErrorKind::UnknownBranchTarget(target) => {
+                write!(f, "Branch to bb{} which doesn't exist", target.0)
+            }
+            VerifyErrorKind::UnknownPlace(place) => {
+                write!(f, "{:?} doesn't exist", place)
+            }
+            VerifyErrorKind::MisplacedPhi(temp_id) => {
+                write!(f, "Phi {} is not at the start of the basic block", temp_id)
+            }
+            VerifyErrorKind::PhiPredecessorMismatch(temp_id) => {
+                write!(f, "Phi {} doesn't have a value for each predecessor", temp_id)
+            }
+            VerifyErrorKind::UndefinedTemp(temp_id) => {
+                write!(f, "{} is used but never defined", temp_id)
+            }
+            VerifyErrorKind::RedefinedTemp(temp_id) => {
+                write!(f, "{} is defined more than once", temp_id)
+            }
+            VerifyErrorKind::UseBeforeDefinition(temp_id) => {
+                write!(f, "{} is used before it's defined", temp_id)
+            }
+            VerifyErrorKind::TyMismatch { expected, found } => {
+                write!(f, "Expected operand of type `{}`, found `{}`", expected, found)
+            }
+        }
+    }
+}
+
+impl Icfg<'_> {
+    /// Checks the structure and types of all cfgs
+    pub fn verify(&self) -> Result<(), Vec<VerifyError>> {
+        let errors = self.cfgs
+            .iter()
+            .flat_map(verify_cfg)
+            .collect::<Vec<_>>();
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
+fn verify_cfg(cfg: &Cfg) -> Vec<VerifyError> {
+    // The structure is checked first, as the rest relies on the successors of each block
+    let mut errors = Vec::new();
+    for (i, basic_block) in cfg.basic_blocks.iter().enumerate() {
+        let mut report = |kind| {
+            errors.push(VerifyError {
+                cfg_fn_kind: cfg.cfg_fn_kind,
+                basic_block_id: BasicBlockId(i as u32),
+                kind,
+            });
+        };
+
+        if basic_block.basic_block_id.0 as usize != i {
+            report(VerifyErrorKind::InvalidBasicBlockId);
+        }
+
+        let terminator_index = basic_block.nodes.iter().position(|node| node.kind.is_terminator());
+        match terminator_index {
+            None => report(VerifyErrorKind::MissingTerminator),
+            Some(i) if i != basic_block.nodes.len() - 1 => {
+                report(VerifyErrorKind::NodeAfterTerminator);
+            }
+            Some(_) => {}
+        }
+
+        for successor in basic_block.get_successors() {
+            if successor.0 as usize >= cfg.basic_blocks.len() {
+                report(VerifyErrorKind::UnknownBranchTarget(successor));
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return errors;
+    }
+
+    CfgVerifier::new(cfg).verify()
+}
+
+/// Where a temp is defined
+#[derive(Clone, Copy)]
+enum TempDef {
+    Arg(Ty),
+    Node {
+        basic_block_id: BasicBlockId,
+        node_index: usize,
+        /// Pointers (e.g. the result of an `IndexNode`) have no type in the cfg
+        ty: Option<Ty>,
+    },
+}
+
+struct CfgVerifier<'b> {
+    cfg: &'b Cfg<'b>,
+    graph: CfgGraph,
+    idoms: Vec<Option<BasicBlockId>>,
+    temp_defs: Vec<Option<TempDef>>,
+    errors: Vec<VerifyError>,
+}
+
+impl<'b> CfgVerifier<'b> {
+    fn new(cfg: &'b Cfg<'b>) -> Self {
+        let graph = CfgGraph::new(cfg);
+        let idoms = graph.immediate_dominators();
+
+        Self {
+            cfg,
+            graph,
+            idoms,
+            temp_defs: vec![None; cfg.get_next_temp_id().0 as usize],
+            errors: Vec::new(),
+        }
+    }
+
+    fn verify(mut self) -> Vec<VerifyError> {
+        for (temp_id, ty) in self.cfg.args.iter() {
+            self.define(*temp_id, TempDef::Arg(*ty), BasicBlockId(0));
+        }
+        for basic_block in self.cfg.basic_blocks.iter() {
+            for (node_index, node) in basic_block.nodes.iter().enumerate() {
+                if let Some((temp_id, ty)) = get_temp_def(&node.kind) {
+                    let basic_block_id = basic_block.basic_block_id;
+                    let temp_def = TempDef::Node { basic_block_id, node_index, ty };
+                    self.define(temp_id, temp_def, basic_block_id);
+                }
+            }
+        }
+
+        for basic_block in self.cfg.basic_blocks.iter() {
+            self.verify_nodes(basic_block);
+        }
+
+        self.errors
+    }
+
+    fn report(&mut self, basic_block_id: BasicBlockId, kind: VerifyErrorKind) {
+        self.errors.push(VerifyError {
+            cfg_fn_kind: self.cfg.cfg_fn_kind,
+            basic_block_id,
+            kind,
+        });
+    }
+
+    fn define(&mut self, temp_id: TempId, temp_def: TempDef, basic_block_id: BasicBlockId) {
+        let def = &mut self.temp_defs[temp_id.0 as usize];
+        if def.is_some() {
+            self.report(basic_block_id, VerifyErrorKind::RedefinedTemp(temp_id));
+        } else {
+            *def = Some(temp_def);
+        }
+    }
+
+    fn verify_nodes(&mut self, basic_block: &BasicBlock) {
+        let bb_id = basic_block.basic_block_id;
+        let mut is_start_of_block = true;
+
+        for (i, node) in basic_block.nodes.iter().enumerate() {
+            match &node.kind {
+                NodeKind::PhiNode(phi_node) => {
+                    if !is_start_of_block {
+                        self.report(bb_id, VerifyErrorKind::MisplacedPhi(phi_node.result_place));
+                    }
+
+                    let mut predecessors = self.graph.predecessors(bb_id).to_vec();
+                    let mut incoming_bb_ids = phi_node.incoming
+                        .iter()
+                        .map(|(_, incoming_bb_id)| *incoming_bb_id)
+                        .collect::<Vec<_>>();
+                    predecessors.sort();
+                    incoming_bb_ids.sort();
+                    if predecessors != incoming_bb_ids {
+                        self.report(
+                            bb_id,
+                            VerifyErrorKind::PhiPredecessorMismatch(phi_node.result_place)
+                        );
+                    }
+
+                    // The value only has to be available at the end of the predecessor
+                    for (operand, incoming_bb_id) in phi_node.incoming.iter() {
+                        let incoming_bb = &self.cfg.basic_blocks[incoming_bb_id.0 as usize];
+                        self.verify_operand(
+                            operand,
+                            Some(phi_node.ty),
+                            *incoming_bb_id,
+                            incoming_bb.nodes.len()
+                        );
+                    }
+                    continue;
+                }
+                NodeKind::BinaryNode(binary_node) => {
+                    self.verify_operand(&binary_node.lhs, Some(binary_node.op_ty), bb_id, i);
+                    self.verify_operand(&binary_node.rhs, Some(binary_node.op_ty), bb_id, i);
+                }
+                NodeKind::BranchCondNode(branch_cond_node) => {
+                    self.verify_operand(&branch_cond_node.condition, Some(BOOL_TY), bb_id, i);
+                }
+                NodeKind::SwitchNode(switch_node) => {
+                    self.verify_operand(&switch_node.value, Some(switch_node.ty), bb_id, i);
+                }
+                NodeKind::StoreNode(store_node) => {
+                    self.verify_place(&store_node.setter, None, bb_id, i);
+                    self.verify_operand(&store_node.value, Some(store_node.op_ty), bb_id, i);
+                }
+                NodeKind::LoadNode(load_node) => {
+                    self.verify_place(&load_node.load_place, Some(load_node.load_ty), bb_id, i);
+                }
+                NodeKind::IndexNode(index_node) => {
+                    self.verify_place(&index_node.array_place, None, bb_id, i);
+                    self.verify_operand(&index_node.index, None, bb_id, i);
+                }
+                NodeKind::ByteAccessNode(byte_access_node) => {
+                    self.verify_place(&byte_access_node.access_place, None, bb_id, i);
+                }
+                NodeKind::ReturnNode(return_node) => {
+                    self.verify_ty(return_node.ret_ty, self.cfg.ret_ty, bb_id);
+                    if !return_node.ret_ty.is_void() {
+                        let ret_ty = Some(return_node.ret_ty);
+                        self.verify_operand(&return_node.ret_val, ret_ty, bb_id, i);
+                    }
+                }
+                NodeKind::CallNode(call_node) => {
+                    self.verify_operand(&call_node.callee, None, bb_id, i);
+                    for arg in call_node.args.iter() {
+                        self.verify_operand(arg, None, bb_id, i);
+                    }
+                }
+                NodeKind::TyCastNode(ty_cast_node) => {
+                    let from_ty = Some(ty_cast_node.from_ty);
+                    self.verify_operand(&ty_cast_node.operand, from_ty, bb_id, i);
+                }
+                NodeKind::DropNode(drop_node) => {
+                    self.verify_place(&drop_node.place, None, bb_id, i);
+                    if let Some(drop_flag) = drop_node.drop_flag {
+                        self.verify_temp_use(drop_flag, bb_id, i);
+                    }
+                }
+                NodeKind::BranchNode(_) | NodeKind::UnreachableNode(_) => {}
+            }
+
+            is_start_of_block = false;
+        }
+    }
+
+    /// Verifies an operand used by the node at `node_index`, and that it has the expected type
+    fn verify_operand(
+        &mut self,
+        operand: &Operand,
+        expected_ty: Option<Ty>,
+        basic_block_id: BasicBlockId,
+        node_index: usize
+    ) {
+        let ty = match operand {
+            Operand::PlaceKind(PlaceKind::TempId(temp_id)) => {
+                self.verify_temp_use(*temp_id, basic_block_id, node_index);
+                if let Some(expected_ty) = expected_ty {
+                    self.verify_temp_ty(*temp_id, expected_ty, basic_block_id);
+                }
+                return;
+            }
+            // Variables used as operands are pointers
+            Operand::PlaceKind(place) => {
+                return self.verify_place(place, None, basic_block_id, node_index);
+            }
+            Operand::Const(Const::Int(_, int_ty)) => Ty::PrimTy(PrimTy::Int(*int_ty)),
+            Operand::Const(Const::Bool(_)) => BOOL_TY,
+            Operand::Const(_) => {
+                return;
+            }
+        };
+
+        if let Some(expected_ty) = expected_ty {
+            self.verify_ty(expected_ty, ty, basic_block_id);
+        }
+    }
+
+    /// Verifies a place used by the node at `node_index` as a pointer. If the place is loaded from,
+    /// a variable must have the loaded type
+    fn verify_place(
+        &mut self,
+        place: &PlaceKind,
+        loaded_ty: Option<Ty>,
+        basic_block_id: BasicBlockId,
+        node_index: usize
+    ) {
+        let ty = match place {
+            PlaceKind::LocalMemId(local_mem_id) => {
+                self.cfg.local_mems.get(local_mem_id.0 as usize).map(|local_mem| local_mem.ty)
+            }
+            PlaceKind::ResultMemId(result_mem_id) => {
+                self.cfg.result_mems.get(result_mem_id.0 as usize).map(|result_mem| result_mem.ty)
+            }
+            PlaceKind::TempId(temp_id) => {
+                self.verify_temp_use(*temp_id, basic_block_id, node_index);
+                return;
+            }
+        };
+
+        match ty {
+            Some(ty) => {
+                if let Some(loaded_ty) = loaded_ty {
+                    self.verify_ty(loaded_ty, ty, basic_block_id);
+                }
+            }
+            None => self.report(basic_block_id, VerifyErrorKind::UnknownPlace(*place)),
+        }
+    }
+
+    /// Verifies that a temp is defined before the node at `node_index`
+    fn verify_temp_use(
+        &mut self,
+        temp_id: TempId,
+        basic_block_id: BasicBlockId,
+        node_index: usize
+    ) {
+        let Some(Some(temp_def)) = self.temp_defs.get(temp_id.0 as usize).copied() else {
+            self.report(basic_block_id, VerifyErrorKind::UndefinedTemp(temp_id));
+            return;
+        };
+
+        let is_defined = match temp_def {
+            TempDef::Arg(_) => true,
+            TempDef::Node { basic_block_id: def_bb_id, node_index: def_node_index, .. } => {
+                if def_bb_id == basic_block_id {
+                    def_node_index < node_index
+                } else {
+                    self.dominates(def_bb_id, basic_block_id)
+                }
+            }
+        };
+
+        if !is_defined {
+            self.report(basic_block_id, VerifyErrorKind::UseBeforeDefinition(temp_id));
+        }
+    }
+
+    fn verify_temp_ty(&mut self, temp_id: TempId, expected_ty: Ty, basic_block_id: BasicBlockId) {
+        let ty = match self.temp_defs.get(temp_id.0 as usize).copied().flatten() {
+            Some(TempDef::Arg(ty)) | Some(TempDef::Node { ty: Some(ty), .. }) => ty,
+            _ => {
+                return;
+            }
+        };
+        self.verify_ty(expected_ty, ty, basic_block_id);
+    }
+
+    fn verify_ty(&mut self, expected: Ty, found: Ty, basic_block_id: BasicBlockId) {
+        if is_primitive(expected) && is_primitive(found) && expected != found {
+            self.report(basic_block_id, VerifyErrorKind::TyMismatch { expected, found });
+        }
+    }
+
+    /// Uses in unreachable basic blocks are never run, so only reachable ones are checked
+    fn dominates(&self, dominator: BasicBlockId, mut basic_block_id: BasicBlockId) -> bool {
+        if self.idoms[basic_block_id.0 as usize].is_none() {
+            return true;
+        }
+
+        loop {
+            if basic_block_id == dominator {
+                return true;
+            }
+            match self.idoms[basic_block_id.0 as usize] {
+                Some(idom) if idom != basic_block_id => {
+                    basic_block_id = idom;
+                }
+                _ => {
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// Types that are passed by value in LLVM
+fn is_primitive(ty: Ty) -> bool {
+    matches!(
+        ty,
+        Ty::PrimTy(PrimTy::Int(_) | PrimTy::Uint(_) | PrimTy::Float(_) | PrimTy::Bool)
+    )
+}
+
+/// The temp a node defines and the type of its value
+fn get_temp_def(node_kind: &NodeKind) -> Option<(TempId, Option<Ty>)> {
+    match node_kind {
+        NodeKind::BinaryNode(binary_node) => {
+            let ty = match binary_node.op {
+                op::BinaryOp::ComparisonOp(_) => BOOL_TY,
+                op::BinaryOp::ArithmeticOp(_) => binary_node.op_ty,
+            };
+            Some((binary_node.result_place, Some(ty)))
+        }
+        NodeKind::LoadNode(load_node) => Some((load_node.result_place, Some(load_node.load_ty))),
+        NodeKind::IndexNode(index_node) => Some((index_node.result_place, None)),
+        NodeKind::ByteAccessNode(
+            ByteAccessNode { result_place: PlaceKind::TempId(temp_id), .. },
+        ) => Some((*temp_id, None)),
+        NodeKind::CallNode(call_node) => Some((call_node.result_place, Some(call_node.ret_ty))),
+        NodeKind::TyCastNode(ty_cast_node) => {
+            Some((ty_cast_node.result_place, Some(ty_cast_node.to_ty)))
+        }
+        NodeKind::PhiNode(phi_node) => Some((phi_node.result_place, Some(phi_node.ty))),
+        | NodeKind::ByteAccessNode(_)
+        | NodeKind::StoreNode(_)
+        | NodeKind::BranchNode(_)
+        | NodeKind::BranchCondNode(_)
+        | NodeKind::SwitchNode(_)
+        | NodeKind::ReturnNode(_)
+        | NodeKind::DropNode(_)
+        | NodeKind::UnreachableNode(_) => None,
+    }
+}
@@ -6,6 +6,7 @@ use ast::{
     AsigneeExpr,
     CallExpr,
     CondKind,
+    DeferExpr,
     Expr,
     FieldExpr,
     FnItem,
@@ -13,6 +14,9 @@ use ast::{
     IfExpr,
     IfFalseBranchExpr,
     IndexExpr,
+    IntrinsicExpr,
+    IntrinsicKind,
+    ItemType,
     LoopExpr,
     NullExpr,
     Pat,
@@ -20,6 +24,7 @@ use ast::{
     ReturnExpr,
     Stmt,
     StringExpr,
+    TryExpr,
     Visitor,
 };
 
@@ -34,6 +39,7 @@ use icfg::{
     CallNode,
     Cfg,
     Const,
+    DropNode,
     Icfg,
     IndexNode,
     LoadNode,
@@ -44,14 +50,17 @@ use icfg::{
     ReturnNode,
     StoreKind,
     StoreNode,
+    SwitchNode,
     TyCastKind,
     TyCastNode,
+    UnreachableNode,
 };
 
 use ir::{
     Adt,
     CfgFnKind,
     DefId,
+    EmumVaraintId,
     GetTyAttr,
     HasSelfArg,
     IntTy,
@@ -67,6 +76,7 @@ use ir::{
     Symbol,
     TempId,
     Ty,
+    TyAttr,
     TyCtx,
     BOOL_TY,
     INT_16_TY,
@@ -82,6 +92,7 @@ use ir::{
 };
 use op::{ BinaryOp, ComparisonOp };
 use resolver::ResolvedFunctions;
+use span::Span;
 use threadpool::ThreadPool;
 use threadpool_scope::scope_with;
 
@@ -149,6 +160,54 @@ impl<'icfg, 'th> IcfgBuilder<'icfg, 'th> where 'icfg: 'th {
             .get(&node_id)
             .expect("Expected DefId from NodeId")
     }
+
+    /// Looks up a variant of an enum by its name e.g. `Ok` or `Err`
+    pub(crate) fn get_enum_variant(
+        &self,
+        enum_ty: Ty,
+        variant_name: &str
+    ) -> (EmumVaraintId, &'icfg [Ty]) {
+        let enum_def_id = match enum_ty {
+            Ty::Adt(def_id) => def_id,
+            ty => panic!("Expected enum, got {}", ty),
+        };
+
+        let variants = match self.resolved_information.get_name_binding_from_def_id(&enum_def_id).kind {
+            NameBindingKind::Adt(Adt::Enum(variants)) => variants,
+            t => panic!("Expected enum, got {:?}", t),
+        };
+
+        let variant_def_id = variants
+            .iter()
+            .find(|x| x.symbol.get() == variant_name)
+            .expect("Expected enum variant");
+
+        match self.resolved_information.get_name_binding_from_def_id(variant_def_id).kind {
+            NameBindingKind::Adt(Adt::EnumVariant(_, enum_variant_id, enum_data)) =>
+                (enum_variant_id, enum_data),
+            t => panic!("Expected enum variant, got {:?}", t),
+        }
+    }
+}
+
+/// Keeps track of the jumps out of a loop while its body is being built
+struct LoopScope {
+    label: Option<Symbol>,
+    /// Where `break` values are stored, if the loop yields a value
+    result: Option<(ResultMemId, Ty)>,
+    break_bb_ids: Vec<BasicBlockId>,
+    continue_bb_ids: Vec<BasicBlockId>,
+    /// How many defer scopes were open when entering the loop
+    defer_scope_depth: usize,
+}
+
+/// Something which is run when exiting the scope it belongs to
+#[derive(Clone, Copy)]
+enum Deferred<'ast> {
+    /// A `defer` expression
+    Expr(Expr<'ast>),
+    /// An owning variable which must be dropped (removed again by drop elaboration if it's moved)
+    Drop(LocalMemId, Ty),
 }
 
 pub struct CfgBuilder<'icfg, 'ast, 'c> {
@@ -167,8 +226,14 @@ pub struct CfgBuilder<'icfg, 'ast, 'c> {
     node_id_to_result_mem_id: FxHashMap<NodeId, ResultMemId>,
 
     /* For loops */
-    break_bb_ids: Vec<(BasicBlockId, Option<Operand>)>,
-    continue_bb_ids: Vec<BasicBlockId>,
+    loop_scopes: Vec<LoopScope>,
+
+    /// Each block has its own scope of deferred expressions and drops, which are emitted in reverse
+    /// order on every exit from the block (fallthrough, `ret`, `break` and `continue`)
+    defer_scopes: Vec<Vec<Deferred<'ast>>>,
+
+    /// Span of the most recently used variable, which is given to the nodes using it
+    curr_span: Option<Span>,
 
     next_ssa_id: u32,
 }
@@ -193,8 +258,9 @@ impl<'icfg, 'ast, 'c> CfgBuilder<'icfg, 'ast, 'c> {
             basic_blocks,
             def_id_to_local_mem_id: Default::default(),
             node_id_to_result_mem_id: Default::default(),
-            break_bb_ids: Default::default(),
-            continue_bb_ids: Default::default(),
+            loop_scopes: Vec::with_capacity(4),
+            defer_scopes: Vec::with_capacity(8),
+            curr_span: None,
             next_ssa_id: 0,
         }
     }
@@ -204,26 +270,19 @@ impl<'icfg, 'ast, 'c> CfgBuilder<'icfg, 'ast, 'c> {
             self.compiling_fn.ident_node.ast_node_id
         );
 
-        let name_binding = self.icfg_builder.resolved_information.get_name_binding_from_def_id(
-            &def_id
-        );
-
-        let ret_ty = if let NameBindingKind::Fn(fn_sig, _, _) = name_binding.kind {
-            fn_sig.ret_ty
-        } else {
-            panic!("Expected fn")
-        };
+        let ret_ty = self.get_fn_ret_ty();
 
         if self.is_main_fn {
-            self.visit_stmts(self.compiling_fn.body);
+            self.visit_scope_stmts(self.compiling_fn.body, true);
+            self.terminate_fn(ret_ty);
             Cfg::new(
                 // self.icfg_builder.global_mems,
                 self.args,
                 self.local_mems,
                 self.result_mems,
                 self.basic_blocks,
-                CfgFnKind::Main,
-                *ret_ty
+                CfgFnKind::Main(def_id),
+                ret_ty
             )
         } else {
             for arg_kind in self.compiling_fn.args.iter() {
@@ -242,7 +301,8 @@ impl<'icfg, 'ast, 'c> CfgBuilder<'icfg, 'ast, 'c> {
                         Symbol::from_node_id(ident_node.ast_node_id),
                         ident_node.span,
                         arg_ty,
-                        Mutability::Immutable
+                        Mutability::Immutable,
+                        self.icfg_builder.resolved_information.needs_drop(arg_ty)
                     );
                     self.local_mems.push(local_mem);
 
@@ -265,24 +325,72 @@ impl<'icfg, 'ast, 'c> CfgBuilder<'icfg, 'ast, 'c> {
                     )
                 );
             }
-            self.visit_stmts(self.compiling_fn.body);
 
-            Cfg::new(
+            // The arguments are owned by the function, so they're dropped in an outer scope
+            self.defer_scopes.push(Vec::new());
+            for local_mem_id in 0..self.args.len() {
+                self.defer_drop(LocalMemId(local_mem_id as u32));
+            }
+
+            self.visit_scope_stmts(self.compiling_fn.body, true);
+
+            if !self.is_curr_bb_terminated() {
+                self.emit_deferred_exprs(0);
+            }
+            self.defer_scopes.pop();
+            self.terminate_fn(ret_ty);
+            self.lower_variant_tests_to_switches();
+
+            let mut cfg = Cfg::new(
                 // self.icfg_builder.global_mems,
                 self.args,
                 self.local_mems,
                 self.result_mems,
                 self.basic_blocks,
                 CfgFnKind::Fn(def_id),
-                *ret_ty
-            )
+                ret_ty
+            );
+            cfg.is_exported = self.compiling_fn.item_type == ItemType::C;
+            cfg.inline_attr = self.compiling_fn.inline_attr;
+            cfg
         }
     }
 
+    /// Ends the function where the body falls through. Only functions returning nothing can get
+    /// there, otherwise every path has already returned
+    fn terminate_fn(&mut self, ret_ty: Ty) {
+        if self.is_curr_bb_terminated() {
+            return;
+        }
+
+        let kind = if ret_ty.is_void() {
+            NodeKind::ReturnNode(ReturnNode::new(Operand::Const(Const::Void), VOID_TY))
+        } else {
+            NodeKind::UnreachableNode(UnreachableNode)
+        };
+        self.push_node(Node::new(kind));
+    }
+
     // pub(crate) fn get_ty(ty: Ty) -> Ty {
     //     TyCtx.intern_type(ty)
     // }
 
+    pub(crate) fn get_fn_ret_ty(&self) -> Ty {
+        let def_id = self.icfg_builder.get_def_id_from_node_id(
+            self.compiling_fn.ident_node.ast_node_id
+        );
+
+        let name_binding = self.icfg_builder.resolved_information.get_name_binding_from_def_id(
+            &def_id
+        );
+
+        if let NameBindingKind::Fn(fn_sig, _, _) = name_binding.kind {
+            *fn_sig.ret_ty
+        } else {
+            panic!("Expected fn")
+        }
+    }
+
     pub(crate) fn get_next_ssa_id(&mut self) -> u32 {
         self.next_ssa_id += 1;
         self.next_ssa_id - 1
@@ -319,21 +427,192 @@ impl<'icfg, 'ast, 'c> CfgBuilder<'icfg, 'ast, 'c> {
         self.push_node_to(bb_id, node);
     }
 
-    pub(crate) fn push_node_to(&mut self, basic_block_id: BasicBlockId, node: Node<'icfg>) {
+    pub(crate) fn push_node_to(&mut self, basic_block_id: BasicBlockId, mut node: Node<'icfg>) {
+        node.span = node.span.or(self.curr_span);
+
         let basic_block = self.basic_blocks
             .get_mut(basic_block_id.0 as usize)
             .expect("Expected basic block");
 
-        if let Some(last_node) = basic_block.nodes.last() {
-            match &last_node.kind {
-                NodeKind::BranchNode(_) | NodeKind::BranchCondNode(_) => {
-                    return;
+        // Callers must check `is_bb_terminated` first. In release builds a node after a
+        // terminator is left for the verifier to report
+        debug_assert!(
+            !basic_block.nodes.last().is_some_and(|last_node| last_node.kind.is_terminator()),
+            "Pushed {:?} after the terminator of {:?}",
+            node.kind,
+            basic_block_id
+        );
+
+        basic_block.push_node(node);
+    }
+
+    pub(crate) fn is_curr_bb_terminated(&self) -> bool {
+        self.is_bb_terminated(self.get_curr_bb_id())
+    }
+
+    pub(crate) fn is_bb_terminated(&self, basic_block_id: BasicBlockId) -> bool {
+        let basic_block = self.basic_blocks
+            .get(basic_block_id.0 as usize)
+            .expect("Expected basic block");
+
+        basic_block.nodes.last().is_some_and(|node| node.kind.is_terminator())
+    }
+
+    /// Branches from the end of a block, unless the block already ended (e.g. in a `ret`), since
+    /// the branch could never be taken
+    fn push_branch_out(&mut self, from_bb_id: BasicBlockId, to_bb_id: BasicBlockId) {
+        if !self.is_bb_terminated(from_bb_id) {
+            let branch_node = Node::new(NodeKind::BranchNode(BranchNode::new(to_bb_id)));
+            self.push_node_to(from_bb_id, branch_node);
+        }
+    }
+
+    /// Finds the loop targeted by a `break` or `continue` (the innermost one if there's no label)
+    pub(crate) fn get_loop_scope_idx(&self, label: Option<&'ast IdentNode>) -> usize {
+        match label {
+            Some(label) => {
+                let label = Symbol::from_node_id(label.ast_node_id);
+                self.loop_scopes.iter().rposition(|loop_scope| loop_scope.label == Some(label))
+            }
+            None => self.loop_scopes.len().checked_sub(1),
+        }.expect("Should have been caught by resolving and type checking")
+    }
+
+    /// Visits the deferred expressions (and drops the variables) of all scopes from
+    /// `from_scope_depth` and inwards, starting with the most recently deferred one
+    pub(crate) fn emit_deferred_exprs(&mut self, from_scope_depth: usize) {
+        let deferred_exprs = self.defer_scopes[from_scope_depth..]
+            .iter()
+            .flatten()
+            .rev()
+            .copied()
+            .collect::<Vec<_>>();
+
+        for deferred in deferred_exprs {
+            match deferred {
+                Deferred::Expr(expr) => {
+                    self.visit_expr(expr);
+                }
+                Deferred::Drop(local_mem_id, ty) => {
+                    self.push_node(
+                        Node::new(
+                            NodeKind::DropNode(
+                                DropNode::new(PlaceKind::LocalMemId(local_mem_id), ty, None)
+                            )
+                        )
+                    );
                 }
-                _ => {}
             }
         }
+    }
 
-        basic_block.push_node(node);
+    /// Visits the statements of a block in its own scope. The value of the last statement is the
+    /// value of the block, except in a function body where it's unused like the other values
+    pub(crate) fn visit_scope_stmts(
+        &mut self,
+        stmts: &'ast [Stmt<'ast>],
+        is_fn_body: bool
+    ) -> VisitResult {
+        self.defer_scopes.push(Vec::new());
+
+        let mut visit_result = Self::default_result();
+        for (i, stmt) in stmts.iter().enumerate() {
+            // Statements after a `ret` are never reached
+            if self.is_curr_bb_terminated() {
+                break;
+            }
+            let stmt_visit_result = self.visit_stmt(*stmt);
+
+            match stmt {
+                Stmt::ExprStmt(expr) if is_fn_body || i + 1 < stmts.len() => {
+                    self.drop_unused_value(*expr, stmt_visit_result);
+                }
+                _ => {
+                    visit_result = stmt_visit_result;
+                }
+            }
+        }
+
+        if !self.is_curr_bb_terminated() {
+            self.emit_deferred_exprs(self.defer_scopes.len() - 1);
+        }
+        self.defer_scopes.pop();
+
+        visit_result
+    }
+
+    /// Drops the value of an expression statement right away if its type needs to be dropped,
+    /// since it isn't bound to a variable which would drop it
+    fn drop_unused_value(&mut self, expr: Expr<'ast>, visit_result: VisitResult) {
+        if self.is_curr_bb_terminated() {
+            return;
+        }
+
+        let ty = self.get_value_ty_from_expr(expr);
+        if ty.is_void() || !self.icfg_builder.resolved_information.needs_drop(ty) {
+            return;
+        }
+
+        let local_mem_id = LocalMemId(self.local_mems.len() as u32);
+        self.local_mems.push(
+            LocalMem::new(
+                local_mem_id,
+                Symbol::new("tmp"),
+                self.curr_span.unwrap_or(Span::dummy()),
+                ty,
+                Mutability::Immutable,
+                true
+            )
+        );
+
+        let (operand, _, op_ty) = self.get_operand_from_visit_result(visit_result, ty);
+        self.push_node(
+            Node::new(
+                NodeKind::StoreNode(
+                    StoreNode::new(
+                        PlaceKind::LocalMemId(local_mem_id),
+                        op_ty,
+                        operand,
+                        StoreKind::Init
+                    )
+                )
+            )
+        );
+        self.push_node(
+            Node::new(
+                NodeKind::DropNode(DropNode::new(PlaceKind::LocalMemId(local_mem_id), ty, None))
+            )
+        );
+    }
+
+    /// The type of the value of an expression, where enum variants get the type of their enum
+    pub(crate) fn get_value_ty_from_expr(&self, expr: Expr<'ast>) -> Ty {
+        let ty = self.icfg_builder.get_ty_from_node_id(get_node_id_from_expr(expr));
+
+        match ty {
+            Ty::Adt(enum_variant_def_id) => {
+                let name_binding =
+                    self.icfg_builder.resolved_information.get_name_binding_from_def_id(
+                        &enum_variant_def_id
+                    );
+                match name_binding.kind {
+                    NameBindingKind::Adt(Adt::EnumVariant(enum_def_id, _, _)) =>
+                        Ty::Adt(enum_def_id),
+                    _ => ty,
+                }
+            }
+            _ => ty,
+        }
+    }
+
+    /// Drops the variable when exiting the current scope, if its type needs to be dropped
+    pub(crate) fn defer_drop(&mut self, local_mem_id: LocalMemId) {
+        let local_mem = &self.local_mems[local_mem_id.0 as usize];
+
+        if local_mem.requires_drop {
+            let deferred = Deferred::Drop(local_mem_id, local_mem.ty);
+            self.defer_scopes.last_mut().expect("Expected a scope").push(deferred);
+        }
     }
 
     pub(crate) fn new_result_mem(&mut self, ty: Ty) -> ResultMemId {
@@ -619,17 +898,43 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
         match ty {
             Ty::FnDef(_) | Ty::FnSig(_) => { VisitResult::Const(Const::FnPtr(def_id), None) }
             Ty::AtdConstructer(_) => { Default::default() }
+            // Zero sized enum variant imported with e.g. `import Option.*`
+            Ty::Adt(adt_def_id) if adt_def_id == def_id => {
+                let name_binding =
+                    self.icfg_builder.resolved_information.get_name_binding_from_def_id(&def_id);
+                match name_binding.kind {
+                    NameBindingKind::Adt(Adt::EnumVariant(enum_def_id, variant_id, _)) => {
+                        self.build_zero_sized_enum_variant(enum_def_id, variant_id)
+                    }
+                    name_binding => unreachable!("Expected enum variant: {:?}", name_binding),
+                }
+            }
             _ => {
+                self.curr_span = Some(ident_node.span);
                 let local_mem_id = self.get_local_mem_id_from_def_id(def_id);
                 VisitResult::PlaceKind(PlaceKind::LocalMemId(local_mem_id), ty)
             }
         }
     }
 
+    fn visit_stmts(&mut self, stmts: &'ast [Stmt<'ast>]) -> Self::Result {
+        self.visit_scope_stmts(stmts, false)
+    }
+
+    fn visit_defer_expr(&mut self, defer_expr: &'ast DeferExpr<'ast>) -> Self::Result {
+        self.defer_scopes
+            .last_mut()
+            .expect("Expected defer expression to be inside a block")
+            .push(Deferred::Expr(defer_expr.expr));
+
+        Self::default_result()
+    }
+
     fn visit_return_expr(&mut self, return_expr: &'ast ReturnExpr) -> Self::Result {
         let ret_ty = self.icfg_builder.get_ty_from_node_id(return_expr.ast_node_id);
 
         let mut push_void_node = || {
+            self.emit_deferred_exprs(0);
             self.push_node(
                 Node::new(
                     NodeKind::ReturnNode(ReturnNode::new(Operand::Const(Const::Void), VOID_TY))
@@ -648,6 +953,8 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
                             self.get_operand_from_visit_result(return_visit_result, ret_ty).0
                         };
 
+                        // The return value is evaluated before running the deferred expressions
+                        self.emit_deferred_exprs(0);
                         self.push_node(
                             Node::new(NodeKind::ReturnNode(ReturnNode::new(return_operand, ty)))
                         );
@@ -734,6 +1041,10 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
                     let mut byte_offset = 8;
                     for (i, pat) in tuple_struct_pat.fields.iter().enumerate() {
                         let ty = &enum_data[i];
+                        let ty_attr = ty.get_ty_attr(
+                            &cfg_builder.icfg_builder.resolved_information
+                        );
+                        byte_offset = ty_attr.align_offset(byte_offset);
 
                         if let Pat::IdentPat(ident_pat) = pat {
                             let local_mem_id = {
@@ -743,7 +1054,8 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
                                     Symbol::from_node_id(ident_pat.ast_node_id),
                                     ident_pat.span,
                                     *ty,
-                                    Mutability::Immutable
+                                    Mutability::Immutable,
+                                    false
                                 );
                                 cfg_builder.local_mems.push(local_mem);
 
@@ -798,9 +1110,7 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
                             store_nodes.extend(other_false_bb_ids.1);
                         }
 
-                        byte_offset += ty.get_ty_attr(
-                            &cfg_builder.icfg_builder.resolved_information
-                        ).size_bytes;
+                        byte_offset += ty_attr.size_bytes;
                     }
 
                     (false_bb_ids, store_nodes)
@@ -818,6 +1128,8 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
         ) -> (BasicBlockId, BasicBlockId) {
             let first_true_bb_id = cfg_builder.new_basic_block();
             let true_visit_result = cfg_builder.visit_stmts(true_block);
+            // A block which returned has no value
+            let result_mem_id = result_mem_id.filter(|_| !cfg_builder.is_curr_bb_terminated());
             if let Some(result_mem_id) = result_mem_id {
                 let (true_operand, _, true_ty) = cfg_builder.get_operand_from_visit_result(
                     true_visit_result,
@@ -874,6 +1186,8 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
                     }
 
                     let true_visit_result = self.visit_stmts(if_expr.true_block);
+                    // A block which returned has no value
+                    let result_mem_id = result_mem_id.filter(|_| !self.is_curr_bb_terminated());
                     if let Some(result_mem_id) = result_mem_id {
                         let (true_operand, _, true_ty) = self.get_operand_from_visit_result(
                             true_visit_result,
@@ -898,14 +1212,12 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
 
                 if let Some(false_branch) = &if_expr.false_block {
                     let false_bb_id = self.new_basic_block();
-                    let bb_id_after_false_expr = BasicBlockId(false_bb_id.0 + 1);
-                    let branch_out_node = Node::new(
-                        NodeKind::BranchNode(BranchNode::new(bb_id_after_false_expr))
-                    );
 
                     match false_branch {
                         IfFalseBranchExpr::ElifExpr(if_expr) => {
                             self.visit_if_expr(if_expr);
+                            // The elif ends in the block where the whole chain joins
+                            let join_bb_id = self.get_curr_bb_id();
 
                             for (cond, bb) in &bb_ids {
                                 self.push_node_to(
@@ -922,16 +1234,20 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
                                 );
                             }
 
-                            self.push_node_to(last_true_bb_id, branch_out_node);
-                            self.push_node_to(false_bb_id, branch_out_node);
+                            self.push_branch_out(last_true_bb_id, join_bb_id);
                         }
                         IfFalseBranchExpr::ElseExpr(else_expr) => {
                             let false_visit_result = self.visit_block_expr(else_expr);
-                            let (false_operand, _, false_ty) = self.get_operand_from_visit_result(
-                                false_visit_result,
-                                ty_to_match
+                            // A block which returned has no value
+                            let result_mem_id = result_mem_id.filter(
+                                |_| !self.is_curr_bb_terminated()
                             );
                             if let Some(result_mem_id) = result_mem_id {
+                                let (false_operand, _, false_ty) =
+                                    self.get_operand_from_visit_result(
+                                        false_visit_result,
+                                        ty_to_match
+                                    );
                                 self.push_node(
                                     Node::new(
                                         NodeKind::StoreNode(
@@ -960,17 +1276,16 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
                                 );
                             }
 
-                            self.push_node_to(last_true_bb_id, branch_out_node);
-                            self.push_node_to(false_bb_id, branch_out_node);
+                            let join_bb_id = self.get_next_bb_id();
+                            self.push_branch_out(last_true_bb_id, join_bb_id);
+                            self.push_branch_out(self.get_curr_bb_id(), join_bb_id);
 
                             self.new_basic_block();
                         }
                     }
                 } else {
                     let bb_id_after_true_expr = BasicBlockId(last_true_bb_id.0 + 1);
-                    self.push_node(
-                        Node::new(NodeKind::BranchNode(BranchNode::new(bb_id_after_true_expr)))
-                    );
+                    self.push_branch_out(last_true_bb_id, bb_id_after_true_expr);
 
                     for (cond, bb) in &bb_ids {
                         self.push_node_to(
@@ -1005,14 +1320,12 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
 
                 if let Some(false_branch) = &if_expr.false_block {
                     let false_bb_id = self.new_basic_block();
-                    let bb_id_after_false_expr = BasicBlockId(false_bb_id.0 + 1);
-                    let branch_out_node = Node::new(
-                        NodeKind::BranchNode(BranchNode::new(bb_id_after_false_expr))
-                    );
 
                     match false_branch {
                         IfFalseBranchExpr::ElifExpr(if_expr) => {
                             self.visit_if_expr(if_expr);
+                            // The elif ends in the block where the whole chain joins
+                            let join_bb_id = self.get_curr_bb_id();
                             // let cond_ty = self.icfg_builder.get_ty_from_node_id(
                             //     get_node_id_from_expr(if_expr.condition)
                             // );
@@ -1028,16 +1341,20 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
                                     )
                                 )
                             );
-                            self.push_node_to(last_true_bb_id, branch_out_node);
-                            self.push_node_to(false_bb_id, branch_out_node);
+                            self.push_branch_out(last_true_bb_id, join_bb_id);
                         }
                         IfFalseBranchExpr::ElseExpr(else_expr) => {
                             let false_visit_result = self.visit_block_expr(else_expr);
-                            let (false_operand, _, false_ty) = self.get_operand_from_visit_result(
-                                false_visit_result,
-                                ty_to_match
+                            // A block which returned has no value
+                            let result_mem_id = result_mem_id.filter(
+                                |_| !self.is_curr_bb_terminated()
                             );
                             if let Some(result_mem_id) = result_mem_id {
+                                let (false_operand, _, false_ty) =
+                                    self.get_operand_from_visit_result(
+                                        false_visit_result,
+                                        ty_to_match
+                                    );
                                 self.push_node(
                                     Node::new(
                                         NodeKind::StoreNode(
@@ -1063,17 +1380,16 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
                                     )
                                 )
                             );
-                            self.push_node_to(last_true_bb_id, branch_out_node);
-                            self.push_node_to(false_bb_id, branch_out_node);
+                            let join_bb_id = self.get_next_bb_id();
+                            self.push_branch_out(last_true_bb_id, join_bb_id);
+                            self.push_branch_out(self.get_curr_bb_id(), join_bb_id);
 
                             self.new_basic_block();
                         }
                     }
                 } else {
                     let bb_id_after_true_expr = BasicBlockId(last_true_bb_id.0 + 1);
-                    self.push_node(
-                        Node::new(NodeKind::BranchNode(BranchNode::new(bb_id_after_true_expr)))
-                    );
+                    self.push_branch_out(last_true_bb_id, bb_id_after_true_expr);
                     self.push_node_to(
                         bb_id_before_if_expr,
                         Node::new(
@@ -1109,16 +1425,22 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
             )
         }
 
-        let loop_expr_ty = self.icfg_builder.get_ty_from_node_id(loop_expr.ast_node_id).to_ptr_ty();
+        let ty_to_match = self.icfg_builder.get_ty_from_node_id(loop_expr.ast_node_id);
+        let loop_expr_ty = ty_to_match.to_ptr_ty();
 
-        let result_mem_id = if !loop_expr_ty.is_void() {
+        let result_mem_id = if !loop_expr_ty.is_void() && !loop_expr_ty.is_never() {
             Some(set_result_mem_id_to_loop_expr_result(self, loop_expr))
         } else {
             None
         };
 
-        let prev_break_bb_ids = std::mem::take(&mut self.break_bb_ids);
-        let prev_continue_bb_ids = std::mem::take(&mut self.continue_bb_ids);
+        self.loop_scopes.push(LoopScope {
+            label: loop_expr.label.map(|label| Symbol::from_node_id(label.ast_node_id)),
+            result: result_mem_id.map(|result_mem_id| (result_mem_id, ty_to_match)),
+            break_bb_ids: Vec::new(),
+            continue_bb_ids: Vec::new(),
+            defer_scope_depth: self.defer_scopes.len(),
+        });
 
         // Pushes branch node to loop, to the BasicBlock before the loop
         let loop_bb_id = self.get_next_bb_id();
@@ -1128,28 +1450,17 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
         self.visit_block_expr(loop_expr.body);
 
         // Pushes branch to start of loop
-        self.push_node(Node::new(NodeKind::BranchNode(BranchNode::new(loop_bb_id))));
+        self.push_branch_out(self.get_curr_bb_id(), loop_bb_id);
         self.new_basic_block();
         let after_loop_bb_id = self.get_curr_bb_id();
 
-        let break_bb_ids = std::mem::replace(&mut self.break_bb_ids, prev_break_bb_ids);
-        let continue_bb_ids = std::mem::replace(&mut self.continue_bb_ids, prev_continue_bb_ids);
+        let loop_scope = self.loop_scopes.pop().expect("This is always present pushed above");
 
-        for (break_bb_id, break_operand) in break_bb_ids {
-            if let Some(break_operand) = break_operand {
-                // Push Store node
-                panic!("Break with value is not yet supported");
-            }
-            self.push_node_to(
-                break_bb_id,
-                Node::new(NodeKind::BranchNode(BranchNode::new(after_loop_bb_id)))
-            );
+        for break_bb_id in loop_scope.break_bb_ids {
+            self.push_branch_out(break_bb_id, after_loop_bb_id);
         }
-        for continue_bb_id in continue_bb_ids {
-            self.push_node_to(
-                continue_bb_id,
-                Node::new(NodeKind::BranchNode(BranchNode::new(loop_bb_id)))
-            );
+        for continue_bb_id in loop_scope.continue_bb_ids {
+            self.push_branch_out(continue_bb_id, loop_bb_id);
         }
 
         if let Some(result_mem_id) = result_mem_id {
@@ -1160,15 +1471,47 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
     }
 
     fn visit_break_expr(&mut self, break_expr: &'ast ast::BreakExpr<'ast>) -> Self::Result {
-        self.break_bb_ids.push((self.get_curr_bb_id(), None));
+        let loop_scope_idx = self.get_loop_scope_idx(break_expr.label);
+
+        if let Some(value_expr) = break_expr.value {
+            let value_visit_result = self.visit_expr(value_expr);
+
+            if let Some((result_mem_id, ty_to_match)) = self.loop_scopes[loop_scope_idx].result {
+                let (operand, _, op_ty) = self.get_operand_from_visit_result(
+                    value_visit_result,
+                    ty_to_match
+                );
+
+                self.push_node(
+                    Node::new(
+                        NodeKind::StoreNode(
+                            StoreNode::new(
+                                PlaceKind::ResultMemId(result_mem_id),
+                                op_ty,
+                                operand,
+                                StoreKind::Init
+                            )
+                        )
+                    )
+                );
+            }
+        }
+
+        self.emit_deferred_exprs(self.loop_scopes[loop_scope_idx].defer_scope_depth);
+        let curr_bb_id = self.get_curr_bb_id();
+        self.loop_scopes[loop_scope_idx].break_bb_ids.push(curr_bb_id);
         self.new_basic_block();
 
         // Break expressions always return void
         Self::default_result()
     }
 
-    fn visit_continue_expr(&mut self, continue_expr: &'ast ast::ContinueExpr) -> Self::Result {
-        self.continue_bb_ids.push(self.get_curr_bb_id());
+    fn visit_continue_expr(&mut self, continue_expr: &'ast ast::ContinueExpr<'ast>) -> Self::Result {
+        let loop_scope_idx = self.get_loop_scope_idx(continue_expr.label);
+
+        self.emit_deferred_exprs(self.loop_scopes[loop_scope_idx].defer_scope_depth);
+        let curr_bb_id = self.get_curr_bb_id();
+        self.loop_scopes[loop_scope_idx].continue_bb_ids.push(curr_bb_id);
         self.new_basic_block();
 
         // Continue expressions always return void
@@ -1222,6 +1565,9 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
 
             let mut byte_offset = 8; // The discriminant is 8 bytes
             for (operand, _, operand_ty) in arg_operands {
+                let ty_attr = operand_ty.get_ty_attr(&self.icfg_builder.resolved_information);
+                byte_offset = ty_attr.align_offset(byte_offset);
+
                 let temp_id = self.get_temp_id();
                 self.push_node(
                     Node::new(
@@ -1247,9 +1593,7 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
                     )
                 );
 
-                byte_offset += operand_ty.get_ty_attr(
-                    &self.icfg_builder.resolved_information
-                ).size_bytes;
+                byte_offset += ty_attr.size_bytes;
             }
 
             return VisitResult::PlaceKind(
@@ -1336,6 +1680,216 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
         VisitResult::PlaceKind(PlaceKind::TempId(temp_id), ret_ty)
     }
 
+    fn visit_intrinsic_expr(&mut self, intrinsic_expr: &'ast IntrinsicExpr<'ast>) -> Self::Result {
+        let resolved_information = &self.icfg_builder.resolved_information;
+        let ty = self.icfg_builder.get_ty_from_node_id(intrinsic_expr.typing_ast_node_id);
+
+        let val = match (intrinsic_expr.kind, intrinsic_expr.field) {
+            (IntrinsicKind::SizeOf, _) => ty.get_ty_attr(resolved_information).size_bytes,
+            (IntrinsicKind::AlignOf, _) => ty.get_ty_attr(resolved_information).alignment_bytes,
+            (IntrinsicKind::OffsetOf, Some(field)) => {
+                let field_symbol = Symbol::from_node_id(field.ast_node_id);
+                let struct_fields = match ty {
+                    Ty::Adt(def_id) =>
+                        match resolved_information.get_name_binding_from_def_id(&def_id).kind {
+                            NameBindingKind::Adt(Adt::Struct(struct_fields)) => struct_fields,
+                            _ => unreachable!("Expected struct in `offsetof`"),
+                        }
+                    _ => unreachable!("Expected struct in `offsetof`"),
+                };
+
+                let field_idx = struct_fields
+                    .iter()
+                    .position(|(def_id, _)| def_id.symbol.get() == field_symbol.get())
+                    .expect("Expected field in `offsetof`");
+
+                TyAttr::get_field_offsets(
+                    struct_fields.iter().map(|(_, ty)| *ty),
+                    resolved_information
+                )[field_idx]
+            }
+            (IntrinsicKind::OffsetOf, None) => unreachable!("Expected field in `offsetof`"),
+        };
+
+        VisitResult::Const(Const::Int(val as i64, IntTy::Int64), None)
+    }
+
+    fn visit_try_expr(&mut self, try_expr: &'ast TryExpr<'ast>) -> Self::Result {
+        let enum_ty = self.icfg_builder
+            .get_ty_from_node_id(get_node_id_from_expr(try_expr.expr))
+            .deref_if_stack_ptr();
+        let ok_ty = self.icfg_builder.get_ty_from_node_id(try_expr.ast_node_id);
+        let ret_ty = self.get_fn_ret_ty();
+
+        let (ok_variant_id, _) = self.icfg_builder.get_enum_variant(enum_ty, "Ok");
+        let (_, err_data) = self.icfg_builder.get_enum_variant(enum_ty, "Err");
+        let (ret_err_variant_id, _) = self.icfg_builder.get_enum_variant(ret_ty, "Err");
+
+        // The operand is stored in memory, so both the discriminant and the payload can be accessed
+        let enum_result_mem_id = {
+            let visit_result = self.visit_expr(try_expr.expr);
+            let (operand, _, operand_ty) = self.get_operand_from_visit_result(
+                visit_result,
+                enum_ty
+            );
+            let result_mem_id = self.new_result_mem(enum_ty);
+
+            self.push_node(
+                Node::new(
+                    NodeKind::StoreNode(
+                        StoreNode::new(
+                            PlaceKind::ResultMemId(result_mem_id),
+                            operand_ty,
+                            operand,
+                            StoreKind::Init
+                        )
+                    )
+                )
+            );
+
+            result_mem_id
+        };
+
+        let load_temp_id = self.get_temp_id();
+        self.push_node(
+            Node::new(
+                NodeKind::LoadNode(
+                    LoadNode::new(
+                        load_temp_id,
+                        PlaceKind::ResultMemId(enum_result_mem_id),
+                        INT_64_TY
+                    )
+                )
+            )
+        );
+
+        let cmp_temp_id = self.get_temp_id();
+        self.push_node(
+            Node::new(
+                NodeKind::BinaryNode(
+                    BinaryNode::new(
+                        cmp_temp_id,
+                        INT_64_TY,
+                        BinaryOp::ComparisonOp(ComparisonOp::Eq),
+                        Operand::from(load_temp_id),
+                        Operand::Const(Const::Int(ok_variant_id.0 as i64, IntTy::Int64))
+                    )
+                )
+            )
+        );
+
+        let cond_bb_id = self.get_curr_bb_id();
+
+        // The payload of the `Err` variant is copied into the `Err` variant of the return type
+        let err_bb_id = self.new_basic_block();
+        let ret_result_mem_id = self.new_result_mem(ret_ty);
+        self.push_node(
+            Node::new(
+                NodeKind::StoreNode(
+                    StoreNode::new(
+                        PlaceKind::ResultMemId(ret_result_mem_id),
+                        INT_64_TY,
+                        Operand::Const(Const::Int(ret_err_variant_id.0 as i64, IntTy::Int64)),
+                        StoreKind::Init
+                    )
+                )
+            )
+        );
+
+        let mut byte_offset = 8; // The discriminant is 8 bytes
+        for ty in err_data.iter().filter(|ty| **ty != Ty::ZeroSized) {
+            let ty_attr = ty.get_ty_attr(&self.icfg_builder.resolved_information);
+            byte_offset = ty_attr.align_offset(byte_offset);
+
+            let src_temp_id = self.get_temp_id();
+            self.push_node(
+                Node::new(
+                    NodeKind::ByteAccessNode(
+                        ByteAccessNode::new(
+                            PlaceKind::TempId(src_temp_id),
+                            PlaceKind::ResultMemId(enum_result_mem_id),
+                            byte_offset
+                        )
+                    )
+                )
+            );
+
+            let load_temp_id = self.get_temp_id();
+            self.push_node(
+                Node::new(
+                    NodeKind::LoadNode(
+                        LoadNode::new(load_temp_id, PlaceKind::TempId(src_temp_id), *ty)
+                    )
+                )
+            );
+
+            let dest_temp_id = self.get_temp_id();
+            self.push_node(
+                Node::new(
+                    NodeKind::ByteAccessNode(
+                        ByteAccessNode::new(
+                            PlaceKind::TempId(dest_temp_id),
+                            PlaceKind::ResultMemId(ret_result_mem_id),
+                            byte_offset
+                        )
+                    )
+                )
+            );
+
+            self.push_node(
+                Node::new(
+                    NodeKind::StoreNode(
+                        StoreNode::new(
+                            PlaceKind::TempId(dest_temp_id),
+                            *ty,
+                            Operand::from(load_temp_id),
+                            StoreKind::Init
+                        )
+                    )
+                )
+            );
+
+            byte_offset += ty_attr.size_bytes;
+        }
+
+        let (ret_operand, _, _) = self.get_operand_from_visit_result(
+            VisitResult::PlaceKind(PlaceKind::ResultMemId(ret_result_mem_id), ret_ty.to_ptr_ty()),
+            ret_ty
+        );
+
+        self.emit_deferred_exprs(0);
+        self.push_node(Node::new(NodeKind::ReturnNode(ReturnNode::new(ret_operand, ret_ty))));
+
+        let ok_bb_id = self.new_basic_block();
+        self.push_node_to(
+            cond_bb_id,
+            Node::new(
+                NodeKind::BranchCondNode(
+                    BranchCondNode::new(Operand::from(cmp_temp_id), ok_bb_id, err_bb_id)
+                )
+            )
+        );
+
+        if ok_ty.is_void() {
+            return Self::default_result();
+        }
+
+        let temp_id = self.get_temp_id();
+        self.push_node(
+            Node::new(
+                NodeKind::ByteAccessNode(
+                    ByteAccessNode::new(
+                        PlaceKind::TempId(temp_id),
+                        PlaceKind::ResultMemId(enum_result_mem_id),
+                        8
+                    )
+                )
+            )
+        );
+
+        VisitResult::PlaceKind(PlaceKind::TempId(temp_id), ok_ty.to_ptr_ty())
+    }
+
     /// Assumes read for now
     fn visit_tuple_field_expr(
         &mut self,
@@ -1367,14 +1921,10 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
         if idx == 0 {
             VisitResult::PlaceKind(lhs_place, elem_type)
         } else {
-            let mut byte_offset = 0;
-            for (i, ty) in tuple_ty.iter().enumerate() {
-                byte_offset += ty.get_ty_attr(&self.icfg_builder.resolved_information).size_bytes;
-
-                if i == idx - 1 {
-                    break;
-                }
-            }
+            let byte_offset = TyAttr::get_field_offsets(
+                tuple_ty.iter().copied(),
+                &self.icfg_builder.resolved_information
+            )[idx];
 
             let temp_id = self.get_temp_id();
             self.push_node(
@@ -1411,28 +1961,7 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
 
                 match name_binding.kind {
                     NameBindingKind::Adt(Adt::EnumVariant(_, variant_id, _)) => {
-                        // Zerosized enum variant
-                        let result_mem_id = self.new_result_mem(Ty::Adt(def_id));
-
-                        self.push_node(
-                            Node::new(
-                                NodeKind::StoreNode(
-                                    StoreNode::new(
-                                        PlaceKind::ResultMemId(result_mem_id),
-                                        INT_64_TY,
-                                        Operand::Const(
-                                            Const::Int(variant_id.0 as i64, IntTy::Int64)
-                                        ),
-                                        StoreKind::Init
-                                    )
-                                )
-                            )
-                        );
-
-                        return VisitResult::PlaceKind(
-                            PlaceKind::ResultMemId(result_mem_id),
-                            Ty::Adt(def_id).to_ptr_ty()
-                        );
+                        return self.build_zero_sized_enum_variant(def_id, variant_id);
                     }
                     NameBindingKind::Fn(_, _, _) => {
                         // Constructor method e.g. `Adt.new()`
@@ -1499,21 +2028,15 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
 
         let is_mutable = lhs_ty.deref_until_stack_ptr().is_mut_ptr();
 
-        let mut byte_offset = 0;
-        let elem_ty = struct_fields
+        let field_idx = struct_fields
             .iter()
-            .find(|(symbol, ty)| (
-                if symbol.symbol.get() == access_symbol.get() {
-                    true
-                } else {
-                    byte_offset += ty.get_ty_attr(
-                        &self.icfg_builder.resolved_information
-                    ).size_bytes;
-                    false
-                }
-            ))
-            .map(|x| x.1)
+            .position(|(symbol, _)| symbol.symbol.get() == access_symbol.get())
             .expect("Field not found");
+        let elem_ty = struct_fields[field_idx].1;
+        let byte_offset = TyAttr::get_field_offsets(
+            struct_fields.iter().map(|(_, ty)| *ty),
+            &self.icfg_builder.resolved_information
+        )[field_idx];
 
         let elem_ty = if is_mutable { elem_ty.to_mut_ptr_ty() } else { elem_ty.to_ptr_ty() };
 
@@ -1647,6 +2170,18 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
             ty_to_match
         );
 
+        // The old value of an owning variable is dropped before it's overwritten
+        if let PlaceKind::LocalMemId(local_mem_id) = setter_place {
+            let local_mem = self.local_mems[local_mem_id.0 as usize];
+            if local_mem.requires_drop {
+                self.push_node(
+                    Node::new(
+                        NodeKind::DropNode(DropNode::new(setter_place, local_mem.ty, None))
+                    )
+                );
+            }
+        }
+
         self.push_node(
             Node::new(
                 NodeKind::StoreNode(StoreNode::new(setter_place, op_ty, operand, StoreKind::Assign))
@@ -1657,23 +2192,7 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
     }
 
     fn visit_def_stmt(&mut self, def_stmt: &'ast ast::DefineStmt<'ast>) -> Self::Result {
-        let ty = self.icfg_builder.get_ty_from_node_id(get_node_id_from_expr(def_stmt.value_expr));
-
-        // If ty is an enum variant, get the type of the whole enum
-        let ty = match ty {
-            Ty::Adt(enum_variant_def_id) => {
-                let name_binding =
-                    self.icfg_builder.resolved_information.get_name_binding_from_def_id(
-                        &enum_variant_def_id
-                    );
-                match name_binding.kind {
-                    NameBindingKind::Adt(Adt::EnumVariant(enum_def_id, _, _)) =>
-                        Ty::Adt(enum_def_id),
-                    _ => ty,
-                }
-            }
-            _ => ty,
-        };
+        let ty = self.get_value_ty_from_expr(def_stmt.value_expr);
 
         if ty.is_void() {
             self.visit_expr(def_stmt.value_expr);
@@ -1688,7 +2207,8 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
                     Symbol::from_node_id(ident_pat.ast_node_id),
                     ident_pat.span,
                     ty,
-                    Mutability::Immutable
+                    Mutability::Immutable,
+                    self.icfg_builder.resolved_information.needs_drop(ty)
                 );
                 self.local_mems.push(local_mem);
 
@@ -1720,6 +2240,7 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
                 )
             )
         );
+        self.defer_drop(local_mem_id);
 
         Self::default_result()
     }
@@ -1790,6 +2311,9 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
 
         let op_ty = if let Some(biggest_num_ty) = Ty::get_biggest_num_ty(lhs_ty, rhs_ty) {
             biggest_num_ty
+        } else if lhs_ty.is_null() {
+            // `null == ptr` compares pointers
+            rhs_ty.auto_deref()
         } else {
             lhs_ty.auto_deref()
         };
@@ -1805,19 +2329,171 @@ impl<'ast> Visitor<'ast> for CfgBuilder<'_, 'ast, '_> {
 
         let result_place = self.get_temp_id();
 
+        self.push_node(Node {
+            kind: NodeKind::BinaryNode(
+                BinaryNode::new(result_place, op_ty, binary_expr.op, lhs_operand, rhs_operand)
+            ),
+            span: Some(binary_expr.span),
+        });
+
+        VisitResult::PlaceKind(PlaceKind::TempId(result_place), result_ty)
+    }
+}
+
+impl<'ast> CfgBuilder<'_, 'ast, '_> {
+    /// Turns chains of variant tests on the same enum (`if A := x {} elif B := x {} ...`) into
+    /// a single switch on the discriminant. Every test of the chain is a block ending in a load
+    /// of the discriminant, a compare and a conditional branch, and the false branch of a test
+    /// is the next test. Afterwards the skipped tests, and the bodies of repeated variants, are
+    /// removed, unless another block (e.g. a test of a nested pattern) still branches to them
+    fn lower_variant_tests_to_switches(&mut self) {
+        let mut is_skipped = vec![false; self.basic_blocks.len()];
+
+        for bb_index in 0..self.basic_blocks.len() {
+            if is_skipped[bb_index] {
+                continue;
+            }
+            let Some(first_test) = get_variant_test(&self.basic_blocks[bb_index]) else {
+                continue;
+            };
+
+            let mut cases = vec![(first_test.case, first_test.true_branch)];
+            let mut default = first_test.false_branch;
+            let mut skipped_bb_ids = Vec::new();
+            while default.0 as usize > bb_index {
+                let basic_block = &self.basic_blocks[default.0 as usize];
+                let Some(test) = get_variant_test(basic_block) else {
+                    break;
+                };
+                if basic_block.nodes.len() != 3 || test.place != first_test.place {
+                    break;
+                }
+
+                // A repeated variant can never match, since the first test already took it
+                if !cases.iter().any(|(case, _)| *case == test.case) {
+                    cases.push((test.case, test.true_branch));
+                }
+                skipped_bb_ids.push(default);
+                default = test.false_branch;
+            }
+
+            if cases.len() < 2 {
+                continue;
+            }
+            for skipped_bb_id in skipped_bb_ids {
+                is_skipped[skipped_bb_id.0 as usize] = true;
+            }
+
+            let nodes = &mut self.basic_blocks[bb_index].nodes;
+            nodes.truncate(nodes.len() - 2);
+            nodes.push(
+                Node::new(
+                    NodeKind::SwitchNode(
+                        SwitchNode::new(
+                            Operand::from(first_test.discriminant),
+                            INT_64_TY,
+                            default,
+                            TyCtx::intern_many_types(cases)
+                        )
+                    )
+                )
+            );
+        }
+
+        if is_skipped.contains(&true) {
+            self.remove_unreachable_basic_blocks();
+        }
+    }
+
+    fn get_reachable_basic_blocks(&self) -> Vec<bool> {
+        let mut is_reachable = vec![false; self.basic_blocks.len()];
+        let mut stack = vec![BasicBlockId(0)];
+        while let Some(bb_id) = stack.pop() {
+            if std::mem::replace(&mut is_reachable[bb_id.0 as usize], true) {
+                continue;
+            }
+            stack.extend(self.basic_blocks[bb_id.0 as usize].get_successors());
+        }
+        is_reachable
+    }
+
+    /// Removes the basic blocks which can no longer be reached, and renumbers the remaining
+    /// ones. There are no phis yet, so only the branches need to be updated
+    fn remove_unreachable_basic_blocks(&mut self) {
+        let mut new_bb_ids = Vec::with_capacity(self.basic_blocks.len());
+        let mut next_bb_id = 0;
+        for is_reachable in self.get_reachable_basic_blocks() {
+            if !is_reachable {
+                new_bb_ids.push(None);
+            } else {
+                new_bb_ids.push(Some(BasicBlockId(next_bb_id)));
+                next_bb_id += 1;
+            }
+        }
+        if next_bb_id as usize == self.basic_blocks.len() {
+            return;
+        }
+
+        let new_bb_id = |bb_id: BasicBlockId| {
+            new_bb_ids[bb_id.0 as usize].expect("Expected branch to a reachable basic block")
+        };
+
+        self.basic_blocks.retain(|basic_block| {
+            new_bb_ids[basic_block.basic_block_id.0 as usize].is_some()
+        });
+        for basic_block in self.basic_blocks.iter_mut() {
+            basic_block.basic_block_id = new_bb_id(basic_block.basic_block_id);
+
+            for node in basic_block.nodes.iter_mut() {
+                match &mut node.kind {
+                    NodeKind::BranchNode(branch_node) => {
+                        branch_node.branch = new_bb_id(branch_node.branch);
+                    }
+                    NodeKind::BranchCondNode(branch_cond_node) => {
+                        branch_cond_node.true_branch = new_bb_id(branch_cond_node.true_branch);
+                        branch_cond_node.false_branch = new_bb_id(branch_cond_node.false_branch);
+                    }
+                    NodeKind::SwitchNode(switch_node) => {
+                        switch_node.default = new_bb_id(switch_node.default);
+                        let cases = switch_node.cases
+                            .iter()
+                            .map(|(case, bb_id)| (*case, new_bb_id(*bb_id)))
+                            .collect::<Vec<_>>();
+                        switch_node.cases = TyCtx::intern_many_types(cases);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Builds an enum variant without data, which only consists of the discriminant
+    fn build_zero_sized_enum_variant(
+        &mut self,
+        enum_def_id: DefId,
+        variant_id: EmumVaraintId
+    ) -> VisitResult {
+        let result_mem_id = self.new_result_mem(Ty::Adt(enum_def_id));
+
         self.push_node(
             Node::new(
-                NodeKind::BinaryNode(
-                    BinaryNode::new(result_place, op_ty, binary_expr.op, lhs_operand, rhs_operand)
+                NodeKind::StoreNode(
+                    StoreNode::new(
+                        PlaceKind::ResultMemId(result_mem_id),
+                        INT_64_TY,
+                        Operand::Const(Const::Int(variant_id.0 as i64, IntTy::Int64)),
+                        StoreKind::Init
+                    )
                 )
             )
         );
 
-        VisitResult::PlaceKind(PlaceKind::TempId(result_place), result_ty)
+        VisitResult::PlaceKind(
+            PlaceKind::ResultMemId(result_mem_id),
+            Ty::Adt(enum_def_id).to_ptr_ty()
+        )
     }
-}
 
-impl<'ast> CfgBuilder<'_, 'ast, '_> {
     fn init_tuple_or_struct_field(
         &mut self,
         expr: Expr<'ast>,
@@ -1827,10 +2503,10 @@ impl<'ast> CfgBuilder<'_, 'ast, '_> {
     ) -> usize {
         let visit_result = self.visit_expr(expr);
 
-        let (operand, _, operand_ty) = self.get_operand_from_visit_result(
-            visit_result,
-            ty_to_match
-        );
+        let (operand, _, _) = self.get_operand_from_visit_result(visit_result, ty_to_match);
+
+        let ty_attr = ty_to_match.get_ty_attr(&self.icfg_builder.resolved_information);
+        let byte_offset = ty_attr.align_offset(byte_offset);
 
         if byte_offset == 0 {
             self.push_node(
@@ -1872,7 +2548,49 @@ impl<'ast> CfgBuilder<'_, 'ast, '_> {
             );
         }
 
-        let operand_ty_attr = operand_ty.get_ty_attr(&self.icfg_builder.resolved_information);
-        byte_offset + operand_ty_attr.size_bytes
+        byte_offset + ty_attr.size_bytes
     }
 }
+
+/// A test of an enum variant, which ends a basic block
+struct VariantTest {
+    place: PlaceKind,
+    discriminant: TempId,
+    case: i64,
+    true_branch: BasicBlockId,
+    false_branch: BasicBlockId,
+}
+
+fn get_variant_test(basic_block: &BasicBlock) -> Option<VariantTest> {
+    let [load_node, cmp_node, branch_node] = basic_block.nodes.last_chunk::<3>()?;
+    let (
+        NodeKind::LoadNode(load_node),
+        NodeKind::BinaryNode(binary_node),
+        NodeKind::BranchCondNode(branch_cond_node),
+    ) = (load_node.kind, cmp_node.kind, branch_node.kind) else {
+        return None;
+    };
+
+    let is_variant_test =
+        load_node.load_ty == INT_64_TY &&
+        matches!(binary_node.op, BinaryOp::ComparisonOp(ComparisonOp::Eq)) &&
+        matches!(
+            binary_node.lhs,
+            Operand::PlaceKind(PlaceKind::TempId(temp_id)) if temp_id == load_node.result_place
+        ) &&
+        matches!(
+            branch_cond_node.condition,
+            Operand::PlaceKind(PlaceKind::TempId(temp_id)) if temp_id == binary_node.result_place
+        );
+    let Operand::Const(Const::Int(case, IntTy::Int64)) = binary_node.rhs else {
+        return None;
+    };
+
+    is_variant_test.then_some(VariantTest {
+        place: load_node.load_place,
+        discriminant: load_node.result_place,
+        case,
+        true_branch: branch_cond_node.true_branch,
+        false_branch: branch_cond_node.false_branch,
+    })
+}
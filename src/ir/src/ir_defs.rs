@@ -1,7 +1,7 @@
 use std::fmt::Display;
 
 use derive_new::new;
-use fxhash::FxHashMap;
+use fxhash::{ FxHashMap, FxHashSet };
 use span::Span;
 
 use crate::{ Symbol, Ty };
@@ -158,6 +158,14 @@ pub struct DefId {
 #[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
 pub struct PkgId(pub u32);
 
+/// Refers to a module in a package. The directory of a package is its root module,
+/// and each subdirectory is a module nested inside the module of its parent directory
+#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
+pub struct ModuleId {
+    pub pkg_id: PkgId,
+    pub local_id: u32,
+}
+
 impl DefId {
     pub fn new(symbol: Symbol, node_id: NodeId) -> Self {
         Self { symbol, node_id }
@@ -180,6 +188,17 @@ pub enum Externism {
     NoExtern,
 }
 
+/// How a function is treated by the inliner, set with the `@inline` and `@noinline` attributes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InlineAttr {
+    /// Inlined if it's small enough
+    Auto,
+    /// `@inline`: Always inlined (unless the call is recursive)
+    Always,
+    /// `@noinline`: Never inlined
+    Never,
+}
+
 /// Information about a definition
 #[derive(Debug, Clone, Copy)]
 pub struct NameBinding<'res> {
@@ -291,7 +310,7 @@ pub type NodeIdToTy = FxHashMap<NodeId, Ty>;
 pub type NodeIdToDefId = FxHashMap<NodeId, DefId>;
 pub type DefIdToNameBinding<'res> = FxHashMap<DefId, NameBinding<'res>>;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ResolvedInformation<'res> {
     pub node_id_to_ty: NodeIdToTy,
     pub node_id_to_def_id: NodeIdToDefId,
@@ -299,9 +318,34 @@ pub struct ResolvedInformation<'res> {
     // pub def_id_to_global_mem_id: FxHashMap<DefId, GlobalMemId>,
     pub const_strs: Vec<(DefId, ConstStrLen)>,
     pub clib_fns: Vec<DefId>,
+    /// The `onDrop` method of each ADT implementing `Drop`
+    pub adt_def_id_to_drop_fn: FxHashMap<DefId, DefId>,
+    /// ADTs implementing `Copy`
+    pub copy_adt_def_ids: FxHashSet<DefId>,
+    /// Functions only visible in their own file (with the span of their name), which are
+    /// reported if they're never used
+    pub private_fns: FxHashMap<DefId, Span>,
 }
 
 impl<'res> ResolvedInformation<'res> {
+    /// Merges the resolved information of another package into this one
+    pub fn extend(&mut self, other: Self) {
+        self.node_id_to_ty.extend(other.node_id_to_ty);
+        self.node_id_to_def_id.extend(other.node_id_to_def_id);
+        self.def_id_to_name_binding.extend(other.def_id_to_name_binding);
+        self.const_strs.extend(other.const_strs);
+        self.adt_def_id_to_drop_fn.extend(other.adt_def_id_to_drop_fn);
+        self.copy_adt_def_ids.extend(other.copy_adt_def_ids);
+        self.private_fns.extend(other.private_fns);
+
+        // The same C function can be declared in multiple packages, but should only be declared once in LLVM
+        for def_id in other.clib_fns {
+            if !self.clib_fns.iter().any(|x| x.symbol == def_id.symbol) {
+                self.clib_fns.push(def_id);
+            }
+        }
+    }
+
     pub fn get_ty_from_node_id(&self, node_id: &NodeId) -> Ty {
         *self.node_id_to_ty.get(node_id).expect("Expected type to node id")
     }
@@ -324,6 +368,75 @@ impl<'res> ResolvedInformation<'res> {
     //         .expect("Expected global mem to be binded to def id")
     // }
 
+    pub fn get_drop_fn(&self, adt_def_id: &DefId) -> Option<DefId> {
+        self.adt_def_id_to_drop_fn.get(adt_def_id).copied()
+    }
+
+    /// A type needs to be dropped if it implements `Drop`, or if any of its fields
+    /// (or payloads of its enum variants) needs to be dropped
+    pub fn needs_drop(&self, ty: Ty) -> bool {
+        match ty {
+            Ty::Tuple(tys) => tys.iter().any(|ty| self.needs_drop(*ty)),
+            Ty::Adt(def_id) => {
+                if self.adt_def_id_to_drop_fn.contains_key(&def_id) {
+                    return true;
+                }
+
+                match self.get_name_binding_from_def_id(&def_id).kind {
+                    NameBindingKind::Adt(Adt::Struct(fields)) => {
+                        fields.iter().any(|(_, ty)| self.needs_drop(*ty))
+                    }
+                    NameBindingKind::Adt(Adt::Enum(variants)) => {
+                        variants.iter().any(|def_id| self.needs_drop(Ty::Adt(*def_id)))
+                    }
+                    NameBindingKind::Adt(Adt::EnumVariant(_, _, tys)) => {
+                        tys.iter().any(|ty| self.needs_drop(*ty))
+                    }
+                    NameBindingKind::Adt(Adt::Typedef(ty)) => self.needs_drop(ty),
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// A type is copied (instead of moved) when it's used by value. ADTs must implement `Copy`,
+    /// and are still moved if any of their fields are moved, or if they need to be dropped
+    pub fn is_copy(&self, ty: Ty) -> bool {
+        match ty {
+            Ty::Tuple(tys) => tys.iter().all(|ty| self.is_copy(*ty)),
+            Ty::Adt(def_id) => {
+                let name_binding = self.get_name_binding_from_def_id(&def_id);
+                if let NameBindingKind::Adt(Adt::EnumVariant(enum_def_id, _, _)) = name_binding.kind {
+                    return self.is_copy(Ty::Adt(enum_def_id));
+                }
+
+                if !self.copy_adt_def_ids.contains(&def_id) || self.needs_drop(ty) {
+                    return false;
+                }
+
+                match name_binding.kind {
+                    NameBindingKind::Adt(Adt::Struct(fields)) => {
+                        fields.iter().all(|(_, ty)| self.is_copy(*ty))
+                    }
+                    NameBindingKind::Adt(Adt::Enum(variants)) => {
+                        variants.iter().all(|def_id| {
+                            match self.get_name_binding_from_def_id(def_id).kind {
+                                NameBindingKind::Adt(Adt::EnumVariant(_, _, tys)) => {
+                                    tys.iter().all(|ty| self.is_copy(*ty))
+                                }
+                                _ => true,
+                            }
+                        })
+                    }
+                    NameBindingKind::Adt(Adt::Typedef(ty)) => self.is_copy(ty),
+                    _ => true,
+                }
+            }
+            _ => true,
+        }
+    }
+
     pub fn is_clib_fn(&self, def_id: &DefId) -> bool {
         match self.get_name_binding_from_def_id(def_id).kind {
             NameBindingKind::Fn(_, _, Externism::Clib) => true,
@@ -334,8 +447,10 @@ impl<'res> ResolvedInformation<'res> {
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CfgFnKind {
-    Main,
+    Main(DefId),
     Fn(DefId),
+    /// Drops a value of the type (given as a pointer) by calling `onDrop` and dropping its fields
+    DropGlue(Ty),
 }
 
 #[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
@@ -387,7 +502,6 @@ impl Display for GlobalMem {
     }
 }
 
-/// Implement `requires_drop` for when heap objects is implemented
 #[derive(Debug, Clone, Copy, new)]
 pub struct LocalMem {
     pub local_mem_id: LocalMemId,
@@ -395,6 +509,8 @@ pub struct LocalMem {
     pub span: Span,
     pub ty: Ty,
     pub mutability: Mutability,
+    /// If the variable owns a value that has to be dropped when it goes out of scope
+    pub requires_drop: bool,
 }
 
 impl Display for LocalMem {
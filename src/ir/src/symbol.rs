@@ -165,6 +165,15 @@ pub static STR_SYMBOL: LazyLock<Symbol> = LazyLock::new(|| {
 pub static MAIN_SYMBOL: LazyLock<Symbol> = LazyLock::new(|| {
     with_global_session(|globals| globals.intern_str("main"))
 });
+pub static DROP_SYMBOL: LazyLock<Symbol> = LazyLock::new(|| {
+    with_global_session(|globals| globals.intern_str("Drop"))
+});
+pub static COPY_SYMBOL: LazyLock<Symbol> = LazyLock::new(|| {
+    with_global_session(|globals| globals.intern_str("Copy"))
+});
+pub static ON_DROP_SYMBOL: LazyLock<Symbol> = LazyLock::new(|| {
+    with_global_session(|globals| globals.intern_str("onDrop"))
+});
 pub static PKG_SYMBOL: LazyLock<Symbol> = LazyLock::new(|| {
     with_global_session(|globals| globals.intern_str("pkg"))
 });
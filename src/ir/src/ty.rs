@@ -69,6 +69,46 @@ impl TyAttr {
     pub fn new(size_bytes: usize, alignment_bytes: usize) -> Self {
         Self { size_bytes, alignment_bytes }
     }
+
+    /// Lays out fields in order, where each field starts at a multiple of its own alignment and
+    /// the size is padded to a multiple of the largest alignment (the same layout as in LLVM and C)
+    pub fn from_fields(
+        tys: impl IntoIterator<Item = Ty>,
+        resolved_information: &ResolvedInformation
+    ) -> Self {
+        let mut total_size = 0;
+        let mut alignment = 1;
+
+        for ty in tys {
+            let ty_attr = ty.get_ty_attr(resolved_information);
+            total_size = ty_attr.align_offset(total_size) + ty_attr.size_bytes;
+            alignment = alignment.max(ty_attr.alignment_bytes);
+        }
+
+        TyAttr::new(total_size.next_multiple_of(alignment), alignment)
+    }
+
+    /// The byte offset of each field, when laid out like in `from_fields`
+    pub fn get_field_offsets(
+        tys: impl IntoIterator<Item = Ty>,
+        resolved_information: &ResolvedInformation
+    ) -> Vec<usize> {
+        let mut byte_offset = 0;
+
+        tys.into_iter()
+            .map(|ty| {
+                let ty_attr = ty.get_ty_attr(resolved_information);
+                let field_offset = ty_attr.align_offset(byte_offset);
+                byte_offset = field_offset + ty_attr.size_bytes;
+                field_offset
+            })
+            .collect()
+    }
+
+    /// Rounds `byte_offset` up to the first offset where a value of this type can be placed
+    pub fn align_offset(&self, byte_offset: usize) -> usize {
+        byte_offset.next_multiple_of(self.alignment_bytes.max(1))
+    }
 }
 
 pub trait GetTyAttr {
@@ -421,8 +461,13 @@ impl Ty {
                     return Some(BOOL_TY);
                 }
 
+                let is_ptr_or_null = |ty: Ty| ty.is_ptr() || ty == NULL_TY;
                 match (lhs, comparison_op, rhs) {
                     (BOOL_TY, Eq | Ne | Ge | Gt | Le | Lt, BOOL_TY) => Some(BOOL_TY),
+                    // E.g. `ptr != null`
+                    (lhs, Eq | Ne, rhs) if is_ptr_or_null(lhs) && is_ptr_or_null(rhs) => {
+                        Some(BOOL_TY)
+                    }
                     _ => None,
                 }
             }
@@ -490,6 +535,23 @@ impl Ty {
         }
     }
 
+    /// Name of the function dropping a value of this type
+    pub fn display_as_drop_fn(&self) -> String {
+        fn mangle(ty: &Ty) -> String {
+            match ty {
+                Ty::Adt(def_id) => {
+                    format!("{}{}_{}", def_id.symbol.get(), def_id.node_id.mod_id.0, def_id.node_id.node_id)
+                }
+                Ty::Tuple(tys) => {
+                    format!("({})", tys.iter().map(mangle).collect::<Vec<_>>().join(","))
+                }
+                ty => ty.to_string(),
+            }
+        }
+
+        format!("@\"drop.{}\"", mangle(self))
+    }
+
     pub fn try_deref_once(&self) -> Option<Ty> {
         if let Ty::Ptr(inner_ty, _) | Ty::StackPtr(inner_ty, _) = *self {
             Some(*inner_ty)
@@ -509,24 +571,7 @@ impl GetTyAttr for Ty {
             Self::FnDef(_) => TyAttr::new(8, 8),
             Self::FnSig(_) => TyAttr::new(8, 8),
             Self::Null => TyAttr::new(8, 8),
-            Self::Tuple(tuple) => {
-                let mut total_size = 0;
-                let mut alignment = None;
-
-                for ty in tuple.iter() {
-                    let ty_attr = ty.get_ty_attr(resolved_information);
-                    total_size += ty_attr.size_bytes;
-                    if let Some(alignment) = &mut alignment {
-                        if ty_attr.alignment_bytes < *alignment {
-                            *alignment = ty_attr.alignment_bytes;
-                        }
-                    } else {
-                        alignment = Some(ty_attr.alignment_bytes);
-                    }
-                }
-
-                TyAttr::new(total_size, alignment.unwrap_or(0))
-            }
+            Self::Tuple(tuple) => TyAttr::from_fields(tuple.iter().copied(), resolved_information),
             Self::Adt(def_id) => {
                 let name_binding = resolved_information.get_name_binding_from_def_id(def_id);
 
@@ -535,25 +580,12 @@ impl GetTyAttr for Ty {
                         match adt {
                             Adt::EnumVariant(parent_def_id, _, tys) => {
                                 // This is the base size based on only the data inside the enum variant
-                                let mut total_size = 0;
-                                let mut alignment = None;
-
-                                for ty in tys.iter() {
-                                    let ty_attr = ty.get_ty_attr(resolved_information);
-                                    total_size += ty_attr.size_bytes;
-                                    if let Some(alignment) = &mut alignment {
-                                        if ty_attr.alignment_bytes < *alignment {
-                                            *alignment = ty_attr.alignment_bytes;
-                                        }
-                                    } else {
-                                        alignment = Some(ty_attr.alignment_bytes);
-                                    }
-                                }
-
-                                TyAttr::new(total_size, alignment.unwrap_or(0))
+                                TyAttr::from_fields(tys.iter().copied(), resolved_information)
                             }
                             Adt::Enum(variants) => {
                                 let mut largest_variant_size = 0;
+                                // The discriminant is a 64 bit integer
+                                let mut alignment = 8;
 
                                 for def_id in variants {
                                     let variant_ty_attr = Ty::Adt(*def_id).get_ty_attr(
@@ -563,6 +595,7 @@ impl GetTyAttr for Ty {
                                     if variant_ty_attr.size_bytes > largest_variant_size {
                                         largest_variant_size = variant_ty_attr.size_bytes;
                                     }
+                                    alignment = alignment.max(variant_ty_attr.alignment_bytes);
                                 }
 
                                 // The size of the enum is the size of the largest variant + 8 bytes for the discriminant
@@ -571,27 +604,14 @@ impl GetTyAttr for Ty {
                                 // whilst still having enough space in the discriminant to fit all the variants
                                 let total_size = largest_variant_size + 8;
 
-                                TyAttr::new(total_size, 4)
+                                TyAttr::new(total_size.next_multiple_of(alignment), alignment)
                             }
                             Adt::Typedef(ty) => ty.get_ty_attr(resolved_information),
-                            Adt::Struct(fields) => {
-                                let mut total_size = 0;
-                                let mut alignment = None;
-
-                                for (_, ty) in fields.iter() {
-                                    let ty_attr = ty.get_ty_attr(resolved_information);
-                                    total_size += ty_attr.size_bytes;
-                                    if let Some(alignment) = &mut alignment {
-                                        if ty_attr.alignment_bytes < *alignment {
-                                            *alignment = ty_attr.alignment_bytes;
-                                        }
-                                    } else {
-                                        alignment = Some(ty_attr.alignment_bytes);
-                                    }
-                                }
-
-                                TyAttr::new(total_size, alignment.unwrap_or(0))
-                            }
+                            Adt::Struct(fields) =>
+                                TyAttr::from_fields(
+                                    fields.iter().map(|(_, ty)| *ty),
+                                    resolved_information
+                                ),
                         }
                     }
 
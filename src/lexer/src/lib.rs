@@ -63,6 +63,9 @@ impl<'a> Lexer<'a> {
             '=' => self.make_token_or_other_if(TokenKind::Assign, '=', TokenKind::Eq),
             ' ' => self.skip_char_and_scan(),
             ',' => self.make_token(TokenKind::Comma),
+            '?' => self.make_token(TokenKind::Question),
+            '@' => self.make_token(TokenKind::At),
+            '\'' => self.make_label(),
             '\n' => self.newline_and_scan(),
             // this shouldn't be called if char before is ident or ')'
             // '.' if Self::can_be_before_dot_float(prev) && Self::is_digit(self.peek_next()) => {
@@ -136,12 +139,16 @@ impl<'a> Lexer<'a> {
 
         match ident {
             "import" => TokenKind::Import,
+            "pub" => TokenKind::Pub,
+            "export" => TokenKind::Export,
+            "as" => TokenKind::As,
             "fn" => TokenKind::Fn,
             "declare" => TokenKind::Declare,
             "self" => TokenKind::SmallSelf,
             "Self" => TokenKind::BigSelf,
             "mut" => TokenKind::Mut,
             "impl" => TokenKind::Impl,
+            "for" => TokenKind::For,
             "struct" => TokenKind::Struct,
             "enum" => TokenKind::Enum,
             "null" => TokenKind::Null,
@@ -152,15 +159,28 @@ impl<'a> Lexer<'a> {
             "elif" => TokenKind::Elif,
             "break" => TokenKind::Break,
             "continue" => TokenKind::Continue,
+            "defer" => TokenKind::Defer,
             "ret" => TokenKind::Return,
             "true" => TokenKind::True,
             "false" => TokenKind::False,
             "typedef" => TokenKind::Typedef,
             "pkg" => TokenKind::Pkg,
+            "sizeof" => TokenKind::SizeOf,
+            "alignof" => TokenKind::AlignOf,
+            "offsetof" => TokenKind::OffsetOf,
             _ => TokenKind::Ident,
         }
     }
 
+    fn make_label(&mut self) -> Token {
+        if !Self::is_alphabetic(self.peek_next()) {
+            panic!("Expected label name after `'`");
+        }
+
+        self.eat_while_from_next(|c| Self::is_alphabetic(c) || Self::is_digit(c));
+        self.make_token(TokenKind::Label)
+    }
+
     fn make_float_number(&mut self) -> Token {
         self.advance();
         self.eat_while_from_next(Self::is_digit);
@@ -279,4 +299,28 @@ mod test {
         }
         assert_eq!(TokenKind::Eof, lexer.scan_token().get_kind());
     }
+
+    #[test]
+    fn loop_labels() {
+        expect_tokens(
+            "'outer: loop { continue 'outer }",
+            &[
+                TokenKind::Label,
+                TokenKind::Colon,
+                TokenKind::Loop,
+                TokenKind::LeftCurly,
+                TokenKind::Continue,
+                TokenKind::Label,
+                TokenKind::RightCurly,
+            ]
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        expect_tokens(
+            "@inline pub fn",
+            &[TokenKind::At, TokenKind::Ident, TokenKind::Pub, TokenKind::Fn]
+        );
+    }
 }
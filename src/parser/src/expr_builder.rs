@@ -17,6 +17,7 @@ use ast::{
     IfExpr,
     IndexExpr,
     IntegerExpr,
+    IntrinsicExpr,
     LoopExpr,
     NullExpr,
     PkgIdentNode,
@@ -26,6 +27,7 @@ use ast::{
     StructExpr,
     TupleExpr,
     TupleFieldExpr,
+    TryExpr,
     ValueExpr,
 };
 use op::BinaryOp;
@@ -342,12 +344,17 @@ impl<'ast, 'b> ExprBuilder<'ast, 'b> {
         self.exprs.push(expr);
     }
 
-    pub fn emit_binary_expr(&mut self, op: BinaryOp, parser_handle: &mut impl ParserHandle<'ast>) {
+    pub fn emit_binary_expr(
+        &mut self,
+        op: BinaryOp,
+        span: Span,
+        parser_handle: &mut impl ParserHandle<'ast>
+    ) {
         let rhs = self.exprs.pop().expect("TODO: Error handling");
         let lhs = self.exprs.pop().expect("TODO: Error handling");
 
         let binary_expr = self.ast_arena.alloc_expr_or_stmt(
-            BinaryExpr::new(lhs, op, rhs, Span::dummy(), parser_handle.get_ast_node_id())
+            BinaryExpr::new(lhs, op, rhs, span, parser_handle.get_ast_node_id())
         );
 
         let expr = Expr::ExprWithoutBlock(
@@ -357,6 +364,28 @@ impl<'ast, 'b> ExprBuilder<'ast, 'b> {
         self.exprs.push(expr);
     }
 
+    pub fn emit_intrinsic_expr(&mut self, intrinsic_expr: IntrinsicExpr<'ast>) {
+        let intrinsic_expr = self.ast_arena.alloc_expr_or_stmt(intrinsic_expr);
+
+        let expr = Expr::ExprWithoutBlock(
+            ExprWithoutBlock::ValueExpr(ValueExpr::IntrinsicExpr(intrinsic_expr))
+        );
+
+        self.exprs.push(expr);
+    }
+
+    pub fn emit_try_expr(&mut self, span: Span, parser_handle: &mut impl ParserHandle<'ast>) {
+        let expr = self.exprs.pop().expect("TODO: Error handling");
+
+        let try_expr = self.ast_arena.alloc_expr_or_stmt(
+            TryExpr::new(expr, span, parser_handle.get_ast_node_id())
+        );
+
+        let expr = Expr::ExprWithoutBlock(ExprWithoutBlock::ValueExpr(ValueExpr::TryExpr(try_expr)));
+
+        self.exprs.push(expr);
+    }
+
     pub fn emit_post_inc_expr(&mut self, parser_handle: &mut impl ParserHandle<'ast>) {
         todo!()
 
@@ -31,11 +31,13 @@ static PARSE_RULES: LazyLock<[ParseRule; PARSE_RULE_COUNT]> = LazyLock::new(|| {
                 Dot         = { (dot_float  None),      (field_expr PrecCall        ),      (None       None) },
                 Comma       = { (None       None),      (None       None            ),      (None       None) },
                 Bang        = { (None       None),      (None       None            ),      (None       None) },
+                Question    = { (None       None),      (None       None            ),      (try_expr   None) },
                 Increment   = { (pre_inc    None),      (None       None            ),      (post_inc   None) },
                 Decrement   = { (pre_dec    None),      (None       None            ),      (post_dec   None) },
                 DoubleQuote = { (string     None),      (None       None            ),      (None       None) },
                 StringChar  = { (None       None),      (None       None            ),      (None       None) },
                 Ellipsis    = { (None       None),      (None       None            ),      (None       None) },
+                At          = { (None       None),      (None       None            ),      (None       None) },
                 
                     
                 // Numbers
@@ -52,9 +54,16 @@ static PARSE_RULES: LazyLock<[ParseRule; PARSE_RULE_COUNT]> = LazyLock::new(|| {
                 // Identifier
                 Ident       = { (ident      None),      (None       None            ),      (None       None) },
 
+                // Loop label
+                Label       = { (labeled_loop None),    (None       None            ),      (None       None) },
+
                 // Keywords
                 Import      = { (None       None),      (None       None            ),      (None       None) },
+                Pub         = { (None       None),      (None       None            ),      (None       None) },
+                Export      = { (None       None),      (None       None            ),      (None       None) },
+                As          = { (None       None),      (None       None            ),      (None       None) },
                 Impl        = { (None       None),      (None       None            ),      (None       None) },
+                For         = { (None       None),      (None       None            ),      (None       None) },
                 SmallSelf   = { (ident      None),      (None       None            ),      (None       None) },
                 BigSelf     = { (ident      None),      (None       None            ),      (None       None) },
                 Fn          = { (None       None),      (None       None            ),      (None       None) },
@@ -68,10 +77,14 @@ static PARSE_RULES: LazyLock<[ParseRule; PARSE_RULE_COUNT]> = LazyLock::new(|| {
                 Loop        = { (loop_expr  None),      (None       None            ),      (None       None) },
                 Break       = { (None       None),      (None       None            ),      (None       None) },
                 Continue    = { (None       None),      (None       None            ),      (None       None) },
+                Defer       = { (None       None),      (None       None            ),      (None       None) },
                 Return      = { (None       None),      (None       None            ),      (None       None) },
                 Else        = { (None       None),      (None       None            ),      (None       None) },
                 Elif        = { (None       None),      (None       None            ),      (None       None) },
                 Pkg         = { (pkg_ident  None),      (None       None            ),      (None       None) },
+                SizeOf      = { (size_of    None),      (None       None            ),      (None       None) },
+                AlignOf     = { (align_of   None),      (None       None            ),      (None       None) },
+                OffsetOf    = { (offset_of  None),      (None       None            ),      (None       None) },
 
                 Eof         = { (None       None),      (None       None            ),      (None       None) }
                 
@@ -96,6 +109,7 @@ use ast::{
     CompFnDeclItem,
     CondKind,
     ContinueExpr,
+    DeferExpr,
     EnumItem,
     EnumVariant,
     Expr,
@@ -109,14 +123,21 @@ use ast::{
     IfFalseBranchExpr,
     ImplItem,
     ImportItem,
+    ImportPath,
+    ExportItem,
     IntegerExpr,
+    IntrinsicExpr,
+    IntrinsicKind,
     ItemStmt,
     ItemType,
     LoopExpr,
     NullExpr,
     Pat,
     Path,
+    PathAlias,
+    PathEverything,
     PathField,
+    PathMultiple,
     PkgIdentNode,
     PlaceExpr,
     ReturnExpr,
@@ -125,6 +146,7 @@ use ast::{
     StructItem,
     TupleStructPat,
     TypedefItem,
+    Visibility,
     Typing,
     ValueExpr,
 };
@@ -134,6 +156,7 @@ use expr_builder::ExprBuilder;
 use ir::{
     Delimeter,
     ExpectedSymbolKind,
+    InlineAttr,
     ItemErrorKind,
     MissingCommaPlace,
     ModId,
@@ -261,6 +284,7 @@ impl<'a> ParserHandle<'a> for Parser<'a, '_> {
                     }
                     ExprWithoutBlock::BreakExpr(_) => None,
                     ExprWithoutBlock::ContinueExpr(_) => None,
+                    ExprWithoutBlock::DeferExpr(_) => None,
                     ExprWithoutBlock::ReturnExpr(_) => None,
                     ExprWithoutBlock::ValueExpr(value_expr) => {
                         match value_expr {
@@ -270,6 +294,8 @@ impl<'a> ParserHandle<'a> for Parser<'a, '_> {
                             ValueExpr::GroupExpr(_) => None,
                             ValueExpr::StructExpr(_) => None,
                             ValueExpr::TupleExpr(_) => None,
+                            ValueExpr::TryExpr(_) => None,
+                            ValueExpr::IntrinsicExpr(_) => None,
                         }
                     }
                 }
@@ -298,6 +324,7 @@ impl<'a> ParserHandle<'a> for Parser<'a, '_> {
                     }
                     ExprWithoutBlock::BreakExpr(_) => None,
                     ExprWithoutBlock::ContinueExpr(_) => None,
+                    ExprWithoutBlock::DeferExpr(_) => None,
                     ExprWithoutBlock::ReturnExpr(_) => None,
                     ExprWithoutBlock::ValueExpr(expr) => {
                         match expr {
@@ -345,6 +372,8 @@ impl<'a> ParserHandle<'a> for Parser<'a, '_> {
                             ValueExpr::StructExpr(_) => None,
                             ValueExpr::TupleExpr(tuple_expr) =>
                                 todo!("As pattern: {:#?}", tuple_expr),
+                            ValueExpr::TryExpr(_) => None,
+                            ValueExpr::IntrinsicExpr(_) => None,
                         }
                     }
                 }
@@ -361,6 +390,7 @@ impl<'a> ParserHandle<'a> for Parser<'a, '_> {
                     ExprWithoutBlock::BreakExpr(_) => None,
                     ExprWithoutBlock::ReturnExpr(_) => None,
                     ExprWithoutBlock::ContinueExpr(_) => None,
+                    ExprWithoutBlock::DeferExpr(_) => None,
                     ExprWithoutBlock::ValueExpr(expr) => {
                         match expr {
                             ValueExpr::BinaryExpr(_) => None,
@@ -371,6 +401,8 @@ impl<'a> ParserHandle<'a> for Parser<'a, '_> {
                             ValueExpr::StructExpr(_) => None,
                             ValueExpr::TupleExpr(tuple_expr) =>
                                 todo!("As place expr: {:#?}", tuple_expr),
+                            ValueExpr::TryExpr(_) => None,
+                            ValueExpr::IntrinsicExpr(_) => None,
                         }
                     }
                 }
@@ -386,6 +418,7 @@ impl<'a> ParserHandle<'a> for Parser<'a, '_> {
                     ExprWithoutBlock::BreakExpr(_) => None,
                     ExprWithoutBlock::ReturnExpr(_) => None,
                     ExprWithoutBlock::ContinueExpr(_) => None,
+                    ExprWithoutBlock::DeferExpr(_) => None,
                     ExprWithoutBlock::ValueExpr(_) => None,
                     ExprWithoutBlock::PlaceExpr(expr) => {
                         match expr {
@@ -468,32 +501,140 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
     pub(crate) fn statement(&mut self) -> Option<Stmt<'a>> {
         match self.current.get_kind() {
             TokenKind::Impl => self.impl_statement(),
+            | TokenKind::Typedef
+            | TokenKind::Struct
+            | TokenKind::Enum
+            | TokenKind::Fn
+            | TokenKind::Declare => self.item_statement(Visibility::Private),
+            TokenKind::Pub => self.pub_statement(),
+            TokenKind::At => self.attributed_fn_statement(),
+            TokenKind::Mut => Some(self.mut_stmt()),
+            TokenKind::Break => Some(self.break_expr()),
+            TokenKind::Continue => Some(self.continue_expr()),
+            TokenKind::Defer => Some(self.defer_expr()),
+            TokenKind::Return => Some(self.return_expr()),
+            TokenKind::Import => self.import_statement(),
+            TokenKind::Export => self.export_statement(),
+            _ => self.expression_statement(),
+        }
+    }
+
+    pub(crate) fn item_statement(&mut self, visibility: Visibility) -> Option<Stmt<'a>> {
+        match self.current.get_kind() {
             TokenKind::Typedef => {
                 self.def_count += 1;
-                Some(self.typedef_statement())
+                Some(self.typedef_statement(visibility))
             }
             TokenKind::Struct => {
                 self.def_count += 1;
-                Some(self.struct_item())
+                Some(self.struct_item(visibility))
             }
             TokenKind::Enum => {
                 self.def_count += 1;
-                Some(self.enum_item())
+                Some(self.enum_item(visibility))
             }
-            TokenKind::Mut => Some(self.mut_stmt()),
-            TokenKind::Break => Some(self.break_expr()),
-            TokenKind::Continue => Some(self.continue_expr()),
             TokenKind::Fn => {
-                if let Some(fn_item) = self.function_statement() {
+                if let Some(fn_item) = self.function_statement(visibility, InlineAttr::Auto) {
                     Some(Stmt::ItemStmt(ItemStmt::FnItem(fn_item)))
                 } else {
                     None
                 }
             }
-            TokenKind::Declare => self.declare_statement(),
-            TokenKind::Return => Some(self.return_expr()),
-            TokenKind::Import => Some(self.import_statement()),
-            _ => self.expression_statement(),
+            TokenKind::Declare => self.declare_statement(visibility),
+            _ => unreachable!("Expected item"),
+        }
+    }
+
+    /// A function with attributes, e.g. `@inline pub fn add(a int, b int) int { ... }`
+    pub(crate) fn attributed_fn_statement(&mut self) -> Option<Stmt<'a>> {
+        let inline_attr = self.parse_fn_attributes();
+        let is_public = self.is_curr_kind(TokenKind::Pub) || self.is_curr_kind(TokenKind::Export);
+        let visibility = if is_public {
+            self.advance();
+            Visibility::Public
+        } else {
+            Visibility::Private
+        };
+
+        if !self.is_curr_kind(TokenKind::Fn) {
+            self.report_error(
+                ErrorKind::UnexpectedTokens {
+                    expected_str: "Expected function after attributes",
+                },
+                self.current.get_span()
+            );
+            self.synchronize();
+            return None;
+        }
+
+        self.function_statement(visibility, inline_attr).map(|fn_item| {
+            Stmt::ItemStmt(ItemStmt::FnItem(fn_item))
+        })
+    }
+
+    /// Parses the attributes before a function (`@inline` or `@noinline`), if any
+    pub(crate) fn parse_fn_attributes(&mut self) -> InlineAttr {
+        let mut inline_attr = InlineAttr::Auto;
+
+        while self.is_curr_kind(TokenKind::At) {
+            self.advance();
+            let attr_span = self.current.get_span();
+            if !self.is_curr_kind(TokenKind::Ident) {
+                self.report_error(
+                    ErrorKind::ExpectedIdent {
+                        additional_info: Some("after `@`"),
+                        found: Symbol::new(self.get_lexeme_of_current()),
+                    },
+                    attr_span
+                );
+                continue;
+            }
+
+            let attr_inline_attr = match self.get_lexeme_of_current() {
+                "inline" => InlineAttr::Always,
+                "noinline" => InlineAttr::Never,
+                attr => {
+                    let symbol = Symbol::new(attr);
+                    self.report_error(ErrorKind::UnknownAttribute { symbol }, attr_span);
+                    self.advance();
+                    continue;
+                }
+            };
+            self.advance();
+
+            if inline_attr != InlineAttr::Auto && inline_attr != attr_inline_attr {
+                self.report_error(
+                    ErrorKind::UnexpectedTokens {
+                        expected_str: "`@inline` and `@noinline` can't be used together",
+                    },
+                    attr_span
+                );
+            }
+            inline_attr = attr_inline_attr;
+        }
+
+        inline_attr
+    }
+
+    pub(crate) fn pub_statement(&mut self) -> Option<Stmt<'a>> {
+        self.advance();
+
+        match self.current.get_kind() {
+            | TokenKind::Typedef
+            | TokenKind::Struct
+            | TokenKind::Enum
+            | TokenKind::Fn
+            | TokenKind::Declare => self.item_statement(Visibility::Public),
+            _ => {
+                self.report_error(
+                    ErrorKind::UnexpectedTokens {
+                        expected_str: "Expected item after `pub`",
+                    },
+                    self.current.get_span()
+                );
+                self.synchronize();
+                None
+            }
         }
     }
 
@@ -536,16 +677,155 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
         path
     }
 
-    pub(crate) fn import_statement(&mut self) -> Stmt<'a> {
+    /// Parses a path in an import, which (unlike other paths) can end with
+    /// `as alias`, `*` or a group of paths in brackets e.g. `pkg.{Point, Vec as V}`
+    ///
+    /// Returns `None` after reporting an error if the path is malformed
+    pub(crate) fn parse_import_path(&mut self) -> Option<ImportPath<'a>> {
+        let start_span = self.current.get_span();
+
+        let path = if self.is_curr_kind(TokenKind::Pkg) {
+            let pkg_ident = self.consume_pkg_ident("Expected package ident in import");
+            Path::PathPkg(self.ast_arena.alloc_expr_or_stmt(pkg_ident))
+        } else {
+            let first_ident = self.consume_import_ident("Expected ident in import")?;
+            Path::PathSegment(self.ast_arena.alloc_expr_or_stmt(first_ident))
+        };
+
+        self.parse_import_path_rest(path, start_span)
+    }
+
+    fn parse_import_path_rest(
+        &mut self,
+        mut path: Path<'a>,
+        start_span: Span
+    ) -> Option<ImportPath<'a>> {
+        while self.is_curr_kind(TokenKind::Dot) {
+            self.advance();
+
+            if self.is_curr_kind(TokenKind::Star) {
+                self.advance();
+                let span = Span::merge(start_span, self.prev.get_span());
+                return Some(
+                    ImportPath::PathEverything(
+                        self.ast_arena.alloc_expr_or_stmt(
+                            PathEverything::new(path, span, self.get_ast_node_id())
+                        )
+                    )
+                );
+            }
+
+            if self.is_curr_kind(TokenKind::LeftCurly) {
+                self.advance();
+                let mut paths = Vec::new();
+
+                while !self.is_eof() && !self.is_curr_kind(TokenKind::RightCurly) {
+                    let item_start_span = self.current.get_span();
+                    let ident = self.consume_import_ident("Expected ident in import group")?;
+                    let item_path = Path::PathField(
+                        self.ast_arena.alloc_expr_or_stmt(
+                            PathField::new(
+                                path,
+                                self.ast_arena.alloc_expr_or_stmt(ident),
+                                Span::merge(start_span, self.prev.get_span()),
+                                self.get_ast_node_id()
+                            )
+                        )
+                    );
+                    paths.push(self.parse_import_path_rest(item_path, item_start_span)?);
+
+                    if self.is_curr_kind(TokenKind::Comma) {
+                        self.advance();
+                    } else {
+                        break;
+                    }
+                }
+
+                if !self.is_curr_kind(TokenKind::RightCurly) {
+                    self.report_error(
+                        ErrorKind::UnexpectedTokens {
+                            expected_str: "Expected `}` after import group",
+                        },
+                        self.current.get_span()
+                    );
+                    return None;
+                }
+                self.advance();
+                let span = Span::merge(start_span, self.prev.get_span());
+                return Some(
+                    ImportPath::PathMultiple(
+                        self.ast_arena.alloc_expr_or_stmt(
+                            PathMultiple::new(
+                                self.ast_arena.alloc_vec(paths),
+                                span,
+                                self.get_ast_node_id()
+                            )
+                        )
+                    )
+                );
+            }
+
+            let ident = self.consume_import_ident("Expected ident in import")?;
+            let span = Span::merge(start_span, self.prev.get_span());
+            path = Path::PathField(
+                self.ast_arena.alloc_expr_or_stmt(
+                    PathField::new(
+                        path,
+                        self.ast_arena.alloc_expr_or_stmt(ident),
+                        span,
+                        self.get_ast_node_id()
+                    )
+                )
+            );
+        }
+
+        if self.is_curr_kind(TokenKind::As) {
+            self.advance();
+            let alias = self.consume_import_ident("Expected ident after `as`")?;
+            let span = Span::merge(start_span, self.prev.get_span());
+            return Some(
+                ImportPath::PathAlias(
+                    self.ast_arena.alloc_expr_or_stmt(
+                        PathAlias::new(
+                            path,
+                            self.ast_arena.alloc_expr_or_stmt(alias),
+                            span,
+                            self.get_ast_node_id()
+                        )
+                    )
+                )
+            );
+        }
+
+        Some(ImportPath::Path(path))
+    }
+
+    /// Reports an error if the current token isn't an ident
+    fn consume_import_ident(&mut self, expected_str: &'static str) -> Option<IdentNode> {
+        let ident_node = self.try_consume_ident();
+        if ident_node.is_none() {
+            self.report_error(ErrorKind::UnexpectedTokens { expected_str }, self.current.get_span());
+        }
+        ident_node
+    }
+
+    pub(crate) fn import_statement(&mut self) -> Option<Stmt<'a>> {
         let start_span = self.current.get_span();
         self.advance();
-        let mut import_items = vec![self.parse_path()];
+        let mut import_items = Vec::new();
+
+        loop {
+            let Some(import_path) = self.parse_import_path() else {
+                // Skips the rest of an import group too
+                if self.synchronize_with_callback(|kind| kind == TokenKind::RightCurly) {
+                    self.advance();
+                }
+                return None;
+            };
+            import_items.push(import_path);
 
-        while !self.is_eof() {
             if self.is_curr_kind(TokenKind::Comma) {
                 self.advance();
-                import_items.push(self.parse_path());
-                continue;
             } else {
                 break;
             }
@@ -561,14 +841,83 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
             )
         );
 
-        Stmt::ItemStmt(import_stmt)
+        Some(Stmt::ItemStmt(import_stmt))
+    }
+
+    /// Either an item made public like with `pub` (e.g. `export struct Token {}`), or a list of
+    /// items to make public (e.g. `export Foo, Bar`)
+    pub(crate) fn export_statement(&mut self) -> Option<Stmt<'a>> {
+        let start_span = self.current.get_span();
+        self.advance();
+
+        match self.current.get_kind() {
+            | TokenKind::Typedef
+            | TokenKind::Struct
+            | TokenKind::Enum
+            | TokenKind::Fn
+            | TokenKind::Declare => {
+                return self.item_statement(Visibility::Public);
+            }
+            TokenKind::Ident => {}
+            _ => {
+                self.report_error(
+                    ErrorKind::UnexpectedTokens {
+                        expected_str: "Expected item or ident after `export`",
+                    },
+                    self.current.get_span()
+                );
+                self.synchronize();
+                return None;
+            }
+        }
+
+        let mut exported_items = vec![
+            self.ast_arena.alloc_expr_or_stmt(self.consume_ident("Expected ident after `export`"))
+        ];
+
+        while self.is_curr_kind(TokenKind::Comma) {
+            self.advance();
+            let Some(ident_node) = self.try_consume_ident() else {
+                self.report_error(
+                    ErrorKind::UnexpectedTokens {
+                        expected_str: "Expected ident after `,` in export",
+                    },
+                    self.current.get_span()
+                );
+                self.synchronize();
+                return None;
+            };
+            exported_items.push(self.ast_arena.alloc_expr_or_stmt(ident_node));
+        }
+
+        let export_stmt = ItemStmt::ExportItem(
+            self.ast_arena.alloc_expr_or_stmt(
+                ExportItem::new(
+                    self.ast_arena.alloc_vec(exported_items),
+                    Span::merge(start_span, self.current.get_span()),
+                    self.get_ast_node_id()
+                )
+            )
+        );
+
+        Some(Stmt::ItemStmt(export_stmt))
     }
 
     pub(crate) fn impl_statement(&mut self) -> Option<Stmt<'a>> {
         let start_span = self.current.get_span();
         self.advance();
 
-        let impl_path = self.parse_path();
+        let (impl_path, implemented_trait) = {
+            let path = self.parse_path();
+
+            // `impl Drop for Foo` where `Drop` is the trait and `Foo` is the implementor
+            if self.is_curr_kind(TokenKind::For) {
+                self.advance();
+                (self.parse_path(), Some(path))
+            } else {
+                (path, None)
+            }
+        };
 
         let success = self.consume_or_report_error(
             TokenKind::LeftCurly,
@@ -588,13 +937,15 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
         let mut impl_fn_items = Vec::with_capacity(8);
 
         while !self.is_eof() && !self.is_curr_kind(TokenKind::RightCurly) {
-            let fn_item = self.function_statement();
+            let inline_attr = self.parse_fn_attributes();
+            // Methods are accessed through their type, so they're as visible as the type itself
+            let fn_item = self.function_statement(Visibility::Public, inline_attr);
 
             if let Some(fn_item) = fn_item {
                 impl_fn_items.push(fn_item);
             }
 
-            if !self.is_curr_kind(TokenKind::Fn) {
+            if !self.is_curr_kind(TokenKind::Fn) && !self.is_curr_kind(TokenKind::At) {
                 break;
             }
         }
@@ -619,6 +970,7 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
 
         let impl_item = ImplItem::new(
             impl_path,
+            implemented_trait,
             self.ast_arena.alloc_vec(impl_fn_items),
             Span::merge(start_span, self.current.get_span()),
             self.get_ast_node_id()
@@ -629,13 +981,21 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
         Some(Stmt::ItemStmt(impl_item_stmt))
     }
 
-    pub(crate) fn declare_statement(&mut self) -> Option<Stmt<'a>> {
+    pub(crate) fn declare_statement(&mut self, visibility: Visibility) -> Option<Stmt<'a>> {
         let start_span = self.current.get_span();
         self.advance();
 
         if !(self.current.get_kind() == TokenKind::Fn) {
-            panic!("Error: Declare statement must be a C function declaration");
+            self.report_error(
+                ErrorKind::UnexpectedTokens {
+                    expected_str: "Declare statement must be a C function declaration",
+                },
+                self.current.get_span()
+            );
+            self.synchronize();
+            return None;
         }
+        let fn_span = self.current.get_span();
 
         let parsed_fn_sig = self.parse_fn_signature(ParsingDeclareFn::Yes);
 
@@ -648,18 +1008,30 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
         };
 
         if ItemType::Normal == item_type {
-            panic!("Error: Declare statement must be a C function declaration");
+            self.report_error(
+                ErrorKind::UnexpectedTokens {
+                    expected_str: "Declare statement must be a C function declaration (`fn.C`)",
+                },
+                fn_span
+            );
+            return None;
         }
 
-        let fields = parsed_fn_sig.args
-            .iter()
-            .map(|arg| {
-                match *arg {
-                    ArgKind::Arg(field) => field,
-                    _ => panic!("Error: Only normal arguments are allowed in function declaration"),
+        let mut fields = Vec::with_capacity(parsed_fn_sig.args.len());
+        for arg in parsed_fn_sig.args.iter() {
+            match *arg {
+                ArgKind::Arg(field) => fields.push(field),
+                _ => {
+                    self.report_error(
+                        ErrorKind::UnexpectedTokens {
+                            expected_str: "Only normal arguments are allowed in a declaration",
+                        },
+                        fn_span
+                    );
+                    return None;
                 }
-            })
-            .collect::<Vec<_>>();
+            }
+        }
 
         let comp_decl_item = CompDeclItem::CompFnDeclItem(
             self.ast_arena.alloc_expr_or_stmt(
@@ -667,6 +1039,7 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
                     parsed_fn_sig.ident,
                     self.ast_arena.alloc_vec(fields),
                     parsed_fn_sig.ret_typing,
+                    visibility,
                     Span::merge(start_span, self.current.get_span()),
                     self.get_ast_node_id()
                 )
@@ -676,7 +1049,7 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
         Some(Stmt::ItemStmt(ItemStmt::CompDeclItem(comp_decl_item)))
     }
 
-    pub(crate) fn typedef_statement(&mut self) -> Stmt<'a> {
+    pub(crate) fn typedef_statement(&mut self, visibility: Visibility) -> Stmt<'a> {
         let start_span = self.current.get_span();
         self.advance();
         let ident_node = self.consume_ident("Expected ident after `typedef`");
@@ -688,6 +1061,7 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
                     self.ast_arena.alloc_expr_or_stmt(ident_node),
                     ty,
                     ItemType::Normal,
+                    visibility,
                     Span::merge(start_span, self.current.get_span()),
                     self.get_ast_node_id()
                 )
@@ -697,7 +1071,7 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
         Stmt::ItemStmt(typedef_stmt)
     }
 
-    pub(crate) fn enum_item(&mut self) -> Stmt<'a> {
+    pub(crate) fn enum_item(&mut self, visibility: Visibility) -> Stmt<'a> {
         let start_span = self.current.get_span();
         self.advance();
         let ident_node = self.consume_ident("Expected ident after `enum`");
@@ -772,6 +1146,7 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
             self.ast_arena.alloc_expr_or_stmt(ident_node),
             self.ast_arena.alloc_vec(variants),
             ItemType::Normal,
+            visibility,
             Span::merge(start_span, self.current.get_span()),
             self.get_ast_node_id()
         );
@@ -779,7 +1154,7 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
         Stmt::ItemStmt(ItemStmt::EnumItem(self.ast_arena.alloc_expr_or_stmt(enum_item)))
     }
 
-    pub(crate) fn struct_item(&mut self) -> Stmt<'a> {
+    pub(crate) fn struct_item(&mut self, visibility: Visibility) -> Stmt<'a> {
         let start_span = self.current.get_span();
         self.advance();
 
@@ -835,6 +1210,7 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
             self.ast_arena.alloc_expr_or_stmt(ident_node),
             fields,
             item_type,
+            visibility,
             Span::merge(start_span, self.current.get_span()),
             self.get_ast_node_id()
         );
@@ -930,20 +1306,43 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
     }
 
     pub(crate) fn break_expr(&mut self) -> Stmt<'a> {
+        let start_span = self.current.get_span();
+        self.advance();
+
+        let label = self.try_consume_label().map(|label| self.ast_arena.alloc_expr_or_stmt(label));
+
+        let break_value_expr = if self.get_parse_rule_of_current().prefix_method.is_some() {
+            Some(self.parse_expr_and_take(Precedence::PrecAssign.get_next()))
+        } else {
+            None
+        };
+
         let break_expr = self.ast_arena.alloc_expr_or_stmt(
-            BreakExpr::new(None, self.current.get_span(), self.get_ast_node_id())
+            BreakExpr::new(
+                label,
+                break_value_expr,
+                Span::merge(start_span, self.prev.get_span()),
+                self.get_ast_node_id()
+            )
         );
         let expr = Expr::ExprWithoutBlock(ExprWithoutBlock::BreakExpr(break_expr));
-        self.advance();
         Stmt::ExprStmt(expr)
     }
 
     pub(crate) fn continue_expr(&mut self) -> Stmt<'a> {
+        let start_span = self.current.get_span();
+        self.advance();
+
+        let label = self.try_consume_label().map(|label| self.ast_arena.alloc_expr_or_stmt(label));
+
         let continue_expr = self.ast_arena.alloc_expr_or_stmt(
-            ContinueExpr::new(self.current.get_span(), self.get_ast_node_id())
+            ContinueExpr::new(
+                label,
+                Span::merge(start_span, self.prev.get_span()),
+                self.get_ast_node_id()
+            )
         );
         let expr = Expr::ExprWithoutBlock(ExprWithoutBlock::ContinueExpr(continue_expr));
-        self.advance();
         Stmt::ExprStmt(expr)
     }
 
@@ -1160,7 +1559,11 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
         ))
     }
 
-    pub(crate) fn function_statement(&mut self) -> Option<&'a FnItem<'a>> {
+    pub(crate) fn function_statement(
+        &mut self,
+        visibility: Visibility,
+        inline_attr: InlineAttr
+    ) -> Option<&'a FnItem<'a>> {
         let start_span = self.current.get_span();
         let parsed_fn_sig = self.parse_fn_signature(ParsingDeclareFn::No);
 
@@ -1208,6 +1611,8 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
                 parsed_fn_sig.args,
                 parsed_fn_sig.ret_typing,
                 item_type,
+                inline_attr,
+                visibility,
                 Span::merge(start_span, self.current.get_span()),
                 self.get_ast_node_id()
             )
@@ -1239,6 +1644,25 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
         Stmt::ExprStmt(expr)
     }
 
+    fn defer_expr(&mut self) -> Stmt<'a> {
+        let start_span = self.current.get_span();
+        self.advance();
+
+        let deferred_expr = self.parse_expr_and_take(Precedence::PrecAssign.get_next());
+
+        let defer_expr = self.ast_arena.alloc_expr_or_stmt(
+            DeferExpr::new(
+                deferred_expr,
+                Span::merge(start_span, self.current.get_span()),
+                self.get_ast_node_id()
+            )
+        );
+
+        let expr = Expr::ExprWithoutBlock(ExprWithoutBlock::DeferExpr(defer_expr));
+
+        Stmt::ExprStmt(expr)
+    }
+
     pub(crate) fn mut_stmt(&mut self) -> Stmt<'a> {
         let mut_span = self.prev.get_span();
         self.advance();
@@ -1419,6 +1843,85 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
         IntegerExpr::new(val, self.prev.get_span(), self.get_ast_node_id())
     }
 
+    /// Parse rule method: `size_of`
+    pub(crate) fn size_of(&mut self, expr_builder: &mut ExprBuilder<'a, 'b>) {
+        let intrinsic_expr = self.parse_intrinsic_expr(IntrinsicKind::SizeOf);
+        expr_builder.emit_intrinsic_expr(intrinsic_expr);
+    }
+
+    /// Parse rule method: `align_of`
+    pub(crate) fn align_of(&mut self, expr_builder: &mut ExprBuilder<'a, 'b>) {
+        let intrinsic_expr = self.parse_intrinsic_expr(IntrinsicKind::AlignOf);
+        expr_builder.emit_intrinsic_expr(intrinsic_expr);
+    }
+
+    /// Parse rule method: `offset_of`
+    pub(crate) fn offset_of(&mut self, expr_builder: &mut ExprBuilder<'a, 'b>) {
+        let intrinsic_expr = self.parse_intrinsic_expr(IntrinsicKind::OffsetOf);
+        expr_builder.emit_intrinsic_expr(intrinsic_expr);
+    }
+
+    /// Parses the arguments of an intrinsic e.g. `(T)` in `sizeof(T)`,
+    /// or `(Struct, field)` in `offsetof(Struct, field)`
+    fn parse_intrinsic_expr(&mut self, kind: IntrinsicKind) -> IntrinsicExpr<'a> {
+        let start_span = self.prev.get_span();
+        self.consume(TokenKind::LeftParen, "Expected `(` after intrinsic");
+
+        let typing = self.parse_typing().expect("Expected type in intrinsic");
+        let typing_ast_node_id = self.get_ast_node_id();
+
+        let mut success = true;
+        let field = if kind == IntrinsicKind::OffsetOf {
+            success = self.consume_in_intrinsic(TokenKind::Comma, "after the type in `offsetof`");
+            success.then(|| {
+                let ident = self.consume_ident("Expected field name in `offsetof`");
+                self.ast_arena.alloc_expr_or_stmt(ident)
+            })
+        } else {
+            None
+        };
+
+        if success {
+            let additional_info = match kind {
+                IntrinsicKind::SizeOf => "after the type in `sizeof`",
+                IntrinsicKind::AlignOf => "after the type in `alignof`",
+                IntrinsicKind::OffsetOf => "after the field in `offsetof`",
+            };
+            self.consume_in_intrinsic(TokenKind::RightParen, additional_info);
+        }
+
+        IntrinsicExpr::new(
+            kind,
+            typing,
+            field,
+            typing_ast_node_id,
+            Span::merge(start_span, self.prev.get_span()),
+            self.get_ast_node_id()
+        )
+    }
+
+    /// Reports an error if the current token isn't `kind` (e.g. a path like `E.A` is given as the
+    /// type), and skips past the closing `)` of the intrinsic
+    fn consume_in_intrinsic(&mut self, kind: TokenKind, additional_info: &'static str) -> bool {
+        let success = self.consume_or_report_error(
+            kind,
+            ErrorKind::ExpectedToken {
+                additional_info: Some(additional_info),
+                expected: kind,
+                found: Symbol::new(self.get_lexeme_of_current()),
+            },
+            self.current.get_span()
+        );
+
+        if !success {
+            let found_paren = self.synchronize_with_callback(|token_kind| {
+                token_kind == TokenKind::RightParen
+            });
+            self.advance_if(found_paren);
+        }
+        success
+    }
+
     /// Parse rule method: `dot_float`
     pub(crate) fn dot_float(&mut self, _expr_builder: &mut ExprBuilder<'a, 'b>) {
         todo!()
@@ -1647,9 +2150,18 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
 
     /// Logic of binary parse rule methods
     pub(crate) fn binary(&mut self, expr_builder: &mut ExprBuilder<'a, 'b>, binary_op: BinaryOp) {
+        let op_span = self.prev.get_span();
         self.parse_precedence(self.get_parse_rule_of_prev().infix_prec.get_next(), expr_builder);
 
-        expr_builder.emit_binary_expr(binary_op, self)
+        // Spans from the operator to the end of the rhs
+        let span = Span::merge(op_span, self.prev.get_span());
+        expr_builder.emit_binary_expr(binary_op, span, self)
+    }
+
+    /// Parse rule method: `try_expr`
+    pub(crate) fn try_expr(&mut self, expr_builder: &mut ExprBuilder<'a, 'b>) {
+        self.advance();
+        expr_builder.emit_try_expr(self.prev.get_span(), self)
     }
 
     pub(crate) fn post_inc(&mut self, expr_builder: &mut ExprBuilder<'a, 'b>) {
@@ -1678,6 +2190,24 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
 
     /// Parse rule method: `loop_expr`
     pub(crate) fn loop_expr(&mut self, expr_builder: &mut ExprBuilder<'a, 'b>) {
+        self.parse_loop_expr(None, expr_builder);
+    }
+
+    /// Parse rule method: `labeled_loop`
+    pub(crate) fn labeled_loop(&mut self, expr_builder: &mut ExprBuilder<'a, 'b>) {
+        let label = self.make_ident_node_from_prev();
+        self.consume(TokenKind::Colon, "Expected `:` after loop label");
+        self.consume(TokenKind::Loop, "Expected `loop` after loop label");
+
+        let label = self.ast_arena.alloc_expr_or_stmt(label);
+        self.parse_loop_expr(Some(label), expr_builder);
+    }
+
+    fn parse_loop_expr(
+        &mut self,
+        label: Option<&'a IdentNode>,
+        expr_builder: &mut ExprBuilder<'a, 'b>
+    ) {
         let start_span = self.current.get_span();
         self.consume(TokenKind::LeftCurly, "Expected `{` before loop");
         let block = self.parse_block();
@@ -1685,6 +2215,7 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
 
         let loop_expr = self.ast_arena.alloc_expr_or_stmt(
             LoopExpr::new(
+                label,
                 block,
                 Span::merge(start_span, self.current.get_span()),
                 self.get_ast_node_id()
@@ -1826,6 +2357,7 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
                 | TokenKind::Typedef
                 | TokenKind::Declare
                 | TokenKind::Import
+                | TokenKind::Export
                 | TokenKind::Loop
                 | TokenKind::Impl
                 | TokenKind::If
@@ -2042,6 +2574,17 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
         }
     }
 
+    pub(crate) fn try_consume_label(&mut self) -> Option<IdentNode> {
+        match self.current.get_kind() {
+            TokenKind::Label => {
+                let label_node = self.make_ident_node_from_current();
+                self.advance();
+                Some(label_node)
+            }
+            _ => None,
+        }
+    }
+
     pub(crate) fn consume_pkg_ident(&mut self, err_msg: &str) -> PkgIdentNode {
         match self.current.get_kind() {
             TokenKind::Pkg => {
@@ -2071,6 +2614,13 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
         ident_node
     }
 
+    fn make_ident_node_from_prev(&mut self) -> IdentNode {
+        let ident_node = IdentNode::new(self.prev.get_span(), self.get_ast_node_id());
+        let lexeme = self.get_lexeme(ident_node.span);
+        Symbol::new_with_node_id(lexeme, ident_node.ast_node_id);
+        ident_node
+    }
+
     pub(crate) fn get_parse_rule_of_current(&self) -> &ParseRule {
         &PARSE_RULES[self.current.get_kind() as usize]
     }
@@ -2079,3 +2629,85 @@ impl<'a, 'b> Parser<'a, 'b> where 'a: 'b {
         &PARSE_RULES[self.prev.get_kind() as usize]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use ast::{ AstArena, ItemStmt, Stmt, Visibility };
+    use diagnostics::Severity;
+    use ir::ModId;
+
+    use crate::Parser;
+
+    #[test]
+    fn export_items() {
+        let src = "export struct Token { kind int }\nexport fn new() {}\nexport Token, new";
+        let ast_arena = AstArena::new();
+        let ast_arena_obj = ast_arena.get();
+        let (ast, diagnostics) = Parser::new(src, &ast_arena_obj, ModId(0)).parse_ast();
+
+        assert!(diagnostics.is_empty());
+        let stmts = ast.main_scope.stmts;
+        assert_eq!(stmts.len(), 3);
+        assert!(
+            matches!(stmts[0], Stmt::ItemStmt(ItemStmt::StructItem(struct_item))
+                if struct_item.visibility == Visibility::Public)
+        );
+        assert!(
+            matches!(stmts[1], Stmt::ItemStmt(ItemStmt::FnItem(fn_item))
+                if fn_item.visibility == Visibility::Public)
+        );
+        assert!(
+            matches!(stmts[2], Stmt::ItemStmt(ItemStmt::ExportItem(export_item))
+                if export_item.exported_items.len() == 2)
+        );
+    }
+
+    #[test]
+    fn malformed_export() {
+        let src = "export 5\nexport Token,\nfn main() {}";
+        let ast_arena = AstArena::new();
+        let ast_arena_obj = ast_arena.get();
+        let (ast, diagnostics) = Parser::new(src, &ast_arena_obj, ModId(0)).parse_ast();
+
+        assert_eq!(diagnostics.len(), 2);
+        assert!(
+            diagnostics
+                .iter()
+                .all(|diagnostic| diagnostic.get_severity() == Some(Severity::Severe))
+        );
+        // Parsing continues after the malformed exports
+        assert!(
+            ast.main_scope.stmts
+                .iter()
+                .any(|stmt| matches!(stmt, Stmt::ItemStmt(ItemStmt::FnItem(_))))
+        );
+    }
+
+    #[test]
+    fn malformed_declare() {
+        let src = "declare struct Socket\ndeclare fn socket(domain int) int\nfn main() {}";
+        let ast_arena = AstArena::new();
+        let ast_arena_obj = ast_arena.get();
+        let (ast, diagnostics) = Parser::new(src, &ast_arena_obj, ModId(0)).parse_ast();
+
+        assert_eq!(diagnostics.len(), 2);
+        // Parsing resumes at `struct` after the first error, and after the C function signature
+        let stmts = ast.main_scope.stmts;
+        assert_eq!(stmts.len(), 2);
+        assert!(matches!(stmts[0], Stmt::ItemStmt(ItemStmt::StructItem(_))));
+        assert!(matches!(stmts[1], Stmt::ItemStmt(ItemStmt::FnItem(_))));
+    }
+
+    #[test]
+    fn malformed_import() {
+        let src = "import *\nimport other.{Point, 5}\nimport other.{Point, Vec as V}";
+        let ast_arena = AstArena::new();
+        let ast_arena_obj = ast_arena.get();
+        let (ast, diagnostics) = Parser::new(src, &ast_arena_obj, ModId(0)).parse_ast();
+
+        assert_eq!(diagnostics.len(), 2);
+        let stmts = ast.main_scope.stmts;
+        assert_eq!(stmts.len(), 1);
+        assert!(matches!(stmts[0], Stmt::ItemStmt(ItemStmt::ImportItem(_))));
+    }
+}
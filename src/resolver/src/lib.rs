@@ -1,16 +1,19 @@
 use std::sync::{ Mutex, OnceLock };
 
-use ast::{ AstState, FnItem, ResolverHandle, StringExpr };
+use ast::{ AstState, FnItem, ResolverHandle, StringExpr, Visibility };
 use bumpalo::Bump;
-use error::{ Error, Severity };
-use fxhash::{ FxBuildHasher, FxHashMap };
+use error::Error;
+use fxhash::{ FxBuildHasher, FxHashMap, FxHashSet };
 use ir::{
     ConstStrLen,
     DefId,
     LexicalBinding,
     LexicalContext,
+    ModId,
+    ModuleId,
     NameBinding,
     NodeId,
+    PkgId,
     ResKind,
     ResolvedInformation,
     Symbol,
@@ -18,16 +21,28 @@ use ir::{
     Ty,
     PKG_SYMBOL,
 };
+use span::Span;
 
 /// Main resolver struct. This is responsible for validating all the Asts in a package
 pub struct Resolver<'ctx, 'ast> {
     // Package information
-    pkg_symbol_to_def_id: FxHashMap<Symbol, DefId>,
+    pkg_id: PkgId,
+    /// Modules of this package and of the packages it can import from
+    pkg_modules: FxHashMap<ModuleId, PkgModule>,
+    mod_id_to_module_id: FxHashMap<ModId, ModuleId>,
     pkg_def_id_to_res_kind: FxHashMap<DefId, ResKind>,
+    pkg_def_id_to_visibility: FxHashMap<DefId, (Visibility, Span)>,
+    pkg_enum_def_id_to_variants: FxHashMap<DefId, Vec<DefId>>,
     pkg_def_id_to_name_binding: FxHashMap<DefId, NameBinding<'ctx>>,
     pkg_trait_impl_id_to_def_ids: FxHashMap<TraitImplId, Vec<DefId>>,
+    /// The `onDrop` method of each ADT implementing `Drop` in this package
+    adt_def_id_to_drop_fn: FxHashMap<DefId, DefId>,
+    copy_adt_def_ids: FxHashSet<DefId>,
     pkg_def_id: OnceLock<DefId>,
 
+    /// Root modules of already resolved packages, which this package can import from
+    extern_pkgs: FxHashMap<Symbol, ModuleId>,
+
     node_id_to_def_id: FxHashMap<NodeId, DefId>,
     node_id_to_ty: FxHashMap<NodeId, Ty>,
     def_id_to_name_binding: FxHashMap<DefId, NameBinding<'ctx>>,
@@ -71,18 +86,56 @@ pub struct LocalTraitImplId {
 //     def_id_to_name_binding: FxHashMap<LocalDefId, NameBinding<'ctx>>,
 // }
 
+#[derive(Default)]
 pub struct ResolvedFunctions<'ast> {
     pub pending_functions: Vec<&'ast FnItem<'ast>>,
     pub main_fn: Option<&'ast FnItem<'ast>>,
 }
 
+/// The tables of a resolved package, which is what other packages need in order to import from it
+///
+/// Tables keyed by `DefId` also contains the entries of the packages this package depends on,
+/// since types of its members can refer to items in those packages
+#[derive(Debug, Clone)]
+pub struct ResolvedPkg<'ctx> {
+    pub pkg_symbol: Symbol,
+    root_module_id: ModuleId,
+    pkg_modules: FxHashMap<ModuleId, PkgModule>,
+    pkg_def_id_to_res_kind: FxHashMap<DefId, ResKind>,
+    pkg_def_id_to_visibility: FxHashMap<DefId, (Visibility, Span)>,
+    pkg_enum_def_id_to_variants: FxHashMap<DefId, Vec<DefId>>,
+    pkg_def_id_to_name_binding: FxHashMap<DefId, NameBinding<'ctx>>,
+    pkg_trait_impl_id_to_def_ids: FxHashMap<TraitImplId, Vec<DefId>>,
+}
+
+/// Members and submodules of a module
+#[derive(Debug, Clone, Default)]
+pub struct PkgModule {
+    members: FxHashMap<Symbol, DefId>,
+    submodules: FxHashMap<Symbol, ModuleId>,
+}
+
 impl<'ctx, 'ast> Resolver<'ctx, 'ast> where 'ctx: 'ast {
     pub fn take_resolved_information(self) -> (ResolvedFunctions<'ast>, ResolvedInformation<'ctx>) {
+        // Errors of any severity stop the compilation, since the ICFG can't be built from a
+        // package which failed resolution or type checking
         if self.has_errors() {
             self.print_errors();
-            self.exit_if_has(Severity::NoImpact);
+            std::process::exit(1);
         }
 
+        // Functions of impls aren't members of the package, so they're never private
+        let private_fns = self.pending_functions
+            .iter()
+            .filter_map(|fn_item| {
+                let def_id = self.node_id_to_def_id.get(&fn_item.ident_node.ast_node_id)?;
+                match self.pkg_def_id_to_visibility.get(def_id) {
+                    Some((Visibility::Private, span)) => Some((*def_id, *span)),
+                    _ => None,
+                }
+            })
+            .collect();
+
         (
             ResolvedFunctions {
                 main_fn: self.found_main_fn.get().map(|v| &**v),
@@ -95,12 +148,16 @@ impl<'ctx, 'ast> Resolver<'ctx, 'ast> where 'ctx: 'ast {
                 // def_id_to_global_mem_id: self.def_id_to_global_mem_id,
                 const_strs: self.str_symbol_to_def_id.into_inner().unwrap().into_values().collect(),
                 clib_fns: self.clib_fns,
+                adt_def_id_to_drop_fn: self.adt_def_id_to_drop_fn,
+                copy_adt_def_ids: self.copy_adt_def_ids,
+                private_fns,
             },
         )
     }
 
     pub fn new(
         _arena: &'ctx Bump,
+        pkg_id: PkgId,
         total_nodes: usize,
         total_def_count: usize
         // global_mems: &'ctx RefCell<Vec<GlobalMem>>
@@ -120,9 +177,18 @@ impl<'ctx, 'ast> Resolver<'ctx, 'ast> where 'ctx: 'ast {
 
             pkg_def_id_to_name_binding: Default::default(),
             pkg_def_id_to_res_kind: Default::default(),
-            pkg_symbol_to_def_id: Default::default(),
+            pkg_def_id_to_visibility: Default::default(),
+            pkg_enum_def_id_to_variants: Default::default(),
+            pkg_id,
+            pkg_modules: FxHashMap::from_iter([
+                (ModuleId { pkg_id, local_id: 0 }, PkgModule::default()),
+            ]),
+            mod_id_to_module_id: Default::default(),
             pkg_trait_impl_id_to_def_ids: Default::default(),
+            adt_def_id_to_drop_fn: Default::default(),
+            copy_adt_def_ids: Default::default(),
             pkg_def_id: OnceLock::new(),
+            extern_pkgs: Default::default(),
             constants: Vec::new(),
 
             node_id_to_def_id: hashmap_with_capacity!(total_nodes),
@@ -135,18 +201,99 @@ impl<'ctx, 'ast> Resolver<'ctx, 'ast> where 'ctx: 'ast {
         }
     }
 
+    fn get_root_module_id(&self) -> ModuleId {
+        ModuleId { pkg_id: self.pkg_id, local_id: 0 }
+    }
+
+    /// Places the file `mod_id` in the module given by `module_path` e.g. `[net, http]` for
+    /// a file in the directory `net/http` of the package. Modules are created when needed
+    pub fn add_mod_to_module(&mut self, mod_id: ModId, module_path: &[Symbol]) {
+        let mut module_id = self.get_root_module_id();
+
+        for symbol in module_path {
+            let submodule_id = self.pkg_modules[&module_id].submodules.get(symbol).copied();
+
+            module_id = match submodule_id {
+                Some(submodule_id) => submodule_id,
+                None => {
+                    let local_id = self.pkg_modules
+                        .keys()
+                        .filter(|x| x.pkg_id == self.pkg_id)
+                        .count() as u32;
+                    let submodule_id = ModuleId { pkg_id: self.pkg_id, local_id };
+
+                    self.pkg_modules.insert(submodule_id, PkgModule::default());
+                    self.pkg_modules
+                        .get_mut(&module_id)
+                        .unwrap()
+                        .submodules.insert(*symbol, submodule_id);
+
+                    submodule_id
+                }
+            };
+        }
+
+        self.mod_id_to_module_id.insert(mod_id, module_id);
+    }
+
+    /// Makes the members of an already resolved package importable from this package
+    pub fn use_resolved_pkg(&mut self, resolved_pkg: &ResolvedPkg<'ctx>) {
+        self.extern_pkgs.insert(resolved_pkg.pkg_symbol, resolved_pkg.root_module_id);
+        self.pkg_modules.extend(
+            resolved_pkg.pkg_modules.iter().map(|(k, v)| (*k, v.clone()))
+        );
+        self.pkg_def_id_to_res_kind.extend(
+            resolved_pkg.pkg_def_id_to_res_kind.iter().map(|(k, v)| (*k, *v))
+        );
+        self.pkg_def_id_to_visibility.extend(
+            resolved_pkg.pkg_def_id_to_visibility.iter().map(|(k, v)| (*k, *v))
+        );
+        self.pkg_enum_def_id_to_variants.extend(
+            resolved_pkg.pkg_enum_def_id_to_variants.iter().map(|(k, v)| (*k, v.clone()))
+        );
+        self.pkg_def_id_to_name_binding.extend(
+            resolved_pkg.pkg_def_id_to_name_binding.iter().map(|(k, v)| (*k, *v))
+        );
+        for (trait_impl_id, new_def_ids) in resolved_pkg.pkg_trait_impl_id_to_def_ids.iter() {
+            let def_ids = self.pkg_trait_impl_id_to_def_ids.entry(*trait_impl_id).or_default();
+            def_ids.extend(new_def_ids);
+        }
+    }
+
+    /// Should be called after type checking, so other packages can import from this one
+    pub fn get_resolved_pkg(&self, pkg_symbol: Symbol) -> ResolvedPkg<'ctx> {
+        ResolvedPkg {
+            pkg_symbol,
+            root_module_id: self.get_root_module_id(),
+            pkg_modules: self.pkg_modules.clone(),
+            pkg_def_id_to_res_kind: self.pkg_def_id_to_res_kind.clone(),
+            pkg_def_id_to_visibility: self.pkg_def_id_to_visibility.clone(),
+            pkg_enum_def_id_to_variants: self.pkg_enum_def_id_to_variants.clone(),
+            pkg_def_id_to_name_binding: self.pkg_def_id_to_name_binding.clone(),
+            pkg_trait_impl_id_to_def_ids: self.pkg_trait_impl_id_to_def_ids.clone(),
+        }
+    }
+
     pub fn use_visit_result_from_pre_resolve(
         &mut self,
         global_visit_result: ast::ast_pre_resolver::GlobalVisitResult
     ) {
-        for symbol in global_visit_result.pkg_symbol_to_def_id.keys() {
-            if self.pkg_symbol_to_def_id.contains_key(symbol) {
+        for (symbol, def_id) in global_visit_result.pkg_symbol_to_def_id {
+            let module_id = self.mod_id_to_module_id
+                .get(&def_id.node_id.mod_id)
+                .copied()
+                .unwrap_or(self.get_root_module_id());
+            let module = self.pkg_modules.get_mut(&module_id).unwrap();
+
+            if module.members.contains_key(&symbol) {
                 panic!("Symbol in package already exists: {:?}", symbol.get());
             }
+            module.members.insert(symbol, def_id);
         }
 
-        self.pkg_symbol_to_def_id.extend(global_visit_result.pkg_symbol_to_def_id);
         self.pkg_def_id_to_res_kind.extend(global_visit_result.pkg_def_id_to_res_kind);
+        self.pkg_def_id_to_visibility.extend(global_visit_result.pkg_def_id_to_visibility);
+        self.pkg_enum_def_id_to_variants.extend(global_visit_result.pkg_enum_def_id_to_variants);
     }
 
     pub fn use_visit_result_from_resolve(
@@ -165,6 +312,9 @@ impl<'ctx, 'ast> Resolver<'ctx, 'ast> where 'ctx: 'ast {
 
             def_ids.extend(new_def_ids);
         }
+
+        self.adt_def_id_to_drop_fn.extend(global_visit_result.adt_def_id_to_drop_fn);
+        self.copy_adt_def_ids.extend(global_visit_result.copy_adt_def_ids);
     }
 
     pub fn use_visit_result_from_type_check(
@@ -191,26 +341,32 @@ impl<'ctx, 'ast> Resolver<'ctx, 'ast> where 'ctx: 'ast {
 
         println!("{}", buffer);
     }
-
-    fn exit_if_has(&self, severity: Severity) {
-        self.print_errors();
-        if self.has_error_severity(severity) {
-            std::process::exit(1)
-        }
-    }
-
-    fn has_error_severity(&self, severity: Severity) -> bool {
-        let errors = self.errors.lock().unwrap();
-
-        errors.iter().any(|e| e.get_severity() == severity)
-    }
 }
 
 impl<'ctx, 'ast, T> ResolverHandle<'ctx, 'ast, T> for Resolver<'ctx, 'ast> where T: AstState {
     /* Methods used during all passes */
 
     fn lookup_pkg_member(&self, symbol: Symbol) -> Option<DefId> {
-        self.pkg_symbol_to_def_id.get(&symbol).copied()
+        let root_module_id = ModuleId { pkg_id: self.pkg_id, local_id: 0 };
+        self.pkg_modules.get(&root_module_id)?.members.get(&symbol).copied()
+    }
+    fn lookup_pkg_root_module(&self) -> ModuleId {
+        ModuleId { pkg_id: self.pkg_id, local_id: 0 }
+    }
+    fn lookup_extern_pkg(&self, pkg_symbol: Symbol) -> Option<ModuleId> {
+        self.extern_pkgs.get(&pkg_symbol).copied()
+    }
+    fn lookup_module_member(&self, module_id: ModuleId, symbol: Symbol) -> Option<DefId> {
+        self.pkg_modules.get(&module_id)?.members.get(&symbol).copied()
+    }
+    fn lookup_submodule(&self, module_id: ModuleId, symbol: Symbol) -> Option<ModuleId> {
+        self.pkg_modules.get(&module_id)?.submodules.get(&symbol).copied()
+    }
+    fn lookup_module_members(&self, module_id: ModuleId) -> Vec<DefId> {
+        self.pkg_modules
+            .get(&module_id)
+            .map(|module| module.members.values().copied().collect())
+            .unwrap_or_default()
     }
     fn get_or_set_pkg_def_id(&self, pkg_ident_node: &'ast ast::PkgIdentNode) -> DefId {
         *self.pkg_def_id.get_or_init(|| DefId::new(*PKG_SYMBOL, pkg_ident_node.ast_node_id))
@@ -219,6 +375,12 @@ impl<'ctx, 'ast, T> ResolverHandle<'ctx, 'ast, T> for Resolver<'ctx, 'ast> where
     fn lookup_pkg_member_res_kind(&self, def_id: &DefId) -> ResKind {
         *self.pkg_def_id_to_res_kind.get(def_id).expect("Expected ResKind")
     }
+    fn lookup_pkg_member_visibility(&self, def_id: &DefId) -> (Visibility, Span) {
+        *self.pkg_def_id_to_visibility.get(def_id).expect("Expected Visibility")
+    }
+    fn lookup_enum_variants(&self, def_id: &DefId) -> Option<&Vec<DefId>> {
+        self.pkg_enum_def_id_to_variants.get(def_id)
+    }
     fn lookup_pkg_member_name_binding(&self, def_id: &DefId) -> Option<&NameBinding<'ctx>> {
         self.pkg_def_id_to_name_binding.get(def_id)
     }
@@ -77,6 +77,8 @@ pub enum TokenKind {
     Comma,
     /// `!`
     Bang,
+    /// `?`
+    Question,
     /// `++`
     Increment,
     /// `--`
@@ -87,6 +89,8 @@ pub enum TokenKind {
     StringChar,
     /// `...`
     Ellipsis,
+    /// `@`
+    At,
 
     /* Literals */
     /// Integer e.g. `69`
@@ -104,6 +108,10 @@ pub enum TokenKind {
     /// Identifier e.g. `a`
     Ident,
 
+    /* Label */
+    /// Loop label e.g. `'outer`
+    Label,
+
     /* Null */
     /// Null literal `null`
     Null,
@@ -111,6 +119,8 @@ pub enum TokenKind {
     /* Keywords */
     /// Keyword `impl`
     Impl,
+    /// Keyword `for` e.g. `impl Drop for Foo`
+    For,
     /// Keyword `self`
     SmallSelf,
     /// Keyword `Self`
@@ -133,6 +143,8 @@ pub enum TokenKind {
     Break,
     /// Keyword `continue`
     Continue,
+    /// Keyword `defer`
+    Defer,
     /// Keyword `else`
     Else,
     /// Keyword `elif`
@@ -145,8 +157,20 @@ pub enum TokenKind {
     Typedef,
     /// Keyword `import`
     Import,
+    /// Keyword `pub`
+    Pub,
+    /// Keyword `export`
+    Export,
+    /// Keyword `as`
+    As,
     /// Keyword `pkg`
     Pkg,
+    /// Keyword `sizeof`
+    SizeOf,
+    /// Keyword `alignof`
+    AlignOf,
+    /// Keyword `offsetof`
+    OffsetOf,
 
     /// End of ofile
     Eof,
@@ -164,6 +188,7 @@ impl TokenKind {
     pub const fn to_keyword_str(&self) -> &str {
         match self {
             Self::Impl => "impl",
+            Self::For => "for",
             Self::SmallSelf => "self",
             Self::BigSelf => "Self",
             Self::Declare => "declare",
@@ -175,16 +200,23 @@ impl TokenKind {
             Self::Loop => "loop",
             Self::Break => "break",
             Self::Continue => "continue",
+            Self::Defer => "defer",
             Self::Else => "else",
             Self::Elif => "elif",
             Self::Mut => "mut",
             Self::Return => "ret",
             Self::Typedef => "typedef",
             Self::Import => "import",
+            Self::Pub => "pub",
+            Self::Export => "export",
+            Self::As => "as",
             Self::True => "true",
             Self::False => "false",
             Self::Null => "null",
             Self::Pkg => "pkg",
+            Self::SizeOf => "sizeof",
+            Self::AlignOf => "alignof",
+            Self::OffsetOf => "offsetof",
             _ => "",
         }
     }
@@ -215,18 +247,22 @@ impl Display for TokenKind {
             Self::Dot => write!(f, "."),
             Self::Comma => write!(f, ","),
             Self::Bang => write!(f, "!"),
+            Self::Question => write!(f, "?"),
             Self::Increment => write!(f, "++"),
             Self::Decrement => write!(f, "--"),
             Self::DoubleQuote => write!(f, "\""),
             Self::StringChar => write!(f, "character"),
             Self::Ellipsis => write!(f, "..."),
+            Self::At => write!(f, "@"),
             Self::Integer => write!(f, "integer"),
             Self::Float => write!(f, "float"),
             Self::True => write!(f, "true"),
             Self::False => write!(f, "false"),
             Self::Ident => write!(f, "identifier"),
+            Self::Label => write!(f, "label"),
             Self::Null => write!(f, "null"),
             Self::Impl => write!(f, "impl"),
+            Self::For => write!(f, "for"),
             Self::SmallSelf => write!(f, "self"),
             Self::BigSelf => write!(f, "Self"),
             Self::Declare => write!(f, "declare"),
@@ -238,13 +274,20 @@ impl Display for TokenKind {
             Self::Loop => write!(f, "loop"),
             Self::Break => write!(f, "break"),
             Self::Continue => write!(f, "continue"),
+            Self::Defer => write!(f, "defer"),
             Self::Else => write!(f, "else"),
             Self::Elif => write!(f, "elif"),
             Self::Mut => write!(f, "mut"),
             Self::Return => write!(f, "ret"),
             Self::Typedef => write!(f, "typedef"),
             Self::Import => write!(f, "import"),
+            Self::Pub => write!(f, "pub"),
+            Self::Export => write!(f, "export"),
+            Self::As => write!(f, "as"),
             Self::Pkg => write!(f, "pkg"),
+            Self::SizeOf => write!(f, "sizeof"),
+            Self::AlignOf => write!(f, "alignof"),
+            Self::OffsetOf => write!(f, "offsetof"),
             Self::Eof => write!(f, "EOF"),
         }
     }
@@ -0,0 +1,38 @@
+//! Failures of the C compiler are reported as errors on stderr, with a non-zero exit code
+
+mod common;
+
+use common::{ run_compiler, write_script };
+
+const HELLO: &str = "declare fn.C printf(fmt str, args ...) int
+
+fn main() {
+    printf(\"hello\\n\")
+}
+";
+
+#[test]
+fn missing_c_compiler() {
+    let output = run_compiler("missing_c_compiler", HELLO, &["--cc", "viskum-missing-cc"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(1), "{}{}", stdout, stderr);
+    assert!(stderr.contains("Errors:"), "{}", stderr);
+    assert!(stderr.contains("Couldn't run the C compiler `viskum-missing-cc`"), "{}", stderr);
+    assert!(!stdout.contains("viskum-missing-cc"), "{}", stdout);
+}
+
+#[test]
+fn failing_c_compiler() {
+    let failing_cc = write_script("failing-cc", "#!/bin/sh\nexit 3\n");
+    let cc_arg = failing_cc.display().to_string();
+    let output = run_compiler("failing_c_compiler", HELLO, &["--cc", &cc_arg]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(1), "{}{}", stdout, stderr);
+    assert!(stderr.contains("Compiling the LLVM IR in"), "{}", stderr);
+    assert!(stderr.contains(&format!("(`{}` exited with code 3)", cc_arg)), "{}", stderr);
+    assert!(!stdout.contains("LLVM IR"), "{}", stdout);
+}
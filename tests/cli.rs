@@ -0,0 +1,70 @@
+//! Values of command line options, which may start with a `-` or be given after a `=`
+
+mod common;
+
+use common::{ compile, pkg_dir, stub_cc };
+
+const HELLO: &str = "declare fn.C printf(fmt str, args ...) int
+
+fn main() {
+    printf(\"hello\\n\")
+}
+";
+
+#[test]
+fn output_starting_with_dash() {
+    let (exit_code, output) = compile("output_starting_with_dash", HELLO, &["-o", "-out"]);
+
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert!(pkg_dir("output_starting_with_dash").join("-out").exists());
+}
+
+#[test]
+fn values_after_equals() {
+    let out_dir = pkg_dir("values_after_equals").join("build");
+    let out_dir_arg = format!("--out-dir={}", out_dir.display());
+    let cc_arg = format!("--cc={}", stub_cc().display());
+    let (exit_code, output) = compile(
+        "values_after_equals",
+        HELLO,
+        &[&out_dir_arg, "-o=hello", &cc_arg]
+    );
+
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert!(pkg_dir("values_after_equals").join("hello").exists());
+
+    let (exit_code, output) = compile("values_after_equals", HELLO, &[&out_dir_arg]);
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert!(out_dir.join("app").exists());
+}
+
+#[test]
+fn missing_value() {
+    for args in [&["-o", "--dot"][..], &["-o="], &["--cc", "--linker=lld"], &["--out-dir"]] {
+        let (exit_code, output) = compile("missing_value", HELLO, args);
+
+        assert_eq!(exit_code, Some(1), "{}", output);
+        assert!(output.contains("Missing value after"), "{}", output);
+    }
+}
+
+#[test]
+fn default_out_dir() {
+    let pkg_dir = pkg_dir("default_out_dir");
+    std::fs::create_dir_all(&pkg_dir).unwrap();
+    std::fs::write(pkg_dir.join("main.vs"), HELLO).unwrap();
+    let work_dir = pkg_dir.parent().unwrap();
+    let _ = std::fs::remove_dir_all(work_dir.join("viskum"));
+    let _ = std::fs::remove_dir_all(pkg_dir.join("viskum"));
+
+    // The generated files go to `viskum/dist` in the current directory, not in the entry package
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_viskum-compiler"))
+        .args(["app/main.vs", "--interpret", "--dot"])
+        .current_dir(work_dir)
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(work_dir.join("viskum/dist/app.dot").exists());
+    assert!(!pkg_dir.join("viskum").exists());
+}
@@ -0,0 +1,78 @@
+//! Helpers for running the compiler on small packages
+
+// Each test crate only uses some of the helpers
+#![allow(dead_code)]
+
+use std::{ os::unix::fs::PermissionsExt, path::PathBuf, process::{ Command, Output } };
+
+/// Creates the file after `-o` in place of compiling the LLVM IR
+const STUB_CC: &str = "#!/bin/sh
+while [ $# -gt 0 ]; do
+    if [ \"$1\" = -o ]; then
+        touch \"$2\"
+    fi
+    shift
+done
+";
+
+pub fn pkg_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join("viskum-tests").join(name).join("app")
+}
+
+/// Writes an executable shell script, which can be used as the C compiler with `--cc`
+pub fn write_script(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join("viskum-tests").join("bin");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // Written to a file of this process first, so a script which is being run isn't overwritten
+    let script = dir.join(name);
+    let tmp_script = dir.join(format!("{}.{}", name, std::process::id()));
+    std::fs::write(&tmp_script, content).unwrap();
+    std::fs::set_permissions(&tmp_script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::fs::rename(&tmp_script, &script).unwrap();
+    script
+}
+
+/// The C compiler used by the tests, so they don't depend on `clang` being installed
+pub fn stub_cc() -> PathBuf {
+    write_script("stub-cc", STUB_CC)
+}
+
+/// Compiles `src` as the only file of a package, from inside the package directory
+pub fn run_compiler(name: &str, src: &str, args: &[&str]) -> Output {
+    let pkg_dir = pkg_dir(name);
+    std::fs::create_dir_all(&pkg_dir).unwrap();
+    let entry_file = pkg_dir.join("main.vs");
+    std::fs::write(&entry_file, src).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_viskum-compiler"))
+        .arg(&entry_file)
+        .arg("--out-dir")
+        .arg(pkg_dir.join("dist"))
+        .args(args)
+        .env("VISKUM_CC", stub_cc())
+        .current_dir(&pkg_dir)
+        .output()
+        .unwrap()
+}
+
+/// Compiles `src` as the only file of a package, returns the exit code and the output
+pub fn compile(name: &str, src: &str, args: &[&str]) -> (Option<i32>, String) {
+    let output = run_compiler(name, src, args);
+
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    (output.status.code(), text)
+}
+
+/// Interprets the ICFG of `src`, returns the exit code and what the program printed
+pub fn interpret(name: &str, src: &str) -> (Option<i32>, String) {
+    let (exit_code, output) = compile(name, src, &["--interpret"]);
+
+    // Everything before the end of the compilation is printed by the compiler
+    let program_output = match output.split_once("Viskum compilation took") {
+        Some((_, rest)) => rest.split_once('\n').map_or("", |(_, rest)| rest),
+        None => panic!("Compilation failed:\n{}", output),
+    };
+    (exit_code, program_output.to_string())
+}
@@ -0,0 +1,109 @@
+//! Programs which must be rejected with an error (and a non-zero exit code) instead of a panic
+
+mod common;
+
+use common::{ compile, pkg_dir };
+
+const RESULT_ENUM: &str = "enum Result {\n    Ok(int),\n    Err(int),\n}\n";
+
+const OTHER_PKG: &str = "pub fn make() int {\n    ret 1\n}\n";
+
+/// Writes the package `other` next to the package of the test
+fn write_other_pkg(name: &str) {
+    let other_dir = pkg_dir(name).parent().unwrap().join("other");
+    std::fs::create_dir_all(&other_dir).unwrap();
+    std::fs::write(other_dir.join("lib.vs"), OTHER_PKG).unwrap();
+}
+
+/// Returns the output, so more of the reported errors can be checked
+fn assert_rejected(name: &str, src: &str, expected_msg: &str) -> String {
+    let (exit_code, output) = compile(name, src, &[]);
+    assert_eq!(exit_code, Some(1), "{}", output);
+    assert!(output.contains(expected_msg), "{}", output);
+    assert!(!output.contains("panicked"), "{}", output);
+    assert!(!pkg_dir(name).join("dist/app").exists());
+    output
+}
+
+#[test]
+fn try_on_non_result_value() {
+    let src = format!(
+        "{}\nfn half(x int) Result {{\n    y := x?\n    ret Result.Ok(y)\n}}\n\n\
+         fn main() {{\n    half(4)\n}}\n",
+        RESULT_ENUM
+    );
+    assert_rejected("try_on_non_result_value", &src, "Operator `?` expected an enum");
+}
+
+#[test]
+fn try_on_enum_without_ok_and_err() {
+    let src = format!(
+        "{}\nenum Shape {{\n    Circle(int),\n    Square(int),\n}}\n\n\
+         fn area(s Shape) Result {{\n    r := s?\n    ret Result.Ok(r)\n}}\n\n\
+         fn main() {{\n    area(Shape.Circle(2))\n}}\n",
+        RESULT_ENUM
+    );
+    assert_rejected(
+        "try_on_enum_without_ok_and_err",
+        &src,
+        "Operator `?` expected an enum with `Ok` and `Err` variants but found type `Shape"
+    );
+}
+
+#[test]
+fn try_in_fn_not_returning_result() {
+    let src = format!(
+        "{}\nfn half(x int) Result {{\n    ret Result.Ok(x / 2)\n}}\n\n\
+         fn quarter(x int) int {{\n    y := half(x)?\n    ret y\n}}\n\n\
+         fn main() {{\n    quarter(8)\n}}\n",
+        RESULT_ENUM
+    );
+    assert_rejected(
+        "try_in_fn_not_returning_result",
+        &src,
+        "Operator `?` cannot propagate the `Err` variant"
+    );
+}
+
+#[test]
+fn undefined_type() {
+    let src = "struct Point {\n    x Missing,\n}\n\nfn main() {\n}\n";
+    assert_rejected("undefined_type", src, "Undefined type `Missing`");
+}
+
+#[test]
+fn unknown_package_member() {
+    write_other_pkg("unknown_package_member");
+    let src = "import other.{ make, nope }\n\nfn main() {\n    make()\n}\n";
+    assert_rejected("unknown_package_member", src, "`nope` is not a member of `other` at line 1");
+}
+
+#[test]
+fn import_path_through_non_module() {
+    write_other_pkg("import_path_through_non_module");
+    let src = "import other.make.x.y\nimport other.make.*\n\nfn main() {\n}\n";
+    let output = assert_rejected(
+        "import_path_through_non_module",
+        src,
+        "`make` is not a package or module at line 2, so nothing can be imported from it"
+    );
+    assert!(output.contains("`make` is not a package or module at line 1"), "{}", output);
+}
+
+#[test]
+fn intrinsic_of_enum_variant() {
+    let src = format!(
+        "{}\nfn main() {{\n    size := sizeof(Result.Ok)\n    offset := offsetof(Result.Ok)\n}}\n",
+        RESULT_ENUM
+    );
+    let output = assert_rejected(
+        "intrinsic_of_enum_variant",
+        &src,
+        "Expected `)` after the type in `sizeof` at line 7. Found `.`"
+    );
+    assert!(
+        output.contains("Expected `,` after the type in `offsetof` at line 8. Found `.`"),
+        "{}",
+        output
+    );
+}
@@ -0,0 +1,57 @@
+//! Values which need to be dropped are dropped exactly once, also when they're never bound
+
+mod common;
+
+use common::interpret;
+
+const FOO: &str = "declare fn.C printf(fmt str, args ...) int
+
+struct Foo {
+    a int,
+}
+
+impl Drop for Foo {
+    fn onDrop(*self) {
+        printf(\"drop %d\\n\", self.a)
+    }
+}
+
+fn make(a int) Foo {
+    ret Foo { a: a }
+}
+
+fn consume(f Foo) {
+    printf(\"consume %d\\n\", f.a)
+}
+";
+
+#[test]
+fn unbound_values() {
+    let src = format!(
+        "{}\nfn main() {{\n    make(1)\n    printf(\"middle\\n\")\n    make(2)\n}}\n",
+        FOO
+    );
+    let (exit_code, output) = interpret("unbound_values", &src);
+
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert_eq!(output, "drop 1\nmiddle\ndrop 2\n");
+}
+
+#[test]
+fn partially_moved_struct() {
+    let src = format!(
+        "{}\nstruct Pair {{\n    a Foo,\n    n int,\n    b Foo,\n}}\n\n\
+         fn partial(c int) {{\n    pr := Pair {{ a: Foo {{ a: 1 }}, n: 0, b: Foo {{ a: 2 }} }}\n    \
+         if c == 1 {{\n        consume(pr.a)\n    }}\n    printf(\"end %d\\n\", c)\n}}\n\n\
+         fn main() {{\n    pr := Pair {{ a: Foo {{ a: 3 }}, n: 0, b: Foo {{ a: 4 }} }}\n    \
+         consume(pr.b)\n    partial(1)\n    partial(0)\n}}\n",
+        FOO
+    );
+    let (exit_code, output) = interpret("partially_moved_struct", &src);
+
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert_eq!(
+        output,
+        "consume 4\ndrop 4\nconsume 1\ndrop 1\nend 1\ndrop 2\nend 0\ndrop 1\ndrop 2\ndrop 3\n"
+    );
+}
@@ -0,0 +1,55 @@
+//! `sizeof`, `alignof` and `offsetof` agree with the layout used when fields are accessed, where
+//! each field is aligned like in a LLVM struct
+
+mod common;
+
+use common::{ compile, interpret };
+
+const MIXED: &str = "declare fn.C printf(fmt str, args ...) int
+
+struct Mixed {
+    a int8,
+    b int64,
+    c int16,
+}
+
+enum Wrapped {
+    Small(int8),
+    Big(Mixed),
+}
+
+fn main() {
+    printf(\"%d %d\\n\", sizeof(Mixed), alignof(Mixed))
+    printf(\"%d %d %d\\n\", offsetof(Mixed, a), offsetof(Mixed, b), offsetof(Mixed, c))
+    printf(\"%d %d %d\\n\", sizeof((int8, int32)), alignof((int8, int32)), sizeof(Wrapped))
+
+    m := Mixed { a: 1, b: 2, c: 3 }
+    printf(\"%d %d %d\\n\", m.a, m.b, m.c)
+    w := Wrapped.Big(m)
+    if Wrapped.Big(inner) := w {
+        printf(\"%d %d %d\\n\", inner.a, inner.b, inner.c)
+    }
+}
+";
+
+const LAYOUT: &str = "24 8\n0 8 16\n8 4 32\n1 2 3\n1 2 3\n";
+
+#[test]
+fn padded_layout() {
+    let (exit_code, output) = interpret("padded_layout", MIXED);
+
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert_eq!(output, LAYOUT);
+}
+
+#[test]
+fn padded_layout_without_inlining() {
+    let (exit_code, output) = compile(
+        "padded_layout_without_inlining",
+        MIXED,
+        &["--interpret", "--no-inline", "--mem2reg"]
+    );
+
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert!(output.ends_with(LAYOUT), "{}", output);
+}
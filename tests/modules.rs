@@ -0,0 +1,49 @@
+//! Subdirectories of a package are only parsed as nested modules when a path goes through them
+
+mod common;
+
+use common::{ interpret, pkg_dir };
+
+/// Writes a file of the test, relative to the directory which contains the package `app`
+fn write_file(name: &str, path: &str, content: &str) {
+    let file = pkg_dir(name).parent().unwrap().join(path);
+    std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+    std::fs::write(file, content).unwrap();
+}
+
+const BROKEN: &str = "fn broken(x) {\n    x++\n}\n";
+
+#[test]
+fn unreferenced_directories_are_ignored() {
+    let name = "unreferenced_directories_are_ignored";
+    write_file(name, "app/net/sock.vs", "pub fn port() int {\n    ret 80\n}\n");
+    write_file(name, "app/scratch/broken.vs", BROKEN);
+
+    let src = "declare fn.C printf(fmt str, args ...) int\n\n\
+               fn main() {\n    printf(\"%d\\n\", pkg.net.port())\n}\n";
+    let (exit_code, output) = interpret(name, src);
+
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert_eq!(output, "80\n");
+}
+
+#[test]
+fn module_referenced_by_later_package() {
+    let name = "module_referenced_by_later_package";
+    write_file(name, "other/lib.vs", "pub fn one() int {\n    ret 1\n}\n");
+    write_file(name, "other/util/seven.vs", "pub fn seven() int {\n    ret 7\n}\n");
+    write_file(name, "other/junk/broken.vs", BROKEN);
+    // `b` is parsed after `other`, and is the only package using `other.util`
+    write_file(
+        name,
+        "b/lib.vs",
+        "import other.util.seven\n\npub fn bee() int {\n    ret seven() + 1\n}\n"
+    );
+
+    let src = "declare fn.C printf(fmt str, args ...) int\nimport other.one\nimport b.bee\n\n\
+               fn main() {\n    printf(\"%d %d\\n\", one(), bee())\n}\n";
+    let (exit_code, output) = interpret(name, src);
+
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert_eq!(output, "1 8\n");
+}
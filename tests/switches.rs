@@ -0,0 +1,76 @@
+//! Chains of variant tests are lowered to a single switch, without leaving dead blocks behind
+
+mod common;
+
+use common::{ compile, interpret, pkg_dir };
+
+const SHAPE: &str = "declare fn.C printf(fmt str, args ...) int
+
+enum Shape {
+    Circle(int),
+    Square(int),
+    Rect(int, int),
+    Empty,
+}
+
+fn area(s Shape) int {
+    if Shape.Circle(r) := s {
+        ret 3 * r * r
+    } elif Shape.Square(a) := s {
+        ret a * a
+    } elif Shape.Circle(r) := s {
+        ret 0 - 1
+    } elif Shape.Rect(w, h) := s {
+        ret w * h
+    } else {
+        ret 0
+    }
+}
+
+fn kind(s Shape) int {
+    mut k := 0
+    if Shape.Square(a) := s {
+        k = 1
+    } elif Shape.Rect(w, h) := s {
+        k = 2
+    }
+    ret k
+}
+";
+
+const MAIN: &str = "fn main() {
+    printf(\"%d %d %d %d\\n\", area(Shape.Circle(2)), area(Shape.Square(3)), \
+     area(Shape.Rect(2, 5)), area(Shape.Circle(0)))
+    printf(\"%d %d %d\\n\", kind(Shape.Circle(1)), kind(Shape.Square(1)), kind(Shape.Rect(1, 1)))
+}
+";
+
+#[test]
+fn variant_chains() {
+    let src = format!("{}\n{}", SHAPE, MAIN);
+    let (exit_code, output) = interpret("variant_chains", &src);
+
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert_eq!(output, "12 9 10 0\n0 1 2\n");
+}
+
+#[test]
+fn no_blocks_left_behind() {
+    let src = format!("{}\n{}", SHAPE, MAIN);
+    let (exit_code, output) = compile(
+        "no_blocks_left_behind",
+        &src,
+        &["--interpret", "--dot", "--no-inline"]
+    );
+    assert_eq!(exit_code, Some(0), "{}", output);
+
+    let dot_file = pkg_dir("no_blocks_left_behind").join("dist/app.dot");
+    let dot = std::fs::read_to_string(dot_file).unwrap();
+    let area = dot.split("label=\"fn area").nth(1).unwrap().split("subgraph").next().unwrap();
+
+    // The switch, the bodies of the circle, square and rect cases, and the else
+    assert!(area.contains("switch int64"), "{}", area);
+    assert!(area.contains("cfg1_bb4 [label"), "{}", area);
+    assert!(!area.contains("cfg1_bb5 [label"), "{}", area);
+    assert!(!area.contains("ret int32 -1i32"), "{}", area);
+}
@@ -0,0 +1,95 @@
+//! `?` unwraps the `Ok` variant, or returns the `Err` variant early. It works on any enum with
+//! `Ok` and `Err` variants
+
+mod common;
+
+use common::interpret;
+
+const HALF: &str = "declare fn.C printf(fmt str, args ...) int
+
+enum Result {
+    Ok(int),
+    Err(int),
+}
+
+fn half(x int) Result {
+    if x / 2 * 2 != x {
+        ret Result.Err(x)
+    }
+    ret Result.Ok(x / 2)
+}
+
+fn quarter(x int) Result {
+    y := half(x)?
+    printf(\"half of %d is %d\\n\", x, y)
+    z := half(y)?
+    ret Result.Ok(z)
+}
+
+fn show(result Result) {
+    if Result.Ok(value) := result {
+        printf(\"ok %d\\n\", value)
+    } elif Result.Err(value) := result {
+        printf(\"err %d\\n\", value)
+    }
+}
+";
+
+#[test]
+fn unwraps_ok() {
+    let src = format!("{}\nfn main() {{\n    show(quarter(12))\n}}\n", HALF);
+    let (exit_code, output) = interpret("unwraps_ok", &src);
+
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert_eq!(output, "half of 12 is 6\nok 3\n");
+}
+
+#[test]
+fn returns_err_early() {
+    let src = format!("{}\nfn main() {{\n    show(quarter(7))\n    show(quarter(6))\n}}\n", HALF);
+    let (exit_code, output) = interpret("returns_err_early", &src);
+
+    // The rest of `quarter` is skipped after the first `Err`
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert_eq!(output, "err 7\nhalf of 6 is 3\nerr 3\n");
+}
+
+#[test]
+fn other_enums_with_ok_and_err() {
+    let src = "declare fn.C printf(fmt str, args ...) int
+
+enum Parsed {
+    Ok(int),
+    Err(int),
+    Empty,
+}
+
+fn parse(x int) Parsed {
+    if x < 0 {
+        ret Parsed.Err(x)
+    }
+    ret Parsed.Ok(x * 10)
+}
+
+fn parseTwice(x int) Parsed {
+    y := parse(x)?
+    z := parse(y - 100)?
+    ret Parsed.Ok(z)
+}
+
+fn main() {
+    ok := parseTwice(20)
+    if Parsed.Ok(value) := ok {
+        printf(\"ok %d\\n\", value)
+    }
+    err := parseTwice(5)
+    if Parsed.Err(value) := err {
+        printf(\"err %d\\n\", value)
+    }
+}
+";
+    let (exit_code, output) = interpret("other_enums_with_ok_and_err", src);
+
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert_eq!(output, "ok 1000\nerr -50\n");
+}
@@ -0,0 +1,77 @@
+//! Code after a `ret`, `break` or `continue` is never reached and leaves no nodes behind the
+//! terminator of its block
+
+mod common;
+
+use common::{ compile, interpret };
+
+const EARLY: &str = "declare fn.C printf(fmt str, args ...) int
+
+fn early(x int) int {
+    ret x
+    printf(\"never\\n\")
+}
+
+fn pick(x int) int {
+    mut y := 2
+    if x > 0 {
+        ret 100
+    }
+    if x < 0 - 5 {
+        y = y + 3
+    }
+    ret y
+}
+
+fn loopRet(n int) int {
+    mut i := 0
+    loop {
+        if i == n {
+            ret i * 10
+            break
+        }
+        i = i + 1
+        continue
+        i = i + 100
+    }
+    ret 0
+}
+
+fn nested(x int) int {
+    if x > 0 {
+        if x > 10 {
+            ret 1
+        } else {
+            ret 2
+        }
+    } elif x < 0 - 10 {
+        ret 3
+    }
+    ret 4
+}
+
+fn main() {
+    printf(\"%d %d %d %d %d\\n\", early(5), pick(1), pick(0 - 1), pick(0 - 10), loopRet(4))
+    printf(\"%d %d %d %d\\n\", nested(20), nested(5), nested(0 - 20), nested(0))
+}
+";
+
+#[test]
+fn statements_after_terminators() {
+    let (exit_code, output) = interpret("statements_after_terminators", EARLY);
+
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert_eq!(output, "5 100 2 5 40\n1 2 3 4\n");
+}
+
+#[test]
+fn verifies_without_inlining() {
+    let (exit_code, output) = compile(
+        "verifies_without_inlining",
+        EARLY,
+        &["--interpret", "--verify", "--no-inline", "--mem2reg"]
+    );
+
+    assert_eq!(exit_code, Some(0), "{}", output);
+    assert!(output.ends_with("5 100 2 5 40\n1 2 3 4\n"), "{}", output);
+}
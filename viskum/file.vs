@@ -65,7 +65,7 @@ impl Vec {
 
         if self.len == self.cap {
             self.cap = if self.cap == 0 { 2 } else { self.cap * 2 }
-            size := self.cap * 4
+            size := self.cap * sizeof(int)
             
             self.items = if self.len == 0 { malloc(size) } else { realloc(self.items, size) }
         }
@@ -1,4 +1,4 @@
-struct Point { 
+pub struct Point { 
     x int,
     y int,
     z int,
@@ -18,6 +18,10 @@ impl Point {
     }
 }
 
-fn doSomething() int {
+pub fn doSomething() int {
     ret 2 * 3
-}
\ No newline at end of file
+}
+pub enum Result {
+    Ok(int),
+    Err(int),
+}